@@ -1,10 +1,17 @@
 //! Domain of parameter and objective values.
+use crate::trial::Params;
 use crate::{Error, ErrorKind, Result};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
 use structopt::StructOpt;
 
+/// Maximum number of attempts to find a value satisfying the constraint of a variable
+/// (see `Domain::sample_params`).
+pub const MAX_SAMPLE_ATTEMPTS: usize = 1000;
+
 /// Domain.
 ///
 /// A `Domain` instance consists of a vector of `Variable`.
@@ -42,6 +49,121 @@ impl Domain {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    /// Samples parameters from this domain.
+    ///
+    /// The variables are sampled in order.
+    /// If a variable has a constraint, the constraint is evaluated against the values sampled so far,
+    /// and the variable becomes inactive (i.e., `NaN`) if the constraint isn't satisfied.
+    /// Thus, the resulting parameters always pass `validate_params`.
+    ///
+    /// The constraint of an active variable is evaluated again with the sampled value of the variable,
+    /// so a constraint can also restrict the value of the constrained variable itself
+    /// (e.g., `b == nil or b < a` makes `b` always active and less than `a`).
+    /// If the sampled value doesn't satisfy the constraint, the variable is resampled.
+    /// When no value satisfies the constraint in `MAX_SAMPLE_ATTEMPTS` attempts,
+    /// an `ErrorKind::UnevaluableParams` error is returned.
+    pub fn sample_params<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Result<Params> {
+        use rand::distributions::Distribution as _;
+
+        track!(self.make_params(|_, var, _| var.sample(rng)))
+    }
+
+    /// Makes parameters whose values are given by `value`, activating the variables in the same way
+    /// as `sample_params`.
+    ///
+    /// `value(i, var, attempt)` returns a candidate value of the `i`-th variable `var`, and it's called
+    /// only if the variable is active. `attempt` is the number of the candidates rejected so far
+    /// by the constraint of the variable, so `value` should return a different candidate if it's positive.
+    pub fn make_params<F>(&self, mut value: F) -> Result<Params>
+    where
+        F: FnMut(usize, &Variable, usize) -> f64,
+    {
+        let mut params = Vec::with_capacity(self.0.len());
+        for (i, var) in self.0.iter().enumerate() {
+            let Some(constraint) = &var.constraint else {
+                params.push(value(i, var, 0));
+                continue;
+            };
+            if !track!(constraint.is_satisfied(&self.0, &params); var.name)? {
+                params.push(f64::NAN);
+                continue;
+            }
+
+            let mut attempt = 0;
+            loop {
+                track_assert!(
+                    attempt < MAX_SAMPLE_ATTEMPTS,
+                    ErrorKind::UnevaluableParams,
+                    "No value of {:?} satisfying the constraint was found in {} attempts",
+                    var.name,
+                    MAX_SAMPLE_ATTEMPTS
+                );
+
+                params.push(value(i, var, attempt));
+                attempt += 1;
+                if track!(constraint.is_satisfied(&self.0, &params); var.name)? {
+                    break;
+                }
+                params.pop();
+            }
+        }
+        Ok(Params::new(params))
+    }
+
+    /// Returns `true` if the given parameters belong to this domain, otherwise `false`.
+    ///
+    /// See `validate_params` for the details.
+    pub fn contains(&self, params: &[f64]) -> bool {
+        self.validate_params(params).is_ok()
+    }
+
+    /// Checks whether the given parameters belong to this domain.
+    ///
+    /// Each value must be within the range of the corresponding variable, and
+    /// the values of discrete and categorical variables must be integers.
+    /// Conditional variables (i.e., variables that have constraints) are allowed to be `NaN` (inactive).
+    /// Note that whether the constraints are satisfied or not isn't checked.
+    ///
+    /// The returned error names the offending variable and value.
+    pub fn validate_params(&self, params: &[f64]) -> Result<()> {
+        track_assert_eq!(
+            params.len(),
+            self.0.len(),
+            ErrorKind::InvalidInput,
+            "Wrong number of parameters"
+        );
+        for (var, &v) in self.0.iter().zip(params.iter()) {
+            if v.is_nan() {
+                track_assert!(
+                    var.constraint.is_some(),
+                    ErrorKind::InvalidInput,
+                    "Non-conditional variable {:?} has NaN",
+                    var.name
+                );
+                continue;
+            }
+
+            track_assert!(
+                var.range.contains(v),
+                ErrorKind::InvalidInput,
+                "Out of range value: variable={:?}, value={}, range={:?}",
+                var.name,
+                v,
+                var.range
+            );
+            if !matches!(var.range, Range::Continuous { .. }) {
+                track_assert!(
+                    v.fract() == 0.0,
+                    ErrorKind::InvalidInput,
+                    "Non-integer value: variable={:?}, value={}",
+                    var.name,
+                    v
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Returns a `VariableBuilder` which was initialized with the given variable name.
@@ -57,7 +179,9 @@ pub struct VariableBuilder {
     name: String,
     range: Range,
     distribution: Distribution,
+    weights: Option<CategoricalWeights>,
     constraint: Option<Constraint>,
+    fidelity: bool,
 }
 impl VariableBuilder {
     /// Makes a new `VariableBuilder` with the given variable name.
@@ -67,9 +191,12 @@ impl VariableBuilder {
             range: Range::Continuous {
                 low: f64::NEG_INFINITY,
                 high: f64::INFINITY,
+                step: None,
             },
             distribution: Distribution::Uniform,
+            weights: None,
             constraint: None,
+            fidelity: false,
         }
     }
 
@@ -95,7 +222,23 @@ impl VariableBuilder {
 
     /// Sets the range of this variable to the given continuous numerical range.
     pub fn continuous(mut self, low: f64, high: f64) -> Self {
-        self.range = Range::Continuous { low, high };
+        self.range = Range::Continuous {
+            low,
+            high,
+            step: None,
+        };
+        self
+    }
+
+    /// Sets the range of this variable to the given continuous numerical range quantized by `step`.
+    ///
+    /// The possible values of the variable are `low`, `low + step`, `low + 2 * step`, ... (less than `high`).
+    pub fn continuous_step(mut self, low: f64, high: f64, step: f64) -> Self {
+        self.range = Range::Continuous {
+            low,
+            high,
+            step: Some(step),
+        };
         self
     }
 
@@ -105,6 +248,19 @@ impl VariableBuilder {
         self
     }
 
+    /// Sets the range of this variable to the given integer choices.
+    ///
+    /// Unlike `discrete`, the variable only takes the listed values (e.g., `[16, 32, 64, 128]`).
+    pub fn int_choices<I>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        self.range = Range::IntChoices {
+            values: values.into_iter().collect(),
+        };
+        self
+    }
+
     /// Sets the range of this variable to the given categorical range.
     pub fn categorical<I, T>(mut self, choices: I) -> Self
     where
@@ -114,6 +270,40 @@ impl VariableBuilder {
         self.range = Range::Categorical {
             choices: choices.into_iter().map(|c| c.as_ref().to_owned()).collect(),
         };
+        self.weights = None;
+        self
+    }
+
+    /// Sets the range of this variable to the given ordinal range.
+    ///
+    /// The choices must be given in ascending order (e.g., `["low", "medium", "high"]`).
+    pub fn ordinal<I, T>(mut self, choices: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.range = Range::Ordinal {
+            choices: choices.into_iter().map(|c| c.as_ref().to_owned()).collect(),
+        };
+        self.weights = None;
+        self
+    }
+
+    /// Sets the range of this variable to the given categorical range with prior weights.
+    ///
+    /// The prior probability of each choice is proportional to its weight.
+    /// Weights must be non-negative finite numbers and at least one of them must be positive.
+    pub fn categorical_weighted<I, T>(mut self, choices: I) -> Self
+    where
+        I: IntoIterator<Item = (T, f64)>,
+        T: AsRef<str>,
+    {
+        let (choices, weights) = choices
+            .into_iter()
+            .map(|(c, w)| (c.as_ref().to_owned(), w))
+            .unzip();
+        self.range = Range::Categorical { choices };
+        self.weights = Some(CategoricalWeights(weights));
         self
     }
 
@@ -125,8 +315,20 @@ impl VariableBuilder {
     }
 
     /// Sets the range of this variable.
+    ///
+    /// Note that the categorical weights set by `categorical_weighted` are cleared by this method.
     pub fn range(mut self, range: Range) -> Self {
         self.range = range;
+        self.weights = None;
+        self
+    }
+
+    /// Marks this variable as a fidelity (budget) knob.
+    ///
+    /// Solvers that have `Capability::MultiFidelity` may control the fidelity of evaluations
+    /// through this variable. Other solvers handle it as an ordinary variable.
+    pub fn fidelity(mut self) -> Self {
+        self.fidelity = true;
         self
     }
 
@@ -139,13 +341,38 @@ impl VariableBuilder {
     /// Builds a `Variable` instance with the given settings.
     pub fn finish(self) -> Result<Variable> {
         match &self.range {
-            Range::Continuous { low, high } => {
-                track_assert!(low < high, ErrorKind::InvalidInput; self)
+            Range::Continuous { low, high, step } => {
+                track_assert!(low < high, ErrorKind::InvalidInput; self);
+                if let Some(step) = step {
+                    track_assert!(
+                        step.is_finite() && *step > 0.0,
+                        ErrorKind::InvalidInput; self
+                    );
+                    track_assert!(
+                        self.distribution == Distribution::Uniform,
+                        ErrorKind::InvalidInput,
+                        "Log-uniform distribution can't be quantized: {:?}",
+                        self
+                    );
+                }
             }
             Range::Discrete { low, high } => {
                 track_assert!(low < high, ErrorKind::InvalidInput; self)
             }
-            Range::Categorical { choices } => {
+            Range::IntChoices { values } => {
+                track_assert!(!values.is_empty(), ErrorKind::InvalidInput; self);
+                let mut sorted = values.clone();
+                sorted.sort_unstable();
+                sorted.dedup();
+                track_assert_eq!(
+                    sorted.len(),
+                    values.len(),
+                    ErrorKind::InvalidInput,
+                    "Duplicate integer choices";
+                    self
+                );
+            }
+            Range::Categorical { choices } | Range::Ordinal { choices } => {
                 track_assert!(!choices.is_empty(), ErrorKind::InvalidInput; self)
             }
         }
@@ -158,11 +385,38 @@ impl VariableBuilder {
             }
         }
 
+        if let Some(constraint) = &self.constraint {
+            track!(constraint.validate(); self.name)?;
+        }
+
+        if let Some(weights) = &self.weights {
+            let choices = if let Range::Categorical { choices } = &self.range {
+                choices.len()
+            } else {
+                track_panic!(ErrorKind::InvalidInput, "Weights are only allowed for categorical variables"; self);
+            };
+            track_assert_eq!(weights.0.len(), choices, ErrorKind::InvalidInput; self);
+            track_assert!(
+                weights.0.iter().all(|&w| w.is_finite() && w >= 0.0),
+                ErrorKind::InvalidInput,
+                "Weights must be non-negative finite numbers";
+                self
+            );
+            track_assert!(
+                weights.0.iter().any(|&w| w > 0.0),
+                ErrorKind::InvalidInput,
+                "At least one weight must be positive";
+                self
+            );
+        }
+
         Ok(Variable {
             name: self.name,
             range: self.range,
             distribution: self.distribution,
+            weights: self.weights,
             constraint: self.constraint,
+            fidelity: self.fidelity,
         })
     }
 }
@@ -172,7 +426,9 @@ impl From<Variable> for VariableBuilder {
             name: f.name,
             range: f.range,
             distribution: f.distribution,
+            weights: f.weights,
             constraint: f.constraint,
+            fidelity: f.fidelity,
         }
     }
 }
@@ -184,7 +440,11 @@ pub struct Variable {
     range: Range,
     distribution: Distribution,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    weights: Option<CategoricalWeights>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     constraint: Option<Constraint>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    fidelity: bool,
 }
 impl Variable {
     /// Returns the name of this variable.
@@ -202,17 +462,63 @@ impl Variable {
         self.distribution
     }
 
+    /// Returns the inclusive lower bound of this variable that is consistent with its distribution.
+    ///
+    /// For uniform variables, this is identical to `Some(self.range().low())`.
+    /// For log-uniform variables, `None` is returned if the lower bound isn't positive
+    /// because such a bound can't be used in log space (e.g., as the lower limit of a log-scale axis).
+    /// In that case, callers should choose a floor based on their data
+    /// (e.g., the smallest positive value observed).
+    pub fn effective_low(&self) -> Option<f64> {
+        let low = self.range.low();
+        match self.distribution {
+            Distribution::Uniform => Some(low),
+            Distribution::LogUniform if low > 0.0 => Some(low),
+            Distribution::LogUniform => None,
+        }
+    }
+
+    /// Returns the exclusive upper bound of this variable that is consistent with its distribution.
+    ///
+    /// For uniform variables, this is identical to `self.range().high()`.
+    /// For log-uniform variables, the result is never less than `self.effective_low()` (if any).
+    pub fn effective_high(&self) -> f64 {
+        let high = self.range.high();
+        match (self.distribution, self.effective_low()) {
+            (Distribution::LogUniform, Some(low)) => high.max(low),
+            _ => high,
+        }
+    }
+
+    /// Returns the prior weights of the choices of this categorical variable (if any).
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_ref().map(|w| &w.0[..])
+    }
+
     /// Returns the constraint required to evaluate this variable.
     pub fn constraint(&self) -> Option<&Constraint> {
         self.constraint.as_ref()
     }
+
+    /// Returns `true` if this variable is a fidelity (budget) knob.
+    pub fn is_fidelity(&self) -> bool {
+        self.fidelity
+    }
+
+    /// Returns `true` if this is an ordinal variable whose ordering may be ignored.
+    ///
+    /// Solvers that don't have `Capability::Ordinal` can handle such a variable
+    /// as a categorical one that has the same choices.
+    pub fn is_ordinality_ignorable(&self) -> bool {
+        matches!(self.range, Range::Ordinal { .. })
+    }
 }
 
 impl rand::distributions::Distribution<f64> for Variable {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> f64 {
         match &self.range {
-            Range::Continuous { low, high } => match self.distribution {
-                Distribution::Uniform => rng.gen_range(*low..*high),
+            Range::Continuous { low, high, .. } => match self.distribution {
+                Distribution::Uniform => self.range.quantize(rng.gen_range(*low..*high)),
                 Distribution::LogUniform => rng.gen_range(low.log2()..high.log2()).exp2(),
             },
             Range::Discrete { low, high } => match self.distribution {
@@ -222,7 +528,47 @@ impl rand::distributions::Distribution<f64> for Variable {
                     .exp2()
                     .floor(),
             },
-            Range::Categorical { choices } => rng.gen_range(0..choices.len()) as f64,
+            Range::IntChoices { values } => values[rng.gen_range(0..values.len())] as f64,
+            Range::Ordinal { choices } => rng.gen_range(0..choices.len()) as f64,
+            Range::Categorical { choices } => {
+                if let Some(weights) = &self.weights {
+                    let sum = weights.0.iter().sum::<f64>();
+                    let mut r = rng.gen_range(0.0..sum);
+                    for (i, &w) in weights.0.iter().enumerate() {
+                        if r < w {
+                            return i as f64;
+                        }
+                        r -= w;
+                    }
+                    // Only reachable due to rounding errors.
+                    weights.0.iter().rposition(|&w| w > 0.0).unwrap_or(0) as f64
+                } else {
+                    rng.gen_range(0..choices.len()) as f64
+                }
+            }
+        }
+    }
+}
+
+/// Prior weights of the choices of a categorical variable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+struct CategoricalWeights(Vec<f64>);
+impl PartialEq for CategoricalWeights {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|(a, b)| OrderedFloat(*a) == OrderedFloat(*b))
+    }
+}
+impl Eq for CategoricalWeights {}
+impl Hash for CategoricalWeights {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for w in &self.0 {
+            OrderedFloat(*w).hash(state);
         }
     }
 }
@@ -236,6 +582,11 @@ pub enum Distribution {
     LogUniform,
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
 #[allow(clippy::trivially_copy_pass_by_ref)]
 fn is_not_finite(x: &f64) -> bool {
     !x.is_finite()
@@ -264,6 +615,13 @@ pub enum Range {
         /// Upper bound of this range (exclusive).
         #[serde(skip_serializing_if = "is_not_finite", default = "infinity")]
         high: f64,
+
+        /// Quantization step of this range.
+        ///
+        /// If this is specified, the possible values are `low + k * step` (`k = 0, 1, ...`) less than `high`.
+        #[structopt(long)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        step: Option<f64>,
     },
 
     /// Discrete numerical range: `[low..high)`.
@@ -275,11 +633,28 @@ pub enum Range {
         high: i64,
     },
 
+    /// Integer range that consists of the given values.
+    ///
+    /// The value of a parameter is the chosen integer itself (not its index),
+    /// so the numeric ordering of the values is preserved.
+    IntChoices {
+        /// Possible values.
+        values: Vec<i64>,
+    },
+
     /// Categorical range.
     Categorical {
         /// Possible choices.
         choices: Vec<String>,
     },
+
+    /// Ordinal range.
+    ///
+    /// This is the same as `Categorical` except that the choices are ordered (in ascending order).
+    Ordinal {
+        /// Possible choices.
+        choices: Vec<String>,
+    },
 }
 impl Range {
     /// Returns the inclusive lower bound of this range.
@@ -287,7 +662,8 @@ impl Range {
         match self {
             Self::Continuous { low, .. } => *low,
             Self::Discrete { low, .. } => *low as f64,
-            Self::Categorical { .. } => 0.0,
+            Self::IntChoices { values } => values.iter().min().map_or(f64::NAN, |&v| v as f64),
+            Self::Categorical { .. } | Self::Ordinal { .. } => 0.0,
         }
     }
 
@@ -296,29 +672,77 @@ impl Range {
         match self {
             Self::Continuous { high, .. } => *high,
             Self::Discrete { high, .. } => *high as f64,
-            Self::Categorical { choices } => choices.len() as f64,
+            Self::IntChoices { values } => {
+                values.iter().max().map_or(f64::NAN, |&v| v as f64 + 1.0)
+            }
+            Self::Categorical { choices } | Self::Ordinal { choices } => choices.len() as f64,
         }
     }
 
     /// Returns `true` if the given value is contained in this range.
     pub fn contains(&self, v: f64) -> bool {
         match self {
-            Self::Continuous { low, high } => *low <= v && v < *high,
+            Self::Continuous { low, high, step } => {
+                let on_grid = step.is_none_or(|step| {
+                    let k = (v - low) / step;
+                    (k - k.round()).abs() <= 1e-8 * k.abs().max(1.0)
+                });
+                *low <= v && v < *high && on_grid
+            }
             Self::Discrete { low, high } => *low as f64 <= v && v < *high as f64,
-            Self::Categorical { choices } => 0.0 <= v && v < choices.len() as f64,
+            Self::IntChoices { values } => values.iter().any(|&x| x as f64 == v),
+            Self::Categorical { choices } | Self::Ordinal { choices } => {
+                0.0 <= v && v < choices.len() as f64
+            }
+        }
+    }
+
+    /// Rounds the given value to the nearest grid point of this range.
+    ///
+    /// If this is a continuous range that has `step`, the result is the nearest point
+    /// `low + k * step` that is less than `high`. Otherwise, `v` is returned as is.
+    pub fn quantize(&self, v: f64) -> f64 {
+        if let Self::Continuous {
+            low,
+            high,
+            step: Some(step),
+        } = self
+        {
+            let mut x = low + ((v - low) / step).round() * step;
+            if x >= *high {
+                x -= step;
+            }
+            x.max(*low)
+        } else {
+            v
         }
     }
 }
 impl PartialEq for Range {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::Continuous { low: l0, high: h0 }, Self::Continuous { low: l1, high: h1 }) => {
-                OrderedFloat(*l0) == OrderedFloat(*l1) && OrderedFloat(*h0) == OrderedFloat(*h1)
+            (
+                Self::Continuous {
+                    low: l0,
+                    high: h0,
+                    step: s0,
+                },
+                Self::Continuous {
+                    low: l1,
+                    high: h1,
+                    step: s1,
+                },
+            ) => {
+                OrderedFloat(*l0) == OrderedFloat(*l1)
+                    && OrderedFloat(*h0) == OrderedFloat(*h1)
+                    && s0.map(OrderedFloat) == s1.map(OrderedFloat)
             }
             (Self::Discrete { low: l0, high: h0 }, Self::Discrete { low: l1, high: h1 }) => {
                 l0 == l1 && h0 == h1
             }
+            (Self::IntChoices { values: v0 }, Self::IntChoices { values: v1 }) => v0 == v1,
             (Self::Categorical { choices: c0 }, Self::Categorical { choices: c1 }) => c0 == c1,
+            (Self::Ordinal { choices: c0 }, Self::Ordinal { choices: c1 }) => c0 == c1,
             _ => false,
         }
     }
@@ -327,25 +751,39 @@ impl Eq for Range {}
 impl Hash for Range {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            Self::Continuous { low, high } => {
+            Self::Continuous { low, high, step } => {
                 OrderedFloat(*low).hash(state);
                 OrderedFloat(*high).hash(state);
+                if let Some(step) = step {
+                    OrderedFloat(*step).hash(state);
+                }
             }
             Self::Discrete { low, high } => {
                 low.hash(state);
                 high.hash(state);
             }
+            Self::IntChoices { values } => {
+                values.hash(state);
+            }
             Self::Categorical { choices } => {
                 choices.hash(state);
             }
+            Self::Ordinal { choices } => {
+                "ordinal".hash(state);
+                choices.hash(state);
+            }
         }
     }
 }
 
 /// Evaluation constraint.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Constraint {
     lua_script: String,
+
+    // Lazily compiled script that is reused across `is_satisfied` calls.
+    #[serde(skip)]
+    compiled: Mutex<Option<CompiledScript>>,
 }
 impl Constraint {
     /// Makes a new `Constraint` instance.
@@ -353,35 +791,125 @@ impl Constraint {
     /// `lua_script` is the Lua script code that represents the constraint.
     /// In this script, you can access the variables that are located before
     /// the constrainted variable as global variables.
+    /// The constrained variable itself is also accessible when a sampled value is checked
+    /// (and is `nil` when whether the variable is active is decided; see `Domain::sample_params`).
     /// This script must return a boolean value.
     pub fn new(lua_script: &str) -> Self {
         Self {
             lua_script: lua_script.to_owned(),
+            compiled: Mutex::new(None),
         }
     }
 
+    /// Checks that the script of this constraint can be compiled.
+    ///
+    /// If the script has a syntax error, an `ErrorKind::InvalidInput` error that contains
+    /// the location reported by Lua (e.g., `constraint:1: ...`) and the script is returned.
+    pub fn validate(&self) -> Result<()> {
+        let lua = rlua::Lua::new();
+        lua.context(|lua_ctx| track!(compile(lua_ctx, &self.lua_script)).map(|_| ()))
+    }
+
     /// Returns `Ok(true)` if this constraint is satisfied, otherwise `Ok(false)` or an error.
     pub fn is_satisfied(&self, vars: &[Variable], vals: &[f64]) -> Result<bool> {
-        use rlua::Lua;
+        let mut compiled = self.compiled.lock().unwrap_or_else(|e| panic!("{}", e));
+        if compiled
+            .as_ref()
+            .is_none_or(|c| c.lua_script != self.lua_script)
+        {
+            *compiled = Some(track!(CompiledScript::new(&self.lua_script))?);
+        }
+        let compiled = compiled.as_ref().unwrap_or_else(|| unreachable!());
 
-        let lua = Lua::new();
-        lua.context(|lua_ctx| {
+        compiled.lua.context(|lua_ctx| {
             let globals = lua_ctx.globals();
 
-            for (var, &val) in vars.iter().zip(vals.iter()) {
-                if !val.is_finite() {
-                    continue;
-                }
+            for (i, var) in vars.iter().enumerate() {
+                let name = var.name.as_str();
+                let val = match vals.get(i) {
+                    Some(&val) if val.is_finite() => val,
+                    _ => {
+                        // Clears the value set by the previous call.
+                        track!(globals.set(name, rlua::Nil).map_err(Error::from))?;
+                        continue;
+                    }
+                };
 
-                if let Range::Categorical { choices } = &var.range {
+                if let Range::Categorical { choices } | Range::Ordinal { choices } = &var.range {
                     let val = choices[val as usize].as_str();
-                    track!(globals.set(var.name.as_str(), val).map_err(Error::from))?;
+                    track!(globals.set(name, val).map_err(Error::from))?;
                 } else {
-                    track!(globals.set(var.name.as_str(), val).map_err(Error::from))?;
+                    track!(globals.set(name, val).map_err(Error::from))?;
                 }
             }
 
-            lua_ctx.load(&self.lua_script).eval().map_err(Error::from)
+            let function: rlua::Function = track!(lua_ctx
+                .registry_value(&compiled.function)
+                .map_err(Error::from))?;
+            function.call(()).map_err(Error::from)
+        })
+    }
+}
+impl fmt::Debug for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Constraint")
+            .field("lua_script", &self.lua_script)
+            .finish()
+    }
+}
+impl Clone for Constraint {
+    fn clone(&self) -> Self {
+        Self::new(&self.lua_script)
+    }
+}
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.lua_script == other.lua_script
+    }
+}
+impl Eq for Constraint {}
+impl Hash for Constraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.lua_script.hash(state);
+    }
+}
+
+fn compile<'lua>(lua_ctx: rlua::Context<'lua>, lua_script: &str) -> Result<rlua::Function<'lua>> {
+    // Like `rlua::Chunk::eval`, the script is interpreted as an expression first, then as statements.
+    let load = |source: &str| {
+        lua_ctx
+            .load(source)
+            .set_name("=constraint")
+            .and_then(|chunk| chunk.into_function())
+    };
+    match load(&format!("return {}", lua_script)).or_else(|_| load(lua_script)) {
+        Ok(function) => Ok(function),
+        Err(rlua::Error::SyntaxError { message, .. }) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Invalid constraint script: {} (script={:?})",
+            message,
+            lua_script
+        ),
+        Err(e) => Err(track!(Error::from(e))),
+    }
+}
+
+struct CompiledScript {
+    lua_script: String,
+    lua: rlua::Lua,
+    function: rlua::RegistryKey,
+}
+impl CompiledScript {
+    fn new(lua_script: &str) -> Result<Self> {
+        let lua = rlua::Lua::new();
+        let function = track!(lua.context(|lua_ctx| {
+            let function = track!(compile(lua_ctx, lua_script))?;
+            lua_ctx.create_registry_value(function).map_err(Error::from)
+        }))?;
+        Ok(Self {
+            lua_script: lua_script.to_owned(),
+            lua,
+            function,
         })
     }
 }
@@ -410,4 +938,324 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn constraint_validate_test() -> trackable::result::TopLevelResult {
+        track!(Constraint::new("(a + b) < 2").validate())?;
+        track!(Constraint::new("if a then return true else return false end").validate())?;
+
+        let e = Constraint::new("a <").validate().err().unwrap();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        let message = e.to_string();
+        assert!(message.contains("constraint:1:"), "{}", message);
+        assert!(message.contains("\"a <\""), "{}", message);
+
+        let e = var("b")
+            .continuous(0.0, 1.0)
+            .constraint(Constraint::new("a <"))
+            .finish()
+            .err()
+            .unwrap();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constraint_repeated_evaluation_test() -> trackable::result::TopLevelResult {
+        let vars = vec![
+            var("a").continuous(-10.0, 10.0).finish()?,
+            var("b").discrete(0, 5).finish()?,
+        ];
+
+        // With a fresh Lua VM per call, this took more than ten seconds in debug builds.
+        let constraint = Constraint::new("(a + b) < 2");
+        let start = std::time::Instant::now();
+        for i in 0..100_000 {
+            let a = (i % 20) as f64 - 10.0;
+            let satisfied = track!(constraint.is_satisfied(&vars, &[a, 1.0]))?;
+            assert_eq!(satisfied, a + 1.0 < 2.0);
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        // Values set by the previous calls must not be visible.
+        let constraint = Constraint::new("a == nil");
+        assert!(!track!(constraint.is_satisfied(&vars, &[0.0, 1.0]))?);
+        assert!(track!(constraint.is_satisfied(&vars, &[f64::NAN, 1.0]))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_params_test() -> trackable::result::TopLevelResult {
+        let domain = track!(Domain::new(vec![
+            var("a").continuous(-10.0, 10.0),
+            var("b").discrete(0, 5),
+            var("c")
+                .categorical(["foo", "bar"])
+                .constraint(Constraint::new("b == 1")),
+        ]))?;
+
+        assert!(domain.contains(&[0.5, 1.0, 1.0]));
+        assert!(domain.contains(&[-10.0, 4.0, f64::NAN]));
+        assert!(!domain.contains(&[10.0, 1.0, 0.0]));
+        assert!(!domain.contains(&[0.5, 1.5, 0.0]));
+        assert!(!domain.contains(&[0.5, 1.0, 2.0]));
+        assert!(!domain.contains(&[f64::NAN, 1.0, 0.0]));
+        assert!(!domain.contains(&[0.5, 1.0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn continuous_step_test() -> trackable::result::TopLevelResult {
+        use rand::distributions::Distribution as _;
+        use rand::SeedableRng;
+
+        assert!(var("a").continuous_step(0.0, 1.0, 0.0).finish().is_err());
+        assert!(var("a")
+            .continuous_step(1.0, 10.0, 0.5)
+            .log_uniform()
+            .finish()
+            .is_err());
+
+        let v = var("a").continuous_step(0.0, 1.0, 0.25).finish()?;
+        assert!(v.range().contains(0.75));
+        assert!(!v.range().contains(0.7));
+        assert!(!v.range().contains(1.0));
+        assert_eq!(v.range().quantize(0.9), 0.75);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert!(v.range().contains(v.sample(&mut rng)));
+        }
+
+        let json = serde_json::to_string(&v).map_err(Error::from)?;
+        let restored: Variable = serde_json::from_str(&json).map_err(Error::from)?;
+        assert_eq!(restored, v);
+        assert!(
+            !serde_json::to_string(&var("b").continuous(0.0, 1.0).finish()?)
+                .map_err(Error::from)?
+                .contains("step")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn int_choices_test() -> trackable::result::TopLevelResult {
+        use rand::distributions::Distribution as _;
+        use rand::SeedableRng;
+
+        assert!(var("a").int_choices(vec![]).finish().is_err());
+        assert!(var("a").int_choices(vec![16, 32, 16]).finish().is_err());
+
+        let v = var("a").int_choices(vec![16, 32, 64, 128]).finish()?;
+        assert_eq!(v.range().low(), 16.0);
+        assert_eq!(v.range().high(), 129.0);
+        assert!(v.range().contains(64.0));
+        assert!(!v.range().contains(48.0));
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert!(v.range().contains(v.sample(&mut rng)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn ordinal_test() -> trackable::result::TopLevelResult {
+        use crate::problem::ProblemSpecBuilder;
+        use crate::solver::Capability;
+
+        let ordinal = var("a").ordinal(["low", "medium", "high"]).finish()?;
+        let categorical = var("a").categorical(["low", "medium", "high"]).finish()?;
+        assert!(ordinal.is_ordinality_ignorable());
+        assert!(!categorical.is_ordinality_ignorable());
+        assert_ne!(ordinal, categorical);
+
+        let json = serde_json::to_string(&ordinal).map_err(Error::from)?;
+        assert_ne!(
+            json,
+            serde_json::to_string(&categorical).map_err(Error::from)?
+        );
+        let restored: Variable = serde_json::from_str(&json).map_err(Error::from)?;
+        assert_eq!(restored, ordinal);
+
+        let spec = ProblemSpecBuilder::new("foo")
+            .param(ordinal.into())
+            .value(var("v"))
+            .finish()?;
+        let requirements = spec.requirements();
+        assert!(requirements.is_capable(Capability::Ordinal));
+        assert!(requirements.is_capable(Capability::Categorical));
+        assert!(Capability::Ordinal.is_advisory());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sample_params_test() -> trackable::result::TopLevelResult {
+        use rand::SeedableRng;
+
+        let domain = track!(Domain::new(vec![
+            var("a").continuous(-10.0, 10.0),
+            var("b").discrete(0, 5),
+            var("c")
+                .categorical(["foo", "bar", "baz"])
+                .constraint(Constraint::new("(a + b) < 2")),
+        ]))?;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let (mut active, mut inactive) = (0, 0);
+        for _ in 0..100 {
+            let params = track!(domain.sample_params(&mut rng))?;
+            let params = params.get();
+            track!(domain.validate_params(params))?;
+            if params[0] + params[1] < 2.0 {
+                assert!(!params[2].is_nan());
+                active += 1;
+            } else {
+                assert!(params[2].is_nan());
+                inactive += 1;
+            }
+        }
+        assert!(active > 0);
+        assert!(inactive > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sample_params_retry_test() -> trackable::result::TopLevelResult {
+        use rand::SeedableRng;
+
+        // `b` is always active and its values are restricted by the constraint.
+        let domain = track!(Domain::new(vec![
+            var("a").continuous(-10.0, 10.0),
+            var("b")
+                .discrete(0, 5)
+                .constraint(Constraint::new("b == nil or (a + b) < 2")),
+            var("c").categorical(["foo", "bar", "baz"]),
+        ]))?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut sampled = 0;
+        while sampled < 100 {
+            let params = match domain.sample_params(&mut rng) {
+                Ok(params) => params,
+                Err(e) => {
+                    // No value of `b` satisfies the constraint if `a` is greater than or equal to `2`.
+                    assert_eq!(*e.kind(), ErrorKind::UnevaluableParams);
+                    continue;
+                }
+            };
+            let params = params.get();
+            track!(domain.validate_params(params))?;
+            assert!(params[0] + params[1] < 2.0, "{:?}", params);
+            sampled += 1;
+        }
+
+        // The attempts are exhausted.
+        let domain = track!(Domain::new(vec![
+            var("a").continuous(-10.0, 10.0),
+            var("b")
+                .discrete(0, 5)
+                .constraint(Constraint::new("b == nil or b > 5")),
+        ]))?;
+        let e = domain
+            .sample_params(&mut rng)
+            .expect_err("should be exhausted");
+        assert_eq!(*e.kind(), ErrorKind::UnevaluableParams);
+
+        Ok(())
+    }
+
+    #[test]
+    fn effective_bounds_test() -> trackable::result::TopLevelResult {
+        let uniform = var("a").continuous(0.0, 10.0).finish()?;
+        assert_eq!(uniform.effective_low(), Some(uniform.range().low()));
+        assert_eq!(uniform.effective_high(), uniform.range().high());
+
+        let log_uniform = var("a").continuous(0.5, 10.0).log_uniform().finish()?;
+        assert_eq!(log_uniform.effective_low(), Some(0.5));
+        assert_eq!(log_uniform.effective_high(), 10.0);
+
+        // Deserialized variables aren't validated by `VariableBuilder::finish`.
+        let log_uniform: Variable = serde_json::from_str(
+            r#"{"name":"a","range":{"type":"CONTINUOUS","high":10.0},"distribution":"LOG_UNIFORM"}"#,
+        )
+        .map_err(Error::from)?;
+        assert_eq!(log_uniform.range().low(), f64::NEG_INFINITY);
+        assert_eq!(log_uniform.effective_low(), None);
+        assert_eq!(log_uniform.effective_high(), 10.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fidelity_test() -> trackable::result::TopLevelResult {
+        use crate::problem::ProblemSpecBuilder;
+        use crate::solver::Capability;
+
+        let v = var("epochs").discrete(1, 100).fidelity().finish()?;
+        assert!(v.is_fidelity());
+        let json = serde_json::to_string(&v).map_err(Error::from)?;
+        let restored: Variable = serde_json::from_str(&json).map_err(Error::from)?;
+        assert_eq!(restored, v);
+
+        // Old specs that don't have the field are still parsed.
+        let old: Variable = serde_json::from_str(
+            r#"{"name":"x","range":{"type":"DISCRETE","low":1,"high":100},"distribution":"UNIFORM"}"#,
+        )
+        .map_err(Error::from)?;
+        assert!(!old.is_fidelity());
+        assert!(!serde_json::to_string(&old)
+            .map_err(Error::from)?
+            .contains("fidelity"));
+
+        let spec = ProblemSpecBuilder::new("foo")
+            .param(old.into())
+            .value(var("v"))
+            .finish()?;
+        assert!(!spec.requirements().is_capable(Capability::MultiFidelity));
+
+        let spec = ProblemSpecBuilder::new("foo")
+            .param(v.into())
+            .value(var("v"))
+            .finish()?;
+        assert!(spec.requirements().is_capable(Capability::MultiFidelity));
+
+        Ok(())
+    }
+
+    #[test]
+    fn categorical_weighted_test() -> trackable::result::TopLevelResult {
+        use rand::distributions::Distribution as _;
+        use rand::SeedableRng;
+
+        assert!(var("a")
+            .categorical_weighted([("foo", 1.0), ("bar", -1.0)])
+            .finish()
+            .is_err());
+        assert!(var("a")
+            .categorical_weighted([("foo", 0.0), ("bar", 0.0)])
+            .finish()
+            .is_err());
+
+        let v = var("a")
+            .categorical_weighted([("foo", 0.0), ("bar", 3.0), ("baz", 1.0)])
+            .finish()?;
+        assert_eq!(v.weights(), Some(&[0.0, 3.0, 1.0][..]));
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..1000 {
+            counts[v.sample(&mut rng) as usize] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert!(counts[1] > counts[2]);
+
+        Ok(())
+    }
 }
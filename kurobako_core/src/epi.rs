@@ -1,4 +1,6 @@
 //! **E**xternal **P**rogram **I**nterface.
 pub mod channel;
+pub mod jsonrpc;
 pub mod problem;
 pub mod solver;
+pub mod transport;
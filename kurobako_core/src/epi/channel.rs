@@ -1,13 +1,95 @@
 //! The receiving and sending channels used to communicate with the external problems that support EPI.
-use crate::{Error, Result};
+use crate::epi::jsonrpc::JsonRpcCodec;
+use crate::{Error, ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Protocol used to communicate with external programs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Kurobako specific protocol (each line is a JSON object that has `type` field).
+    #[default]
+    Kurobako,
+
+    /// JSON-RPC 2.0 (see the [`jsonrpc`](crate::epi::jsonrpc) module for the methods).
+    JsonRpc,
+}
+impl Protocol {
+    /// Possible values.
+    pub const POSSIBLE_VALUES: &'static [&'static str] = &["kurobako", "jsonrpc"];
+
+    /// Makes a new framing of this protocol.
+    ///
+    /// The sender and receiver of a connection have to share the same framing (or its clones).
+    pub fn framing(self) -> Framing {
+        match self {
+            Self::Kurobako => Framing::Kurobako,
+            Self::JsonRpc => Framing::JsonRpc(JsonRpcCodec::new()),
+        }
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn is_kurobako(&self) -> bool {
+        *self == Self::Kurobako
+    }
+}
+impl FromStr for Protocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "kurobako" => Ok(Self::Kurobako),
+            "jsonrpc" => Ok(Self::JsonRpc),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown protocol: {:?}", s),
+        }
+    }
+}
+
+/// Framing of the messages exchanged with an external program.
+#[derive(Debug, Clone, Default)]
+pub enum Framing {
+    /// Messages are sent as they are.
+    #[default]
+    Kurobako,
+
+    /// Messages are converted to and from JSON-RPC objects.
+    JsonRpc(JsonRpcCodec),
+}
+impl Framing {
+    fn encode<T: Serialize>(&self, message: &T) -> Result<String> {
+        match self {
+            Self::Kurobako => track!(serde_json::to_string(message).map_err(Error::from)),
+            Self::JsonRpc(codec) => {
+                let message = track!(serde_json::to_value(message).map_err(Error::from))?;
+                let object = track!(codec.encode(message))?;
+                track!(serde_json::to_string(&object).map_err(Error::from))
+            }
+        }
+    }
+
+    fn decode<T: for<'a> Deserialize<'a>>(&self, line: &str) -> Result<T> {
+        match self {
+            Self::Kurobako => track!(serde_json::from_str(line).map_err(Error::from); line),
+            Self::JsonRpc(codec) => {
+                let object = track!(serde_json::from_str(line).map_err(Error::from); line)?;
+                let message = track!(codec.decode(object); line)?;
+                track!(serde_json::from_value(message).map_err(Error::from); line)
+            }
+        }
+    }
+}
 
 /// Sending channel.
 pub struct MessageSender<T, W: Write> {
     writer: BufWriter<W>,
+    framing: Framing,
     _message: PhantomData<T>,
 }
 impl<T, W> MessageSender<T, W>
@@ -17,16 +99,22 @@ where
 {
     /// Makes a new `MessageSender` instance.
     pub fn new(writer: W) -> Self {
+        Self::with_framing(writer, Framing::Kurobako)
+    }
+
+    /// Makes a new `MessageSender` instance that uses the given framing.
+    pub fn with_framing(writer: W, framing: Framing) -> Self {
         Self {
             writer: BufWriter::new(writer),
+            framing,
             _message: PhantomData,
         }
     }
 
     /// Sends a message.
     pub fn send(&mut self, message: &T) -> Result<()> {
-        track!(serde_json::to_writer(&mut self.writer, message).map_err(Error::from))?;
-        track!(writeln!(self.writer).map_err(Error::from))?;
+        let line = track!(self.framing.encode(message))?;
+        track!(writeln!(self.writer, "{}", line).map_err(Error::from))?;
         track!(self.writer.flush().map_err(Error::from))?;
         Ok(())
     }
@@ -40,6 +128,7 @@ impl<T, W: Write> fmt::Debug for MessageSender<T, W> {
 /// Receiving channel.
 pub struct MessageReceiver<T, R: Read> {
     reader: BufReader<R>,
+    framing: Framing,
     _message: PhantomData<T>,
 }
 impl<T, R> MessageReceiver<T, R>
@@ -49,18 +138,21 @@ where
 {
     /// Makes a new `MessageReceiver` instance.
     pub fn new(reader: R) -> Self {
+        Self::with_framing(reader, Framing::Kurobako)
+    }
+
+    /// Makes a new `MessageReceiver` instance that uses the given framing.
+    pub fn with_framing(reader: R, framing: Framing) -> Self {
         Self {
             reader: BufReader::new(reader),
+            framing,
             _message: PhantomData,
         }
     }
 
     /// Receives a message.
     pub fn recv(&mut self) -> Result<T> {
-        let mut line = String::new();
-        track!(self.reader.read_line(&mut line).map_err(Error::from))?;
-        let message = track!(serde_json::from_str(&line).map_err(Error::from); line)?;
-        Ok(message)
+        track!(read_message(&mut self.reader, &self.framing))
     }
 }
 impl<T, R: Read> fmt::Debug for MessageReceiver<T, R> {
@@ -68,3 +160,76 @@ impl<T, R: Read> fmt::Debug for MessageReceiver<T, R> {
         write!(f, "MessageReceiver {{ .. }}")
     }
 }
+
+/// Receiving channel that reads messages in a background thread.
+///
+/// Unlike `MessageReceiver`, this can give up waiting for a message after a timeout.
+/// The background thread exits when the reader reaches EOS or this channel is dropped
+/// (and a message arrives after that).
+pub struct BackgroundMessageReceiver<T> {
+    rx: mpsc::Receiver<Result<T>>,
+}
+impl<T> BackgroundMessageReceiver<T>
+where
+    T: for<'a> Deserialize<'a> + Send + 'static,
+{
+    /// Makes a new `BackgroundMessageReceiver` instance.
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Self::with_framing(reader, Framing::Kurobako)
+    }
+
+    /// Makes a new `BackgroundMessageReceiver` instance that uses the given framing.
+    pub fn with_framing<R>(reader: R, framing: Framing) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                let message = read_message(&mut reader, &framing);
+                let failed = message.is_err();
+                if tx.send(message).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Self { rx }
+    }
+
+    /// Receives a message.
+    pub fn recv(&mut self) -> Result<T> {
+        match self.rx.recv() {
+            Ok(message) => track!(message),
+            Err(_) => track_panic!(ErrorKind::UnexpectedEos),
+        }
+    }
+
+    /// Receives a message, or returns `Ok(None)` if no message arrives within the given timeout.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<T>> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(message) => track!(message).map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => track_panic!(ErrorKind::UnexpectedEos),
+        }
+    }
+}
+impl<T> fmt::Debug for BackgroundMessageReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BackgroundMessageReceiver {{ .. }}")
+    }
+}
+
+fn read_message<T, R>(reader: &mut BufReader<R>, framing: &Framing) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let mut line = String::new();
+    let size = track!(reader.read_line(&mut line).map_err(Error::from))?;
+    track_assert_ne!(size, 0, ErrorKind::UnexpectedEos);
+    track!(framing.decode(&line))
+}
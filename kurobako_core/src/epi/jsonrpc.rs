@@ -0,0 +1,302 @@
+//! [JSON-RPC 2.0] framing of the EPI messages (enabled by `--protocol jsonrpc`).
+//!
+//! Each line is a JSON-RPC object, kurobako is the client and the external program is the server.
+//! The messages of the default protocol are mapped to JSON-RPC as follows:
+//!
+//! - `*_CALL` messages are sent as requests and the external program must respond to them,
+//! - `*_CAST` messages are sent as notifications (i.e., without `id`) and must not be responded,
+//! - the fields of a message (except `type`) are given as the named `params` of the request
+//!   (or the `result` object of the response).
+//!
+//! Just after being launched, the external program sends a `specification` notification
+//! that has the specification of the problem (or solver) as `spec` parameter.
+//!
+//! # Problem methods
+//!
+//! | method             | kind         | params                                       | result                       |
+//! |--------------------|--------------|----------------------------------------------|------------------------------|
+//! | `specification`    | notification | `spec: ProblemSpec`                          | -                            |
+//! | `create_problem`   | notification | `problem_id: u64, random_seed: u64`          | -                            |
+//! | `drop_problem`     | notification | `problem_id: u64`                            | -                            |
+//! | `create_evaluator` | request      | `problem_id: u64, evaluator_id: u64, params` | `{}` or `null`               |
+//! | `drop_evaluator`   | notification | `evaluator_id: u64`                          | -                            |
+//! | `evaluate`         | request      | `evaluator_id: u64, next_step: u64`          | `current_step: u64, values`  |
+//!
+//! # Solver methods
+//!
+//! | method          | kind         | params                                          | result                                 |
+//! |-----------------|--------------|-------------------------------------------------|----------------------------------------|
+//! | `specification` | notification | `spec: SolverSpec`                              | -                                      |
+//! | `create_solver` | notification | `solver_id: u64, random_seed: u64, problem`     | -                                      |
+//! | `drop_solver`   | notification | `solver_id: u64`                                | -                                      |
+//! | `ask`           | request      | `solver_id: u64, next_trial_id: u64`            | `trial: NextTrial, next_trial_id: u64` |
+//! | `tell`          | request      | `solver_id: u64, trial: EvaluatedTrial`         | `{}` or `null`                         |
+//! | `tell_prior`    | request      | `solver_id: u64, trial_id: u64, params, values` | `{}` or `null`                         |
+//!
+//! The results of `evaluate` and `ask` can have an optional `unseeded_rng_used: bool` field
+//! which tells kurobako that random numbers not derived from `random_seed` were used.
+//!
+//! # Errors
+//!
+//! A request can fail with an error response.
+//! The kind of the error (e.g., `"UNEVALUABLE_PARAMS"`) can be given as `kind` field of `data`
+//! (`"OTHER"` is used if it's omitted), and `message` is included in the error reported by kurobako.
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "id": 3, "error": {"code": -32000, "message": "...", "data": {"kind": "UNEVALUABLE_PARAMS"}}}
+//! ```
+//!
+//! [JSON-RPC 2.0]: https://www.jsonrpc.org/specification
+use crate::{Error, ErrorKind, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::{Arc, Mutex};
+
+const VERSION: &str = "2.0";
+
+/// Codec that converts the EPI messages to and from JSON-RPC objects.
+///
+/// A codec instance has the states of a connection (e.g., the IDs of the pending requests),
+/// so the sender and receiver of a connection have to share the same instance (or its clones).
+#[derive(Debug, Clone, Default)]
+pub struct JsonRpcCodec {
+    next_id: Arc<AtomicU64>,
+
+    // Request ID => method name.
+    pending: Arc<Mutex<HashMap<u64, String>>>,
+}
+impl JsonRpcCodec {
+    /// Makes a new `JsonRpcCodec` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts a message (serialized as a JSON object that has `type` field) to a JSON-RPC object.
+    pub fn encode(&self, message: Value) -> Result<Value> {
+        let (ty, params) = track!(split_type(message))?;
+        if let Some(name) = ty.strip_suffix("_CALL") {
+            let method = name.to_ascii_lowercase();
+            let id = self.next_id.fetch_add(1, atomic::Ordering::SeqCst);
+            let mut pending = track!(self.pending.lock().map_err(Error::from))?;
+            pending.insert(id, method.clone());
+            Ok(json!({"jsonrpc": VERSION, "id": id, "method": method, "params": params}))
+        } else if let Some(name) = ty.strip_suffix("_CAST") {
+            let method = if name.ends_with("_SPEC") {
+                "specification".to_owned()
+            } else {
+                name.to_ascii_lowercase()
+            };
+            Ok(json!({"jsonrpc": VERSION, "method": method, "params": params}))
+        } else {
+            track_panic!(
+                ErrorKind::Bug,
+                "JSON-RPC clients can't send replies: {:?}",
+                ty
+            );
+        }
+    }
+
+    /// Converts a JSON-RPC object to a message (a JSON object that has `type` field).
+    pub fn decode(&self, object: Value) -> Result<Value> {
+        let mut object = match object {
+            Value::Object(object) => object,
+            _ => track_panic!(ErrorKind::InvalidInput, "Not a JSON-RPC object: {}", object),
+        };
+        track_assert_eq!(
+            object.get("jsonrpc").and_then(Value::as_str),
+            Some(VERSION),
+            ErrorKind::InvalidInput
+        );
+
+        if let Some(method) = object.remove("method") {
+            let method = track_assert_some!(method.as_str(), ErrorKind::InvalidInput);
+            let ty = format!("{}_CAST", method.to_ascii_uppercase());
+            let params = object.remove("params").unwrap_or(Value::Null);
+            return track!(join_type(ty, params));
+        }
+
+        let id = object.get("id").and_then(Value::as_u64);
+        let id = track_assert_some!(id, ErrorKind::InvalidInput; object);
+        let method = track!(self.pending.lock().map_err(Error::from))?.remove(&id);
+        let method = track_assert_some!(
+            method,
+            ErrorKind::InvalidInput,
+            "Unknown request ID: {}",
+            id
+        );
+
+        if let Some(error) = object.remove("error") {
+            let kind = error
+                .pointer("/data/kind")
+                .cloned()
+                .unwrap_or_else(|| json!(ErrorKind::Other));
+            let message = error.get("message").cloned().unwrap_or(Value::Null);
+            Ok(json!({"type": "ERROR_REPLY", "kind": kind, "message": message}))
+        } else {
+            let result = object.remove("result").unwrap_or(Value::Null);
+            let ty = format!("{}_REPLY", method.to_ascii_uppercase());
+            track!(join_type(ty, result))
+        }
+    }
+}
+
+fn split_type(message: Value) -> Result<(String, Value)> {
+    let mut message = match message {
+        Value::Object(message) => message,
+        _ => track_panic!(ErrorKind::Bug, "Not a message: {}", message),
+    };
+    let ty = message
+        .remove("type")
+        .and_then(|ty| ty.as_str().map(str::to_owned));
+    let ty = track_assert_some!(ty, ErrorKind::Bug; message);
+    Ok((ty, Value::Object(message)))
+}
+
+fn join_type(ty: String, fields: Value) -> Result<Value> {
+    let mut message = match fields {
+        Value::Object(fields) => fields,
+        Value::Null => Map::new(),
+        _ => track_panic!(ErrorKind::InvalidInput, "Not a JSON object: {}", fields),
+    };
+    message.insert("type".to_owned(), Value::String(ty));
+    Ok(Value::Object(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain;
+    use crate::epi::channel::{MessageReceiver, MessageSender, Protocol};
+    use crate::epi::solver::SolverMessage;
+    use crate::problem::ProblemSpecBuilder;
+    use crate::solver::SolverSpecBuilder;
+    use crate::trial::{EvaluatedTrial, Params, TrialId, Values};
+    use std::io::{self, BufRead as _, BufReader, Write as _};
+    use std::thread;
+    use trackable::result::TopLevelResult;
+
+    // A JSON-RPC solver that asks `x=0.5`, accepts priors and rejects all tells.
+    fn mock_peer(reader: io::PipeReader, mut writer: io::PipeWriter) -> Result<Vec<Value>> {
+        let spec = SolverSpecBuilder::new("mock").finish();
+        let notification =
+            json!({"jsonrpc": "2.0", "method": "specification", "params": {"spec": spec}});
+        track!(writeln!(writer, "{}", notification).map_err(Error::from))?;
+
+        let mut received = Vec::new();
+        for line in BufReader::new(reader).lines() {
+            let line = track!(line.map_err(Error::from))?;
+            let request: Value = track!(serde_json::from_str(&line).map_err(Error::from))?;
+            let response = match request["method"].as_str() {
+                Some("ask") => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {"trial": {"id": 0, "params": [0.5], "next_step": 1}, "next_trial_id": 1}
+                }),
+                Some("tell_prior") => json!({"jsonrpc": "2.0", "id": request["id"], "result": {}}),
+                Some("tell") => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32000, "message": "rejected", "data": {"kind": "INVALID_INPUT"}}
+                }),
+                _ => Value::Null,
+            };
+            received.push(request);
+            if !response.is_null() {
+                track!(writeln!(writer, "{}", response).map_err(Error::from))?;
+            }
+        }
+        Ok(received)
+    }
+
+    #[test]
+    fn jsonrpc_round_trip_works() -> TopLevelResult {
+        let (reader, peer_writer) = io::pipe().map_err(Error::from)?;
+        let (peer_reader, writer) = io::pipe().map_err(Error::from)?;
+        let peer = thread::spawn(move || mock_peer(peer_reader, peer_writer));
+
+        let framing = Protocol::JsonRpc.framing();
+        let mut tx = MessageSender::with_framing(writer, framing.clone());
+        let mut rx = MessageReceiver::<SolverMessage, _>::with_framing(reader, framing);
+
+        match rx.recv()? {
+            SolverMessage::SolverSpecCast { spec } => assert_eq!(spec.name, "mock"),
+            m => panic!("unexpected message: {:?}", m),
+        }
+
+        let problem = ProblemSpecBuilder::new("foo")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y"))
+            .finish()?;
+        tx.send(&SolverMessage::CreateSolverCast {
+            solver_id: 0,
+            random_seed: 1,
+            problem,
+        })?;
+
+        tx.send(&SolverMessage::AskCall {
+            solver_id: 0,
+            next_trial_id: 0,
+        })?;
+        match rx.recv()? {
+            SolverMessage::AskReply {
+                trial,
+                next_trial_id,
+                ..
+            } => {
+                assert_eq!(trial.params.get(), [0.5]);
+                assert_eq!(next_trial_id, 1);
+            }
+            m => panic!("unexpected message: {:?}", m),
+        }
+
+        tx.send(&SolverMessage::TellPriorCall {
+            solver_id: 0,
+            trial_id: TrialId::new(1),
+            params: Params::new(vec![0.25]),
+            values: Values::new(vec![2.0]),
+        })?;
+        match rx.recv()? {
+            SolverMessage::TellPriorReply => {}
+            m => panic!("unexpected message: {:?}", m),
+        }
+
+        let trial = EvaluatedTrial {
+            id: TrialId::new(0),
+            values: Values::new(vec![1.0]),
+            current_step: 1,
+        };
+        tx.send(&SolverMessage::TellCall {
+            solver_id: 0,
+            trial,
+        })?;
+        match rx.recv()? {
+            SolverMessage::ErrorReply { kind, message } => {
+                assert_eq!(kind, ErrorKind::InvalidInput);
+                assert_eq!(message.as_deref(), Some("rejected"));
+            }
+            m => panic!("unexpected message: {:?}", m),
+        }
+
+        std::mem::drop(tx);
+        let received = peer.join().expect("mock peer panicked")?;
+        let methods = received
+            .iter()
+            .map(|r| (r["method"].as_str(), r.get("id").and_then(Value::as_u64)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            methods,
+            [
+                (Some("create_solver"), None),
+                (Some("ask"), Some(0)),
+                (Some("tell_prior"), Some(1)),
+                (Some("tell"), Some(2))
+            ]
+        );
+        assert_eq!(received[0]["params"]["random_seed"], 1);
+        assert_eq!(received[2]["params"]["trial_id"], 1);
+        assert_eq!(received[2]["params"]["params"], json!([0.25]));
+        assert_eq!(received[3]["params"]["trial"]["values"], json!([1.0]));
+        Ok(())
+    }
+}
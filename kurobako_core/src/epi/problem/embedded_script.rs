@@ -1,7 +1,9 @@
+use crate::epi::channel::Protocol;
 use crate::epi::problem::{
     ExternalProgramEvaluator, ExternalProgramProblem, ExternalProgramProblemFactory,
     ExternalProgramProblemRecipe,
 };
+use crate::epi::transport::Transport;
 use crate::problem::{Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec};
 use crate::registry::FactoryRegistry;
 use crate::rng::ArcRng;
@@ -28,6 +30,12 @@ pub struct EmbeddedScriptProblemRecipe {
 
     /// Command line arguments that are passed to the script.
     pub args: Vec<String>,
+
+    /// Timeout in seconds to wait for each response from the script
+    /// (see `ExternalProgramProblemRecipe::epi_timeout`).
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epi_timeout: Option<u64>,
 }
 impl ProblemRecipe for EmbeddedScriptProblemRecipe {
     type Factory = EmbeddedScriptProblemFactory;
@@ -60,7 +68,13 @@ impl ProblemRecipe for EmbeddedScriptProblemRecipe {
         };
 
         let args = self.args.clone();
-        let eppr = ExternalProgramProblemRecipe { path, args };
+        let eppr = ExternalProgramProblemRecipe {
+            path,
+            args,
+            epi_timeout: self.epi_timeout,
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+        };
         let inner = track!(eppr.create_factory(registry))?;
 
         Ok(EmbeddedScriptProblemFactory { inner })
@@ -1,5 +1,6 @@
-use crate::epi::channel::{MessageReceiver, MessageSender};
+use crate::epi::channel::{BackgroundMessageReceiver, MessageSender, Protocol};
 use crate::epi::problem::ProblemMessage;
+use crate::epi::transport::{BoxWriter, Connection, Transport};
 use crate::problem::{Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec};
 use crate::registry::FactoryRegistry;
 use crate::rng::{ArcRng, Rng as _};
@@ -9,10 +10,11 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::cell::RefCell;
 use std::path::PathBuf;
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::process::{Child, Command};
 use std::sync::atomic::{self, AtomicU64};
 use std::sync::{Arc, Mutex};
 use std::thread_local;
+use std::time::Duration;
 use structopt::StructOpt;
 
 thread_local! {
@@ -29,33 +31,68 @@ pub struct ExternalProgramProblemRecipe {
 
     /// The command line arguments that are passed to the program.
     pub args: Vec<String>,
+
+    /// Timeout in seconds to wait for each response from the program.
+    ///
+    /// If the program doesn't respond within this timeout (e.g., it hangs in an evaluation),
+    /// it's killed and the study fails with an error.
+    /// If this is omitted, the responses are waited forever.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epi_timeout: Option<u64>,
+
+    /// Protocol used to communicate with the program.
+    #[structopt(
+        long,
+        default_value = "kurobako",
+        possible_values = Protocol::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "Protocol::is_kurobako")]
+    pub protocol: Protocol,
+
+    /// Transport used to communicate with the program.
+    #[structopt(
+        long,
+        default_value = "stdio",
+        possible_values = Transport::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "Transport::is_stdio")]
+    pub transport: Transport,
 }
 impl ExternalProgramProblemRecipe {
     fn create_new_factory(
         &self,
         _registry: &FactoryRegistry,
     ) -> Result<ExternalProgramProblemFactory> {
-        let mut child = track!(Command::new(&self.path)
-            .args(&self.args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .map_err(Error::from))?;
-
-        let stdin = track_assert_some!(child.stdin.take(), ErrorKind::IoError);
-        let stdout = track_assert_some!(child.stdout.take(), ErrorKind::IoError);
-
-        let tx = MessageSender::new(stdin);
-        let mut rx = MessageReceiver::new(stdout);
-        let spec = match track!(rx.recv())? {
+        let Connection {
+            child,
+            reader,
+            writer,
+        } = track!(self
+            .transport
+            .spawn(Command::new(&self.path).args(&self.args)))?;
+
+        let process = Arc::new(ChildProcess {
+            child: Mutex::new(child),
+            command_line: self.command_line(),
+            timeout: self.epi_timeout.map(Duration::from_secs),
+        });
+        let framing = self.protocol.framing();
+        let tx = MessageSender::with_framing(writer, framing.clone());
+        let mut rx = BackgroundMessageReceiver::with_framing(reader, framing);
+        let mut spec = match track!(process.recv(&mut rx))? {
             ProblemMessage::ProblemSpecCast { spec } => spec,
             m => track_panic!(ErrorKind::InvalidInput, "Unexpected message: {:?}", m),
         };
 
+        // All the evaluations of this factory are serialized over the connection to the process.
+        spec.attrs
+            .insert("concurrent".to_owned(), "false".to_owned());
+
         Ok(ExternalProgramProblemFactory(Arc::new(
             ExternalProgramProblemFactoryInner {
                 spec,
-                child,
+                process,
                 tx: Arc::new(Mutex::new(tx)),
                 rx: Arc::new(Mutex::new(rx)),
                 next_problem_id: AtomicU64::new(0),
@@ -70,8 +107,26 @@ impl ExternalProgramProblemRecipe {
         for arg in &self.args {
             hasher.update(arg.as_bytes());
         }
+        if let Some(timeout) = self.epi_timeout {
+            hasher.update(timeout.to_le_bytes());
+        }
+        if !self.protocol.is_kurobako() {
+            hasher.update(b"jsonrpc");
+        }
+        if !self.transport.is_stdio() {
+            hasher.update(b"unix");
+        }
         hasher.finalize().to_vec()
     }
+
+    fn command_line(&self) -> String {
+        let mut command_line = self.path.to_string_lossy().into_owned();
+        for arg in &self.args {
+            command_line.push(' ');
+            command_line.push_str(arg);
+        }
+        command_line
+    }
 }
 impl ProblemRecipe for ExternalProgramProblemRecipe {
     type Factory = ExternalProgramProblemFactory;
@@ -82,7 +137,8 @@ impl ProblemRecipe for ExternalProgramProblemRecipe {
             let key = self.cache_key();
 
             if let Some((k, factory)) = f.as_ref() {
-                if k == &key {
+                // The program may have been killed due to a timeout.
+                if k == &key && factory.0.process.is_alive() {
                     return Ok(factory.clone());
                 }
             }
@@ -111,9 +167,9 @@ impl ProblemFactory for ExternalProgramProblemFactory {
 #[derive(Debug)]
 struct ExternalProgramProblemFactoryInner {
     spec: ProblemSpec,
-    child: Child,
-    tx: Arc<Mutex<MessageSender<ProblemMessage, ChildStdin>>>,
-    rx: Arc<Mutex<MessageReceiver<ProblemMessage, ChildStdout>>>,
+    process: Arc<ChildProcess>,
+    tx: Arc<Mutex<MessageSender<ProblemMessage, BoxWriter>>>,
+    rx: Arc<Mutex<BackgroundMessageReceiver<ProblemMessage>>>,
     next_problem_id: AtomicU64,
     next_evaluator_id: Arc<AtomicU64>,
 }
@@ -135,17 +191,60 @@ impl ProblemFactory for ExternalProgramProblemFactoryInner {
 
         Ok(ExternalProgramProblem {
             problem_id,
+            problem_name: self.spec.name.clone(),
+            values_len: self.spec.values_domain.len(),
+            process: Arc::clone(&self.process),
             tx: Arc::clone(&self.tx),
             rx: Arc::clone(&self.rx),
             next_evaluator_id: Arc::clone(&self.next_evaluator_id),
+            rng,
         })
     }
 }
-impl Drop for ExternalProgramProblemFactoryInner {
-    fn drop(&mut self) {
-        if self.child.kill().is_ok() {
-            let _ = self.child.wait(); // for preventing the child process becomes a zombie.
+
+#[derive(Debug)]
+struct ChildProcess {
+    child: Mutex<Child>,
+    command_line: String,
+    timeout: Option<Duration>,
+}
+impl ChildProcess {
+    // Receives a message from the process, and kills it if the message doesn't arrive within the timeout.
+    fn recv(&self, rx: &mut BackgroundMessageReceiver<ProblemMessage>) -> Result<ProblemMessage> {
+        let Some(timeout) = self.timeout else {
+            return track!(rx.recv());
+        };
+        if let Some(m) = track!(rx.recv_timeout(timeout))? {
+            return Ok(m);
         }
+
+        self.kill();
+        track_panic!(
+            ErrorKind::Other,
+            "External program didn't respond within {:?} (killed): {}",
+            timeout,
+            self.command_line
+        );
+    }
+
+    fn is_alive(&self) -> bool {
+        self.child
+            .lock()
+            .ok()
+            .is_some_and(|mut child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    fn kill(&self) {
+        if let Ok(mut child) = self.child.lock() {
+            if child.kill().is_ok() {
+                let _ = child.wait(); // for preventing the child process becomes a zombie.
+            }
+        }
+    }
+}
+impl Drop for ChildProcess {
+    fn drop(&mut self) {
+        self.kill();
     }
 }
 
@@ -153,9 +252,13 @@ impl Drop for ExternalProgramProblemFactoryInner {
 #[derive(Debug)]
 pub struct ExternalProgramProblem {
     problem_id: u64,
-    tx: Arc<Mutex<MessageSender<ProblemMessage, ChildStdin>>>,
-    rx: Arc<Mutex<MessageReceiver<ProblemMessage, ChildStdout>>>,
+    problem_name: String,
+    values_len: usize,
+    process: Arc<ChildProcess>,
+    tx: Arc<Mutex<MessageSender<ProblemMessage, BoxWriter>>>,
+    rx: Arc<Mutex<BackgroundMessageReceiver<ProblemMessage>>>,
     next_evaluator_id: Arc<AtomicU64>,
+    rng: ArcRng,
 }
 impl Problem for ExternalProgramProblem {
     type Evaluator = ExternalProgramEvaluator;
@@ -173,7 +276,7 @@ impl Problem for ExternalProgramProblem {
         track!(tx.send(&m))?;
 
         let mut rx = track!(self.rx.lock().map_err(Error::from))?;
-        match track!(rx.recv())? {
+        match track!(self.process.recv(&mut rx))? {
             ProblemMessage::CreateEvaluatorReply => {}
             ProblemMessage::ErrorReply { kind, message } => {
                 if let Some(message) = message {
@@ -189,8 +292,12 @@ impl Problem for ExternalProgramProblem {
 
         Ok(ExternalProgramEvaluator {
             evaluator_id,
+            problem_name: self.problem_name.clone(),
+            values_len: self.values_len,
+            process: Arc::clone(&self.process),
             tx: Arc::clone(&self.tx),
             rx: Arc::clone(&self.rx),
+            rng: self.rng.clone(),
         })
     }
 }
@@ -208,8 +315,12 @@ impl Drop for ExternalProgramProblem {
 #[derive(Debug)]
 pub struct ExternalProgramEvaluator {
     evaluator_id: u64,
-    tx: Arc<Mutex<MessageSender<ProblemMessage, ChildStdin>>>,
-    rx: Arc<Mutex<MessageReceiver<ProblemMessage, ChildStdout>>>,
+    problem_name: String,
+    values_len: usize,
+    process: Arc<ChildProcess>,
+    tx: Arc<Mutex<MessageSender<ProblemMessage, BoxWriter>>>,
+    rx: Arc<Mutex<BackgroundMessageReceiver<ProblemMessage>>>,
+    rng: ArcRng,
 }
 impl Evaluator for ExternalProgramEvaluator {
     fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
@@ -222,11 +333,26 @@ impl Evaluator for ExternalProgramEvaluator {
         track!(tx.send(&m))?;
 
         let mut rx = track!(self.rx.lock().map_err(Error::from))?;
-        match track!(rx.recv())? {
+        match track!(self.process.recv(&mut rx))? {
             ProblemMessage::EvaluateReply {
                 current_step,
                 values,
-            } => Ok((current_step, values)),
+                unseeded_rng_used,
+            } => {
+                if unseeded_rng_used {
+                    self.rng.mark_unseeded_rng_used();
+                }
+                // Empty values mean that the parameters couldn't be evaluated.
+                track_assert!(
+                    values.is_empty() || values.len() == self.values_len,
+                    ErrorKind::InvalidInput,
+                    "Problem {:?} returned wrong number of values: expected={}, actual={}",
+                    self.problem_name,
+                    self.values_len,
+                    values.len()
+                );
+                Ok((current_step, values))
+            }
             ProblemMessage::ErrorReply { kind, message } => {
                 if let Some(message) = message {
                     track_panic!(kind, "{}", message);
@@ -250,3 +376,93 @@ impl Drop for ExternalProgramEvaluator {
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::domain;
+    use crate::epi::solver::ExternalProgramSolverRecipe;
+    use crate::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn hung_program_is_killed() -> TopLevelResult {
+        let spec = track!(ProblemSpecBuilder::new("hung")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y"))
+            .finish())?;
+        let spec = ProblemMessage::ProblemSpecCast { spec };
+        let reply = ProblemMessage::CreateEvaluatorReply;
+
+        // A stub program that never replies to evaluation requests.
+        let script = r#"echo "$1"; read m; read m; echo "$2"; exec sleep 1000"#;
+        let recipe = ExternalProgramProblemRecipe {
+            path: PathBuf::from("sh"),
+            args: vec![
+                "-c".to_owned(),
+                script.to_owned(),
+                "sh".to_owned(),
+                track!(serde_json::to_string(&spec).map_err(Error::from))?,
+                track!(serde_json::to_string(&reply).map_err(Error::from))?,
+            ],
+            epi_timeout: Some(1),
+            protocol: Protocol::Kurobako,
+            transport: Transport::Stdio,
+        };
+
+        let registry =
+            FactoryRegistry::new::<ExternalProgramProblemRecipe, ExternalProgramSolverRecipe>();
+        let factory = track!(recipe.create_factory(&registry))?;
+        let problem = track!(factory.create_problem(ArcRng::new(0)))?;
+        let mut evaluator = track!(problem.create_evaluator(Params::new(vec![0.5])))?;
+
+        let e = evaluator.evaluate(1).expect_err("should time out");
+        assert_eq!(*e.kind(), ErrorKind::Other);
+        assert!(e.to_string().contains("sh -c"));
+        assert!(!factory.0.process.is_alive());
+        Ok(())
+    }
+
+    #[test]
+    fn unseeded_evaluation_is_reported() -> TopLevelResult {
+        let spec = track!(ProblemSpecBuilder::new("noisy")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y"))
+            .finish())?;
+        let spec = ProblemMessage::ProblemSpecCast { spec };
+        let reply = ProblemMessage::CreateEvaluatorReply;
+        let evaluated = ProblemMessage::EvaluateReply {
+            current_step: 1,
+            values: Values::new(vec![0.1]),
+            unseeded_rng_used: true,
+        };
+
+        // A stub program that reports the use of a non-seeded RNG.
+        let script = r#"echo "$1"; read m; read m; echo "$2"; read m; echo "$3"; exec sleep 1000"#;
+        let mut args = vec!["-c".to_owned(), script.to_owned(), "sh".to_owned()];
+        for m in &[spec, reply, evaluated] {
+            args.push(track!(serde_json::to_string(m).map_err(Error::from))?);
+        }
+        let recipe = ExternalProgramProblemRecipe {
+            path: PathBuf::from("sh"),
+            args,
+            epi_timeout: Some(10),
+            protocol: Protocol::Kurobako,
+            transport: Transport::Stdio,
+        };
+
+        let registry =
+            FactoryRegistry::new::<ExternalProgramProblemRecipe, ExternalProgramSolverRecipe>();
+        let factory = track!(recipe.create_factory(&registry))?;
+        assert!(!track!(factory.specification())?.is_concurrent());
+        let rng = ArcRng::new(0);
+        let problem = track!(factory.create_problem(rng.substream(1)))?;
+        let mut evaluator = track!(problem.create_evaluator(Params::new(vec![0.5])))?;
+        assert!(!rng.unseeded_rng_used());
+
+        let (_, values) = track!(evaluator.evaluate(1))?;
+        assert_eq!(values.into_vec(), [0.1]);
+        assert!(rng.unseeded_rng_used());
+        Ok(())
+    }
+}
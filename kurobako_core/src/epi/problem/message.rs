@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 #[allow(missing_docs)]
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ProblemMessage {
+    // The name used by the JSON-RPC framing (see `epi::jsonrpc`).
+    #[serde(alias = "SPECIFICATION_CAST")]
     ProblemSpecCast {
         spec: ProblemSpec,
     },
@@ -34,6 +36,11 @@ pub enum ProblemMessage {
     EvaluateReply {
         current_step: u64,
         values: Values,
+
+        // `true` means that the evaluation consumed random numbers that
+        // cannot be reproduced by the seed given via `CreateProblemCast`.
+        #[serde(default, skip_serializing_if = "is_false")]
+        unseeded_rng_used: bool,
     },
     ErrorReply {
         kind: ErrorKind,
@@ -41,3 +48,7 @@ pub enum ProblemMessage {
         message: Option<String>,
     },
 }
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
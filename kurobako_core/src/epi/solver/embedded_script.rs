@@ -1,11 +1,13 @@
+use crate::epi::channel::Protocol;
 use crate::epi::solver::{
     ExternalProgramSolver, ExternalProgramSolverFactory, ExternalProgramSolverRecipe,
 };
+use crate::epi::transport::Transport;
 use crate::problem::ProblemSpec;
 use crate::registry::FactoryRegistry;
 use crate::rng::ArcRng;
 use crate::solver::{Solver, SolverFactory, SolverRecipe, SolverSpec};
-use crate::trial::{EvaluatedTrial, IdGen, NextTrial};
+use crate::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
 use crate::{Error, Result};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
@@ -60,7 +62,12 @@ impl SolverRecipe for EmbeddedScriptSolverRecipe {
         };
 
         let args = self.args.clone();
-        let eppr = ExternalProgramSolverRecipe { path, args };
+        let eppr = ExternalProgramSolverRecipe {
+            path,
+            args,
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+        };
         let inner = track!(eppr.create_factory(registry))?;
         Ok(EmbeddedScriptSolverFactory { inner })
     }
@@ -97,4 +104,8 @@ impl Solver for EmbeddedScriptSolver {
     fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
         track!(self.inner.tell(trial))
     }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        track!(self.inner.tell_prior(id, params, values))
+    }
 }
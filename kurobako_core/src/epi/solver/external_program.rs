@@ -1,17 +1,18 @@
-use crate::epi::channel::{MessageReceiver, MessageSender};
+use crate::epi::channel::{MessageReceiver, MessageSender, Protocol};
 use crate::epi::solver::SolverMessage;
+use crate::epi::transport::{BoxReader, BoxWriter, Connection, Transport};
 use crate::problem::ProblemSpec;
 use crate::registry::FactoryRegistry;
 use crate::rng::{ArcRng, Rng as _};
 use crate::solver::{Solver, SolverFactory, SolverRecipe, SolverSpec};
-use crate::trial::{EvaluatedTrial, IdGen, NextTrial};
+use crate::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
 use crate::{Error, ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::process::{Child, Command};
 use std::sync::atomic::{self, AtomicU64};
 use std::sync::{Arc, Mutex};
 use std::thread_local;
@@ -31,24 +32,41 @@ pub struct ExternalProgramSolverRecipe {
 
     /// The command line arguments that are passed to the program.
     pub args: Vec<String>,
+
+    /// Protocol used to communicate with the program.
+    #[structopt(
+        long,
+        default_value = "kurobako",
+        possible_values = Protocol::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "Protocol::is_kurobako")]
+    pub protocol: Protocol,
+
+    /// Transport used to communicate with the program.
+    #[structopt(
+        long,
+        default_value = "stdio",
+        possible_values = Transport::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "Transport::is_stdio")]
+    pub transport: Transport,
 }
 impl ExternalProgramSolverRecipe {
     fn create_new_factory(
         &self,
         _registry: &FactoryRegistry,
     ) -> Result<ExternalProgramSolverFactory> {
-        let mut child = track!(Command::new(&self.path)
-            .args(&self.args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .map_err(Error::from))?;
-
-        let stdin = track_assert_some!(child.stdin.take(), ErrorKind::IoError);
-        let stdout = track_assert_some!(child.stdout.take(), ErrorKind::IoError);
-
-        let tx = MessageSender::new(stdin);
-        let mut rx = MessageReceiver::new(stdout);
+        let Connection {
+            child,
+            reader,
+            writer,
+        } = track!(self
+            .transport
+            .spawn(Command::new(&self.path).args(&self.args)))?;
+
+        let framing = self.protocol.framing();
+        let tx = MessageSender::with_framing(writer, framing.clone());
+        let mut rx = MessageReceiver::with_framing(reader, framing);
         let spec = match track!(rx.recv())? {
             SolverMessage::SolverSpecCast { spec } => spec,
             m => track_panic!(ErrorKind::InvalidInput, "Unexpected message: {:?}", m),
@@ -71,6 +89,12 @@ impl ExternalProgramSolverRecipe {
         for arg in &self.args {
             hasher.update(arg.as_bytes());
         }
+        if !self.protocol.is_kurobako() {
+            hasher.update(b"jsonrpc");
+        }
+        if !self.transport.is_stdio() {
+            hasher.update(b"unix");
+        }
         hasher.finalize().to_vec()
     }
 }
@@ -108,8 +132,8 @@ impl SolverFactory for ExternalProgramSolverFactory {
 struct ExternalProgramSolverFactoryInner {
     spec: SolverSpec,
     child: Child,
-    tx: Arc<Mutex<MessageSender<SolverMessage, ChildStdin>>>,
-    rx: Arc<Mutex<MessageReceiver<SolverMessage, ChildStdout>>>,
+    tx: Arc<Mutex<MessageSender<SolverMessage, BoxWriter>>>,
+    rx: Arc<Mutex<MessageReceiver<SolverMessage, BoxReader>>>,
     next_solver_id: AtomicU64,
 }
 impl SolverFactory for ExternalProgramSolverFactoryInner {
@@ -131,8 +155,11 @@ impl SolverFactory for ExternalProgramSolverFactoryInner {
 
         Ok(ExternalProgramSolver {
             solver_id,
+            solver_name: self.spec.name.clone(),
+            params_len: problem.params_domain.len(),
             tx: Arc::clone(&self.tx),
             rx: Arc::clone(&self.rx),
+            rng,
         })
     }
 }
@@ -148,8 +175,11 @@ impl Drop for ExternalProgramSolverFactoryInner {
 #[derive(Debug)]
 pub struct ExternalProgramSolver {
     solver_id: u64,
-    tx: Arc<Mutex<MessageSender<SolverMessage, ChildStdin>>>,
-    rx: Arc<Mutex<MessageReceiver<SolverMessage, ChildStdout>>>,
+    solver_name: String,
+    params_len: usize,
+    tx: Arc<Mutex<MessageSender<SolverMessage, BoxWriter>>>,
+    rx: Arc<Mutex<MessageReceiver<SolverMessage, BoxReader>>>,
+    rng: ArcRng,
 }
 impl Solver for ExternalProgramSolver {
     fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
@@ -165,7 +195,11 @@ impl Solver for ExternalProgramSolver {
             SolverMessage::AskReply {
                 trial,
                 next_trial_id,
+                unseeded_rng_used,
             } => {
+                if unseeded_rng_used {
+                    self.rng.mark_unseeded_rng_used();
+                }
                 track_assert!(
                     idg.peek_id().get() <= next_trial_id,
                     ErrorKind::InvalidInput; idg.peek_id().get(), next_trial_id
@@ -174,6 +208,13 @@ impl Solver for ExternalProgramSolver {
                     idg.generate();
                 }
 
+                track_assert_eq!(
+                    trial.params.len(),
+                    self.params_len,
+                    ErrorKind::InvalidInput,
+                    "Solver {:?} asked wrong number of parameters",
+                    self.solver_name
+                );
                 Ok(trial)
             }
             SolverMessage::ErrorReply { kind, message } => {
@@ -194,12 +235,28 @@ impl Solver for ExternalProgramSolver {
             solver_id: self.solver_id,
             trial,
         };
+        track!(self.call_tell(&m))
+    }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        let m = SolverMessage::TellPriorCall {
+            solver_id: self.solver_id,
+            trial_id: id,
+            params,
+            values,
+        };
+        track!(self.call_tell(&m))
+    }
+}
+impl ExternalProgramSolver {
+    // Sends a `TellCall` (or `TellPriorCall`) message and waits for its reply.
+    fn call_tell(&self, m: &SolverMessage) -> Result<()> {
         let mut tx = track!(self.tx.lock().map_err(Error::from))?;
-        track!(tx.send(&m))?;
+        track!(tx.send(m))?;
 
         let mut rx = track!(self.rx.lock().map_err(Error::from))?;
         match track!(rx.recv())? {
-            SolverMessage::TellReply => Ok(()),
+            SolverMessage::TellReply | SolverMessage::TellPriorReply => Ok(()),
             SolverMessage::ErrorReply { kind, message } => {
                 if let Some(message) = message {
                     track_panic!(kind, "{}", message);
@@ -1,6 +1,6 @@
 use crate::problem::ProblemSpec;
 use crate::solver::SolverSpec;
-use crate::trial::{EvaluatedTrial, NextTrial};
+use crate::trial::{EvaluatedTrial, NextTrial, Params, TrialId, Values};
 use crate::ErrorKind;
 use serde::{Deserialize, Serialize};
 
@@ -9,6 +9,8 @@ use serde::{Deserialize, Serialize};
 #[allow(missing_docs)]
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SolverMessage {
+    // The name used by the JSON-RPC framing (see `epi::jsonrpc`).
+    #[serde(alias = "SPECIFICATION_CAST")]
     SolverSpecCast {
         spec: SolverSpec,
     },
@@ -27,15 +29,31 @@ pub enum SolverMessage {
     AskReply {
         trial: NextTrial,
         next_trial_id: u64,
+
+        // `true` means that the asking consumed random numbers that
+        // cannot be reproduced by the seed given via `CreateSolverCast`.
+        #[serde(default, skip_serializing_if = "is_false")]
+        unseeded_rng_used: bool,
     },
     TellCall {
         solver_id: u64,
         trial: EvaluatedTrial,
     },
     TellReply,
+    TellPriorCall {
+        solver_id: u64,
+        trial_id: TrialId,
+        params: Params,
+        values: Values,
+    },
+    TellPriorReply,
     ErrorReply {
         kind: ErrorKind,
         #[serde(default)]
         message: Option<String>,
     },
 }
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
@@ -0,0 +1,237 @@
+//! Transports used to communicate with external programs.
+use crate::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+
+/// Name of the environment variable that has the path of the socket which an external program connects to.
+///
+/// This is only set if the program is launched with the Unix-domain-socket transport.
+pub const SOCKET_PATH_ENV: &str = "KUROBAKO_EPI_SOCKET";
+
+/// Boxed stream to read messages from an external program.
+pub type BoxReader = Box<dyn Read + Send>;
+
+/// Boxed stream to write messages to an external program.
+pub type BoxWriter = Box<dyn Write + Send>;
+
+/// Transport used to communicate with external programs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// The messages are exchanged via the standard input and output of the program.
+    #[default]
+    Stdio,
+
+    /// The messages are exchanged via a Unix domain socket.
+    ///
+    /// The program has to connect to the socket specified by `KUROBAKO_EPI_SOCKET` environment variable
+    /// just after being launched.
+    /// The standard output of the program is redirected to the standard error of kurobako,
+    /// so the program can freely use it for logging.
+    #[cfg(unix)]
+    Unix,
+}
+impl Transport {
+    /// Possible values.
+    #[cfg(unix)]
+    pub const POSSIBLE_VALUES: &'static [&'static str] = &["stdio", "unix"];
+
+    /// Possible values.
+    #[cfg(not(unix))]
+    pub const POSSIBLE_VALUES: &'static [&'static str] = &["stdio"];
+
+    /// Launches an external program and connects to it.
+    pub fn spawn(self, command: &mut Command) -> Result<Connection> {
+        match self {
+            Self::Stdio => {
+                let mut child = track!(command
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .map_err(Error::from))?;
+                let stdin = track_assert_some!(child.stdin.take(), ErrorKind::IoError);
+                let stdout = track_assert_some!(child.stdout.take(), ErrorKind::IoError);
+                Ok(Connection {
+                    child,
+                    reader: Box::new(stdout),
+                    writer: Box::new(stdin),
+                })
+            }
+            #[cfg(unix)]
+            Self::Unix => {
+                let listener = track!(unix::SocketListener::bind())?;
+                let mut child = track!(command
+                    .env(SOCKET_PATH_ENV, listener.path())
+                    .stdin(Stdio::null())
+                    .stdout(std::io::stderr())
+                    .spawn()
+                    .map_err(Error::from))?;
+                let stream = match track!(listener.accept(&mut child)) {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        if child.kill().is_ok() {
+                            let _ = child.wait();
+                        }
+                        return Err(e);
+                    }
+                };
+                let reader = track!(stream.try_clone().map_err(Error::from))?;
+                Ok(Connection {
+                    child,
+                    reader: Box::new(reader),
+                    writer: Box::new(stream),
+                })
+            }
+        }
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn is_stdio(&self) -> bool {
+        *self == Self::Stdio
+    }
+}
+impl FromStr for Transport {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stdio" => Ok(Self::Stdio),
+            #[cfg(unix)]
+            "unix" => Ok(Self::Unix),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown transport: {:?}", s),
+        }
+    }
+}
+
+/// Connection to a launched external program.
+pub struct Connection {
+    /// The process of the program.
+    pub child: Child,
+
+    /// Stream to read messages from the program.
+    pub reader: BoxReader,
+
+    /// Stream to write messages to the program.
+    pub writer: BoxWriter,
+}
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connection {{ child: {:?}, .. }}", self.child)
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use crate::{Error, ErrorKind, Result};
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::process::Child;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    // Socket that waits for the connection from a launched program.
+    //
+    // The socket file is placed in a temporary directory that is deleted when this is dropped.
+    #[derive(Debug)]
+    pub struct SocketListener {
+        listener: UnixListener,
+        dir: TempDir,
+    }
+    impl SocketListener {
+        pub fn bind() -> Result<Self> {
+            let dir = track!(tempfile::Builder::new()
+                .prefix("kurobako-epi-")
+                .tempdir()
+                .map_err(Error::from))?;
+            let path = dir.path().join("epi.sock");
+            let listener = track!(UnixListener::bind(&path).map_err(Error::from); path)?;
+            track!(listener.set_nonblocking(true).map_err(Error::from))?;
+            Ok(Self { listener, dir })
+        }
+
+        pub fn path(&self) -> PathBuf {
+            self.dir.path().join("epi.sock")
+        }
+
+        // Waits until the program connects to the socket (or exits).
+        pub fn accept(&self, child: &mut Child) -> Result<UnixStream> {
+            loop {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        track!(stream.set_nonblocking(false).map_err(Error::from))?;
+                        return Ok(stream);
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(track!(Error::from(e))),
+                }
+
+                if let Some(status) = track!(child.try_wait().map_err(Error::from))? {
+                    track_panic!(
+                        ErrorKind::Other,
+                        "External program exited before connecting to the socket: {}",
+                        status
+                    );
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::epi::channel::{MessageReceiver, MessageSender};
+        use crate::epi::problem::ProblemMessage;
+        use crate::problem::ProblemSpecBuilder;
+        use crate::{domain, ErrorKind};
+        use std::process::Command;
+        use trackable::result::TopLevelResult;
+
+        #[test]
+        fn specification_is_exchanged_over_socket() -> TopLevelResult {
+            let listener = SocketListener::bind()?;
+            let mut child = Command::new("sleep")
+                .arg("10")
+                .spawn()
+                .map_err(Error::from)?;
+
+            // Plays the part of the program (which would get the path from `KUROBAKO_EPI_SOCKET`).
+            let path = listener.path();
+            let peer = thread::spawn(move || -> Result<()> {
+                let stream = track!(UnixStream::connect(&path).map_err(Error::from))?;
+                let spec = track!(ProblemSpecBuilder::new("foo")
+                    .param(domain::var("x").continuous(0.0, 1.0))
+                    .value(domain::var("y"))
+                    .finish())?;
+                let mut tx = MessageSender::new(stream);
+                track!(tx.send(&ProblemMessage::ProblemSpecCast { spec }))
+            });
+
+            let stream = listener.accept(&mut child)?;
+            let mut rx = MessageReceiver::new(stream);
+            match rx.recv()? {
+                ProblemMessage::ProblemSpecCast { spec } => assert_eq!(spec.name, "foo"),
+                m => panic!("unexpected message: {:?}", m),
+            }
+            peer.join().expect("peer panicked")?;
+
+            child.kill().map_err(Error::from)?;
+            child.wait().map_err(Error::from)?;
+            Ok(())
+        }
+
+        #[test]
+        fn program_exited_before_connecting() -> TopLevelResult {
+            let listener = SocketListener::bind()?;
+            let mut child = Command::new("true").spawn().map_err(Error::from)?;
+            let e = listener.accept(&mut child).expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::Other);
+            Ok(())
+        }
+    }
+}
@@ -25,6 +25,11 @@ impl From<std::num::ParseIntError> for Error {
         ErrorKind::InvalidInput.cause(f).into()
     }
 }
+impl From<std::num::ParseFloatError> for Error {
+    fn from(f: std::num::ParseFloatError) -> Self {
+        ErrorKind::InvalidInput.cause(f).into()
+    }
+}
 impl<T> From<std::sync::PoisonError<T>> for Error {
     fn from(f: std::sync::PoisonError<T>) -> Self {
         ErrorKind::Other.cause(f.to_string()).into()
@@ -1,3 +1,90 @@
 //! Numbers.
 
 pub use ordered_float::OrderedFloat;
+
+/// Returns the values in `values` except NaNs.
+///
+/// NaNs may be given by misbehaving problems, and they easily corrupt statistics and rankings.
+/// Note that infinities are kept because they are meaningful values
+/// (e.g., the AUC of a timed-out study that has no complete trials is regarded as infinity).
+pub fn sanitize(values: &[f64]) -> Vec<f64> {
+    values.iter().copied().filter(|v| !v.is_nan()).collect()
+}
+
+/// Summary statistics of numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SummaryStats {
+    /// Mean.
+    pub mean: f64,
+
+    /// (Population) standard deviation.
+    pub sd: f64,
+
+    /// Minimum value.
+    pub min: f64,
+
+    /// Maximum value.
+    pub max: f64,
+
+    /// Number of the values.
+    pub count: usize,
+}
+impl SummaryStats {
+    /// Computes the statistics of the values in `values` except NaNs (see `sanitize`).
+    ///
+    /// If there are no such values, this returns `None`.
+    /// If some of the values are infinite, `mean` is infinite (or NaN if both signs are included)
+    /// and `sd` is infinity.
+    pub fn new(values: &[f64]) -> Option<Self> {
+        let values = sanitize(values);
+        let count = values.len();
+        if count == 0 {
+            return None;
+        }
+
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let sd = if values.iter().all(|v| v.is_finite()) {
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+            variance.sqrt()
+        } else {
+            f64::INFINITY
+        };
+        Some(Self {
+            mean,
+            sd,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_works() {
+        let values = [1.0, f64::NAN, 2.0, f64::INFINITY, f64::NEG_INFINITY, 3.0];
+        assert_eq!(
+            sanitize(&values),
+            [1.0, 2.0, f64::INFINITY, f64::NEG_INFINITY, 3.0]
+        );
+    }
+
+    #[test]
+    fn summary_stats_works() {
+        let stats = SummaryStats::new(&[1.0, 2.0, f64::NAN, 2.0, 3.0, 100.0]).expect("finite");
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.mean, 21.6);
+        assert!((stats.sd - 39.205).abs() < 1e-3);
+        assert_eq!((stats.min, stats.max), (1.0, 100.0));
+
+        let stats = SummaryStats::new(&[1.0, f64::INFINITY]).expect("non-NaN");
+        assert_eq!((stats.mean, stats.sd), (f64::INFINITY, f64::INFINITY));
+        assert_eq!((stats.min, stats.max), (1.0, f64::INFINITY));
+
+        assert_eq!(SummaryStats::new(&[f64::NAN]), None);
+        assert_eq!(SummaryStats::new(&[]), None);
+    }
+}
@@ -4,7 +4,8 @@ use crate::registry::FactoryRegistry;
 use crate::rng::ArcRng;
 use crate::solver::{Capabilities, Capability};
 use crate::trial::{Params, Values};
-use crate::{ErrorKind, Result};
+use crate::{Error, ErrorKind, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt;
@@ -137,6 +138,16 @@ pub struct ProblemSpec {
     pub reference_point: Option<Params>,
 }
 impl ProblemSpec {
+    /// Returns `false` if the evaluations of this problem can't proceed concurrently.
+    ///
+    /// This is determined by the `concurrent` attribute:
+    /// a problem whose evaluations are serialized (e.g., because they are served by a single process)
+    /// sets it to `"false"`.
+    /// If the attribute is absent, the problem is regarded as concurrent.
+    pub fn is_concurrent(&self) -> bool {
+        self.attrs.get("concurrent").map(String::as_str) != Some("false")
+    }
+
     /// Returns the capabilities required to solver to handle this problem.
     pub fn requirements(&self) -> Capabilities {
         let mut c = Capabilities::empty();
@@ -163,14 +174,59 @@ impl ProblemSpec {
                 (Range::Discrete { .. }, Distribution::LogUniform) => {
                     c.add_capability(Capability::LogUniformDiscrete);
                 }
-                (Range::Categorical { .. }, _) => {
+                (Range::IntChoices { .. }, _) | (Range::Categorical { .. }, _) => {
                     c.add_capability(Capability::Categorical);
                 }
+                (Range::Ordinal { .. }, _) => {
+                    c.add_capability(Capability::Categorical);
+                    c.add_capability(Capability::Ordinal);
+                }
+            }
+
+            if v.weights().is_some() {
+                c.add_capability(Capability::CategoricalPrior);
+            }
+
+            if v.is_fidelity() {
+                c.add_capability(Capability::MultiFidelity);
             }
         }
 
         c
     }
+
+    /// Parses the value of the given attribute as a JSON.
+    ///
+    /// Returns `Ok(None)` if this problem doesn't have the attribute.
+    pub fn parse_attr<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        if let Some(value) = self.attrs.get(key) {
+            let value = track!(serde_json::from_str(value).map_err(Error::from); key, value)?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the ideal and nadir points used for normalizing the objective values of this problem.
+    ///
+    /// These are taken from the `ideal_point` and `nadir_point` attributes
+    /// that are JSON arrays of numbers (e.g., `"[0.0, 10.5]"`).
+    /// Returns `Ok(None)` if either of the attributes isn't defined.
+    pub fn normalization_points(&self) -> Result<Option<(Vec<f64>, Vec<f64>)>> {
+        let ideal = track!(self.parse_attr::<Vec<f64>>("ideal_point"))?;
+        let nadir = track!(self.parse_attr::<Vec<f64>>("nadir_point"))?;
+        if let (Some(ideal), Some(nadir)) = (ideal, nadir) {
+            let n = self.values_domain.len();
+            track_assert_eq!(ideal.len(), n, ErrorKind::InvalidInput);
+            track_assert_eq!(nadir.len(), n, ErrorKind::InvalidInput);
+            for (i, n) in ideal.iter().zip(nadir.iter()) {
+                track_assert!(i < n, ErrorKind::InvalidInput; ideal, nadir);
+            }
+            Ok(Some((ideal, nadir)))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 /// Recipe of a problem.
@@ -380,3 +436,49 @@ impl EvaluableStepsInner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain;
+    use trackable::result::TopLevelResult;
+
+    fn spec(ideal: &str, nadir: &str) -> Result<ProblemSpec> {
+        track!(ProblemSpecBuilder::new("foo")
+            .attr("ideal_point", ideal)
+            .attr("nadir_point", nadir)
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish())
+    }
+
+    #[test]
+    fn normalization_points_works() -> TopLevelResult {
+        let points = spec("[0.0, -1.0]", "[1.0, 100.0]")?.normalization_points()?;
+        assert_eq!(points, Some((vec![0.0, -1.0], vec![1.0, 100.0])));
+
+        let spec_without_nadir = ProblemSpecBuilder::new("foo")
+            .attr("ideal_point", "[0.0, 0.0]")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish()?;
+        assert_eq!(spec_without_nadir.normalization_points()?, None);
+
+        // Wrong arity.
+        assert!(spec("[0.0]", "[1.0]")?.normalization_points().is_err());
+
+        // The ideal point must be better than the nadir point.
+        assert!(spec("[0.0, 1.0]", "[1.0, 1.0]")?
+            .normalization_points()
+            .is_err());
+
+        // Not a JSON array of numbers.
+        let e = spec("0.0, 0.0", "[1.0, 1.0]")?
+            .normalization_points()
+            .expect_err("should fail");
+        assert!(e.to_string().contains("ideal_point"));
+        Ok(())
+    }
+}
@@ -1,19 +1,44 @@
 //! Registry of problem and solver factories.
+//!
+//! In addition to the built-in recipe types given to `FactoryRegistry::new`,
+//! user-defined recipe types can be registered with names.
+//! A recipe JSON object which has a registered name as its single key, such as
+//! `{"my_problem": {...}}`, is dispatched to the corresponding user-defined recipe type.
+//!
+//! Recipe instances can also be registered with names (e.g., `FactoryRegistry::register_problem`),
+//! and their factories can be created by the names (e.g., `FactoryRegistry::get_problem`).
+//! This allows external crates to build dynamic dispatch tables of problems and solvers.
+//!
+//! # Thread safety
+//!
+//! `FactoryRegistry` is `Send` but not `Sync`, and registrations require `&mut self`.
+//! So registrations should be done before the registry is used to create factories.
+//! Note that the benchmark runner creates a registry per worker thread
+//! (see `kurobako::runner::Runner::with_registry`), so the registrations have to be done
+//! in the function that creates the registries.
 // FIXME: Rename this module and structs.
 use crate::json::JsonRecipe;
 use crate::problem::{BoxProblemFactory, ProblemRecipe};
 use crate::solver::{BoxSolverFactory, SolverRecipe};
-use crate::{Error, Result};
+use crate::{Error, ErrorKind, Result};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 type CreateProblemFactory =
     dyn Fn(&JsonRecipe, &FactoryRegistry) -> Result<BoxProblemFactory> + Send;
 type CreateSolverFactory = dyn Fn(&JsonRecipe, &FactoryRegistry) -> Result<BoxSolverFactory> + Send;
+type CreateNamedProblemFactory = dyn Fn(&FactoryRegistry) -> Result<BoxProblemFactory> + Send;
+type CreateNamedSolverFactory = dyn Fn(&FactoryRegistry) -> Result<BoxSolverFactory> + Send;
 
 /// Factory registry.
 pub struct FactoryRegistry {
     create_problem_factory: Box<CreateProblemFactory>,
     create_solver_factory: Box<CreateSolverFactory>,
+    user_problem_recipes: HashMap<String, Box<CreateProblemFactory>>,
+    user_solver_recipes: HashMap<String, Box<CreateSolverFactory>>,
+    named_problems: BTreeMap<String, Box<CreateNamedProblemFactory>>,
+    named_solvers: BTreeMap<String, Box<CreateNamedSolverFactory>>,
 }
 impl FactoryRegistry {
     /// Makes a new `FactoryRegistry` instance.
@@ -22,29 +47,145 @@ impl FactoryRegistry {
         P: 'static + ProblemRecipe,
         S: 'static + SolverRecipe,
     {
-        let create_problem_factory = Box::new(|json: &JsonRecipe, registry: &FactoryRegistry| {
-            let recipe: P = track!(serde_json::from_value(json.clone()).map_err(Error::from))?;
-            let factory = track!(recipe.create_factory(registry)).map(BoxProblemFactory::new)?;
-            Ok(factory)
-        });
-        let create_solver_factory = Box::new(|json: &JsonRecipe, registry: &FactoryRegistry| {
-            let recipe: S = track!(serde_json::from_value(json.clone()).map_err(Error::from))?;
-            let factory = track!(recipe.create_factory(registry)).map(BoxSolverFactory::new)?;
-            Ok(factory)
-        });
         Self {
-            create_problem_factory,
-            create_solver_factory,
+            create_problem_factory: problem_factory_creator::<P>(),
+            create_solver_factory: solver_factory_creator::<S>(),
+            user_problem_recipes: HashMap::new(),
+            user_solver_recipes: HashMap::new(),
+            named_problems: BTreeMap::new(),
+            named_solvers: BTreeMap::new(),
         }
     }
 
+    /// Registers a user-defined problem recipe type with the given name.
+    ///
+    /// If the name has already been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn register_problem_recipe<R>(&mut self, name: &str) -> Result<()>
+    where
+        R: 'static + ProblemRecipe,
+    {
+        track_assert!(
+            !self.user_problem_recipes.contains_key(name),
+            ErrorKind::InvalidInput,
+            "Duplicate problem recipe name: {:?}",
+            name
+        );
+        self.user_problem_recipes
+            .insert(name.to_owned(), problem_factory_creator::<R>());
+        Ok(())
+    }
+
+    /// Registers a user-defined solver recipe type with the given name.
+    ///
+    /// If the name has already been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn register_solver_recipe<R>(&mut self, name: &str) -> Result<()>
+    where
+        R: 'static + SolverRecipe,
+    {
+        track_assert!(
+            !self.user_solver_recipes.contains_key(name),
+            ErrorKind::InvalidInput,
+            "Duplicate solver recipe name: {:?}",
+            name
+        );
+        self.user_solver_recipes
+            .insert(name.to_owned(), solver_factory_creator::<R>());
+        Ok(())
+    }
+
+    /// Registers a problem recipe with the given name.
+    ///
+    /// If the name has already been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn register_problem<R>(&mut self, name: &str, recipe: R) -> Result<()>
+    where
+        R: 'static + ProblemRecipe,
+    {
+        track_assert!(
+            !self.named_problems.contains_key(name),
+            ErrorKind::InvalidInput,
+            "Duplicate problem name: {:?}",
+            name
+        );
+        self.named_problems.insert(
+            name.to_owned(),
+            Box::new(move |registry: &FactoryRegistry| {
+                track!(recipe.create_factory(registry)).map(BoxProblemFactory::new)
+            }),
+        );
+        Ok(())
+    }
+
+    /// Registers a solver recipe with the given name.
+    ///
+    /// If the name has already been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn register_solver<R>(&mut self, name: &str, recipe: R) -> Result<()>
+    where
+        R: 'static + SolverRecipe,
+    {
+        track_assert!(
+            !self.named_solvers.contains_key(name),
+            ErrorKind::InvalidInput,
+            "Duplicate solver name: {:?}",
+            name
+        );
+        self.named_solvers.insert(
+            name.to_owned(),
+            Box::new(move |registry: &FactoryRegistry| {
+                track!(recipe.create_factory(registry)).map(BoxSolverFactory::new)
+            }),
+        );
+        Ok(())
+    }
+
+    /// Creates the factory of the problem registered with the given name.
+    ///
+    /// If the name hasn't been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn get_problem(&self, name: &str) -> Result<BoxProblemFactory> {
+        let create = track_assert_some!(
+            self.named_problems.get(name),
+            ErrorKind::InvalidInput,
+            "Unknown problem name: {:?}",
+            name
+        );
+        track!(create(self); name)
+    }
+
+    /// Creates the factory of the solver registered with the given name.
+    ///
+    /// If the name hasn't been registered, this method returns an `ErrorKind::InvalidInput` error.
+    pub fn get_solver(&self, name: &str) -> Result<BoxSolverFactory> {
+        let create = track_assert_some!(
+            self.named_solvers.get(name),
+            ErrorKind::InvalidInput,
+            "Unknown solver name: {:?}",
+            name
+        );
+        track!(create(self); name)
+    }
+
+    /// Returns the names of the registered problems in lexicographical order.
+    pub fn problem_names(&self) -> impl Iterator<Item = &str> {
+        self.named_problems.keys().map(String::as_str)
+    }
+
+    /// Returns the names of the registered solvers in lexicographical order.
+    pub fn solver_names(&self) -> impl Iterator<Item = &str> {
+        self.named_solvers.keys().map(String::as_str)
+    }
+
     /// Creates a problem factory associated with the given recipe JSON.
     pub fn create_problem_factory_from_json(&self, json: &JsonRecipe) -> Result<BoxProblemFactory> {
+        if let Some((create, inner)) = lookup(&self.user_problem_recipes, json) {
+            return track!(create(inner, self); json);
+        }
         track!((self.create_problem_factory)(json, self); json)
     }
 
     /// Creates a solver factory associated with the given recipe JSON.
     pub fn create_solver_factory_from_json(&self, json: &JsonRecipe) -> Result<BoxSolverFactory> {
+        if let Some((create, inner)) = lookup(&self.user_solver_recipes, json) {
+            return track!(create(inner, self); json);
+        }
         track!((self.create_solver_factory)(json, self); json)
     }
 }
@@ -53,3 +194,153 @@ impl fmt::Debug for FactoryRegistry {
         write!(f, "FactoryRegistry {{ .. }}")
     }
 }
+
+fn problem_factory_creator<R>() -> Box<CreateProblemFactory>
+where
+    R: 'static + ProblemRecipe,
+{
+    Box::new(|json: &JsonRecipe, registry: &FactoryRegistry| {
+        let recipe: R = track!(deserialize(json))?;
+        let factory = track!(recipe.create_factory(registry)).map(BoxProblemFactory::new)?;
+        Ok(factory)
+    })
+}
+
+fn solver_factory_creator<R>() -> Box<CreateSolverFactory>
+where
+    R: 'static + SolverRecipe,
+{
+    Box::new(|json: &JsonRecipe, registry: &FactoryRegistry| {
+        let recipe: R = track!(deserialize(json))?;
+        let factory = track!(recipe.create_factory(registry)).map(BoxSolverFactory::new)?;
+        Ok(factory)
+    })
+}
+
+fn deserialize<T: DeserializeOwned>(json: &JsonRecipe) -> Result<T> {
+    track!(serde_json::from_value(json.clone()).map_err(Error::from))
+}
+
+fn lookup<'a, F: ?Sized>(
+    recipes: &'a HashMap<String, Box<F>>,
+    json: &'a JsonRecipe,
+) -> Option<(&'a F, &'a JsonRecipe)> {
+    let object = json.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    let (name, inner) = object.iter().next()?;
+    recipes.get(name).map(|f| (&**f, inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain;
+    use crate::problem::{BoxProblem, ProblemFactory, ProblemSpec, ProblemSpecBuilder};
+    use crate::rng::ArcRng;
+    use crate::solver::{BoxSolver, SolverFactory, SolverSpec, SolverSpecBuilder};
+    use serde::{Deserialize, Serialize};
+    use structopt::StructOpt;
+    use trackable::result::TopLevelResult;
+
+    #[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+    struct DummyRecipe {
+        name: String,
+    }
+    impl ProblemRecipe for DummyRecipe {
+        type Factory = DummyFactory;
+
+        fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+            Ok(DummyFactory(self.name.clone()))
+        }
+    }
+    impl SolverRecipe for DummyRecipe {
+        type Factory = DummyFactory;
+
+        fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+            Ok(DummyFactory(self.name.clone()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct DummyFactory(String);
+    impl ProblemFactory for DummyFactory {
+        type Problem = BoxProblem;
+
+        fn specification(&self) -> Result<ProblemSpec> {
+            track!(ProblemSpecBuilder::new(&self.0)
+                .param(domain::var("x").continuous(0.0, 1.0))
+                .value(domain::var("y"))
+                .finish())
+        }
+
+        fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+            track_panic!(ErrorKind::Bug)
+        }
+    }
+    impl SolverFactory for DummyFactory {
+        type Solver = BoxSolver;
+
+        fn specification(&self) -> Result<SolverSpec> {
+            Ok(SolverSpecBuilder::new(&self.0).finish())
+        }
+
+        fn create_solver(&self, _rng: ArcRng, _problem: &ProblemSpec) -> Result<Self::Solver> {
+            track_panic!(ErrorKind::Bug)
+        }
+    }
+
+    fn dummy(name: &str) -> DummyRecipe {
+        DummyRecipe {
+            name: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn user_recipes_are_dispatched_by_name() -> TopLevelResult {
+        let mut registry = FactoryRegistry::new::<DummyRecipe, DummyRecipe>();
+        registry.register_problem_recipe::<DummyRecipe>("my_problem")?;
+        registry.register_solver_recipe::<DummyRecipe>("my_solver")?;
+        assert!(registry
+            .register_problem_recipe::<DummyRecipe>("my_problem")
+            .is_err());
+
+        let json = serde_json::json!({"my_problem": {"name": "Foo"}});
+        let factory = registry.create_problem_factory_from_json(&json)?;
+        assert_eq!(factory.specification()?.name, "Foo");
+
+        let json = serde_json::json!({"my_solver": {"name": "Bar"}});
+        let factory = registry.create_solver_factory_from_json(&json)?;
+        assert_eq!(factory.specification()?.name, "Bar");
+
+        // Unregistered names are given to the built-in recipe types.
+        let json = serde_json::json!({"name": "Baz"});
+        let factory = registry.create_problem_factory_from_json(&json)?;
+        assert_eq!(factory.specification()?.name, "Baz");
+
+        let json = serde_json::json!({"unknown": {"name": "Qux"}});
+        assert!(registry.create_problem_factory_from_json(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn named_factories_work() -> TopLevelResult {
+        let mut registry = FactoryRegistry::new::<DummyRecipe, DummyRecipe>();
+        registry.register_problem("foo", dummy("Foo"))?;
+        registry.register_problem("bar", dummy("Bar"))?;
+        registry.register_solver("baz", dummy("Baz"))?;
+        assert!(registry.register_problem("foo", dummy("Qux")).is_err());
+
+        assert_eq!(registry.problem_names().collect::<Vec<_>>(), ["bar", "foo"]);
+        assert_eq!(registry.solver_names().collect::<Vec<_>>(), ["baz"]);
+
+        assert_eq!(registry.get_problem("foo")?.specification()?.name, "Foo");
+        assert_eq!(registry.get_solver("baz")?.specification()?.name, "Baz");
+
+        let e = registry.get_problem("baz").expect_err("unknown problem");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        assert!(registry.get_solver("foo").is_err());
+        Ok(())
+    }
+}
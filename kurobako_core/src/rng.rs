@@ -1,22 +1,89 @@
 //! **R**andom **N**number **G**enerator.
 use crate::Result;
-use rand::rngs::StdRng;
+use rand::rngs::{StdRng, ThreadRng};
 use rand::{Error, RngCore, SeedableRng};
+use std::sync::atomic::{self, AtomicBool};
 use std::sync::{Arc, Mutex};
 
 pub use rand::Rng;
 
 /// The random number generator for `kurobako`.
+///
+/// Note that the clones of an instance share the same random number stream.
+/// Use `ArcRng::substream` to get an independent stream.
+///
+/// An instance and all of its clones and substreams also share a flag that indicates
+/// whether random numbers that cannot be reproduced by the seed have been consumed
+/// (see `ArcRng::unseeded_rng` and `ArcRng::unseeded_rng_used`).
 #[derive(Debug, Clone)]
-pub struct ArcRng(Arc<Mutex<StdRng>>);
+pub struct ArcRng {
+    inner: Arc<Mutex<StdRng>>,
+    seed: [u8; 32],
+    unseeded_rng_used: Arc<AtomicBool>,
+}
 impl ArcRng {
     /// Makes a new `ArcRng` with the given random seed.
     pub fn new(seed: u64) -> Self {
         let mut seed256 = [0; 32];
         seed256[0..8].copy_from_slice(&seed.to_be_bytes());
+        Self::from_seed(seed256)
+    }
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        let inner = StdRng::from_seed(seed);
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            seed,
+            unseeded_rng_used: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Makes a child RNG that has the random number stream identified by `index`.
+    ///
+    /// The seed of the child is derived from the seed of this instance and `index`,
+    /// so the result doesn't depend on how many random numbers have been drawn from this instance
+    /// (and drawing numbers from the child doesn't affect this instance and the other children).
+    pub fn substream(&self, index: u64) -> Self {
+        let mut state = splitmix64(index);
+        for chunk in self.seed.chunks(8) {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            state = splitmix64(state ^ u64::from_be_bytes(word));
+        }
+
+        let mut seed = [0; 32];
+        for chunk in seed.chunks_mut(8) {
+            state = splitmix64(state);
+            chunk.copy_from_slice(&state.to_be_bytes());
+        }
+        Self {
+            unseeded_rng_used: Arc::clone(&self.unseeded_rng_used),
+            ..Self::from_seed(seed)
+        }
+    }
 
-        let inner = StdRng::from_seed(seed256);
-        Self(Arc::new(Mutex::new(inner)))
+    /// Returns a non-seeded RNG (i.e., `rand::thread_rng()`).
+    ///
+    /// Calling this method marks this instance as having consumed a non-seeded RNG.
+    /// Because such random numbers cannot be reproduced by the seed,
+    /// this method should be used only where the random numbers of this instance can't be.
+    pub fn unseeded_rng(&self) -> ThreadRng {
+        self.mark_unseeded_rng_used();
+        rand::thread_rng()
+    }
+
+    /// Marks this instance as having consumed random numbers that cannot be reproduced by the seed.
+    ///
+    /// This is useful if such random numbers are consumed outside of this process
+    /// (e.g., by an external program that ignores the given seed).
+    pub fn mark_unseeded_rng_used(&self) {
+        self.unseeded_rng_used.store(true, atomic::Ordering::SeqCst);
+    }
+
+    /// Returns `true` if random numbers that cannot be reproduced by the seed have been consumed
+    /// via this instance, its clones or its substreams.
+    pub fn unseeded_rng_used(&self) -> bool {
+        self.unseeded_rng_used.load(atomic::Ordering::SeqCst)
     }
 
     /// Acquires the lock of this instance and invokes `f` with the internal RNG.
@@ -24,30 +91,85 @@ impl ArcRng {
     where
         F: FnOnce(&mut StdRng) -> T,
     {
-        let mut rng = track!(self.0.lock().map_err(crate::Error::from))?;
+        let mut rng = track!(self.inner.lock().map_err(crate::Error::from))?;
         Ok(f(&mut rng))
     }
 }
 impl RngCore for ArcRng {
     fn next_u32(&mut self) -> u32 {
-        self.0.lock().unwrap_or_else(|e| panic!("{}", e)).next_u32()
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next_u32()
     }
 
     fn next_u64(&mut self) -> u64 {
-        self.0.lock().unwrap_or_else(|e| panic!("{}", e)).next_u64()
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next_u64()
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        self.0
+        self.inner
             .lock()
             .unwrap_or_else(|e| panic!("{}", e))
             .fill_bytes(dest)
     }
 
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        self.0
+        self.inner
             .lock()
             .unwrap_or_else(|e| panic!("{}", e))
             .try_fill_bytes(dest)
     }
 }
+
+/// Samples a value from the standard normal distribution by using the Box-Muller transform.
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u0: f64 = 1.0 - rng.gen::<f64>();
+    let u1: f64 = rng.gen();
+    (-2.0 * u0.ln()).sqrt() * (2.0 * std::f64::consts::PI * u1).cos()
+}
+
+// See: https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substream_works() {
+        let mut rng = ArcRng::new(0);
+        let first = rng.substream(1).next_u64();
+
+        // Substreams don't depend on the draws from the parent.
+        rng.next_u64();
+        assert_eq!(rng.substream(1).next_u64(), first);
+
+        assert_ne!(rng.substream(2).next_u64(), first);
+        assert_ne!(ArcRng::new(1).substream(1).next_u64(), first);
+        assert_ne!(ArcRng::new(0).next_u64(), first);
+    }
+
+    #[test]
+    fn unseeded_rng_used_is_shared() {
+        let rng = ArcRng::new(0);
+        let child = rng.substream(1);
+        assert!(!rng.unseeded_rng_used());
+
+        let other = child.clone();
+        std::thread::spawn(move || other.unseeded_rng().next_u64())
+            .join()
+            .expect("thread panicked");
+        assert!(rng.unseeded_rng_used());
+        assert!(child.substream(2).unseeded_rng_used());
+        assert!(!ArcRng::new(0).unseeded_rng_used());
+    }
+}
@@ -2,8 +2,8 @@
 use crate::problem::ProblemSpec;
 use crate::registry::FactoryRegistry;
 use crate::rng::ArcRng;
-use crate::trial::{EvaluatedTrial, IdGen, NextTrial};
-use crate::Result;
+use crate::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use crate::{ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
@@ -158,6 +158,20 @@ pub trait Solver: Send {
 
     /// Tells the evaluation result of a trial.
     fn tell(&mut self, trial: EvaluatedTrial) -> Result<()>;
+
+    /// Tells an observation that wasn't asked by this solver (e.g., a trial evaluated in a past study).
+    ///
+    /// `id` is generated by the `IdGen` given to `ask`, so it never collides with the identifiers of asked trials.
+    /// The default implementation returns an `ErrorKind::InvalidInput` error
+    /// so that prior observations are never ignored silently.
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        let _ = (params, values);
+        track_panic!(
+            ErrorKind::InvalidInput,
+            "This solver doesn't support prior observations: {:?}",
+            id
+        );
+    }
 }
 
 /// Boxed solver.
@@ -179,6 +193,10 @@ impl Solver for BoxSolver {
     fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
         track!(self.0.tell(trial))
     }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        track!(self.0.tell_prior(id, params, values))
+    }
 }
 impl fmt::Debug for BoxSolver {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
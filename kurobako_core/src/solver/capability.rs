@@ -24,6 +24,9 @@ impl Capabilities {
             Capability::Conditional,
             Capability::MultiObjective,
             Capability::Concurrent,
+            Capability::CategoricalPrior,
+            Capability::Ordinal,
+            Capability::MultiFidelity,
         ]
         .iter()
         .copied()
@@ -47,8 +50,14 @@ impl Capabilities {
     }
 
     /// Iterates over the capabilities required by `required` but not owned by this instance.
+    ///
+    /// Note that advisory capabilities (see `Capability::is_advisory`) are never included.
     pub fn incapables<'a>(&'a self, required: &'a Self) -> impl 'a + Iterator<Item = Capability> {
-        required.0.difference(&self.0).copied()
+        required
+            .0
+            .difference(&self.0)
+            .copied()
+            .filter(|c| !c.is_advisory())
     }
 
     /// Iterates over all the capabilities that this instance has.
@@ -87,4 +96,31 @@ pub enum Capability {
 
     MultiObjective,
     Concurrent,
+
+    /// Prior weights of categorical choices.
+    ///
+    /// This is an advisory capability:
+    /// solvers that don't have this capability can safely ignore the weights.
+    CategoricalPrior,
+
+    /// Ordering of the choices of ordinal variables.
+    ///
+    /// This is an advisory capability:
+    /// solvers that don't have this capability can handle ordinal variables as categorical ones.
+    Ordinal,
+
+    /// Fidelity (budget) variables that solvers may control.
+    ///
+    /// This is an advisory capability:
+    /// solvers that don't have this capability can handle fidelity variables as ordinary ones.
+    MultiFidelity,
+}
+impl Capability {
+    /// Returns `true` if this capability is advisory (i.e., not mandatory to solve a problem).
+    pub fn is_advisory(self) -> bool {
+        matches!(
+            self,
+            Self::CategoricalPrior | Self::Ordinal | Self::MultiFidelity
+        )
+    }
 }
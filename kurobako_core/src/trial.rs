@@ -18,6 +18,18 @@ impl TrialId {
     pub const fn get(self) -> u64 {
         self.0
     }
+
+    /// Returns the namespace part (i.e., the upper 32 bits) of this identifier.
+    ///
+    /// This is always `0` for the identifiers generated by `IdGen::new()`.
+    pub const fn namespace(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    /// Returns the local part (i.e., the lower 32 bits) of this identifier.
+    pub const fn local(self) -> u32 {
+        self.0 as u32
+    }
 }
 
 /// A trial that has a parameter set to be evaluated.
@@ -87,6 +99,18 @@ impl IdGen {
         Self { next }
     }
 
+    /// Makes a new `IdGen` instance which generates identifiers belonging to the given namespace.
+    ///
+    /// The upper 32 bits of the generated identifiers are `namespace` and
+    /// the lower 32 bits are a counter started from `0`.
+    /// So, if each study uses a distinct namespace (e.g., a prefix of the study ID hash),
+    /// the identifiers are unique across the studies and are sorted by namespace and then by generation order.
+    pub const fn with_namespace(namespace: u32) -> Self {
+        Self {
+            next: (namespace as u64) << 32,
+        }
+    }
+
     /// Generates a new identifier.
     pub fn generate(&mut self) -> TrialId {
         let id = TrialId(self.next);
@@ -212,9 +236,7 @@ mod nullable_f64_vec {
         D: Deserializer<'de>,
     {
         let v: Vec<Option<f64>> = Deserialize::deserialize(deserializer)?;
-        Ok(v.into_iter()
-            .map(|v| if let Some(v) = v { v } else { f64::NAN })
-            .collect())
+        Ok(v.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
     }
 
     pub fn serialize<S>(v: &[f64], serializer: S) -> Result<S::Ok, S::Error>
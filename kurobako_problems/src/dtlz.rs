@@ -0,0 +1,321 @@
+//! Scalable multi-objective problems that take their name from their authors Deb, Thiele, Laumanns and Zitzler.
+//!
+//! Every function is defined on `[0, 1]^n` where `n` is the number of dimensions.
+//! The last `k = n - m + 1` parameters (where `m` is the number of objectives) are distance-related ones,
+//! and the objective values are Pareto-optimal if all of them are `0.5`.
+//!
+//! # References
+//!
+//! - [Scalable Test Problems for Evolutionary Multiobjective Optimization](https://link.springer.com/chapter/10.1007/1-84628-137-7_6)
+use kurobako_core::domain;
+use kurobako_core::problem::{
+    Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use structopt::StructOpt;
+
+/// Recipe of `DtlzProblem`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DtlzProblemRecipe {
+    /// Function number (`1`, `2`, `3` or `4`).
+    pub problem: u8,
+
+    /// Number of objectives.
+    #[structopt(long, default_value = "3")]
+    pub objectives: usize,
+
+    /// Number of dimensions of the parameter space.
+    ///
+    /// This must be greater than or equal to `objectives`.
+    #[structopt(long, default_value = "12")]
+    pub dimensions: usize,
+}
+impl ProblemRecipe for DtlzProblemRecipe {
+    type Factory = DtlzProblemFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let dtlz = match self.problem {
+            1 => Dtlz::Function1,
+            2 => Dtlz::Function2,
+            3 => Dtlz::Function3,
+            4 => Dtlz::Function4,
+            _ => track_panic!(
+                ErrorKind::InvalidInput,
+                "Unknown DTLZ function: {}",
+                self.problem
+            ),
+        };
+        track_assert!(
+            self.objectives >= 2,
+            ErrorKind::InvalidInput,
+            "DTLZ problems require at least two objectives: objectives={}",
+            self.objectives
+        );
+        track_assert!(
+            self.objectives <= self.dimensions,
+            ErrorKind::InvalidInput,
+            "The number of objectives must be less than or equal to the number of dimensions: \
+             objectives={}, dimensions={}",
+            self.objectives,
+            self.dimensions
+        );
+        Ok(DtlzProblemFactory {
+            dtlz,
+            objectives: self.objectives,
+            dimensions: self.dimensions,
+        })
+    }
+}
+
+/// Factory of `DtlzProblem`.
+#[derive(Debug)]
+pub struct DtlzProblemFactory {
+    dtlz: Dtlz,
+    objectives: usize,
+    dimensions: usize,
+}
+impl ProblemFactory for DtlzProblemFactory {
+    type Problem = DtlzProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        let name = format!(
+            "{}(objectives={}, dim={})",
+            self.dtlz.name(),
+            self.objectives,
+            self.dimensions
+        );
+        let nadir = match self.dtlz {
+            Dtlz::Function1 => 0.5,
+            _ => 1.0,
+        };
+        let mut spec = ProblemSpecBuilder::new(&name)
+            .attr(
+                "version",
+                &format!("kurobako_problems={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr(
+                "paper",
+                "Deb, Kalyanmoy, et al. \"Scalable test problems for evolutionary multiobjective \
+                 optimization.\" Evolutionary multiobjective optimization. Springer, London, 2005. 105-145.",
+            )
+            .attr("ideal_point", &format!("{:?}", vec![0.0; self.objectives]))
+            .attr("nadir_point", &format!("{:?}", vec![nadir; self.objectives]));
+
+        for i in 0..self.dimensions {
+            spec = spec.param(domain::var(&format!("x{}", i)).continuous(0.0, 1.0));
+        }
+        for i in 0..self.objectives {
+            spec = spec.value(domain::var(&format!("f{}", i + 1)));
+        }
+        track!(spec.finish())
+    }
+
+    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+        Ok(DtlzProblem {
+            dtlz: self.dtlz,
+            objectives: self.objectives,
+        })
+    }
+}
+
+/// DTLZ problem.
+#[derive(Debug)]
+pub struct DtlzProblem {
+    dtlz: Dtlz,
+    objectives: usize,
+}
+impl Problem for DtlzProblem {
+    type Evaluator = DtlzEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        Ok(DtlzEvaluator {
+            params,
+            dtlz: self.dtlz,
+            objectives: self.objectives,
+        })
+    }
+}
+
+/// Evaluator of `DtlzProblem`.
+#[derive(Debug)]
+pub struct DtlzEvaluator {
+    params: Params,
+    dtlz: Dtlz,
+    objectives: usize,
+}
+impl Evaluator for DtlzEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        let values = self.dtlz.evaluate(self.params.get(), self.objectives);
+        Ok((next_step, Values::new(values)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Dtlz {
+    // Linear Pareto-optimal front (`sum(f) = 0.5`) with `11^k - 1` local fronts.
+    Function1,
+
+    // Spherical Pareto-optimal front (`sum(f^2) = 1`).
+    Function2,
+
+    // DTLZ2's front with `3^k - 1` local fronts.
+    Function3,
+
+    // DTLZ2's front with a biased density of solutions (the parameters are mapped by `x^100`).
+    Function4,
+}
+impl Dtlz {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Function1 => "DTLZ1",
+            Self::Function2 => "DTLZ2",
+            Self::Function3 => "DTLZ3",
+            Self::Function4 => "DTLZ4",
+        }
+    }
+
+    fn evaluate(self, xs: &[f64], objectives: usize) -> Vec<f64> {
+        let (position, distance) = xs.split_at(objectives - 1);
+        match self {
+            Self::Function1 => {
+                let g = rastrigin_like_g(distance);
+                linear_front(position, 0.5 * (1.0 + g))
+            }
+            Self::Function2 => spherical_front(position, 1.0 + sphere_g(distance)),
+            Self::Function3 => spherical_front(position, 1.0 + rastrigin_like_g(distance)),
+            Self::Function4 => {
+                let position = position.iter().map(|x| x.powi(100)).collect::<Vec<_>>();
+                spherical_front(&position, 1.0 + sphere_g(distance))
+            }
+        }
+    }
+}
+
+fn sphere_g(xs: &[f64]) -> f64 {
+    xs.iter().map(|x| (x - 0.5).powi(2)).sum()
+}
+
+fn rastrigin_like_g(xs: &[f64]) -> f64 {
+    let sum = xs
+        .iter()
+        .map(|x| (x - 0.5).powi(2) - (20.0 * PI * (x - 0.5)).cos())
+        .sum::<f64>();
+    100.0 * (xs.len() as f64 + sum)
+}
+
+// `f_i = scale * x_0 * ... * x_{m-i-1} * (1 - x_{m-i})`.
+fn linear_front(xs: &[f64], scale: f64) -> Vec<f64> {
+    front(xs, scale, |x| x, |x| 1.0 - x)
+}
+
+// `f_i = scale * cos(x_0 * pi / 2) * ... * cos(x_{m-i-1} * pi / 2) * sin(x_{m-i} * pi / 2)`.
+fn spherical_front(xs: &[f64], scale: f64) -> Vec<f64> {
+    front(
+        xs,
+        scale,
+        |x| (x * PI / 2.0).cos(),
+        |x| (x * PI / 2.0).sin(),
+    )
+}
+
+fn front<F, G>(xs: &[f64], scale: f64, f: F, g: G) -> Vec<f64>
+where
+    F: Fn(f64) -> f64,
+    G: Fn(f64) -> f64,
+{
+    let m = xs.len() + 1;
+    (0..m)
+        .map(|i| {
+            let v = xs[..m - 1 - i].iter().map(|&x| f(x)).product::<f64>();
+            if i == 0 {
+                scale * v
+            } else {
+                scale * v * g(xs[m - 1 - i])
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::epi::solver::ExternalProgramSolverRecipe;
+    use trackable::result::TopLevelResult;
+
+    fn approx_eq(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn dtlz1_works() {
+        // Pareto-optimal points (the distance-related parameters are `0.5`).
+        let xs = [0.0, 0.0, 0.5, 0.5, 0.5, 0.5, 0.5];
+        assert!(approx_eq(
+            &Dtlz::Function1.evaluate(&xs, 3),
+            &[0.0, 0.0, 0.5]
+        ));
+
+        let xs = [1.0, 1.0, 0.5, 0.5, 0.5, 0.5, 0.5];
+        assert!(approx_eq(
+            &Dtlz::Function1.evaluate(&xs, 3),
+            &[0.5, 0.0, 0.0]
+        ));
+
+        let xs = [0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5];
+        let values = Dtlz::Function1.evaluate(&xs, 3);
+        assert!(approx_eq(&values, &[0.125, 0.125, 0.25]));
+        assert!((values.iter().sum::<f64>() - 0.5).abs() < 1e-9);
+
+        // Non-optimal point.
+        let xs = [0.5, 0.5, 0.0, 0.5, 0.5, 0.5, 0.5];
+        let values = Dtlz::Function1.evaluate(&xs, 3);
+        assert!(values.iter().sum::<f64>() > 0.5);
+    }
+
+    #[test]
+    fn dtlz2_works() {
+        let xs = [0.5, 0.5, 0.5, 0.5, 0.5];
+        let values = Dtlz::Function2.evaluate(&xs, 3);
+        assert!((values.iter().map(|v| v * v).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(approx_eq(
+            &Dtlz::Function3.evaluate(&xs, 3),
+            &Dtlz::Function2.evaluate(&xs, 3)
+        ));
+    }
+
+    #[test]
+    fn dtlz_recipe_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<DtlzProblemRecipe, ExternalProgramSolverRecipe>();
+
+        let recipe = DtlzProblemRecipe {
+            problem: 1,
+            objectives: 3,
+            dimensions: 7,
+        };
+        let spec = recipe.create_factory(&registry)?.specification()?;
+        assert_eq!(spec.name, "DTLZ1(objectives=3, dim=7)");
+        assert_eq!(spec.params_domain.variables().len(), 7);
+        assert_eq!(spec.values_domain.variables().len(), 3);
+
+        let recipe = DtlzProblemRecipe {
+            problem: 2,
+            objectives: 4,
+            dimensions: 3,
+        };
+        assert!(recipe.create_factory(&registry).is_err());
+
+        let recipe = DtlzProblemRecipe {
+            problem: 5,
+            objectives: 3,
+            dimensions: 12,
+        };
+        assert!(recipe.create_factory(&registry).is_err());
+        Ok(())
+    }
+}
@@ -4,9 +4,11 @@
 #[macro_use]
 extern crate trackable;
 
+pub mod dtlz;
 pub mod hpobench;
 pub mod nasbench;
 pub mod sigopt;
 pub mod surrogate;
+pub mod table;
 pub mod warm_starting;
 pub mod zdt;
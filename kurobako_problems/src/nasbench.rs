@@ -10,7 +10,7 @@ use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::{ArcRng, Rng};
 use kurobako_core::trial::{Params, Values};
 use kurobako_core::{Error, ErrorKind, Result};
-use nasbench::{AdjacencyMatrix, ModelSpec, NasBench, Op};
+use nasbench::{AdjacencyMatrix, EpochStats, ModelSpec, ModelStats, NasBench, Op};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -52,6 +52,14 @@ pub struct NasbenchProblemRecipe {
     )]
     #[serde(default = "default_metrics")]
     pub metrics: Vec<Metric>,
+
+    /// If this flag is set, the problem can be evaluated at any epoch (step) and
+    /// the validation accuracy between the recorded epochs is linearly interpolated.
+    ///
+    /// Otherwise, the problem can be evaluated only at the recorded epochs (i.e., 4, 12, 36 and 108).
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub interpolate: bool,
 }
 impl ProblemRecipe for NasbenchProblemRecipe {
     type Factory = NasbenchProblemFactory;
@@ -67,21 +75,40 @@ impl ProblemRecipe for NasbenchProblemRecipe {
                     Arc::new(track!(NasBench::new(&self.dataset))?),
                 );
             }
+            let nasbench = Arc::clone(&map[&self.dataset]);
+            let max_params = nasbench
+                .models()
+                .values()
+                .map(|m| m.trainable_parameters)
+                .max()
+                .unwrap_or(0);
             Ok(NasbenchProblemFactory {
-                nasbench: Arc::clone(&map[&self.dataset]),
+                nasbench,
+                max_params,
                 encoding: self.encoding,
                 metrics: self.metrics.clone(),
+                interpolate: self.interpolate,
             })
         })
     }
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
+
 /// Factory of `NasbenchProblem`.
 #[derive(Debug)]
 pub struct NasbenchProblemFactory {
     nasbench: Arc<NasBench>,
+
+    // The maximum number of the trainable parameters of the models in the dataset.
+    max_params: u32,
+
     encoding: Encoding,
     metrics: Vec<Metric>,
+    interpolate: bool,
 }
 impl ProblemFactory for NasbenchProblemFactory {
     type Problem = NasbenchProblem;
@@ -98,8 +125,12 @@ impl ProblemFactory for NasbenchProblemFactory {
                  neural architecture search.\" arXiv preprint arXiv:1902.09635 (2019).",
             )
             .attr("github", "https://github.com/automl/nas_benchmarks")
-            .params(self.encoding.params())
-            .steps(vec![4, 12, 36, 108]);
+            .params(self.encoding.params());
+        if self.interpolate {
+            spec = spec.attr("interpolate", "true").steps(1..=108);
+        } else {
+            spec = spec.steps(vec![4, 12, 36, 108]);
+        }
         for metric in &self.metrics {
             match metric {
                 Metric::Accuracy => {
@@ -112,6 +143,20 @@ impl ProblemFactory for NasbenchProblemFactory {
             }
         }
 
+        // The default reference point (`100.0`) is too small for the number of parameters,
+        // so the worst possible value of each objective is used instead.
+        if self.metrics.len() > 1 {
+            let reference_point = self
+                .metrics
+                .iter()
+                .map(|metric| match metric {
+                    Metric::Accuracy => 1.0,
+                    Metric::Params => f64::from(self.max_params),
+                })
+                .collect();
+            spec = spec.reference_point(Some(Params::new(reference_point)));
+        }
+
         track!(spec.finish())
     }
 
@@ -120,6 +165,7 @@ impl ProblemFactory for NasbenchProblemFactory {
             nasbench: Arc::clone(&self.nasbench),
             encoding: self.encoding,
             metrics: self.metrics.clone(),
+            interpolate: self.interpolate,
             rng,
         })
     }
@@ -131,6 +177,7 @@ pub struct NasbenchProblem {
     nasbench: Arc<NasBench>,
     encoding: Encoding,
     metrics: Vec<Metric>,
+    interpolate: bool,
     rng: ArcRng,
 }
 impl Problem for NasbenchProblem {
@@ -161,6 +208,7 @@ impl Problem for NasbenchProblem {
         Ok(NasbenchEvaluator {
             nasbench: Arc::clone(&self.nasbench),
             metrics: self.metrics.clone(),
+            interpolate: self.interpolate,
             model_spec,
             sample_index: track!(self.rng.with_lock(|rng| rng.gen()))?,
         })
@@ -172,29 +220,52 @@ impl Problem for NasbenchProblem {
 pub struct NasbenchEvaluator {
     nasbench: Arc<NasBench>,
     metrics: Vec<Metric>,
+    interpolate: bool,
     model_spec: ModelSpec,
     sample_index: usize,
 }
+impl NasbenchEvaluator {
+    // Returns the validation accuracy at the given epoch and the epoch at which the accuracy is evaluated.
+    fn validation_accuracy(&self, model: &ModelStats, epoch_num: u8) -> Result<(u8, f64)> {
+        let accuracy = |epochs: &[EpochStats]| {
+            epochs[self.sample_index % epochs.len()]
+                .complete
+                .validation_accuracy
+        };
+
+        let upper = model
+            .epochs
+            .range((Bound::Included(epoch_num), Bound::Unbounded))
+            .next();
+        let lower = model.epochs.range(..epoch_num).next_back();
+        match (lower, upper) {
+            (Some((&e0, a0)), Some((&e1, a1))) if self.interpolate && e1 != epoch_num => {
+                let r = f64::from(epoch_num - e0) / f64::from(e1 - e0);
+                let accuracy = accuracy(a0) + (accuracy(a1) - accuracy(a0)) * r;
+                Ok((epoch_num, accuracy))
+            }
+            (_, Some((&epoch, epochs))) => Ok((epoch, accuracy(epochs))),
+            (_, None) => track_panic!(
+                ErrorKind::InvalidInput,
+                "No recorded epochs after {}",
+                epoch_num
+            ),
+        }
+    }
+}
 impl Evaluator for NasbenchEvaluator {
     fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
         let model =
             track_assert_some!(self.nasbench.models().get(&self.model_spec), ErrorKind::Bug);
 
         let epoch_num = next_step as u8;
-        let (current_step, epoch_candidates) = track_assert_some!(
-            model
-                .epochs
-                .range((Bound::Included(epoch_num), Bound::Unbounded))
-                .next(),
-            ErrorKind::InvalidInput
-        );
-        let epoch = &epoch_candidates[self.sample_index % epoch_candidates.len()];
+        let (current_step, accuracy) = track!(self.validation_accuracy(model, epoch_num))?;
 
         let mut values = Vec::new();
         for metric in &self.metrics {
             match metric {
                 Metric::Accuracy => {
-                    values.push(1.0 - epoch.complete.validation_accuracy);
+                    values.push(1.0 - accuracy);
                 }
                 Metric::Params => {
                     values.push(model.trainable_parameters as f64);
@@ -202,7 +273,7 @@ impl Evaluator for NasbenchEvaluator {
             }
         }
 
-        Ok((u64::from(*current_step), Values::new(values)))
+        Ok((u64::from(current_step), Values::new(values)))
     }
 }
 
@@ -373,3 +444,97 @@ impl FromStr for Metric {
 fn default_metrics() -> Vec<Metric> {
     vec![Metric::Accuracy]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    // Makes a dataset that contains a single model that has `params` trainable parameters
+    // and whose validation accuracies at the epochs 12 and 36 are `0.5` and `0.8` respectively.
+    fn dataset(params: u32) -> Result<NasBench> {
+        let mut bytes = b"nasbench-rs".to_vec();
+        bytes.extend_from_slice(&1u32.to_be_bytes()); // number of models
+        bytes.extend_from_slice(&[2, 0, 4]); // ops (input and output)
+        bytes.push(2); // dimension of the adjacency matrix
+        bytes.extend_from_slice(&1u32.to_be_bytes()); // edges (upper triangle)
+        bytes.extend_from_slice(&0u128.to_be_bytes()); // module hash
+        bytes.extend_from_slice(&params.to_be_bytes());
+        bytes.push(2); // number of epochs
+        for &(epoch, accuracy) in &[(12u8, 0.5f64), (36, 0.8)] {
+            bytes.extend_from_slice(&[epoch, 1]); // epoch number and number of stats
+            for _ in 0..2 {
+                // training time, training accuracy, validation accuracy and test accuracy
+                for v in &[0.0, 0.0, accuracy, 0.0] {
+                    bytes.extend_from_slice(&f64::to_be_bytes(*v));
+                }
+            }
+        }
+        track!(NasBench::from_reader(&bytes[..]).map_err(Error::from))
+    }
+
+    #[test]
+    fn multi_metric_spec_has_reference_point() -> TopLevelResult {
+        let nasbench = Arc::new(dataset(1_234_567)?);
+        let factory = NasbenchProblemFactory {
+            nasbench: Arc::clone(&nasbench),
+            max_params: 1_234_567,
+            encoding: Encoding::A,
+            metrics: vec![Metric::Accuracy, Metric::Params],
+            interpolate: false,
+        };
+        let spec = factory.specification()?;
+        let json = serde_json::to_value(&spec).map_err(Error::from)?;
+        let reference_point: Vec<f64> =
+            serde_json::from_value(json["reference_point"].clone()).map_err(Error::from)?;
+        assert_eq!(reference_point, [1.0, 1_234_567.0]);
+
+        let factory = NasbenchProblemFactory {
+            nasbench,
+            max_params: 1_234_567,
+            encoding: Encoding::A,
+            metrics: vec![Metric::Accuracy],
+            interpolate: false,
+        };
+        assert!(factory.specification()?.reference_point.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn interpolation_works() -> TopLevelResult {
+        let nasbench = Arc::new(dataset(100)?);
+        let model_spec = nasbench
+            .models()
+            .keys()
+            .next()
+            .cloned()
+            .unwrap_or_else(|| unreachable!());
+        let evaluate = |interpolate, step| {
+            let mut evaluator = NasbenchEvaluator {
+                nasbench: Arc::clone(&nasbench),
+                metrics: vec![Metric::Accuracy],
+                interpolate,
+                model_spec: model_spec.clone(),
+                sample_index: 0,
+            };
+            evaluator
+                .evaluate(step)
+                .map(|(step, values)| (step, values.into_vec()[0]))
+        };
+
+        // Snapped up to the next recorded epoch.
+        let (step, value) = evaluate(false, 20)?;
+        assert_eq!(step, 36);
+        assert!((value - 0.2).abs() < 1e-12);
+
+        // Interpolated between the epochs 12 and 36.
+        let (step, value) = evaluate(true, 20)?;
+        assert_eq!(step, 20);
+        assert!((value - (1.0 - (0.5 + 0.3 * 8.0 / 24.0))).abs() < 1e-12);
+
+        // Recorded epochs and the epochs before the first recorded one aren't interpolated.
+        assert_eq!(evaluate(true, 36)?, evaluate(false, 36)?);
+        assert_eq!(evaluate(true, 4)?, (12, 0.5));
+        Ok(())
+    }
+}
@@ -8,19 +8,28 @@
 //! [sigopt/evalset]: https://github.com/sigopt/evalset
 #![allow(clippy::format_push_string)]
 use self::functions::TestFunction;
+use self::transform::AffineTransform;
 use kurobako_core::domain;
 use kurobako_core::problem::{
     Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
 };
 use kurobako_core::registry::FactoryRegistry;
-use kurobako_core::rng::ArcRng;
+use kurobako_core::rng::{standard_normal, ArcRng};
 use kurobako_core::trial::{Params, Values};
-use kurobako_core::{ErrorKind, Result};
+use kurobako_core::{Error, ErrorKind, Result};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
 use structopt::StructOpt;
+use trackable::error::ErrorKindExt as _;
+
+pub use self::transform::Transform;
+
+pub mod constrained;
 
 mod bessel;
 mod functions;
+mod transform;
 
 /// Recipe of `SigoptProblem`.
 #[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
@@ -47,19 +56,45 @@ pub struct SigoptProblemRecipe {
     #[serde(default)]
     #[structopt(long)]
     pub int: Vec<usize>,
+
+    /// Standard deviation of the gaussian noise added to the values of the test function.
+    ///
+    /// The noise is sampled from the RNG of the problem, so it's reproducible with the same seed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[structopt(long)]
+    pub noise_stddev: Option<f64>,
+
+    /// Affine transform applied to the inputs of the test function.
+    #[serde(skip_serializing_if = "Transform::is_identity")]
+    #[serde(default)]
+    #[structopt(flatten)]
+    pub transform: Transform,
 }
 impl ProblemRecipe for SigoptProblemRecipe {
     type Factory = SigoptProblemFactory;
 
     fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
         let test_function = self.name.to_test_function();
+        let dim = self
+            .dim
+            .unwrap_or_else(|| test_function.default_dimension());
+        let bounds = track!(test_function.bounds(dim))?;
+        if let Some(stddev) = self.noise_stddev {
+            track_assert!(
+                stddev.is_finite() && stddev >= 0.0,
+                ErrorKind::InvalidInput,
+                "`noise_stddev` must be a non-negative number: {}",
+                stddev
+            );
+        }
         Ok(SigoptProblemFactory {
             name: self.name,
-            dim: self
-                .dim
-                .unwrap_or_else(|| test_function.default_dimension()),
+            dim,
             res: self.res,
             int: self.int.clone(),
+            noise_stddev: self.noise_stddev,
+            transform: self.transform.generate(&bounds).map(Arc::new),
         })
     }
 }
@@ -71,6 +106,8 @@ pub struct SigoptProblemFactory {
     dim: usize,
     res: Option<f64>,
     int: Vec<usize>,
+    noise_stddev: Option<f64>,
+    transform: Option<Arc<AffineTransform>>,
 }
 impl ProblemFactory for SigoptProblemFactory {
     type Problem = SigoptProblem;
@@ -85,6 +122,17 @@ impl ProblemFactory for SigoptProblemFactory {
         if !self.int.is_empty() {
             problem_name += &format!(", int={:?}", self.int);
         }
+        if let Some(stddev) = self.noise_stddev {
+            problem_name += &format!(", noise_stddev={}", stddev);
+        }
+        if let Some(transform) = &self.transform {
+            if transform.rotation.is_some() {
+                problem_name += ", rotated";
+            }
+            if transform.shift.is_some() {
+                problem_name += ", shifted";
+            }
+        }
         problem_name += ")";
 
         let paper = "Dewancker, Ian, et al. \"A strategy for ranking optimization methods using multiple criteria.\" Workshop on Automatic Machine Learning. 2016.";
@@ -96,6 +144,27 @@ impl ProblemFactory for SigoptProblemFactory {
             )
             .attr("paper", paper)
             .attr("github", "https://github.com/sigopt/evalset");
+        if test_function.revision() > 0 {
+            // The results of different revisions of a function aren't comparable.
+            spec = spec.attr("revision", &test_function.revision().to_string());
+        }
+        if let Some(transform) = &self.transform {
+            let json = track!(serde_json::to_string(&**transform).map_err(Error::from))?;
+            spec = spec.attr("transform", &json);
+        }
+        if let Some(stddev) = self.noise_stddev {
+            spec = spec.attr("noise_stddev", &stddev.to_string());
+        }
+        if self.transform.is_none() && self.int.is_empty() {
+            // A transformed function may not reach the optimum within the bounds,
+            // and the optimum may not be an integer point.
+            if let Some(mut optimum) = test_function.global_optimum(self.dim) {
+                if let Some(res) = self.res {
+                    optimum = (optimum * res).floor() / res;
+                }
+                spec = spec.attr("global_optimum", &optimum.to_string());
+            }
+        }
 
         for (i, (low, high)) in track!(test_function.bounds(self.dim))?
             .into_iter()
@@ -111,13 +180,16 @@ impl ProblemFactory for SigoptProblemFactory {
             }
         }
 
+        // Note that the range of the objective value is unbounded, so noisy values are always inside it.
         track!(spec.value(domain::var("Objective Value")).finish())
     }
 
-    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+    fn create_problem(&self, rng: ArcRng) -> Result<Self::Problem> {
         Ok(SigoptProblem {
             name: self.name,
             res: self.res,
+            noise: self.noise_stddev.map(|stddev| (stddev, rng)),
+            transform: self.transform.clone(),
         })
     }
 }
@@ -127,6 +199,8 @@ impl ProblemFactory for SigoptProblemFactory {
 pub struct SigoptProblem {
     name: Name,
     res: Option<f64>,
+    noise: Option<(f64, ArcRng)>,
+    transform: Option<Arc<AffineTransform>>,
 }
 impl Problem for SigoptProblem {
     type Evaluator = SigoptEvaluator;
@@ -134,7 +208,9 @@ impl Problem for SigoptProblem {
     fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
         Ok(SigoptEvaluator {
             res: self.res,
+            noise: self.noise.clone(),
             test_function: self.name.to_test_function(),
+            transform: self.transform.clone(),
             params,
         })
     }
@@ -144,14 +220,24 @@ impl Problem for SigoptProblem {
 #[derive(Debug)]
 pub struct SigoptEvaluator {
     res: Option<f64>,
+    noise: Option<(f64, ArcRng)>,
     test_function: Box<dyn TestFunction>,
+    transform: Option<Arc<AffineTransform>>,
     params: Params,
 }
 impl Evaluator for SigoptEvaluator {
     fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
         track_assert_eq!(next_step, 1, ErrorKind::Bug);
 
-        let mut value = self.test_function.evaluate(self.params.get());
+        let mut value = if let Some(transform) = &self.transform {
+            self.test_function
+                .evaluate(&transform.apply(self.params.get()))
+        } else {
+            self.test_function.evaluate(self.params.get())
+        };
+        if let Some((stddev, rng)) = &mut self.noise {
+            value += *stddev * standard_normal(rng);
+        }
         if let Some(res) = self.res {
             value = (value * res).floor() / res;
         }
@@ -209,7 +295,7 @@ pub enum Name {
     // Gear,
     // Giunta,
     // GoldsteinPrice,
-    // Griewank,
+    Griewank,
     // Hansen,
     Hartmann3,
     // Hartmann4,
@@ -225,9 +311,10 @@ pub enum Name {
     // Langermann,
     LennardJones6,
     // Leon,
+    Levy,
     // Levy03,
     // Levy05,
-    // Levy13,
+    Levy13,
     // Matyas,
     // McCormick,
     McCourt01,
@@ -307,7 +394,7 @@ pub enum Name {
     // Schwefel06,
     Schwefel20,
     // Schwefel22,
-    // Schwefel26,
+    Schwefel26,
     Schwefel36,
     Shekel05,
     Shekel07,
@@ -358,6 +445,14 @@ pub enum Name {
     // Problem21,
     // Problem22,
 }
+impl FromStr for Name {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        track!(Self::from_iter_safe(["name", s].iter())
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e.message))))
+    }
+}
 impl Name {
     fn to_test_function(self) -> Box<dyn TestFunction> {
         match self {
@@ -372,11 +467,14 @@ impl Name {
             Self::DeflectedCorrugatedSpring => Box::new(functions::DeflectedCorrugatedSpring),
             Self::Easom => Box::new(functions::Easom),
             Self::Exponential => Box::new(functions::Exponential),
+            Self::Griewank => Box::new(functions::Griewank),
             Self::Hartmann3 => Box::new(functions::Hartmann3),
             Self::Hartmann6 => Box::new(functions::Hartmann6),
             Self::HelicalValley => Box::new(functions::HelicalValley),
             Self::HimmelBlau => Box::new(functions::HimmelBlau),
             Self::LennardJones6 => Box::new(functions::LennardJones6),
+            Self::Levy => Box::new(functions::Levy),
+            Self::Levy13 => Box::new(functions::Levy13),
             Self::McCourt01 => Box::new(functions::McCourt01),
             Self::McCourt02 => Box::new(functions::McCourt02),
             Self::McCourt03 => Box::new(functions::McCourt03),
@@ -412,6 +510,7 @@ impl Name {
             Self::RosenbrockLog => Box::new(functions::RosenbrockLog),
             Self::Sargan => Box::new(functions::Sargan),
             Self::Schwefel20 => Box::new(functions::Schwefel20),
+            Self::Schwefel26 => Box::new(functions::Schwefel26),
             Self::Schwefel36 => Box::new(functions::Schwefel36),
             Self::Shekel05 => Box::new(functions::Shekel05),
             Self::Shekel07 => Box::new(functions::Shekel07),
@@ -426,3 +525,99 @@ impl Name {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    fn evaluate(problem: &SigoptProblem, xs: &[f64]) -> Result<f64> {
+        let mut evaluator = track!(problem.create_evaluator(Params::new(xs.to_owned())))?;
+        let (_, values) = track!(evaluator.evaluate(1))?;
+        Ok(values[0])
+    }
+
+    #[test]
+    fn shifted_sphere_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<
+            SigoptProblemRecipe,
+            kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+        >();
+        let mut recipe: SigoptProblemRecipe =
+            serde_json::from_str(r#"{"name":"SPHERE","dim":2}"#).map_err(Error::from)?;
+        let factory = recipe.create_factory(&registry)?;
+        let spec = factory.specification()?;
+        assert!(!spec.attrs.contains_key("transform"));
+        assert_eq!(spec.parse_attr::<f64>("global_optimum")?, Some(0.0));
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        assert_eq!(evaluate(&problem, &[0.0, 0.0])?, 0.0);
+
+        recipe.transform.shift = true;
+        recipe.transform.seed = 10;
+        let factory = recipe.create_factory(&registry)?;
+        let spec = factory.specification()?;
+        let transform: AffineTransform =
+            track_assert_some!(spec.parse_attr("transform")?, ErrorKind::Bug);
+        let shift = track_assert_some!(transform.shift, ErrorKind::Bug);
+        assert!(!spec.attrs.contains_key("global_optimum"));
+        assert!(shift.iter().all(|&o| o != 0.0));
+
+        // The optimum is moved to the shift vector.
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        assert_eq!(evaluate(&problem, &shift)?, 0.0);
+        assert!(evaluate(&problem, &[0.0, 0.0])? > 0.0);
+
+        // Rotation keeps the center of the bounds (i.e., `[-1.5, -1.5]`).
+        recipe.transform.rotate = true;
+        recipe.transform.shift = false;
+        let problem = recipe
+            .create_factory(&registry)?
+            .create_problem(ArcRng::new(0))?;
+        let value = evaluate(&problem, &[-1.5, -1.5])?;
+        assert!((value - 4.5).abs() < 1e-9, "value={}", value);
+        Ok(())
+    }
+
+    #[test]
+    fn global_optimum_is_omitted_for_int_params() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<
+            SigoptProblemRecipe,
+            kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+        >();
+        let recipe: SigoptProblemRecipe =
+            serde_json::from_str(r#"{"name":"ACKLEY","dim":2}"#).map_err(Error::from)?;
+        let spec = recipe.create_factory(&registry)?.specification()?;
+        assert_eq!(spec.parse_attr::<f64>("global_optimum")?, Some(0.0));
+
+        let recipe: SigoptProblemRecipe =
+            serde_json::from_str(r#"{"name":"ACKLEY","dim":2,"int":[0]}"#).map_err(Error::from)?;
+        let spec = recipe.create_factory(&registry)?.specification()?;
+        assert!(!spec.attrs.contains_key("global_optimum"));
+        Ok(())
+    }
+
+    #[test]
+    fn noisy_sphere_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<
+            SigoptProblemRecipe,
+            kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+        >();
+        let recipe: SigoptProblemRecipe =
+            serde_json::from_str(r#"{"name":"SPHERE","dim":2,"noise_stddev":0.5}"#)
+                .map_err(Error::from)?;
+        let factory = recipe.create_factory(&registry)?;
+        let spec = factory.specification()?;
+        assert_eq!(spec.parse_attr::<f64>("noise_stddev")?, Some(0.5));
+
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        let v0 = evaluate(&problem, &[1.0, 2.0])?;
+        let v1 = evaluate(&problem, &[1.0, 2.0])?;
+        assert_ne!(v0, v1);
+        assert!(spec.values_domain.variables()[0].range().contains(v0));
+
+        // The same seed yields the same noise.
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        assert_eq!(evaluate(&problem, &[1.0, 2.0])?, v0);
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! A constrained single-objective problem composed of the test functions of [sigopt/evalset].
+//!
+//! The objective function and the constraint functions share the same parameter domain
+//! (i.e., the domain of the objective function).
+//! Parameters are feasible if `constraint(params) - threshold <= 0` holds for every constraint.
+//! Evaluating infeasible parameters results in an `ErrorKind::UnevaluableParams` error,
+//! so solvers are told that the trials have no values.
+//!
+//! [sigopt/evalset]: https://github.com/sigopt/evalset
+use super::functions::TestFunction;
+use super::Name;
+use kurobako_core::domain;
+use kurobako_core::problem::{
+    Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+/// Recipe of `ConstrainedSigoptProblem`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ConstrainedSigoptProblemRecipe {
+    /// Objective test function name.
+    #[structopt(subcommand)]
+    pub objective: Name,
+
+    /// Constraint test function names.
+    #[structopt(long = "constraint", number_of_values = 1, required = true)]
+    pub constraints: Vec<Name>,
+
+    /// Thresholds of the constraints.
+    ///
+    /// The i-th threshold is applied to the i-th constraint.
+    /// If omitted, `0.0` is used for every constraint.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    #[structopt(long = "threshold", number_of_values = 1)]
+    pub thresholds: Vec<f64>,
+
+    /// Dimension of the test functions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[structopt(long)]
+    pub dim: Option<usize>,
+}
+impl ProblemRecipe for ConstrainedSigoptProblemRecipe {
+    type Factory = ConstrainedSigoptProblemFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        track_assert!(!self.constraints.is_empty(), ErrorKind::InvalidInput);
+        let thresholds = if self.thresholds.is_empty() {
+            vec![0.0; self.constraints.len()]
+        } else {
+            track_assert_eq!(
+                self.thresholds.len(),
+                self.constraints.len(),
+                ErrorKind::InvalidInput
+            );
+            self.thresholds.clone()
+        };
+
+        let dim = self
+            .dim
+            .unwrap_or_else(|| self.objective.to_test_function().default_dimension());
+        track!(self.objective.to_test_function().bounds(dim); self.objective)?;
+        for constraint in &self.constraints {
+            let bounds = constraint.to_test_function().bounds(dim);
+            track_assert!(
+                bounds.is_ok(),
+                ErrorKind::InvalidInput,
+                "The constraint {:?} doesn't support the dimension of the objective: dim={}",
+                constraint,
+                dim
+            );
+        }
+
+        Ok(ConstrainedSigoptProblemFactory {
+            objective: self.objective,
+            constraints: self.constraints.clone(),
+            thresholds,
+            dim,
+        })
+    }
+}
+
+/// Factory of `ConstrainedSigoptProblem`.
+#[derive(Debug)]
+pub struct ConstrainedSigoptProblemFactory {
+    objective: Name,
+    constraints: Vec<Name>,
+    thresholds: Vec<f64>,
+    dim: usize,
+}
+impl ProblemFactory for ConstrainedSigoptProblemFactory {
+    type Problem = ConstrainedSigoptProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        let constraints = self
+            .constraints
+            .iter()
+            .zip(self.thresholds.iter())
+            .map(|(c, t)| format!("{:?}<={}", c, t))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let problem_name = format!(
+            "sigopt/evalset/{:?}(dim={}) s.t. {}",
+            self.objective, self.dim, constraints
+        );
+
+        let mut spec = ProblemSpecBuilder::new(&problem_name)
+            .attr(
+                "version",
+                &format!("kurobako_problems={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr("github", "https://github.com/sigopt/evalset")
+            .attr("constraints", &constraints);
+
+        let objective = self.objective.to_test_function();
+        for (i, (low, high)) in track!(objective.bounds(self.dim))?.into_iter().enumerate() {
+            spec = spec.param(domain::var(&format!("p{}", i)).continuous(low, high));
+        }
+
+        track!(spec.value(domain::var("Objective Value")).finish())
+    }
+
+    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+        Ok(ConstrainedSigoptProblem {
+            objective: self.objective,
+            constraints: self
+                .constraints
+                .iter()
+                .map(|c| c.to_test_function())
+                .zip(self.thresholds.iter().copied())
+                .collect(),
+        })
+    }
+}
+
+/// Constrained single-objective problem that uses the test functions defined in [sigopt/evalset](https://github.com/sigopt/evalset).
+#[derive(Debug)]
+pub struct ConstrainedSigoptProblem {
+    objective: Name,
+    constraints: Vec<(Box<dyn TestFunction>, f64)>,
+}
+impl Problem for ConstrainedSigoptProblem {
+    type Evaluator = ConstrainedSigoptEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        for (constraint, threshold) in &self.constraints {
+            let value = constraint.evaluate(params.get());
+            track_assert!(
+                value - threshold <= 0.0,
+                ErrorKind::UnevaluableParams,
+                "Infeasible parameters: constraint={:?}, value={}, threshold={}",
+                constraint,
+                value,
+                threshold
+            );
+        }
+
+        Ok(ConstrainedSigoptEvaluator {
+            test_function: self.objective.to_test_function(),
+            params,
+        })
+    }
+}
+
+/// Evaluator of `ConstrainedSigoptProblem`.
+#[derive(Debug)]
+pub struct ConstrainedSigoptEvaluator {
+    test_function: Box<dyn TestFunction>,
+    params: Params,
+}
+impl Evaluator for ConstrainedSigoptEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        track_assert_eq!(next_step, 1, ErrorKind::Bug);
+
+        let value = self.test_function.evaluate(self.params.get());
+        Ok((1, Values::new(vec![value])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::epi::solver::ExternalProgramSolverRecipe;
+    use kurobako_core::Error;
+    use trackable::result::TopLevelResult;
+
+    fn create_factory(json: &str) -> Result<ConstrainedSigoptProblemFactory> {
+        let registry =
+            FactoryRegistry::new::<ConstrainedSigoptProblemRecipe, ExternalProgramSolverRecipe>();
+        let recipe: ConstrainedSigoptProblemRecipe =
+            track!(serde_json::from_str(json).map_err(Error::from))?;
+        track!(recipe.create_factory(&registry))
+    }
+
+    #[test]
+    fn feasibility_works() -> TopLevelResult {
+        let factory = create_factory(
+            r#"{"objective":"SPHERE","constraints":["SPHERE"],"thresholds":[1.0],"dim":2}"#,
+        )?;
+        let problem = factory.create_problem(ArcRng::new(0))?;
+
+        let mut evaluator = problem.create_evaluator(Params::new(vec![0.5, 0.5]))?;
+        let (_, values) = evaluator.evaluate(1)?;
+        assert_eq!(values.into_vec(), [0.5]);
+
+        let e = problem
+            .create_evaluator(Params::new(vec![1.0, 1.0]))
+            .expect_err("should be infeasible");
+        assert_eq!(*e.kind(), ErrorKind::UnevaluableParams);
+        Ok(())
+    }
+
+    #[test]
+    fn constraint_dimension_mismatch_is_rejected() {
+        let e =
+            create_factory(r#"{"objective":"SPHERE","constraints":["SIX_HUMP_CAMEL"],"dim":3}"#)
+                .expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+
+        assert!(
+            create_factory(r#"{"objective":"SPHERE","constraints":["SIX_HUMP_CAMEL"]}"#).is_ok()
+        );
+    }
+}
@@ -12,6 +12,19 @@ pub trait TestFunction: 'static + fmt::Debug + Send + Sync {
         2
     }
 
+    // Revision of the definition of this function.
+    //
+    // This is incremented when a fix changes the evaluation results of the function,
+    // so that the results before and after the fix are distinguishable.
+    fn revision(&self) -> u32 {
+        0
+    }
+
+    // Returns the global minimum value within the bounds if it's known.
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        None
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>>;
     fn evaluate(&self, xs: &[f64]) -> f64;
 }
@@ -19,8 +32,12 @@ pub trait TestFunction: 'static + fmt::Debug + Send + Sync {
 #[derive(Debug)]
 pub struct Ackley;
 impl TestFunction for Ackley {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-10.0, 30.0)).take(dim).collect())
+        Ok(iter::repeat_n((-10.0, 30.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -115,8 +132,12 @@ impl TestFunction for CarromTable {
 #[derive(Debug)]
 pub struct Csendes;
 impl TestFunction for Csendes {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-0.5, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((-0.5, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -130,7 +151,7 @@ impl TestFunction for Csendes {
 pub struct Deb02;
 impl TestFunction for Deb02 {
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -149,7 +170,7 @@ pub struct DeflectedCorrugatedSpring;
 impl TestFunction for DeflectedCorrugatedSpring {
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         let alpha = 5.0;
-        Ok(iter::repeat((0.0, 1.5 * alpha)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.5 * alpha), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -163,27 +184,36 @@ impl TestFunction for DeflectedCorrugatedSpring {
 #[derive(Debug)]
 pub struct Easom;
 impl TestFunction for Easom {
+    // Revision 1: the classic Easom function (the revision 0 was an Ackley-like function).
+    fn revision(&self) -> u32 {
+        1
+    }
+
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(-1.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-100.0, 20.0)).take(dim).collect())
+        track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
+        Ok(iter::repeat_n((-100.0, 100.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
-        let n = xs.len() as f64;
-
-        let a = 20.0;
-        let b = 0.2;
-        let c = 2.0 * PI;
-        let d = (xs.iter().map(|&x| x * x).sum::<f64>() / n).sqrt();
-        let e = (xs.iter().map(|&x| (c * x).cos()).sum::<f64>() / n).exp();
-        -a * (-b * d).exp() - e + a + 1f64.exp()
+        let x1 = xs[0];
+        let x2 = xs[1];
+        -x1.cos() * x2.cos() * (-((x1 - PI).powi(2) + (x2 - PI).powi(2))).exp()
     }
 }
 
 #[derive(Debug)]
 pub struct Exponential;
 impl TestFunction for Exponential {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(-1.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-0.7, 0.2)).take(dim).collect())
+        Ok(iter::repeat_n((-0.7, 0.2), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -192,6 +222,28 @@ impl TestFunction for Exponential {
     }
 }
 
+#[derive(Debug)]
+pub struct Griewank;
+impl TestFunction for Griewank {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
+        Ok(iter::repeat_n((-600.0, 600.0), dim).collect())
+    }
+
+    fn evaluate(&self, xs: &[f64]) -> f64 {
+        let a = xs.iter().map(|&x| x * x).sum::<f64>() / 4000.0;
+        let b = xs
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| (x / ((i + 1) as f64).sqrt()).cos())
+            .product::<f64>();
+        1.0 + a - b
+    }
+}
+
 #[derive(Debug)]
 pub struct Hartmann3;
 impl TestFunction for Hartmann3 {
@@ -199,9 +251,13 @@ impl TestFunction for Hartmann3 {
         3
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(-3.86278214782076)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 3, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -236,9 +292,13 @@ impl TestFunction for Hartmann6 {
         6
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(-3.32236801141551)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 6, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -281,7 +341,7 @@ impl TestFunction for HelicalValley {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 3, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-1.0, 2.0)).take(dim).collect())
+        Ok(iter::repeat_n((-1.0, 2.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -304,7 +364,7 @@ impl TestFunction for HimmelBlau {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-2.0, 6.0)).take(dim).collect())
+        Ok(iter::repeat_n((-2.0, 6.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -323,7 +383,7 @@ impl TestFunction for LennardJones6 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 6, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-3.0, 3.0)).take(dim).collect())
+        Ok(iter::repeat_n((-3.0, 3.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -347,6 +407,54 @@ impl TestFunction for LennardJones6 {
     }
 }
 
+#[derive(Debug)]
+pub struct Levy;
+impl TestFunction for Levy {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
+        Ok(iter::repeat_n((-10.0, 10.0), dim).collect())
+    }
+
+    fn evaluate(&self, xs: &[f64]) -> f64 {
+        let ws = xs
+            .iter()
+            .map(|&x| 1.0 + (x - 1.0) / 4.0)
+            .collect::<Vec<_>>();
+        let n = ws.len();
+        let a = (PI * ws[0]).sin().powi(2);
+        let b = ws[..n - 1]
+            .iter()
+            .map(|&w| (w - 1.0).powi(2) * (1.0 + 10.0 * (PI * w + 1.0).sin().powi(2)))
+            .sum::<f64>();
+        let c = (ws[n - 1] - 1.0).powi(2) * (1.0 + (2.0 * PI * ws[n - 1]).sin().powi(2));
+        a + b + c
+    }
+}
+
+#[derive(Debug)]
+pub struct Levy13;
+impl TestFunction for Levy13 {
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
+        track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
+        Ok(iter::repeat_n((-10.0, 10.0), dim).collect())
+    }
+
+    fn evaluate(&self, xs: &[f64]) -> f64 {
+        let x1 = xs[0];
+        let x2 = xs[1];
+        (3.0 * PI * x1).sin().powi(2)
+            + (x1 - 1.0).powi(2) * (1.0 + (3.0 * PI * x2).sin().powi(2))
+            + (x2 - 1.0).powi(2) * (1.0 + (2.0 * PI * x2).sin().powi(2))
+    }
+}
+
 struct McCourtBase;
 impl McCourtBase {
     fn evaluate<'a, F, I>(xs: &'a [f64], kernel: F, coefs: &'static [f64]) -> f64
@@ -439,7 +547,7 @@ impl TestFunction for McCourt01 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 7, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -478,7 +586,7 @@ impl TestFunction for McCourt02 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 7, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -523,7 +631,7 @@ impl TestFunction for McCourt03 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 9, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -564,7 +672,7 @@ impl TestFunction for McCourt06 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 5, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -607,7 +715,7 @@ impl TestFunction for McCourt07 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -657,7 +765,7 @@ impl TestFunction for McCourt08 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -700,7 +808,7 @@ impl TestFunction for McCourt09 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -756,7 +864,7 @@ impl TestFunction for McCourt10 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -805,7 +913,7 @@ impl TestFunction for McCourt11 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -854,7 +962,7 @@ impl TestFunction for McCourt12 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -911,7 +1019,7 @@ impl TestFunction for McCourt13 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -938,7 +1046,7 @@ impl TestFunction for McCourt14 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -963,7 +1071,7 @@ impl TestFunction for McCourt16 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -996,7 +1104,7 @@ impl TestFunction for McCourt17 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1035,7 +1143,7 @@ impl TestFunction for McCourt18 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1082,7 +1190,7 @@ impl TestFunction for McCourt19 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1129,7 +1237,7 @@ impl TestFunction for McCourt20 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1170,7 +1278,7 @@ impl TestFunction for McCourt22 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1227,7 +1335,7 @@ impl TestFunction for McCourt23 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1276,7 +1384,7 @@ impl TestFunction for McCourt26 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1325,7 +1433,7 @@ impl TestFunction for McCourt27 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1374,7 +1482,7 @@ impl TestFunction for McCourt28 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, self.default_dimension(), ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1393,7 +1501,7 @@ impl TestFunction for Michalewicz {
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         let max_dim = 12;
         track_assert!(dim <= max_dim, ErrorKind::InvalidInput; dim, max_dim);
-        Ok(iter::repeat((0.0, PI)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, PI), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1414,7 +1522,7 @@ impl TestFunction for Mishra06 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-10.0, 10.0)).take(dim).collect())
+        Ok(iter::repeat_n((-10.0, 10.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1437,7 +1545,7 @@ impl TestFunction for Ned01 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-10.0, 10.0)).take(dim).collect())
+        Ok(iter::repeat_n((-10.0, 10.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1454,7 +1562,7 @@ impl TestFunction for OddSquare {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-3.0 * PI, 3.0 * PI)).take(dim).collect())
+        Ok(iter::repeat_n((-3.0 * PI, 3.0 * PI), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1475,7 +1583,7 @@ impl TestFunction for Parsopoulos {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-5.0, 5.0)).take(dim).collect())
+        Ok(iter::repeat_n((-5.0, 5.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1492,7 +1600,7 @@ impl TestFunction for Pinter {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert!(dim > 1, ErrorKind::InvalidInput; dim);
-        Ok(iter::repeat((-5.0, 2.0)).take(dim).collect())
+        Ok(iter::repeat_n((-5.0, 2.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1523,7 +1631,7 @@ impl TestFunction for Plateau {
     }
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-2.34, 5.12)).take(dim).collect())
+        Ok(iter::repeat_n((-2.34, 5.12), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1540,7 +1648,7 @@ impl TestFunction for Powell {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 4, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-4.0, 5.0)).take(dim).collect())
+        Ok(iter::repeat_n((-4.0, 5.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1564,7 +1672,7 @@ impl TestFunction for Problem03 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 1, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-10.0, 10.0)).take(dim).collect())
+        Ok(iter::repeat_n((-10.0, 10.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1583,6 +1691,10 @@ impl TestFunction for Rastrigin {
         8
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 8, ErrorKind::InvalidInput);
         let a = [-5.0, -5.0, -2.0, -2.0, -5.0, -5.0, -2.0, -2.0];
@@ -1643,7 +1755,7 @@ impl TestFunction for Sargan {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert!(dim > 1, ErrorKind::InvalidInput; dim);
-        Ok(iter::repeat((-2.0, 4.0)).take(dim).collect())
+        Ok(iter::repeat_n((-2.0, 4.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1665,8 +1777,12 @@ impl TestFunction for Schwefel20 {
         2
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-60.0, 100.0)).take(dim).collect())
+        Ok(iter::repeat_n((-60.0, 100.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1674,6 +1790,19 @@ impl TestFunction for Schwefel20 {
     }
 }
 
+#[derive(Debug)]
+pub struct Schwefel26;
+impl TestFunction for Schwefel26 {
+    fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
+        Ok(iter::repeat_n((-500.0, 500.0), dim).collect())
+    }
+
+    fn evaluate(&self, xs: &[f64]) -> f64 {
+        let n = xs.len() as f64;
+        418.9829 * n - xs.iter().map(|&x| x * x.abs().sqrt().sin()).sum::<f64>()
+    }
+}
+
 #[derive(Debug)]
 pub struct Schwefel36;
 impl TestFunction for Schwefel36 {
@@ -1683,7 +1812,7 @@ impl TestFunction for Schwefel36 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-0.0, 20.0)).take(dim).collect())
+        Ok(iter::repeat_n((-0.0, 20.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1702,7 +1831,7 @@ impl TestFunction for Shekel05 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 4, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-0.0, 10.0)).take(dim).collect())
+        Ok(iter::repeat_n((-0.0, 10.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1738,7 +1867,7 @@ impl TestFunction for Shekel07 {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 4, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-0.0, 10.0)).take(dim).collect())
+        Ok(iter::repeat_n((-0.0, 10.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1775,6 +1904,10 @@ impl TestFunction for SixHumpCamel {
         2
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(-1.031628453489877)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
         Ok(vec![(-2.0, 2.0), (-1.5, 1.5)])
@@ -1796,8 +1929,12 @@ impl TestFunction for Sphere {
         2
     }
 
+    fn global_optimum(&self, _dim: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-5.12, 2.12)).take(dim).collect())
+        Ok(iter::repeat_n((-5.12, 2.12), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1812,8 +1949,12 @@ impl TestFunction for StyblinskiTang {
         2
     }
 
+    fn global_optimum(&self, dim: usize) -> Option<f64> {
+        Some(-39.16616570377142 * dim as f64)
+    }
+
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-5.0, 5.0)).take(dim).collect())
+        Ok(iter::repeat_n((-5.0, 5.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1833,7 +1974,7 @@ impl TestFunction for Trid {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 6, ErrorKind::InvalidInput);
-        Ok(iter::repeat((0.0, 20.0)).take(dim).collect())
+        Ok(iter::repeat_n((0.0, 20.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1857,7 +1998,7 @@ impl TestFunction for Tripod {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 2, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-100.0, 100.0)).take(dim).collect())
+        Ok(iter::repeat_n((-100.0, 100.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1879,7 +2020,7 @@ impl TestFunction for Weierstrass {
     }
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-0.5, 0.2)).take(dim).collect())
+        Ok(iter::repeat_n((-0.5, 0.2), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1917,7 +2058,7 @@ impl TestFunction for Xor {
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
         track_assert_eq!(dim, 9, ErrorKind::InvalidInput);
-        Ok(iter::repeat((-1.0, 1.0)).take(dim).collect())
+        Ok(iter::repeat_n((-1.0, 1.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -1945,7 +2086,7 @@ impl TestFunction for YaoLiu {
     }
 
     fn bounds(&self, dim: usize) -> Result<Vec<(f64, f64)>> {
-        Ok(iter::repeat((-5.12, 2.0)).take(dim).collect())
+        Ok(iter::repeat_n((-5.12, 2.0), dim).collect())
     }
 
     fn evaluate(&self, xs: &[f64]) -> f64 {
@@ -2019,8 +2160,10 @@ mod tests {
 
     #[test]
     fn easom_works() {
-        assert_eq!(Easom.evaluate(&[1.2]), 5.62363908902924);
-        assert_eq!(Easom.evaluate(&[1.2, 3.4]), 9.928391855906339);
+        assert_eq!(Easom.evaluate(&[1.2, 3.4]), 0.007555759401201842);
+        assert_eq!(Easom.evaluate(&[3.0, 3.5]), -0.7991439167805361);
+        assert_eq!(Easom.evaluate(&[PI, PI]), -1.0);
+        assert!(Easom.bounds(3).is_err());
     }
 
     #[test]
@@ -2029,6 +2172,34 @@ mod tests {
         assert_eq!(Exponential.evaluate(&[0.12, -0.34]), -0.9370674633774034);
     }
 
+    #[test]
+    fn global_optimums_work() {
+        assert_eq!(
+            Sphere.evaluate(&[0.0, 0.0]),
+            Sphere.global_optimum(2).unwrap()
+        );
+        assert_eq!(Easom.evaluate(&[PI, PI]), Easom.global_optimum(2).unwrap());
+        assert!(
+            (Hartmann3.evaluate(&[0.114614, 0.555649, 0.852547])
+                - Hartmann3.global_optimum(3).unwrap())
+            .abs()
+                < 1e-5
+        );
+        assert!(
+            (StyblinskiTang.evaluate(&[-2.903534; 3]) - StyblinskiTang.global_optimum(3).unwrap())
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(McCourt01.global_optimum(7), None);
+    }
+
+    #[test]
+    fn griewank_works() {
+        assert_eq!(Griewank.evaluate(&[0.0, 0.0]), 0.0);
+        assert_eq!(Griewank.evaluate(&[1.2, 3.4]), 1.2714669593292633);
+        assert_eq!(Griewank.evaluate(&[100.0, -20.0, 3.0]), 3.6015620840905793);
+    }
+
     #[test]
     fn hartmann3_works() {
         assert_eq!(Hartmann3.evaluate(&[0.12, 0.34, 0.56]), -0.5775714789099738);
@@ -2090,6 +2261,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn levy_works() {
+        assert_eq!(Levy.evaluate(&[1.2]), 0.02721047060945449);
+        assert_eq!(Levy.evaluate(&[1.2, 3.4]), 0.5323082768457367);
+        assert_eq!(Levy.evaluate(&[-1.2, 3.4, 5.6]), 5.3969399917943734);
+        assert!(Levy.evaluate(&[1.0, 1.0, 1.0]) < 1e-30);
+    }
+
+    #[test]
+    fn levy13_works() {
+        assert_eq!(Levy13.evaluate(&[1.2, 3.4]), 8.708359213500131);
+        assert_eq!(Levy13.evaluate(&[-0.5, 2.0]), 4.25);
+        assert!(Levy13.evaluate(&[1.0, 1.0]) < 1e-30);
+        assert!(Levy13.bounds(3).is_err());
+    }
+
     #[test]
     fn mccourt01_works() {
         assert_eq!(
@@ -2334,7 +2521,7 @@ mod tests {
     #[test]
     fn rosenbrocklog_works() {
         assert_eq!(
-            RosenbrockLog.evaluate(&iter::repeat(1.0).take(11).collect::<Vec<_>>()),
+            RosenbrockLog.evaluate(&iter::repeat_n(1.0, 11).collect::<Vec<_>>()),
             0.0
         );
         assert_eq!(
@@ -2346,7 +2533,7 @@ mod tests {
     #[test]
     fn sargan_works() {
         assert_eq!(
-            Sargan.evaluate(&iter::repeat(0.0).take(5).collect::<Vec<_>>()),
+            Sargan.evaluate(&iter::repeat_n(0.0, 5).collect::<Vec<_>>()),
             0.0
         );
         assert_eq!(
@@ -2358,12 +2545,23 @@ mod tests {
     #[test]
     fn schwefel20_works() {
         assert_eq!(
-            Schwefel20.evaluate(&iter::repeat(0.0).take(5).collect::<Vec<_>>()),
+            Schwefel20.evaluate(&iter::repeat_n(0.0, 5).collect::<Vec<_>>()),
             0.0
         );
         assert_eq!(Schwefel20.evaluate(&[0.1, 0.2, -0.3, 0.4, 0.5]), 1.5);
     }
 
+    #[test]
+    fn schwefel26_works() {
+        assert!(Schwefel26.evaluate(&[420.9687, 420.9687]) < 1e-4);
+        assert!(Schwefel26.evaluate(&[420.9687, 420.9687, 420.9687]) < 1e-4);
+        assert_eq!(Schwefel26.evaluate(&[1.2, 3.4]), 833.6248591119214);
+        assert_eq!(
+            Schwefel26.evaluate(&[-100.0, 250.0, 3.0]),
+            1225.395695579512
+        );
+    }
+
     #[test]
     fn schwefel36_works() {
         assert_eq!(Schwefel36.evaluate(&[12.0, 12.0]), -3456.0);
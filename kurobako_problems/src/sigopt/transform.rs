@@ -0,0 +1,190 @@
+use kurobako_core::rng::{standard_normal, ArcRng, Rng};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
+
+/// Affine transform applied to the inputs of a test function.
+///
+/// If this is enabled, the test function `f` is evaluated as `f(clamp(c + R * (x - o - c)))`,
+/// where `R` is a random rotation matrix, `o` is a random shift vector,
+/// `c` is the center of the bounds and `clamp` clips the inputs to the bounds.
+/// The transform is generated from `seed`, so it's identical across studies.
+#[derive(Debug, Default, Clone, PartialEq, Eq, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Transform {
+    /// If this flag is set, the inputs are rotated by a random orthogonal matrix.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub rotate: bool,
+
+    /// If this flag is set, the inputs are shifted by a random vector.
+    ///
+    /// Each element of the vector is sampled from `[-(high - low) / 4, (high - low) / 4]`
+    /// where `low` and `high` are the bounds of the corresponding dimension.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub shift: bool,
+
+    /// Random seed used to generate the rotation matrix and the shift vector.
+    #[structopt(long = "transform-seed", default_value = "0")]
+    #[serde(default)]
+    pub seed: u64,
+}
+impl Transform {
+    /// Returns `true` if this transform doesn't change the inputs.
+    pub fn is_identity(&self) -> bool {
+        !(self.rotate || self.shift)
+    }
+
+    pub(crate) fn generate(&self, bounds: &[(f64, f64)]) -> Option<AffineTransform> {
+        if self.is_identity() {
+            return None;
+        }
+
+        let mut rng = ArcRng::new(self.seed);
+        let rotation = if self.rotate {
+            Some(random_rotation(&mut rng, bounds.len()))
+        } else {
+            None
+        };
+        let shift = if self.shift {
+            let shift = bounds
+                .iter()
+                .map(|&(low, high)| {
+                    let width = (high - low) / 4.0;
+                    rng.gen_range(-width..=width)
+                })
+                .collect();
+            Some(shift)
+        } else {
+            None
+        };
+        Some(AffineTransform {
+            bounds: bounds.to_owned(),
+            rotation,
+            shift,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AffineTransform {
+    pub bounds: Vec<(f64, f64)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<Vec<Vec<f64>>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift: Option<Vec<f64>>,
+}
+impl AffineTransform {
+    pub fn apply(&self, xs: &[f64]) -> Vec<f64> {
+        let mut xs = xs.to_owned();
+        if let Some(shift) = &self.shift {
+            for (x, o) in xs.iter_mut().zip(shift.iter()) {
+                *x -= o;
+            }
+        }
+        if let Some(rotation) = &self.rotation {
+            // Rotates around the center of the bounds so that the center stays at the same position.
+            let center = self
+                .bounds
+                .iter()
+                .map(|&(low, high)| (low + high) / 2.0)
+                .collect::<Vec<_>>();
+            xs = rotation
+                .iter()
+                .zip(center.iter())
+                .map(|(row, c)| {
+                    let y = row
+                        .iter()
+                        .zip(xs.iter().zip(center.iter()))
+                        .map(|(r, (x, c))| r * (x - c))
+                        .sum::<f64>();
+                    c + y
+                })
+                .collect();
+        }
+        for (x, &(low, high)) in xs.iter_mut().zip(self.bounds.iter()) {
+            *x = x.max(low).min(high);
+        }
+        xs
+    }
+}
+
+// Generates a random orthogonal matrix by orthonormalizing a gaussian random matrix (Gram-Schmidt).
+fn random_rotation<R: Rng>(rng: &mut R, dim: usize) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dim);
+    while rows.len() < dim {
+        let mut v = (0..dim).map(|_| standard_normal(rng)).collect::<Vec<_>>();
+        for row in &rows {
+            let dot = row.iter().zip(v.iter()).map(|(a, b)| a * b).sum::<f64>();
+            for (x, r) in v.iter_mut().zip(row.iter()) {
+                *x -= dot * r;
+            }
+        }
+
+        let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm < 1e-8 {
+            continue;
+        }
+        v.iter_mut().for_each(|x| *x /= norm);
+        rows.push(v);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transformed_points_are_in_bounds() {
+        let bounds = [(0.0, 10.0), (-5.0, 15.0), (-1.0, 1.0)];
+        let transform = Transform {
+            rotate: true,
+            shift: false,
+            seed: 3,
+        };
+        let affine = transform.generate(&bounds).expect("not identity");
+
+        // The center of the bounds is the center of the rotation.
+        let center = affine.apply(&[5.0, 5.0, 0.0]);
+        for (x, c) in center.iter().zip([5.0, 5.0, 0.0].iter()) {
+            assert!((x - c).abs() < 1e-9, "center={:?}", center);
+        }
+
+        for seed in 0..10 {
+            let transform = Transform {
+                rotate: true,
+                shift: true,
+                seed,
+            };
+            let affine = transform.generate(&bounds).expect("not identity");
+            let mut rng = ArcRng::new(seed);
+            for i in 0..100 {
+                let xs = if i < 8 {
+                    // Corners.
+                    bounds
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &(low, high))| if i & (1 << j) == 0 { low } else { high })
+                        .collect::<Vec<_>>()
+                } else {
+                    bounds
+                        .iter()
+                        .map(|&(low, high)| rng.gen_range(low..=high))
+                        .collect::<Vec<_>>()
+                };
+                let ys = affine.apply(&xs);
+                for (y, &(low, high)) in ys.iter().zip(bounds.iter()) {
+                    assert!(low <= *y && *y <= high, "xs={:?}, ys={:?}", xs, ys);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! A problem that looks up the objective values from a table (CSV file).
+//!
+//! Each row of the table is a parameter set and its objective values.
+//! The parameter space is inferred from the values found in the table:
+//!
+//! - Columns that contain non-numeric values are categorical (the choices are sorted lexicographically).
+//! - Columns that only contain integers are discrete (from the minimum to the maximum).
+//! - The other numeric columns are continuous (from the minimum to the maximum).
+//!
+//! Parameters that don't appear in the table result in `ErrorKind::UnevaluableParams` errors.
+use kurobako_core::domain::{self, Range};
+use kurobako_core::num::OrderedFloat;
+use kurobako_core::problem::{
+    Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+/// Recipe of `TableProblem`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct TableProblemRecipe {
+    /// Path of the CSV file (the first line must be the header).
+    pub path: PathBuf,
+
+    /// Names of the columns used as the parameters.
+    #[structopt(long, required = true, min_values = 1)]
+    pub param_columns: Vec<String>,
+
+    /// Names of the columns used as the objective values.
+    #[structopt(long, required = true, min_values = 1)]
+    pub value_columns: Vec<String>,
+}
+impl ProblemRecipe for TableProblemRecipe {
+    type Factory = TableProblemFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        track_assert!(
+            !self.param_columns.is_empty() && !self.value_columns.is_empty(),
+            ErrorKind::InvalidInput,
+            "Both parameter and value columns must be specified"
+        );
+
+        let text = track!(std::fs::read_to_string(&self.path).map_err(Error::from); self.path)?;
+        let rows = track!(parse_csv(&text); self.path)?;
+        let table = track!(Table::new(rows, &self.param_columns, &self.value_columns); self.path)?;
+
+        let name = self
+            .path
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or("table");
+        let mut spec = ProblemSpecBuilder::new(&format!("Table: {}", name)).attr(
+            "version",
+            &format!("kurobako_problems={}", env!("CARGO_PKG_VERSION")),
+        );
+        for (name, range) in self.param_columns.iter().zip(table.ranges.iter()) {
+            let var = domain::var(name);
+            spec = spec.param(match range {
+                Range::Categorical { choices } => var.categorical(choices),
+                Range::Discrete { low, high } => var.discrete(*low, *high),
+                range => var.continuous(range.low(), range.high()),
+            });
+        }
+        for name in &self.value_columns {
+            spec = spec.value(domain::var(name));
+        }
+        let spec = track!(spec.finish())?;
+
+        Ok(TableProblemFactory {
+            spec,
+            table: Arc::new(table),
+        })
+    }
+}
+
+/// Factory of `TableProblem`.
+#[derive(Debug)]
+pub struct TableProblemFactory {
+    spec: ProblemSpec,
+    table: Arc<Table>,
+}
+impl ProblemFactory for TableProblemFactory {
+    type Problem = TableProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        Ok(self.spec.clone())
+    }
+
+    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+        Ok(TableProblem {
+            table: Arc::clone(&self.table),
+        })
+    }
+}
+
+/// Problem that looks up the objective values from a table.
+#[derive(Debug)]
+pub struct TableProblem {
+    table: Arc<Table>,
+}
+impl Problem for TableProblem {
+    type Evaluator = TableEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        let key = params.iter().copied().map(OrderedFloat).collect::<Vec<_>>();
+        let values = track_assert_some!(
+            self.table.rows.get(&key),
+            ErrorKind::UnevaluableParams,
+            "No such row: {:?}",
+            params
+        );
+        Ok(TableEvaluator {
+            values: values.clone(),
+        })
+    }
+}
+
+/// Evaluator of `TableProblem`.
+#[derive(Debug)]
+pub struct TableEvaluator {
+    values: Values,
+}
+impl Evaluator for TableEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        Ok((next_step, self.values.clone()))
+    }
+}
+
+#[derive(Debug)]
+struct Table {
+    ranges: Vec<Range>,
+
+    // Params => the values of the row.
+    rows: HashMap<Vec<OrderedFloat<f64>>, Values>,
+}
+impl Table {
+    fn new(
+        rows: Vec<Vec<String>>,
+        param_columns: &[String],
+        value_columns: &[String],
+    ) -> Result<Self> {
+        let mut rows = rows.into_iter();
+        let header = track_assert_some!(rows.next(), ErrorKind::InvalidInput, "Empty table");
+        let rows = rows.collect::<Vec<_>>();
+        track_assert!(!rows.is_empty(), ErrorKind::InvalidInput, "No rows");
+        for (i, row) in rows.iter().enumerate() {
+            track_assert_eq!(
+                row.len(),
+                header.len(),
+                ErrorKind::InvalidInput,
+                "Wrong number of columns at row {}",
+                i + 1
+            );
+        }
+
+        let column = |name: &String| {
+            let i = track_assert_some!(
+                header.iter().position(|h| h == name),
+                ErrorKind::InvalidInput,
+                "No such column: {:?}",
+                name
+            );
+            Ok(i)
+        };
+        let param_columns = param_columns
+            .iter()
+            .map(column)
+            .collect::<Result<Vec<_>>>()?;
+        let value_columns = value_columns
+            .iter()
+            .map(column)
+            .collect::<Result<Vec<_>>>()?;
+
+        let ranges = param_columns
+            .iter()
+            .map(|&i| infer_range(rows.iter().map(|row| row[i].as_str())))
+            .collect::<Vec<_>>();
+
+        let mut table = HashMap::new();
+        for (i, row) in rows.iter().enumerate() {
+            let params = param_columns
+                .iter()
+                .zip(ranges.iter())
+                .map(|(&j, range)| match range {
+                    Range::Categorical { choices } => {
+                        let index = choices.iter().position(|c| *c == row[j]);
+                        OrderedFloat(index.unwrap_or_else(|| unreachable!()) as f64)
+                    }
+                    _ => OrderedFloat(row[j].parse().unwrap_or_else(|_| unreachable!())),
+                })
+                .collect::<Vec<_>>();
+            let values = value_columns
+                .iter()
+                .map(|&j| track!(row[j].parse::<f64>().map_err(Error::from); i + 1, row[j]))
+                .collect::<Result<Vec<_>>>()?;
+            track_assert!(
+                table.insert(params, Values::new(values)).is_none(),
+                ErrorKind::InvalidInput,
+                "Duplicate parameters at row {}",
+                i + 1
+            );
+        }
+        Ok(Self {
+            ranges,
+            rows: table,
+        })
+    }
+}
+
+fn infer_range<'a, I>(cells: I) -> Range
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    let numbers = cells
+        .clone()
+        .map(|c| c.parse::<f64>().ok().filter(|x| x.is_finite()))
+        .collect::<Option<Vec<_>>>();
+    match numbers {
+        None => Range::Categorical {
+            choices: cells
+                .map(|c| c.to_owned())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        },
+        Some(xs) => {
+            let low = xs.iter().copied().fold(f64::INFINITY, f64::min);
+            let high = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if xs.iter().all(|x| x.fract() == 0.0) {
+                Range::Discrete {
+                    low: low as i64,
+                    high: high as i64 + 1,
+                }
+            } else {
+                // The upper bound of a continuous range is exclusive.
+                Range::Continuous {
+                    low,
+                    high: high.next_up(),
+                    step: None,
+                }
+            }
+        }
+    }
+}
+
+// Parses a CSV text (RFC 4180). Empty lines are ignored.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => {
+                quoted = true;
+            }
+            ',' if !quoted => {
+                row.push(std::mem::take(&mut field));
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                if !(row.is_empty() && field.is_empty()) {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    track_assert!(
+        !quoted,
+        ErrorKind::InvalidInput,
+        "Unterminated quoted field"
+    );
+    if !(row.is_empty() && field.is_empty()) {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::epi::solver::ExternalProgramSolverRecipe;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn table_problem_works() -> TopLevelResult {
+        let path = std::env::temp_dir().join(format!("kurobako-table-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "optimizer,layers,lr,loss\n\
+             adam,1,0.1,0.5\n\
+             sgd,2,0.01,0.3\n\
+             \"adam\",3,0.001,0.2\n",
+        )
+        .map_err(Error::from)?;
+
+        let registry = FactoryRegistry::new::<TableProblemRecipe, ExternalProgramSolverRecipe>();
+        let recipe = TableProblemRecipe {
+            path: path.clone(),
+            param_columns: vec!["optimizer".to_owned(), "layers".to_owned(), "lr".to_owned()],
+            value_columns: vec!["loss".to_owned()],
+        };
+        let factory = recipe.create_factory(&registry);
+        std::fs::remove_file(&path).map_err(Error::from)?;
+        let factory = factory?;
+
+        let spec = factory.specification()?;
+        let vars = spec.params_domain.variables();
+        assert_eq!(
+            *vars[0].range(),
+            Range::Categorical {
+                choices: vec!["adam".to_owned(), "sgd".to_owned()]
+            }
+        );
+        assert_eq!(*vars[1].range(), Range::Discrete { low: 1, high: 4 });
+        assert_eq!(vars[2].range().low(), 0.001);
+        assert!(vars[2].range().contains(0.1));
+        assert_eq!(spec.values_domain.variables().len(), 1);
+
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        let mut evaluator = problem.create_evaluator(Params::new(vec![1.0, 2.0, 0.01]))?;
+        assert_eq!(evaluator.evaluate(1)?, (1, Values::new(vec![0.3])));
+        let mut evaluator = problem.create_evaluator(Params::new(vec![0.0, 3.0, 0.001]))?;
+        assert_eq!(evaluator.evaluate(1)?, (1, Values::new(vec![0.2])));
+
+        assert_eq!(
+            problem
+                .create_evaluator(Params::new(vec![1.0, 1.0, 0.1]))
+                .err()
+                .map(|e| *e.kind()),
+            Some(ErrorKind::UnevaluableParams)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_csv_works() -> TopLevelResult {
+        let rows = parse_csv("a,b\r\n\"x,\"\"y\"\"\",\n\n1,2")?;
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["x,\"y\"".to_owned(), "".to_owned()],
+                vec!["1".to_owned(), "2".to_owned()],
+            ]
+        );
+        assert!(parse_csv("a,\"b").is_err());
+        Ok(())
+    }
+}
@@ -4,14 +4,26 @@
 //!
 //! - [Comparison of Multiob jective Evolutionary Algorithms: Empirical Results](https://www.mitpressjournals.org/doi/abs/10.1162/106365600568202)
 //! - [A Benchmark Study of Multi-Objective Optimization Methods](http://www.redcedartech.com/pdfs/MO-SHERPA_paper.pdf)
-use kurobako_core::domain::{self, Range};
+//!
+//! # Constrained variant
+//!
+//! If `--constrained` is specified, parameters are feasible only if
+//! `(x0 - 0.5)^2 + x1^2 >= 0.0625` holds
+//! (i.e., the disk of radius `0.25` centered at `(x0, x1) = (0.5, 0.0)` is infeasible).
+//! Because the Pareto-optimal solutions of the functions satisfy `x1 = 0`,
+//! this removes the middle part of the Pareto-optimal front.
+//! Evaluating infeasible parameters results in an `ErrorKind::UnevaluableParams` error,
+//! so solvers are told that the trials have no values.
+//!
+//! The constrained variant isn't available for the function 5 whose parameters are bit strings.
+use kurobako_core::domain::{self, Constraint, Range, Variable};
 use kurobako_core::problem::{
     Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
 };
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::trial::{Params, Values};
-use kurobako_core::Result;
+use kurobako_core::{ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use std::f64;
 use std::f64::consts::PI;
@@ -24,28 +36,56 @@ use structopt::StructOpt;
 pub struct ZdtProblemRecipe {
     #[structopt(flatten)]
     pub zdt: Zdt,
+
+    /// If this flag is set, parameters in the infeasible region are rejected (see the module documentation).
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub constrained: bool,
 }
 
 impl ProblemRecipe for ZdtProblemRecipe {
     type Factory = ZdtProblemFactory;
 
     fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
-        Ok(ZdtProblemFactory { zdt: self.zdt })
+        if self.constrained {
+            track_assert!(
+                !matches!(self.zdt, Zdt::Function5),
+                ErrorKind::InvalidInput,
+                "ZDT5 doesn't support the constrained variant"
+            );
+        }
+        Ok(ZdtProblemFactory {
+            zdt: self.zdt,
+            constrained: self.constrained,
+        })
     }
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
+
+// Lua script of the feasibility function of the constrained variant.
+const FEASIBILITY: &str = "(x0 - 0.5) ^ 2 + x1 ^ 2 >= 0.0625";
+
 /// Factory of `ZdtProblem`.
 #[derive(Debug)]
 pub struct ZdtProblemFactory {
     zdt: Zdt,
+    constrained: bool,
 }
 
 impl ProblemFactory for ZdtProblemFactory {
     type Problem = ZdtProblem;
 
     fn specification(&self) -> Result<ProblemSpec> {
-        let name = self.zdt.name();
-        let mut spec = ProblemSpecBuilder::new(name)
+        let name = if self.constrained {
+            format!("{} (constrained)", self.zdt.name())
+        } else {
+            self.zdt.name().to_owned()
+        };
+        let mut spec = ProblemSpecBuilder::new(&name)
             .attr(
                 "version",
                 &format!("kurobako_problems={}", env!("CARGO_PKG_VERSION")),
@@ -56,6 +96,10 @@ impl ProblemFactory for ZdtProblemFactory {
                  evolutionary algorithms: Empirical results.\" Evolutionary computation 8.2 (2000): 173-195."
             ).value(domain::var("f1")).value(domain::var("f2")).reference_point(Some(Params::new(vec![11.0, 11.0])));
 
+        if self.constrained {
+            spec = spec.attr("feasibility", FEASIBILITY);
+        }
+
         for (i, range) in self.zdt.ranges().into_iter().enumerate() {
             spec = spec.param(domain::var(&format!("x{}", i)).range(range));
         }
@@ -63,7 +107,18 @@ impl ProblemFactory for ZdtProblemFactory {
     }
 
     fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
-        Ok(ZdtProblem { zdt: self.zdt })
+        let feasibility = if self.constrained {
+            // The constraint isn't attached to the variables because it'd make them conditional.
+            let spec = track!(self.specification())?;
+            let vars = spec.params_domain.variables()[..2].to_vec();
+            Some((Constraint::new(FEASIBILITY), vars))
+        } else {
+            None
+        };
+        Ok(ZdtProblem {
+            zdt: self.zdt,
+            feasibility,
+        })
     }
 }
 
@@ -71,12 +126,24 @@ impl ProblemFactory for ZdtProblemFactory {
 #[derive(Debug)]
 pub struct ZdtProblem {
     zdt: Zdt,
+
+    // The feasibility constraint and the variables referred by it.
+    feasibility: Option<(Constraint, Vec<Variable>)>,
 }
 
 impl Problem for ZdtProblem {
     type Evaluator = ZdtEvaluator;
 
     fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        if let Some((constraint, vars)) = &self.feasibility {
+            let feasible = track!(constraint.is_satisfied(vars, &params.get()[..vars.len()]))?;
+            track_assert!(
+                feasible,
+                ErrorKind::UnevaluableParams,
+                "Infeasible parameters: {:?}",
+                params
+            );
+        }
         Ok(ZdtEvaluator {
             params,
             zdt: self.zdt,
@@ -157,20 +224,26 @@ impl Zdt {
                 .map(|_| Range::Continuous {
                     low: 0.0,
                     high: 1.0,
+                    step: None,
                 })
                 .collect(),
             Self::Function4 => std::iter::once((0.0, 1.0))
-                .chain(std::iter::repeat((-5.0, 5.0)).take(9))
-                .map(|(low, high)| Range::Continuous { low, high })
+                .chain(std::iter::repeat_n((-5.0, 5.0), 9))
+                .map(|(low, high)| Range::Continuous {
+                    low,
+                    high,
+                    step: None,
+                })
                 .collect(),
             Self::Function5 => std::iter::once((0, ((1 << 30) - 1)))
-                .chain(std::iter::repeat((0, ((1 << 5) - 1))).take(10))
+                .chain(std::iter::repeat_n((0, ((1 << 5) - 1)), 10))
                 .map(|(low, high)| Range::Discrete { low, high })
                 .collect(),
             Self::Function6 => (0..10)
                 .map(|_| Range::Continuous {
                     low: 0.0,
                     high: 1.0,
+                    step: None,
                 })
                 .collect(),
         }
@@ -249,3 +322,64 @@ impl Zdt {
         vec![f1, f2]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::rng::Rng;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn constrained_zdt_works() -> TopLevelResult {
+        let recipe = ZdtProblemRecipe {
+            zdt: Zdt::Function1,
+            constrained: true,
+        };
+        let factory = recipe.create_factory(&FactoryRegistry::new::<
+            ZdtProblemRecipe,
+            kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+        >())?;
+        assert_eq!(factory.specification()?.name, "ZDT1 (constrained)");
+
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        let mut rng = ArcRng::new(1);
+        let (mut feasibles, mut infeasibles) = (0, 0);
+        for _ in 0..100 {
+            let params = (0..30).map(|i| {
+                if i == 1 {
+                    rng.gen_range(0.0..0.2)
+                } else {
+                    rng.gen()
+                }
+            });
+            let params = Params::new(params.collect());
+            let xs = params.get();
+            let infeasible = (xs[0] - 0.5).powi(2) + xs[1].powi(2) < 0.0625;
+            match problem.create_evaluator(params) {
+                Ok(_) => {
+                    assert!(!infeasible);
+                    feasibles += 1;
+                }
+                Err(e) => {
+                    assert!(infeasible);
+                    assert_eq!(*e.kind(), ErrorKind::UnevaluableParams);
+                    infeasibles += 1;
+                }
+            }
+        }
+        assert!(feasibles > 0);
+        assert!(infeasibles > 0);
+
+        let recipe = ZdtProblemRecipe {
+            zdt: Zdt::Function5,
+            constrained: true,
+        };
+        assert!(recipe
+            .create_factory(&FactoryRegistry::new::<
+                ZdtProblemRecipe,
+                kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+            >())
+            .is_err());
+        Ok(())
+    }
+}
@@ -20,6 +20,9 @@ parser.add_argument("--pruner-kwargs", type=str, default="{}")
 parser.add_argument("--loglevel", choices=["debug", "info", "warning", "error"])
 parser.add_argument("--direction", choices=["minimize", "maximize"], default="minimize")
 parser.add_argument("--use-discrete-uniform", action="store_true")
+parser.add_argument("--n-startup-trials", type=int)
+parser.add_argument("--n-ei-candidates", type=int)
+parser.add_argument("--multivariate", action="store_true")
 
 args = parser.parse_args()
 
@@ -45,6 +48,12 @@ def create_study(seed):
         raise ValueError("Unknown sampler: {}.".format(args.sampler))
 
     sampler_kwargs = json.loads(args.sampler_kwargs)
+    if args.n_startup_trials is not None:
+        sampler_kwargs["n_startup_trials"] = args.n_startup_trials
+    if args.n_ei_candidates is not None:
+        sampler_kwargs["n_ei_candidates"] = args.n_ei_candidates
+    if args.multivariate:
+        sampler_kwargs["multivariate"] = True
     try:
         sampler_kwargs["seed"] = seed
         sampler = sampler_cls(**sampler_kwargs)
@@ -21,6 +21,11 @@ use structopt::StructOpt;
 use yamakan::optimizers::asha::{AshaOptimizer, AshaOptimizerBuilder};
 use yamakan::{self, Budget, MfObs, MultiFidelityOptimizer, Obs, ObsId, Optimizer, Ranked};
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
+
 /// Recipe of `AshaSolver`.
 #[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
 #[structopt(rename_all = "kebab-case")]
@@ -33,10 +38,25 @@ pub struct AshaSolverRecipe {
     pub min_step_rate: f64,
 
     /// Minimum resource parameter of AHSA.
-    #[structopt(long)]
+    #[structopt(long, alias = "min-resource")]
+    #[serde(alias = "min_resource")]
     pub min_step: Option<u64>,
 
+    /// Maximum resource parameter of ASHA.
+    ///
+    /// If omitted, `problem.steps.last()` is used.
+    /// This must not be greater than `problem.steps.last()`.
+    #[structopt(long, alias = "max-resource")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "max_resource"
+    )]
+    pub max_step: Option<u64>,
+
     /// Reduction factor parameter of ASHA.
+    ///
+    /// This must be greater than or equal to `2`.
     #[structopt(long, default_value = "2")]
     pub reduction_factor: usize,
 
@@ -44,6 +64,16 @@ pub struct AshaSolverRecipe {
     #[structopt(long)]
     pub without_checkpoint: bool,
 
+    /// If this flag is set, each trial is evaluated up to the budget of its rung
+    /// and the result is told to ASHA as the observation of that budget.
+    ///
+    /// Otherwise, trials are evaluated up to the steps asked by the base solver and
+    /// all the results are told as the observations of the maximum budget
+    /// (this is the behavior of the recipes that don't have this flag).
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub rung_budgets: bool,
+
     /// Recipe of the base solver.
     pub base_solver: JsonRecipe,
 }
@@ -51,12 +81,30 @@ impl SolverRecipe for AshaSolverRecipe {
     type Factory = AshaSolverFactory;
 
     fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        track_assert!(
+            self.reduction_factor >= 2,
+            ErrorKind::InvalidInput,
+            "`reduction_factor` must be greater than or equal to 2: {}",
+            self.reduction_factor
+        );
+        if let (Some(min), Some(max)) = (self.min_step, self.max_step) {
+            track_assert!(
+                min <= max,
+                ErrorKind::InvalidInput,
+                "`min_step` must not be greater than `max_step`: min_step={}, max_step={}",
+                min,
+                max
+            );
+        }
+
         let base = track!(registry.create_solver_factory_from_json(&self.base_solver))?;
         Ok(AshaSolverFactory {
             min_step_rate: self.min_step_rate,
             min_step: self.min_step,
+            max_step: self.max_step,
             reduction_factor: self.reduction_factor,
             without_checkpoint: self.without_checkpoint,
+            rung_budgets: self.rung_budgets,
             base,
         })
     }
@@ -67,8 +115,10 @@ impl SolverRecipe for AshaSolverRecipe {
 pub struct AshaSolverFactory {
     min_step_rate: f64,
     min_step: Option<u64>,
+    max_step: Option<u64>,
     reduction_factor: usize,
     without_checkpoint: bool,
+    rung_budgets: bool,
     base: BoxSolverFactory,
 }
 impl SolverFactory for AshaSolverFactory {
@@ -94,7 +144,14 @@ impl SolverFactory for AshaSolverFactory {
     }
 
     fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
-        let max_budget = problem.steps.last();
+        let max_budget = self.max_step.unwrap_or_else(|| problem.steps.last());
+        track_assert!(
+            max_budget <= problem.steps.last(),
+            ErrorKind::InvalidInput,
+            "`max_step` must not be greater than the last step of the problem: max_step={}, last_step={}",
+            max_budget,
+            problem.steps.last()
+        );
         let min_budget = if let Some(v) = self.min_step {
             v
         } else {
@@ -119,6 +176,7 @@ impl SolverFactory for AshaSolverFactory {
             rng,
             trials: HashMap::new(),
             max_budget,
+            rung_budgets: self.rung_budgets,
         })
     }
 }
@@ -130,8 +188,11 @@ impl SolverFactory for AshaSolverFactory {
 pub struct AshaSolver {
     optimizer: AshaOptimizer<OrderedFloat<f64>, BaseOptimizer>,
     rng: ArcRng,
-    trials: HashMap<TrialId, NextTrial>,
+
+    // The base trials and the budget amounts of the asked trials.
+    trials: HashMap<TrialId, (NextTrial, u64)>,
     max_budget: u64,
+    rung_budgets: bool,
 }
 impl Solver for AshaSolver {
     fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
@@ -143,13 +204,19 @@ impl Solver for AshaSolver {
 
         let mut trial = obs.param.clone();
         trial.id = TrialId::new(obs.id.get());
+        let amount = if self.rung_budgets {
+            trial.next_step = Some(obs.budget.amount);
+            obs.budget.amount
+        } else {
+            self.max_budget
+        };
 
-        self.trials.insert(trial.id, obs.param);
+        self.trials.insert(trial.id, (obs.param, amount));
         Ok(trial)
     }
 
     fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
-        let param = track_assert_some!(self.trials.remove(&trial.id), ErrorKind::Bug);
+        let (param, amount) = track_assert_some!(self.trials.remove(&trial.id), ErrorKind::Bug);
         let value = if trial.values.is_empty() {
             OrderedFloat(f64::NAN)
         } else {
@@ -159,7 +226,7 @@ impl Solver for AshaSolver {
         let obs = MfObs {
             id: ObsId::new(trial.id.get()),
             budget: Budget {
-                amount: self.max_budget,
+                amount,
                 consumption: trial.current_step,
             },
             param,
@@ -225,3 +292,100 @@ impl Optimizer for BaseOptimizer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::RandomSolverRecipe;
+    use kurobako_core::domain;
+    use kurobako_core::epi::problem::ExternalProgramProblemRecipe;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use kurobako_core::Error;
+    use std::collections::BTreeSet;
+    use trackable::result::TopLevelResult;
+
+    fn asked_steps(max_step: Option<u64>, rung_budgets: bool) -> Result<BTreeSet<u64>> {
+        let problem = track!(ProblemSpecBuilder::new("asha")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("v"))
+            .steps(1..=81)
+            .finish())?;
+
+        let registry = FactoryRegistry::new::<ExternalProgramProblemRecipe, RandomSolverRecipe>();
+        let random: RandomSolverRecipe = track!(serde_json::from_str("{}").map_err(Error::from))?;
+        let factory = AshaSolverFactory {
+            min_step_rate: 0.01,
+            min_step: Some(1),
+            max_step,
+            reduction_factor: 3,
+            without_checkpoint: false,
+            rung_budgets,
+            base: BoxSolverFactory::new(track!(random.create_factory(&registry))?),
+        };
+        let mut solver = track!(factory.create_solver(ArcRng::new(0), &problem))?;
+
+        let mut idg = IdGen::new();
+        let mut steps = BTreeSet::new();
+        for _ in 0..300 {
+            let trial = track!(solver.ask(&mut idg))?;
+            let step = track_assert_some!(trial.next_step, ErrorKind::Bug);
+            steps.insert(step);
+
+            let value = trial.params[0];
+            track!(solver.tell(trial.evaluated(Values::new(vec![value]), step)))?;
+        }
+        Ok(steps)
+    }
+
+    fn create_solver(recipe: &str) -> Result<AshaSolver> {
+        let problem = track!(ProblemSpecBuilder::new("asha")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("v"))
+            .steps(1..=81)
+            .finish())?;
+
+        let registry = FactoryRegistry::new::<ExternalProgramProblemRecipe, RandomSolverRecipe>();
+        let recipe: AshaSolverRecipe = track!(serde_json::from_str(recipe).map_err(Error::from))?;
+        let factory = track!(recipe.create_factory(&registry))?;
+        track!(factory.create_solver(ArcRng::new(0), &problem))
+    }
+
+    #[test]
+    fn asha_parameters_are_validated() -> TopLevelResult {
+        let recipe = |fields: &str| {
+            format!(
+                r#"{{"min_step_rate":0.01,"without_checkpoint":false,
+                     "base_solver":{{}}{}}}"#,
+                fields
+            )
+        };
+        create_solver(&recipe(
+            r#","reduction_factor":3,"min_resource":1,"max_resource":50"#,
+        ))?;
+        create_solver(&recipe(r#","reduction_factor":2,"min_step":81"#))?;
+
+        for fields in &[
+            r#","reduction_factor":1"#,
+            r#","reduction_factor":3,"min_resource":10,"max_resource":9"#,
+            r#","reduction_factor":3,"max_resource":82"#,
+        ] {
+            let e = create_solver(&recipe(fields)).expect_err("should be rejected");
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput, "{}", fields);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn asha_rungs_works() -> TopLevelResult {
+        let steps = track!(asked_steps(None, true))?;
+        assert_eq!(steps.into_iter().collect::<Vec<_>>(), [1, 3, 9, 27, 81]);
+
+        let steps = track!(asked_steps(Some(50), true))?;
+        assert_eq!(steps.into_iter().collect::<Vec<_>>(), [1, 3, 9, 27, 50]);
+
+        // Without `rung_budgets`, the steps asked by the base solver are used as before.
+        let steps = track!(asked_steps(None, false))?;
+        assert_eq!(steps.into_iter().collect::<Vec<_>>(), [81]);
+        Ok(())
+    }
+}
@@ -0,0 +1,542 @@
+//! A solver based on [CMA-ES] (Covariance Matrix Adaptation Evolution Strategy).
+//!
+//! The search space is normalized into the unit hypercube (in log scale for log-uniform variables).
+//! Samples out of the hypercube are re-sampled a few times and then clipped into it.
+//!
+//! This solver only supports problems that consist of bounded continuous variables.
+//!
+//! [CMA-ES]: https://arxiv.org/abs/1604.00772
+use kurobako_core::domain::{Distribution, Range, Variable};
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::{standard_normal, ArcRng, Rng};
+use kurobako_core::solver::{
+    Capabilities, Capability, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f64;
+use structopt::StructOpt;
+
+// The maximum number of re-sampling for out-of-bounds samples.
+const MAX_RESAMPLING: usize = 100;
+
+/// Recipe of `CmaesSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CmaesSolverRecipe {
+    /// Initial step size in the normalized search space (i.e., `[0, 1]` for each variable).
+    ///
+    /// If omitted, `1/6` is used.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sigma0: Option<f64>,
+
+    /// Population size (the number of trials in a generation).
+    ///
+    /// If omitted, `4 + floor(3 * ln(the number of variables))` is used.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    population_size: Option<usize>,
+}
+impl SolverRecipe for CmaesSolverRecipe {
+    type Factory = CmaesSolverFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let sigma0 = self.sigma0.unwrap_or(1.0 / 6.0);
+        track_assert!(
+            sigma0.is_finite() && sigma0 > 0.0,
+            ErrorKind::InvalidInput,
+            "`sigma0` must be a positive number: {}",
+            sigma0
+        );
+        if let Some(n) = self.population_size {
+            track_assert!(
+                n >= 2,
+                ErrorKind::InvalidInput,
+                "`population_size` must be greater than or equal to 2: {}",
+                n
+            );
+        }
+
+        Ok(CmaesSolverFactory {
+            sigma0,
+            population_size: self.population_size,
+        })
+    }
+}
+
+/// Factory of `CmaesSolver`.
+#[derive(Debug)]
+pub struct CmaesSolverFactory {
+    sigma0: f64,
+    population_size: Option<usize>,
+}
+impl SolverFactory for CmaesSolverFactory {
+    type Solver = CmaesSolver;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let capabilities = Capabilities::new(
+            [
+                Capability::UniformContinuous,
+                Capability::LogUniformContinuous,
+                Capability::Concurrent,
+            ]
+            .iter()
+            .copied(),
+        );
+
+        let mut spec = SolverSpecBuilder::new("CMA-ES")
+            .attr(
+                "version",
+                &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr(
+                "paper",
+                "Hansen, Nikolaus. \"The CMA evolution strategy: A tutorial.\" \
+                 arXiv preprint arXiv:1604.00772 (2016).",
+            )
+            .attr("sigma0", &self.sigma0.to_string());
+        if let Some(n) = self.population_size {
+            spec = spec.attr("population_size", &n.to_string());
+        }
+        Ok(spec.capabilities(capabilities).finish())
+    }
+
+    fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        let vars = problem.params_domain.variables();
+        track_assert!(
+            !vars.is_empty(),
+            ErrorKind::InvalidInput,
+            "CMA-ES requires at least one variable"
+        );
+        for var in vars {
+            match var.range() {
+                Range::Continuous { low, high, .. } => {
+                    track_assert!(
+                        low.is_finite() && high.is_finite() && var.constraint().is_none(),
+                        ErrorKind::Incapable,
+                        "CMA-ES only supports bounded and unconditional variables: {:?}",
+                        var
+                    );
+                }
+                _ => track_panic!(
+                    ErrorKind::Incapable,
+                    "CMA-ES only supports continuous variables: {:?}",
+                    var
+                ),
+            }
+        }
+
+        let population_size = self
+            .population_size
+            .unwrap_or_else(|| default_population_size(vars.len()));
+        Ok(CmaesSolver {
+            rng,
+            vars: vars.to_owned(),
+            steps: problem.steps.last(),
+            cma: Cma::new(vars.len(), population_size, self.sigma0),
+            pending: HashMap::new(),
+            solutions: Vec::new(),
+        })
+    }
+}
+
+fn default_population_size(dim: usize) -> usize {
+    4 + (3.0 * (dim as f64).ln()).floor() as usize
+}
+
+/// Solver based on CMA-ES.
+#[derive(Debug)]
+pub struct CmaesSolver {
+    rng: ArcRng,
+    vars: Vec<Variable>,
+    steps: u64,
+    cma: Cma,
+
+    // Normalized parameters and generations of the trials that haven't been told yet.
+    pending: HashMap<TrialId, (Vec<f64>, usize)>,
+
+    // Evaluated solutions of the current generation.
+    solutions: Vec<(Vec<f64>, f64)>,
+}
+impl CmaesSolver {
+    // Maps a point in the unit hypercube to the parameters of the problem.
+    fn decode(&self, x: &[f64]) -> Params {
+        let params = self
+            .vars
+            .iter()
+            .zip(x.iter())
+            .map(|(var, &x)| {
+                let (low, high) = (var.range().low(), var.range().high());
+                let v = match var.distribution() {
+                    Distribution::Uniform => low + (high - low) * x,
+                    Distribution::LogUniform => {
+                        (low.log2() + (high.log2() - low.log2()) * x).exp2()
+                    }
+                };
+                var.range().quantize(v.max(low).min(high.next_down()))
+            })
+            .collect();
+        Params::new(params)
+    }
+}
+impl Solver for CmaesSolver {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        let x = self.cma.sample(&mut self.rng);
+        let params = self.decode(&x);
+
+        let id = idg.generate();
+        self.pending.insert(id, (x, self.cma.generation));
+        Ok(NextTrial {
+            id,
+            params,
+            next_step: Some(self.steps),
+        })
+    }
+
+    fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
+        let (x, generation) = track_assert_some!(
+            self.pending.remove(&trial.id),
+            ErrorKind::InvalidInput,
+            "Unknown trial: {:?}",
+            trial.id
+        );
+        if generation != self.cma.generation || trial.values.is_empty() {
+            return Ok(());
+        }
+        let value = trial.values[0];
+        if value.is_nan() {
+            return Ok(());
+        }
+
+        self.solutions.push((x, value));
+        if self.solutions.len() == self.cma.population_size {
+            self.cma.update(&mut self.solutions);
+            self.solutions.clear();
+        }
+        Ok(())
+    }
+}
+
+// State of CMA-ES.
+//
+// See "The CMA Evolution Strategy: A Tutorial" (Hansen, 2016) for the notations and the update rules.
+#[derive(Debug)]
+struct Cma {
+    dim: usize,
+    population_size: usize,
+    generation: usize,
+
+    weights: Vec<f64>,
+    mu_eff: f64,
+    c_sigma: f64,
+    d_sigma: f64,
+    c_c: f64,
+    c_1: f64,
+    c_mu: f64,
+    chi_n: f64,
+
+    mean: Vec<f64>,
+    sigma: f64,
+    c: Vec<Vec<f64>>,
+    p_sigma: Vec<f64>,
+    p_c: Vec<f64>,
+
+    // Eigen decomposition of `c` (i.e., `c = b * diag(d^2) * b^T`).
+    b: Vec<Vec<f64>>,
+    d: Vec<f64>,
+}
+impl Cma {
+    fn new(dim: usize, population_size: usize, sigma0: f64) -> Self {
+        let n = dim as f64;
+        let mu = population_size / 2;
+        let mut weights = (0..mu)
+            .map(|i| ((population_size as f64 + 1.0) / 2.0).ln() - ((i + 1) as f64).ln())
+            .collect::<Vec<_>>();
+        let sum = weights.iter().sum::<f64>();
+        weights.iter_mut().for_each(|w| *w /= sum);
+        let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+        let d_sigma = 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma;
+        let c_c = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+        let c_1 = 2.0 / ((n + 1.3).powi(2) + mu_eff);
+        let c_mu =
+            (1.0 - c_1).min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff));
+        let chi_n = n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n));
+
+        Self {
+            dim,
+            population_size,
+            generation: 0,
+            weights,
+            mu_eff,
+            c_sigma,
+            d_sigma,
+            c_c,
+            c_1,
+            c_mu,
+            chi_n,
+            mean: vec![0.5; dim],
+            sigma: sigma0,
+            c: identity(dim),
+            p_sigma: vec![0.0; dim],
+            p_c: vec![0.0; dim],
+            b: identity(dim),
+            d: vec![1.0; dim],
+        }
+    }
+
+    fn sample<R: Rng>(&self, mut rng: R) -> Vec<f64> {
+        let mut x = Vec::new();
+        for _ in 0..MAX_RESAMPLING {
+            let z = (0..self.dim)
+                .map(|i| self.d[i] * standard_normal(&mut rng))
+                .collect::<Vec<_>>();
+            let y = mat_vec(&self.b, &z);
+            x = (0..self.dim)
+                .map(|i| self.mean[i] + self.sigma * y[i])
+                .collect::<Vec<_>>();
+            if x.iter().all(|&x| (0.0..=1.0).contains(&x)) {
+                return x;
+            }
+        }
+        x.iter().map(|&x| x.clamp(0.0, 1.0)).collect()
+    }
+
+    fn update(&mut self, solutions: &mut [(Vec<f64>, f64)]) {
+        let n = self.dim as f64;
+        solutions.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or_else(|| unreachable!()));
+        self.generation += 1;
+
+        let old_mean = self.mean.clone();
+        let ys = solutions
+            .iter()
+            .take(self.weights.len())
+            .map(|(x, _)| {
+                (0..self.dim)
+                    .map(|i| (x[i] - old_mean[i]) / self.sigma)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let y_w = (0..self.dim)
+            .map(|i| {
+                self.weights
+                    .iter()
+                    .zip(ys.iter())
+                    .map(|(w, y)| w * y[i])
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+        for i in 0..self.dim {
+            self.mean[i] = old_mean[i] + self.sigma * y_w[i];
+        }
+
+        // Step-size control.
+        let c_inv_sqrt_y = {
+            let bt_y = mat_t_vec(&self.b, &y_w);
+            let scaled = bt_y
+                .iter()
+                .zip(self.d.iter())
+                .map(|(v, d)| v / d)
+                .collect::<Vec<_>>();
+            mat_vec(&self.b, &scaled)
+        };
+        let k = (self.c_sigma * (2.0 - self.c_sigma) * self.mu_eff).sqrt();
+        for (p, y) in self.p_sigma.iter_mut().zip(c_inv_sqrt_y.iter()) {
+            *p = (1.0 - self.c_sigma) * *p + k * y;
+        }
+        let p_sigma_norm = norm(&self.p_sigma);
+        self.sigma *= ((self.c_sigma / self.d_sigma) * (p_sigma_norm / self.chi_n - 1.0)).exp();
+
+        // Covariance matrix adaptation.
+        let h_sigma_threshold = (1.4 + 2.0 / (n + 1.0)) * self.chi_n;
+        let h_sigma = p_sigma_norm
+            / (1.0 - (1.0 - self.c_sigma).powi(2 * self.generation as i32)).sqrt()
+            < h_sigma_threshold;
+        let h_sigma = if h_sigma { 1.0 } else { 0.0 };
+
+        let k = (self.c_c * (2.0 - self.c_c) * self.mu_eff).sqrt();
+        for (p, y) in self.p_c.iter_mut().zip(y_w.iter()) {
+            *p = (1.0 - self.c_c) * *p + h_sigma * k * y;
+        }
+
+        let delta_h_sigma = (1.0 - h_sigma) * self.c_c * (2.0 - self.c_c);
+        for i in 0..self.dim {
+            for j in 0..self.dim {
+                let rank_one = self.p_c[i] * self.p_c[j] + delta_h_sigma * self.c[i][j];
+                let rank_mu = self
+                    .weights
+                    .iter()
+                    .zip(ys.iter())
+                    .map(|(w, y)| w * y[i] * y[j])
+                    .sum::<f64>();
+                self.c[i][j] = (1.0 - self.c_1 - self.c_mu) * self.c[i][j]
+                    + self.c_1 * rank_one
+                    + self.c_mu * rank_mu;
+            }
+        }
+
+        let (eigenvalues, b) = eigen_symmetric(&self.c);
+        self.d = eigenvalues.iter().map(|&v| v.max(1e-20).sqrt()).collect();
+        self.b = b;
+    }
+}
+
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect()
+}
+
+fn mat_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    m.iter()
+        .map(|row| row.iter().zip(v.iter()).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+fn mat_t_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    (0..v.len())
+        .map(|j| m.iter().zip(v.iter()).map(|(row, b)| row[j] * b).sum())
+        .collect()
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+// Computes the eigenvalues and eigenvectors (columns) of a symmetric matrix by using the Jacobi method.
+fn eigen_symmetric(m: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = m.len();
+    let mut a = m.to_owned();
+    let mut v = identity(n);
+    for _ in 0..100 {
+        let off = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| i != j).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum::<f64>();
+        if off < 1e-30 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                rotate_columns(&mut a, p, q, c, s);
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (apk, aqk)) in row_p.into_iter().zip(row_q).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                rotate_columns(&mut v, p, q, c, s);
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), v)
+}
+
+fn rotate_columns(m: &mut [Vec<f64>], p: usize, q: usize, c: f64, s: f64) {
+    for row in m {
+        let (vp, vq) = (row[p], row[q]);
+        row[p] = c * vp - s * vq;
+        row[q] = s * vp + c * vq;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::problem::{
+        Evaluator as _, Problem as _, ProblemFactory as _, ProblemRecipe as _, ProblemSpecBuilder,
+    };
+    use kurobako_core::Error;
+    use kurobako_problems::sigopt::SigoptProblemRecipe;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn default_population_size_works() {
+        assert_eq!(default_population_size(1), 4);
+        assert_eq!(default_population_size(2), 6);
+        assert_eq!(default_population_size(10), 10);
+    }
+
+    #[test]
+    fn eigen_symmetric_works() {
+        let m = vec![
+            vec![4.0, 1.0, 0.5],
+            vec![1.0, 3.0, 0.2],
+            vec![0.5, 0.2, 2.0],
+        ];
+        let (values, vectors) = eigen_symmetric(&m);
+        for (k, &lambda) in values.iter().enumerate() {
+            let v = vectors.iter().map(|row| row[k]).collect::<Vec<_>>();
+            let mv = mat_vec(&m, &v);
+            for i in 0..3 {
+                assert!((mv[i] - lambda * v[i]).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn cmaes_solver_rejects_non_continuous_problems() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("discrete")
+            .param(domain::var("x").discrete(0, 10))
+            .value(domain::var("v"))
+            .finish()?;
+        let factory = CmaesSolverFactory {
+            sigma0: 0.1,
+            population_size: None,
+        };
+        assert_eq!(
+            factory
+                .create_solver(ArcRng::new(0), &problem)
+                .err()
+                .map(|e| *e.kind()),
+            Some(ErrorKind::Incapable)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cmaes_solver_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<
+            SigoptProblemRecipe,
+            kurobako_core::epi::solver::ExternalProgramSolverRecipe,
+        >();
+        let recipe: SigoptProblemRecipe =
+            serde_json::from_str(r#"{"name":"SPHERE","dim":3}"#).map_err(Error::from)?;
+        let problem_factory = recipe.create_factory(&registry)?;
+        let problem_spec = problem_factory.specification()?;
+        let problem = problem_factory.create_problem(ArcRng::new(0))?;
+
+        let factory = CmaesSolverFactory {
+            sigma0: 1.0 / 6.0,
+            population_size: None,
+        };
+        let mut solver = factory.create_solver(ArcRng::new(0), &problem_spec)?;
+
+        let mut idg = IdGen::new();
+        let mut best = f64::INFINITY;
+        for _ in 0..300 {
+            let trial = solver.ask(&mut idg)?;
+            assert!(problem_spec.params_domain.contains(trial.params.get()));
+
+            let mut evaluator = problem.create_evaluator(trial.params.clone())?;
+            let (step, values) = evaluator.evaluate(problem_spec.steps.last())?;
+            best = best.min(values[0]);
+            solver.tell(trial.evaluated(values, step))?;
+        }
+        assert!(best < 0.01, "best={}", best);
+        Ok(())
+    }
+}
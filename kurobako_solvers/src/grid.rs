@@ -0,0 +1,269 @@
+//! A solver based on grid search.
+//!
+//! This solver deterministically walks the Cartesian product of the grid points of the variables.
+//! Discrete and categorical variables use all their possible values as the grid points,
+//! and continuous variables use `resolution` evenly spaced points (in log scale for log-uniform ones).
+//! Once all the grid points have been asked, the grid is walked again from the beginning.
+//!
+//! Conditional search spaces aren't supported for now.
+use kurobako_core::domain::{Distribution, Range, Variable};
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::solver::{
+    Capabilities, Capability, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+/// Recipe of `GridSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct GridSolverRecipe {
+    /// Number of grid points of each continuous variable.
+    ///
+    /// This is required if the problem has continuous variables.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolution: Option<usize>,
+}
+impl SolverRecipe for GridSolverRecipe {
+    type Factory = GridSolverFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        if let Some(resolution) = self.resolution {
+            track_assert!(resolution > 0, ErrorKind::InvalidInput);
+        }
+        Ok(GridSolverFactory {
+            resolution: self.resolution,
+        })
+    }
+}
+
+/// Factory of `GridSolver`.
+#[derive(Debug)]
+pub struct GridSolverFactory {
+    resolution: Option<usize>,
+}
+impl SolverFactory for GridSolverFactory {
+    type Solver = GridSolver;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let mut capabilities = Capabilities::all();
+        capabilities.remove_capability(Capability::Conditional);
+
+        let mut spec = SolverSpecBuilder::new("Grid").attr(
+            "version",
+            &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+        );
+        if let Some(resolution) = self.resolution {
+            spec = spec.attr("resolution", &resolution.to_string());
+        }
+        Ok(spec.capabilities(capabilities).finish())
+    }
+
+    fn create_solver(&self, _rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        track_assert!(
+            !problem.requirements().is_capable(Capability::Conditional),
+            ErrorKind::Incapable,
+            "Grid search doesn't support conditional search spaces"
+        );
+
+        let grid = problem
+            .params_domain
+            .variables()
+            .iter()
+            .map(|var| track!(grid_points(var, self.resolution); var.name()))
+            .collect::<Result<Vec<_>>>()?;
+        let indices = vec![0; grid.len()];
+        Ok(GridSolver {
+            steps: problem.steps.last(),
+            grid,
+            indices,
+            exhausted: false,
+        })
+    }
+}
+
+fn grid_points(var: &Variable, resolution: Option<usize>) -> Result<Vec<f64>> {
+    let points = match var.range() {
+        Range::Continuous { low, high, .. } => {
+            let resolution = track_assert_some!(
+                resolution,
+                ErrorKind::InvalidInput,
+                "`--resolution` is required for continuous variables"
+            );
+            track_assert!(
+                low.is_finite() && high.is_finite(),
+                ErrorKind::InvalidInput,
+                "Unbounded continuous variable: range={:?}",
+                var.range()
+            );
+
+            let mut points = (0..resolution)
+                .map(|i| {
+                    let r = i as f64 / resolution as f64;
+                    let v = match var.distribution() {
+                        Distribution::Uniform => low + (high - low) * r,
+                        Distribution::LogUniform => {
+                            (low.log2() + (high.log2() - low.log2()) * r).exp2()
+                        }
+                    };
+                    var.range().quantize(v.min(*high).max(*low))
+                })
+                .filter(|&v| var.range().contains(v))
+                .collect::<Vec<_>>();
+            points.dedup();
+            points
+        }
+        Range::Discrete { low, high } => (*low..*high).map(|v| v as f64).collect(),
+        Range::IntChoices { values } => values.iter().map(|&v| v as f64).collect(),
+        Range::Categorical { choices } | Range::Ordinal { choices } => {
+            (0..choices.len()).map(|i| i as f64).collect()
+        }
+    };
+    track_assert!(
+        !points.is_empty(),
+        ErrorKind::InvalidInput,
+        "Empty grid: range={:?}",
+        var.range()
+    );
+    Ok(points)
+}
+
+/// Solver based on grid search.
+#[derive(Debug)]
+pub struct GridSolver {
+    steps: u64,
+
+    // The i-th element is the grid points of the i-th variable.
+    grid: Vec<Vec<f64>>,
+
+    // Indices of the grid points of the next parameters (the last variable varies fastest).
+    indices: Vec<usize>,
+
+    exhausted: bool,
+}
+impl GridSolver {
+    fn advance(&mut self) {
+        for (i, points) in self.indices.iter_mut().zip(self.grid.iter()).rev() {
+            *i += 1;
+            if *i < points.len() {
+                return;
+            }
+            *i = 0;
+        }
+
+        if !self.exhausted {
+            eprintln!(
+                "WARNING: The grid has been exhausted, so it'll be repeated from the beginning"
+            );
+            self.exhausted = true;
+        }
+    }
+}
+impl Solver for GridSolver {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        let params = self
+            .indices
+            .iter()
+            .zip(self.grid.iter())
+            .map(|(&i, points)| points[i])
+            .collect();
+        self.advance();
+
+        Ok(NextTrial {
+            id: idg.generate(),
+            params: Params::new(params),
+            next_step: Some(self.steps),
+        })
+    }
+
+    fn tell(&mut self, _trial: EvaluatedTrial) -> Result<()> {
+        Ok(())
+    }
+
+    fn tell_prior(&mut self, _id: TrialId, _params: Params, _values: Values) -> Result<()> {
+        // The grid doesn't depend on observations, so priors are just accepted.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn grid_solver_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("grid")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .param(domain::var("y").discrete(-1, 1))
+            .param(domain::var("c").categorical(["a", "b", "c"]))
+            .value(domain::var("v"))
+            .finish()?;
+
+        let factory = GridSolverFactory {
+            resolution: Some(4),
+        };
+        let mut solver = factory.create_solver(ArcRng::new(0), &problem)?;
+
+        let mut idg = IdGen::new();
+        let mut params = Vec::new();
+        for _ in 0..4 * 2 * 3 {
+            params.push(solver.ask(&mut idg)?.params.into_vec());
+        }
+        assert_eq!(params[0], [0.0, -1.0, 0.0]);
+        assert_eq!(params[1], [0.0, -1.0, 1.0]);
+        assert_eq!(params[3], [0.0, 0.0, 0.0]);
+        assert_eq!(params[6], [0.25, -1.0, 0.0]);
+        assert_eq!(params[23], [0.75, 0.0, 2.0]);
+
+        let mut sorted = params.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or_else(|| unreachable!()));
+        sorted.dedup();
+        assert_eq!(sorted.len(), params.len());
+
+        // The grid is repeated.
+        assert_eq!(solver.ask(&mut idg)?.params.into_vec(), params[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn grid_solver_rejects_invalid_problems() -> TopLevelResult {
+        let continuous = ProblemSpecBuilder::new("continuous")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("v"))
+            .finish()?;
+        let factory = GridSolverFactory { resolution: None };
+        assert_eq!(
+            factory
+                .create_solver(ArcRng::new(0), &continuous)
+                .err()
+                .map(|e| *e.kind()),
+            Some(ErrorKind::InvalidInput)
+        );
+
+        let conditional = ProblemSpecBuilder::new("conditional")
+            .param(domain::var("x").categorical(["a", "b"]))
+            .param(
+                domain::var("y")
+                    .discrete(0, 3)
+                    .constraint(domain::Constraint::new("x == 0")),
+            )
+            .value(domain::var("v"))
+            .finish()?;
+        assert_eq!(
+            factory
+                .create_solver(ArcRng::new(0), &conditional)
+                .err()
+                .map(|e| *e.kind()),
+            Some(ErrorKind::Incapable)
+        );
+        Ok(())
+    }
+}
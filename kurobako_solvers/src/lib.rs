@@ -5,9 +5,15 @@
 extern crate trackable;
 
 pub mod asha;
+pub mod cmaes;
+pub mod grid;
 pub mod nsga2;
 pub mod optuna;
+pub mod pruner;
 pub mod random;
+pub mod restart;
+pub mod sobol;
+pub mod warm_start;
 
 mod error;
 mod yamakan_utils;
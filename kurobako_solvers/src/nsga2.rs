@@ -1,16 +1,19 @@
 //! A solver based on nsga2 search.
 use crate::error::from_yamakan;
 use crate::yamakan_utils::{KurobakoDomain, YamakanIdGen};
+use kurobako_core::domain::{Distribution, Range, Variable};
 use kurobako_core::problem::ProblemSpec;
 use kurobako_core::registry::FactoryRegistry;
-use kurobako_core::rng::ArcRng;
+use kurobako_core::rng::{ArcRng, Rng};
 use kurobako_core::solver::{
     Capabilities, Capability, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
 };
 use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId};
-use kurobako_core::{ErrorKind, Result};
+use kurobako_core::{Error, ErrorKind, Result};
+use rand::distributions::Distribution as _;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 use structopt::StructOpt;
 use yamakan::domains::VecDomain;
 use yamakan::optimizers::nsga2;
@@ -20,8 +23,8 @@ type Nsga2Strategy = nsga2::Nsga2Strategy<
     VecDomain<KurobakoDomain>,
     nsga2::RandomGenerator,
     nsga2::TournamentSelector,
-    nsga2::ExchangeVec,
-    nsga2::ReplaceVec,
+    CrossOver,
+    Mutation,
 >;
 
 type Nsga2Optimizer = nsga2::Nsga2Optimizer<VecDomain<KurobakoDomain>, Nsga2Strategy>;
@@ -45,20 +48,145 @@ pub struct Nsga2SolverRecipe {
     /// Mutation probability of each parameter.
     #[structopt(long, default_value = "0.3")]
     mutation: f64,
+
+    /// Crossover operator.
+    ///
+    /// - `uniform`: Exchanges the values of the parents.
+    /// - `sbx`: Simulated binary crossover (categorical parameters are exchanged as `uniform`).
+    #[structopt(
+        long,
+        default_value = "uniform",
+        possible_values = CrossOverKind::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "CrossOverKind::is_uniform")]
+    crossover_type: CrossOverKind,
+
+    /// Distribution index of the simulated binary crossover.
+    ///
+    /// If omitted, `15.0` is used.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crossover_eta: Option<f64>,
+
+    /// Mutation operator.
+    ///
+    /// - `uniform`: Replaces the value with a randomly sampled one.
+    /// - `polynomial`: Polynomial mutation (categorical parameters are mutated as `uniform`).
+    #[structopt(
+        long,
+        default_value = "uniform",
+        possible_values = MutationKind::POSSIBLE_VALUES
+    )]
+    #[serde(default, skip_serializing_if = "MutationKind::is_uniform")]
+    mutation_type: MutationKind,
+
+    /// Distribution index of the polynomial mutation.
+    ///
+    /// If omitted, `20.0` is used.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mutation_eta: Option<f64>,
 }
 impl SolverRecipe for Nsga2SolverRecipe {
     type Factory = Nsga2SolverFactory;
 
     fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        for (name, p) in &[("crossover", self.crossover), ("mutation", self.mutation)] {
+            track_assert!(
+                (0.0..=1.0).contains(p),
+                ErrorKind::InvalidInput,
+                "`{}` must be a probability in [0, 1]: {}",
+                name,
+                p
+            );
+        }
+
+        let crossover_eta = self.crossover_eta.unwrap_or(15.0);
+        let mutation_eta = self.mutation_eta.unwrap_or(20.0);
+        for (name, eta) in &[
+            ("crossover_eta", crossover_eta),
+            ("mutation_eta", mutation_eta),
+        ] {
+            track_assert!(
+                eta.is_finite() && *eta >= 0.0,
+                ErrorKind::InvalidInput,
+                "`{}` must be a non-negative number: {}",
+                name,
+                eta
+            );
+        }
+
         Ok(Nsga2SolverFactory {
             population: self.population,
             tournament: self.tournament,
             crossover: self.crossover,
             mutation: self.mutation,
+            crossover_type: self.crossover_type,
+            crossover_eta,
+            mutation_type: self.mutation_type,
+            mutation_eta,
         })
     }
 }
 
+/// Crossover operator of NSGA-II.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum CrossOverKind {
+    #[default]
+    Uniform,
+    Sbx,
+}
+impl CrossOverKind {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["uniform", "sbx"];
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_uniform(&self) -> bool {
+        *self == Self::Uniform
+    }
+}
+impl FromStr for CrossOverKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "sbx" => Ok(Self::Sbx),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown crossover type: {:?}", s),
+        }
+    }
+}
+
+/// Mutation operator of NSGA-II.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum MutationKind {
+    #[default]
+    Uniform,
+    Polynomial,
+}
+impl MutationKind {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["uniform", "polynomial"];
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_uniform(&self) -> bool {
+        *self == Self::Uniform
+    }
+}
+impl FromStr for MutationKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "polynomial" => Ok(Self::Polynomial),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown mutation type: {:?}", s),
+        }
+    }
+}
+
 /// Factory of `Nsga2Solver`.
 #[derive(Debug)]
 pub struct Nsga2SolverFactory {
@@ -66,6 +194,10 @@ pub struct Nsga2SolverFactory {
     tournament: usize,
     crossover: f64,
     mutation: f64,
+    crossover_type: CrossOverKind,
+    crossover_eta: f64,
+    mutation_type: MutationKind,
+    mutation_eta: f64,
 }
 impl SolverFactory for Nsga2SolverFactory {
     type Solver = Nsga2Solver;
@@ -94,8 +226,24 @@ impl SolverFactory for Nsga2SolverFactory {
         let params_domain = VecDomain(params_domain);
         let selector =
             track!(nsga2::TournamentSelector::new(self.tournament).map_err(from_yamakan))?;
-        let crossover = track!(nsga2::ExchangeVec::new(self.crossover).map_err(from_yamakan))?;
-        let mutator = track!(nsga2::ReplaceVec::new(self.mutation).map_err(from_yamakan))?;
+        let vars = problem
+            .params_domain
+            .variables()
+            .iter()
+            .map(Numerical::new)
+            .collect::<Vec<_>>();
+        let crossover = CrossOver {
+            kind: self.crossover_type,
+            probability: self.crossover,
+            eta: self.crossover_eta,
+            vars: vars.clone(),
+        };
+        let mutator = Mutation {
+            kind: self.mutation_type,
+            probability: self.mutation,
+            eta: self.mutation_eta,
+            vars,
+        };
         let strategy = Nsga2Strategy::new(nsga2::RandomGenerator, selector, crossover, mutator);
         let optimizer = track!(
             Nsga2Optimizer::new(params_domain, self.population, strategy).map_err(from_yamakan)
@@ -151,3 +299,244 @@ impl Solver for Nsga2Solver {
         }
     }
 }
+
+// Numerical variable on which SBX and polynomial mutation are applied.
+//
+// The operators are applied in the log space for log-uniform variables.
+#[derive(Debug, Clone)]
+struct Numerical {
+    range: Range,
+    log: bool,
+    low: f64,
+    high: f64,
+}
+impl Numerical {
+    fn new(var: &Variable) -> Option<Self> {
+        let log = var.distribution() == Distribution::LogUniform;
+        let (low, high) = match var.range() {
+            Range::Continuous { low, high, .. } => (*low, *high),
+            Range::Discrete { low, high } => (*low as f64, *high as f64),
+            _ => return None,
+        };
+        if !(low.is_finite() && high.is_finite()) {
+            return None;
+        }
+
+        let mut this = Numerical {
+            range: var.range().clone(),
+            log,
+            low: 0.0,
+            high: 0.0,
+        };
+        this.low = this.encode(low);
+        this.high = this.encode(high);
+        Some(this)
+    }
+
+    fn encode(&self, v: f64) -> f64 {
+        if self.log {
+            v.log2()
+        } else {
+            v
+        }
+    }
+
+    fn decode(&self, x: f64) -> f64 {
+        let x = x.max(self.low).min(self.high);
+        let v = if self.log { x.exp2() } else { x };
+        let (low, high) = (self.range.low(), self.range.high());
+        match self.range {
+            Range::Discrete { .. } => v.floor().max(low).min(high - 1.0),
+            _ => self.range.quantize(v.max(low).min(high.next_down())),
+        }
+    }
+}
+
+// Crossover operator applied to each parameter with the given probability.
+#[derive(Debug)]
+struct CrossOver {
+    kind: CrossOverKind,
+    probability: f64,
+    eta: f64,
+
+    // `None` means that the variable is categorical.
+    vars: Vec<Option<Numerical>>,
+}
+impl CrossOver {
+    // Simulated binary crossover for bounded variables (Deb and Agrawal, 1995).
+    fn sbx<R: Rng>(&self, mut rng: R, var: &Numerical, v0: &mut f64, v1: &mut f64) {
+        let (x0, x1) = (var.encode(*v0), var.encode(*v1));
+        if (x0 - x1).abs() < 1e-14 || var.high <= var.low {
+            return;
+        }
+
+        let (y0, y1) = if x0 < x1 { (x0, x1) } else { (x1, x0) };
+        let u: f64 = rng.gen();
+        let betaq = |beta: f64| {
+            let alpha = 2.0 - beta.powf(-(self.eta + 1.0));
+            if u <= 1.0 / alpha {
+                (u * alpha).powf(1.0 / (self.eta + 1.0))
+            } else {
+                (1.0 / (2.0 - u * alpha)).powf(1.0 / (self.eta + 1.0))
+            }
+        };
+        let c0 = 0.5 * ((y0 + y1) - betaq(1.0 + 2.0 * (y0 - var.low) / (y1 - y0)) * (y1 - y0));
+        let c1 = 0.5 * ((y0 + y1) + betaq(1.0 + 2.0 * (var.high - y1) / (y1 - y0)) * (y1 - y0));
+
+        let (c0, c1) = if rng.gen_bool(0.5) {
+            (c0, c1)
+        } else {
+            (c1, c0)
+        };
+        *v0 = var.decode(c0);
+        *v1 = var.decode(c1);
+    }
+}
+impl nsga2::CrossOver<VecDomain<KurobakoDomain>> for CrossOver {
+    fn cross_over<R: Rng>(
+        &mut self,
+        mut rng: R,
+        p0: &mut Vec<f64>,
+        p1: &mut Vec<f64>,
+    ) -> std::result::Result<(), yamakan::Error> {
+        for ((v0, v1), var) in p0.iter_mut().zip(p1.iter_mut()).zip(self.vars.iter()) {
+            if !rng.gen_bool(self.probability) {
+                continue;
+            }
+            match (self.kind, var) {
+                (CrossOverKind::Sbx, Some(var)) => self.sbx(&mut rng, var, v0, v1),
+                _ => std::mem::swap(v0, v1),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Mutation operator applied to each parameter with the given probability.
+#[derive(Debug)]
+struct Mutation {
+    kind: MutationKind,
+    probability: f64,
+    eta: f64,
+
+    // `None` means that the variable is categorical.
+    vars: Vec<Option<Numerical>>,
+}
+impl Mutation {
+    // Polynomial mutation for bounded variables (Deb and Goyal, 1996).
+    fn polynomial<R: Rng>(&self, mut rng: R, var: &Numerical, v: &mut f64) {
+        let width = var.high - var.low;
+        if width <= 0.0 {
+            return;
+        }
+
+        let x = var.encode(*v);
+        let (delta0, delta1) = ((x - var.low) / width, (var.high - x) / width);
+        let u: f64 = rng.gen();
+        let p = 1.0 / (self.eta + 1.0);
+        let deltaq = if u < 0.5 {
+            let y = 2.0 * u + (1.0 - 2.0 * u) * (1.0 - delta0).powf(self.eta + 1.0);
+            y.powf(p) - 1.0
+        } else {
+            let y = 2.0 * (1.0 - u) + 2.0 * (u - 0.5) * (1.0 - delta1).powf(self.eta + 1.0);
+            1.0 - y.powf(p)
+        };
+        *v = var.decode(x + deltaq * width);
+    }
+}
+impl nsga2::Mutate<VecDomain<KurobakoDomain>> for Mutation {
+    fn mutate<R: Rng>(
+        &mut self,
+        mut rng: R,
+        domain: &VecDomain<KurobakoDomain>,
+        p: &mut Vec<f64>,
+    ) -> std::result::Result<(), yamakan::Error> {
+        for ((v, d), var) in p.iter_mut().zip(domain.0.iter()).zip(self.vars.iter()) {
+            if !rng.gen_bool(self.probability) {
+                continue;
+            }
+            match (self.kind, var) {
+                (MutationKind::Polynomial, Some(var)) => self.polynomial(&mut rng, var, v),
+                _ => *v = d.sample(&mut rng),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn recipe_serialization_works() -> TopLevelResult {
+        let json = r#"{"population":10,"tournament":2,"crossover":0.5,"mutation":0.3}"#;
+        let recipe: Nsga2SolverRecipe = serde_json::from_str(json).map_err(Error::from)?;
+        assert_eq!(recipe.crossover_type, CrossOverKind::Uniform);
+        assert_eq!(recipe.mutation_type, MutationKind::Uniform);
+        assert_eq!(serde_json::to_string(&recipe).map_err(Error::from)?, json);
+
+        let json = r#"{"population":10,"tournament":2,"crossover":0.9,"mutation":0.1,"crossover_type":"sbx","crossover_eta":10.0,"mutation_type":"polynomial"}"#;
+        let recipe: Nsga2SolverRecipe = serde_json::from_str(json).map_err(Error::from)?;
+        assert_eq!(recipe.crossover_type, CrossOverKind::Sbx);
+        assert_eq!(recipe.crossover_eta, Some(10.0));
+        assert_eq!(recipe.mutation_type, MutationKind::Polynomial);
+        assert_eq!(serde_json::to_string(&recipe).map_err(Error::from)?, json);
+
+        let json = r#"{"population":10,"tournament":2,"crossover":1.5,"mutation":0.1}"#;
+        let recipe: Nsga2SolverRecipe = serde_json::from_str(json).map_err(Error::from)?;
+        let registry = FactoryRegistry::new::<
+            kurobako_core::epi::problem::ExternalProgramProblemRecipe,
+            Nsga2SolverRecipe,
+        >();
+        assert!(recipe.create_factory(&registry).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn operators_keep_params_in_range() -> TopLevelResult {
+        let vars = [
+            domain::var("x").continuous(-1.0, 1.0).finish()?,
+            domain::var("y")
+                .continuous(1e-3, 1.0)
+                .log_uniform()
+                .finish()?,
+            domain::var("z").discrete(0, 5).finish()?,
+            domain::var("c").categorical(["a", "b"]).finish()?,
+        ];
+        let domain = VecDomain(
+            vars.iter()
+                .map(|v| KurobakoDomain::new(v.range().clone(), v.distribution()))
+                .collect(),
+        );
+        let numericals = vars.iter().map(Numerical::new).collect::<Vec<_>>();
+        let mut crossover = CrossOver {
+            kind: CrossOverKind::Sbx,
+            probability: 1.0,
+            eta: 15.0,
+            vars: numericals.clone(),
+        };
+        let mut mutation = Mutation {
+            kind: MutationKind::Polynomial,
+            probability: 1.0,
+            eta: 20.0,
+            vars: numericals,
+        };
+
+        let mut rng = ArcRng::new(0);
+        let mut p0 = vec![-0.5, 0.01, 1.0, 0.0];
+        let mut p1 = vec![0.5, 0.5, 4.0, 1.0];
+        for _ in 0..1000 {
+            nsga2::CrossOver::cross_over(&mut crossover, &mut rng, &mut p0, &mut p1)?;
+            nsga2::Mutate::mutate(&mut mutation, &mut rng, &domain, &mut p0)?;
+            for p in &[&p0, &p1] {
+                for (var, &v) in vars.iter().zip(p.iter()) {
+                    assert!(var.range().contains(v), "var={:?}, value={}", var, v);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,8 +6,8 @@ use kurobako_core::problem::ProblemSpec;
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::solver::{Solver, SolverFactory, SolverRecipe, SolverSpec};
-use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial};
-use kurobako_core::Result;
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
@@ -88,6 +88,31 @@ pub struct OptunaSolverRecipe {
     #[structopt(long)]
     #[serde(default, skip_serializing_if = "is_false")]
     pub use_discrete_uniform: bool,
+
+    /// Number of random trials before TPE starts (`TPESampler(n_startup_trials=...)`).
+    ///
+    /// If omitted, Optuna's default (`10`) is used.
+    /// This option is available only if the sampler is "TPESampler".
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub n_startup_trials: Option<usize>,
+
+    /// Number of candidates used to calculate the expected improvement of TPE (`TPESampler(n_ei_candidates=...)`).
+    ///
+    /// If omitted, Optuna's default (`24`) is used.
+    /// This option is available only if the sampler is "TPESampler".
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub n_ei_candidates: Option<usize>,
+
+    /// If this is `true`, the multivariate TPE is used (`TPESampler(multivariate=True)`).
+    ///
+    /// This option is available only if the sampler is "TPESampler".
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub multivariate: bool,
 }
 impl OptunaSolverRecipe {
     fn build_args(&self) -> Vec<String> {
@@ -112,13 +137,42 @@ impl OptunaSolverRecipe {
         if self.use_discrete_uniform {
             args.push("--use-discrete-uniform".to_owned());
         }
+        if let Some(v) = self.n_startup_trials {
+            add_arg(&mut args, "--n-startup-trials", &v.to_string());
+        }
+        if let Some(v) = self.n_ei_candidates {
+            add_arg(&mut args, "--n-ei-candidates", &v.to_string());
+        }
+        if self.multivariate {
+            args.push("--multivariate".to_owned());
+        }
         args
     }
+
+    fn has_tpe_options(&self) -> bool {
+        self.n_startup_trials.is_some() || self.n_ei_candidates.is_some() || self.multivariate
+    }
 }
 impl SolverRecipe for OptunaSolverRecipe {
     type Factory = OptunaSolverFactory;
 
     fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        if self.has_tpe_options() {
+            track_assert!(
+                self.sampler.as_ref().is_none_or(|s| s == "TPESampler"),
+                ErrorKind::InvalidInput,
+                "TPE options are available only for \"TPESampler\": sampler={:?}",
+                self.sampler
+            );
+        }
+        if let Some(n) = self.n_ei_candidates {
+            track_assert!(
+                n > 0,
+                ErrorKind::InvalidInput,
+                "`n_ei_candidates` must be a positive integer"
+            );
+        }
+
         let script = include_str!("../scripts/optuna_solver.py");
         let args = self.build_args();
         let recipe = EmbeddedScriptSolverRecipe {
@@ -161,4 +215,62 @@ impl Solver for OptunaSolver {
     fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
         track!(self.inner.tell(trial))
     }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        track!(self.inner.tell_prior(id, params, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::Error;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn tpe_options_work() -> TopLevelResult {
+        let json = r#"{"n_startup_trials":0,"n_ei_candidates":50,"multivariate":true}"#;
+        let recipe: OptunaSolverRecipe = serde_json::from_str(json).map_err(Error::from)?;
+        assert_eq!(recipe.n_startup_trials, Some(0));
+        assert_eq!(recipe.n_ei_candidates, Some(50));
+        assert!(recipe.multivariate);
+        assert_eq!(serde_json::to_string(&recipe).map_err(Error::from)?, json);
+
+        assert_eq!(
+            recipe.build_args(),
+            [
+                "--loglevel",
+                "warning",
+                "--n-startup-trials",
+                "0",
+                "--n-ei-candidates",
+                "50",
+                "--multivariate"
+            ]
+        );
+
+        let recipe: OptunaSolverRecipe = serde_json::from_str("{}").map_err(Error::from)?;
+        assert_eq!(serde_json::to_string(&recipe).map_err(Error::from)?, "{}");
+        assert_eq!(recipe.build_args(), ["--loglevel", "warning"]);
+
+        // Negative numbers are rejected.
+        let json = r#"{"n_startup_trials":-1}"#;
+        assert!(serde_json::from_str::<OptunaSolverRecipe>(json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn tpe_options_are_rejected_for_other_samplers() -> TopLevelResult {
+        let json = r#"{"sampler":"RandomSampler","multivariate":true}"#;
+        let recipe: OptunaSolverRecipe = serde_json::from_str(json).map_err(Error::from)?;
+        let registry = FactoryRegistry::new::<
+            kurobako_core::epi::problem::ExternalProgramProblemRecipe,
+            OptunaSolverRecipe,
+        >();
+        assert_eq!(
+            recipe.create_factory(&registry).err().map(|e| *e.kind()),
+            Some(ErrorKind::InvalidInput)
+        );
+        Ok(())
+    }
 }
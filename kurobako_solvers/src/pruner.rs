@@ -0,0 +1,302 @@
+//! A solver combinator that prunes unpromising trials based on the median stopping rule.
+//!
+//! This solver evaluates the trials asked by the base solver step by step (i.e., at each evaluable step of the problem).
+//! Then, if the intermediate value of a trial is worse than the median of the intermediate values
+//! of the other (non-pruned) trials at the same step, the trial is stopped early.
+//! The intermediate value of a pruned trial is told to the base solver
+//! as the result of the trial (i.e., `EvaluatedTrial::current_step` is less than the requested step).
+//!
+//! Note that this solver only supports single-objective problems.
+use kurobako_core::json::JsonRecipe;
+use kurobako_core::problem::{EvaluableSteps, ProblemSpec};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::solver::{
+    BoxSolver, BoxSolverFactory, Capability, Solver, SolverFactory, SolverRecipe, SolverSpec,
+    SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use structopt::StructOpt;
+
+fn default_warmup_trials() -> usize {
+    5
+}
+
+/// Recipe of `PrunerSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PrunerSolverRecipe {
+    /// Minimum number of trials that have to reach a step before pruning trials at the step.
+    #[structopt(long, default_value = "5")]
+    #[serde(default = "default_warmup_trials")]
+    pub warmup_trials: usize,
+
+    /// Recipe of the base solver.
+    pub base_solver: JsonRecipe,
+}
+impl SolverRecipe for PrunerSolverRecipe {
+    type Factory = PrunerSolverFactory;
+
+    fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let base = track!(registry.create_solver_factory_from_json(&self.base_solver))?;
+        Ok(PrunerSolverFactory {
+            warmup_trials: self.warmup_trials,
+            base,
+        })
+    }
+}
+
+/// Factory of `PrunerSolver`.
+#[derive(Debug)]
+pub struct PrunerSolverFactory {
+    warmup_trials: usize,
+    base: BoxSolverFactory,
+}
+impl SolverFactory for PrunerSolverFactory {
+    type Solver = PrunerSolver<BoxSolver>;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let mut base = track!(self.base.specification())?;
+        base.capabilities
+            .remove_capability(Capability::MultiObjective);
+
+        let spec = SolverSpecBuilder::new(&format!("MedianPruner with {}", base.name))
+            .attr(
+                "version",
+                &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr("warmup_trials", &self.warmup_trials.to_string())
+            .capabilities(base.capabilities);
+        Ok(spec.finish())
+    }
+
+    fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        let base = track!(self.base.create_solver(rng, problem))?;
+        Ok(PrunerSolver::new(base, problem, self.warmup_trials))
+    }
+}
+
+#[derive(Debug)]
+struct TrialState {
+    // The trial asked by the base solver (`next_step` is the step requested by the base solver).
+    trial: NextTrial,
+
+    // Step => intermediate value.
+    values: BTreeMap<u64, f64>,
+
+    pruned: bool,
+}
+impl TrialState {
+    fn current_step(&self) -> u64 {
+        self.values.keys().next_back().copied().unwrap_or(0)
+    }
+}
+
+/// A solver combinator that prunes unpromising trials based on the median stopping rule.
+#[derive(Debug)]
+pub struct PrunerSolver<T> {
+    base: T,
+    steps: EvaluableSteps,
+    warmup_trials: usize,
+    trials: HashMap<TrialId, TrialState>,
+
+    // Trials to be resumed or stopped.
+    queue: VecDeque<NextTrial>,
+}
+impl<T: Solver> PrunerSolver<T> {
+    /// Makes a new `PrunerSolver` instance.
+    pub fn new(base: T, problem: &ProblemSpec, warmup_trials: usize) -> Self {
+        Self {
+            base,
+            steps: problem.steps.clone(),
+            warmup_trials,
+            trials: HashMap::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    // Returns the step at which the trial is evaluated next.
+    fn next_checkpoint(&self, current_step: u64, target_step: u64) -> u64 {
+        self.steps
+            .iter()
+            .find(|&s| s > current_step)
+            .map_or(target_step, |s| s.min(target_step))
+    }
+
+    fn should_prune(&self, id: TrialId, step: u64, value: f64) -> bool {
+        let mut values = self
+            .trials
+            .iter()
+            .filter(|(other, state)| **other != id && !state.pruned)
+            .filter_map(|(_, state)| state.values.get(&step).copied())
+            .filter(|v| !v.is_nan())
+            .collect::<Vec<_>>();
+        if values.is_empty() || values.len() < self.warmup_trials {
+            return false;
+        }
+
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or_else(|| unreachable!()));
+        let n = values.len();
+        let median = if n % 2 == 0 {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        } else {
+            values[n / 2]
+        };
+        value > median
+    }
+}
+impl<T: Solver> Solver for PrunerSolver<T> {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        if let Some(trial) = self.queue.pop_front() {
+            return Ok(trial);
+        }
+
+        let trial = track!(self.base.ask(idg))?;
+        let target_step = if let Some(step) = trial.next_step {
+            step
+        } else {
+            return Ok(trial);
+        };
+
+        let current_step = self.trials.get(&trial.id).map_or(0, |s| s.current_step());
+        let mut next = trial.clone();
+        next.next_step = Some(self.next_checkpoint(current_step, target_step));
+
+        let state = self.trials.entry(trial.id).or_insert_with(|| TrialState {
+            trial: trial.clone(),
+            values: BTreeMap::new(),
+            pruned: false,
+        });
+        state.trial = trial;
+        Ok(next)
+    }
+
+    fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
+        let state = track_assert_some!(
+            self.trials.get_mut(&trial.id),
+            ErrorKind::InvalidInput,
+            "Unknown trial: {:?}",
+            trial.id
+        );
+        if trial.values.is_empty() {
+            return track!(self.base.tell(trial));
+        }
+
+        let value = trial.values[0];
+        state.values.insert(trial.current_step, value);
+
+        let target_step = track_assert_some!(state.trial.next_step, ErrorKind::Bug);
+        if trial.current_step >= target_step {
+            return track!(self.base.tell(trial));
+        }
+
+        let mut next = state.trial.clone();
+        if self.should_prune(trial.id, trial.current_step, value) {
+            next.next_step = None;
+            self.queue.push_back(next);
+            if let Some(state) = self.trials.get_mut(&trial.id) {
+                state.pruned = true;
+            }
+            track!(self.base.tell(trial))
+        } else {
+            next.next_step = Some(self.next_checkpoint(trial.current_step, target_step));
+            self.queue.push_back(next);
+            Ok(())
+        }
+    }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        // Prior observations have no intermediate values, so they are only told to the base solver.
+        track!(self.base.tell_prior(id, params, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::RandomSolverRecipe;
+    use kurobako_core::domain;
+    use kurobako_core::epi::problem::ExternalProgramProblemRecipe;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use kurobako_core::Error;
+    use trackable::result::TopLevelResult;
+
+    // Evaluates the next trial until it finishes or is pruned, and returns the last step and whether it was pruned.
+    fn run_trial<T: Solver>(
+        solver: &mut PrunerSolver<T>,
+        idg: &mut IdGen,
+        value: f64,
+    ) -> Result<(u64, bool)> {
+        let mut trial = track!(solver.ask(idg))?;
+        let id = trial.id;
+        loop {
+            let step = track_assert_some!(trial.next_step, ErrorKind::Bug);
+            track!(solver.tell(trial.evaluated(Values::new(vec![value]), step)))?;
+            if step == 10 {
+                return Ok((step, false));
+            }
+
+            trial = track!(solver.ask(idg))?;
+            track_assert_eq!(trial.id, id, ErrorKind::Bug);
+            if trial.next_step.is_none() {
+                return Ok((step, true));
+            }
+        }
+    }
+
+    #[test]
+    fn pruner_solver_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("pruner")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("v"))
+            .steps(1..=10)
+            .finish()?;
+
+        let registry = FactoryRegistry::new::<ExternalProgramProblemRecipe, RandomSolverRecipe>();
+        let random: RandomSolverRecipe = serde_json::from_str("{}").map_err(Error::from)?;
+        let base = random
+            .create_factory(&registry)?
+            .create_solver(ArcRng::new(0), &problem)?;
+        let mut solver = PrunerSolver::new(base, &problem, 3);
+        let mut idg = IdGen::new();
+
+        // Warmup.
+        for i in 0..3 {
+            assert_eq!(run_trial(&mut solver, &mut idg, i as f64)?, (10, false));
+        }
+
+        // A clearly-worse trial is stopped at the first step.
+        assert_eq!(run_trial(&mut solver, &mut idg, 100.0)?, (1, true));
+
+        // A better trial isn't stopped.
+        assert_eq!(run_trial(&mut solver, &mut idg, -1.0)?, (10, false));
+        Ok(())
+    }
+
+    #[test]
+    fn pruner_solver_does_not_prune_during_warmup() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("pruner")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("v"))
+            .steps(1..=10)
+            .finish()?;
+
+        let registry = FactoryRegistry::new::<ExternalProgramProblemRecipe, RandomSolverRecipe>();
+        let random: RandomSolverRecipe = serde_json::from_str("{}").map_err(Error::from)?;
+        let base = random
+            .create_factory(&registry)?
+            .create_solver(ArcRng::new(0), &problem)?;
+        let mut solver = PrunerSolver::new(base, &problem, 5);
+        let mut idg = IdGen::new();
+
+        for i in 0..5 {
+            // Later trials are worse than the median, but the number of the finished trials is insufficient.
+            assert_eq!(run_trial(&mut solver, &mut idg, i as f64)?, (10, false));
+        }
+        assert_eq!(run_trial(&mut solver, &mut idg, 100.0)?, (1, true));
+        Ok(())
+    }
+}
@@ -1,14 +1,18 @@
 //! A solver based on random search.
+use kurobako_core::domain::Range;
+use kurobako_core::json;
 use kurobako_core::problem::ProblemSpec;
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::solver::{
     Capabilities, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
 };
-use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
 use kurobako_core::{ErrorKind, Result};
 use rand::distributions::Distribution as _;
+use rand::seq::SliceRandom as _;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use structopt::StructOpt;
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
@@ -23,6 +27,16 @@ pub struct RandomSolverRecipe {
     #[structopt(long)]
     #[serde(default, skip_serializing_if = "is_false")]
     ask_all_steps: bool,
+
+    /// Sampling grids of variables (JSON object that maps variable names to lists of values).
+    ///
+    /// The value of a variable listed in this object is uniformly chosen from the given values
+    /// (e.g., `{"batch_size": [16, 32, 64, 128]}`) instead of being sampled from its whole range.
+    /// For categorical variables, the indices of choices are used as the values.
+    /// The other variables are sampled as usual.
+    #[structopt(long, parse(try_from_str = json::parse_json))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grid: Option<BTreeMap<String, Vec<f64>>>,
 }
 impl SolverRecipe for RandomSolverRecipe {
     type Factory = RandomSolverFactory;
@@ -30,6 +44,7 @@ impl SolverRecipe for RandomSolverRecipe {
     fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
         Ok(RandomSolverFactory {
             ask_all_steps: self.ask_all_steps,
+            grid: self.grid.clone().unwrap_or_default(),
         })
     }
 }
@@ -38,6 +53,7 @@ impl SolverRecipe for RandomSolverRecipe {
 #[derive(Debug)]
 pub struct RandomSolverFactory {
     ask_all_steps: bool,
+    grid: BTreeMap<String, Vec<f64>>,
 }
 impl SolverFactory for RandomSolverFactory {
     type Solver = RandomSolver;
@@ -53,10 +69,45 @@ impl SolverFactory for RandomSolverFactory {
     }
 
     fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        let vars = problem.params_domain.variables();
+        for (name, values) in &self.grid {
+            let var = track_assert_some!(
+                vars.iter().find(|v| v.name() == name),
+                ErrorKind::InvalidInput,
+                "Unknown variable: {:?}",
+                name
+            );
+            track_assert!(!values.is_empty(), ErrorKind::InvalidInput; name);
+            for &v in values {
+                track_assert!(
+                    var.range().contains(v),
+                    ErrorKind::InvalidInput,
+                    "Out of range grid value: variable={:?}, value={}, range={:?}",
+                    name,
+                    v,
+                    var.range()
+                );
+                if !matches!(var.range(), Range::Continuous { .. }) {
+                    track_assert!(
+                        v.fract() == 0.0,
+                        ErrorKind::InvalidInput,
+                        "Non-integer grid value: variable={:?}, value={}",
+                        name,
+                        v
+                    );
+                }
+            }
+        }
+
+        let grid = vars
+            .iter()
+            .map(|v| self.grid.get(v.name()).cloned())
+            .collect();
         Ok(RandomSolver {
             problem: problem.clone(),
             rng,
             current_step: if self.ask_all_steps { Some(0) } else { None },
+            grid,
         })
     }
 }
@@ -67,12 +118,25 @@ pub struct RandomSolver {
     rng: ArcRng,
     problem: ProblemSpec,
     current_step: Option<u64>,
+
+    // The i-th element is the sampling grid of the i-th variable.
+    grid: Vec<Option<Vec<f64>>>,
 }
 impl Solver for RandomSolver {
     fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
         let mut params = Vec::new();
-        for p in self.problem.params_domain.variables() {
-            let param = p.sample(&mut self.rng);
+        for (p, grid) in self
+            .problem
+            .params_domain
+            .variables()
+            .iter()
+            .zip(self.grid.iter())
+        {
+            let param = if let Some(grid) = grid {
+                *grid.choose(&mut self.rng).unwrap_or_else(|| unreachable!())
+            } else {
+                p.sample(&mut self.rng)
+            };
             params.push(param);
         }
 
@@ -95,4 +159,9 @@ impl Solver for RandomSolver {
         }
         Ok(())
     }
+
+    fn tell_prior(&mut self, _id: TrialId, _params: Params, _values: Values) -> Result<()> {
+        // The sampling doesn't depend on observations, so priors are just accepted.
+        Ok(())
+    }
 }
@@ -0,0 +1,235 @@
+//! A solver based on random search with restarts.
+//!
+//! This solver keeps the best parameters found since the last restart as an anchor,
+//! and samples new parameters by resampling some of the variables of the anchor.
+//! After every `restart_interval` trials, the RNG is re-seeded and the anchor is discarded,
+//! so the next parameters are sampled from the whole domain again.
+//!
+//! For multi-objective problems, the anchor is replaced only if it's dominated by the new values.
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::{ArcRng, Rng as _};
+use kurobako_core::solver::{
+    Capabilities, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{ErrorKind, Result};
+use rand::distributions::Distribution as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use structopt::StructOpt;
+
+fn default_restart_interval() -> usize {
+    100
+}
+
+/// Recipe of `RestartSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RestartSolverRecipe {
+    /// Number of trials between restarts.
+    #[structopt(long, default_value = "100")]
+    #[serde(default = "default_restart_interval")]
+    restart_interval: usize,
+}
+impl SolverRecipe for RestartSolverRecipe {
+    type Factory = RestartSolverFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        track_assert!(self.restart_interval > 0, ErrorKind::InvalidInput);
+
+        Ok(RestartSolverFactory {
+            restart_interval: self.restart_interval,
+        })
+    }
+}
+
+/// Factory of `RestartSolver`.
+#[derive(Debug)]
+pub struct RestartSolverFactory {
+    restart_interval: usize,
+}
+impl SolverFactory for RestartSolverFactory {
+    type Solver = RestartSolver;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let spec = SolverSpecBuilder::new("RandomRestart")
+            .attr(
+                "version",
+                &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr("restart_interval", &self.restart_interval.to_string())
+            .capabilities(Capabilities::all());
+        Ok(spec.finish())
+    }
+
+    fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        Ok(RestartSolver {
+            rng,
+            problem: problem.clone(),
+            restart_interval: self.restart_interval,
+            asked: 0,
+            pending: HashMap::new(),
+            anchor: None,
+        })
+    }
+}
+
+/// Solver based on random search with restarts.
+#[derive(Debug)]
+pub struct RestartSolver {
+    rng: ArcRng,
+    problem: ProblemSpec,
+    restart_interval: usize,
+
+    // The number of trials asked since the last restart.
+    asked: usize,
+
+    // Parameters of the trials that haven't been told yet
+    // (the second element is `true` if the trial was asked before the last restart).
+    pending: HashMap<TrialId, (Params, bool)>,
+
+    // The best parameters (and their values) found since the last restart.
+    anchor: Option<(Params, Values)>,
+}
+impl RestartSolver {
+    fn restart(&mut self) {
+        self.rng = ArcRng::new(self.rng.gen());
+        self.asked = 0;
+        self.anchor = None;
+        for (_, stale) in self.pending.values_mut() {
+            *stale = true;
+        }
+    }
+
+    // Resamples each variable of the anchor with the probability `1 / (the number of variables)`.
+    //
+    // Variables that become active due to the resampling (or whose values are rejected by their constraints)
+    // are sampled from their whole ranges, and the ones that become inactive are set to `NaN`.
+    fn perturb(&mut self, anchor: &[f64]) -> Result<Params> {
+        let domain = &self.problem.params_domain;
+        let p = 1.0 / domain.len() as f64;
+        let rng = &mut self.rng;
+        track!(domain.make_params(|i, var, attempt| {
+            let v = anchor[i];
+            if attempt > 0 || v.is_nan() || rng.gen_bool(p) {
+                var.sample(rng)
+            } else {
+                v
+            }
+        }))
+    }
+}
+impl Solver for RestartSolver {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        if self.asked == self.restart_interval {
+            self.restart();
+        }
+        self.asked += 1;
+
+        let params = if let Some((anchor, _)) = self.anchor.clone() {
+            track!(self.perturb(anchor.get()))?
+        } else {
+            track!(self.problem.params_domain.sample_params(&mut self.rng))?
+        };
+
+        let id = idg.generate();
+        self.pending.insert(id, (params.clone(), false));
+        Ok(NextTrial {
+            id,
+            params,
+            next_step: Some(self.problem.steps.last()),
+        })
+    }
+
+    fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
+        let (params, stale) = track_assert_some!(
+            self.pending.remove(&trial.id),
+            ErrorKind::InvalidInput,
+            "Unknown trial: {:?}",
+            trial.id
+        );
+        if stale || trial.values.is_empty() {
+            return Ok(());
+        }
+
+        let is_better = self
+            .anchor
+            .as_ref()
+            .is_none_or(|(_, values)| trial.values < *values);
+        if is_better {
+            self.anchor = Some((params, trial.values));
+        }
+        Ok(())
+    }
+
+    fn tell_prior(&mut self, _id: TrialId, params: Params, values: Values) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let is_better = self
+            .anchor
+            .as_ref()
+            .is_none_or(|(_, anchor)| values < *anchor);
+        if is_better {
+            self.anchor = Some((params, values));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use kurobako_core::Error;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn recipe_round_trip_works() -> TopLevelResult {
+        let recipe: RestartSolverRecipe =
+            serde_json::from_str(r#"{"restart_interval": 10}"#).map_err(Error::from)?;
+        assert_eq!(recipe.restart_interval, 10);
+
+        let json = serde_json::to_string(&recipe).map_err(Error::from)?;
+        assert_eq!(json, r#"{"restart_interval":10}"#);
+
+        let recipe: RestartSolverRecipe = serde_json::from_str("{}").map_err(Error::from)?;
+        assert_eq!(recipe.restart_interval, default_restart_interval());
+        Ok(())
+    }
+
+    #[test]
+    fn restart_solver_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("sphere")
+            .param(domain::var("x").continuous(-5.0, 5.0))
+            .param(domain::var("y").discrete(-5, 5))
+            .param(domain::var("c").categorical(["a", "b", "c"]))
+            .value(domain::var("v"))
+            .finish()?;
+
+        let factory = RestartSolverFactory {
+            restart_interval: 10,
+        };
+        let mut solver = factory.create_solver(ArcRng::new(0), &problem)?;
+
+        let mut idg = IdGen::new();
+        let mut best = f64::INFINITY;
+        for _ in 0..50 {
+            let trial = solver.ask(&mut idg)?;
+            assert!(problem.params_domain.contains(trial.params.get()));
+
+            let p = trial.params.get();
+            let value = p[0] * p[0] + p[1] * p[1] + p[2];
+            best = best.min(value);
+            solver.tell(trial.evaluated(Values::new(vec![value]), problem.steps.last()))?;
+        }
+        assert!(best < 10.0);
+
+        let trial = solver.ask(&mut idg)?;
+        solver.tell(trial.unevaluable())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+//! A solver based on the Sobol low-discrepancy sequence.
+//!
+//! Each point of the sequence in `[0, 1)^d` is mapped onto the ranges of the variables
+//! according to their distributions (categorical variables bucket the unit interval by their choices).
+//!
+//! # References
+//!
+//! - [Constructing Sobol sequences with better two-dimensional projections][paper]
+//!
+//! [paper]: https://web.maths.unsw.edu.au/~fkuo/sobol/joe-kuo-08.pdf
+use kurobako_core::domain::{Distribution, Range, Variable};
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::{ArcRng, Rng as _};
+use kurobako_core::solver::{
+    Capabilities, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// Recipe of `SobolSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SobolSolverRecipe {
+    /// If this flag is set, the sequence is scrambled by a random digital shift.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    scramble: bool,
+
+    /// Random seed used to scramble the sequence.
+    ///
+    /// This implies `--scramble`.
+    /// If omitted, the seed is drawn from the random number generator of each study.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scramble_seed: Option<u64>,
+}
+impl SolverRecipe for SobolSolverRecipe {
+    type Factory = SobolSolverFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        Ok(SobolSolverFactory {
+            scramble: self.scramble || self.scramble_seed.is_some(),
+            scramble_seed: self.scramble_seed,
+        })
+    }
+}
+
+/// Factory of `SobolSolver`.
+#[derive(Debug)]
+pub struct SobolSolverFactory {
+    scramble: bool,
+    scramble_seed: Option<u64>,
+}
+impl SolverFactory for SobolSolverFactory {
+    type Solver = SobolSolver;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let spec = SolverSpecBuilder::new("Sobol")
+            .attr(
+                "version",
+                &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+            )
+            .capabilities(Capabilities::all());
+        Ok(spec.finish())
+    }
+
+    fn create_solver(&self, mut rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        let vars = problem.params_domain.variables();
+        for var in vars {
+            if let Range::Continuous { low, high, .. } = var.range() {
+                track_assert!(
+                    low.is_finite() && high.is_finite(),
+                    ErrorKind::InvalidInput,
+                    "Unbounded continuous variable: name={:?}, range={:?}",
+                    var.name(),
+                    var.range()
+                );
+            }
+        }
+
+        let mut sequence = track!(SobolSequence::new(vars.len()))?;
+        if self.scramble {
+            let seed = self.scramble_seed.unwrap_or_else(|| rng.gen());
+            sequence.scramble(&mut ArcRng::new(seed));
+        }
+        Ok(SobolSolver {
+            problem: problem.clone(),
+            sequence,
+        })
+    }
+}
+
+/// Solver based on the Sobol sequence.
+#[derive(Debug)]
+pub struct SobolSolver {
+    problem: ProblemSpec,
+    sequence: SobolSequence,
+}
+impl Solver for SobolSolver {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        // If a value is rejected by the constraint of its variable,
+        // the coordinate is shifted by the golden ratio to get the next candidate.
+        const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+        let point = self.sequence.next_point();
+        let params = track!(self.problem.params_domain.make_params(|i, var, attempt| {
+            let u = (point[i] + attempt as f64 * GOLDEN_RATIO_CONJUGATE).fract();
+            to_param(var, u)
+        }))?;
+
+        Ok(NextTrial {
+            id: idg.generate(),
+            params,
+            next_step: Some(self.problem.steps.last()),
+        })
+    }
+
+    fn tell(&mut self, _trial: EvaluatedTrial) -> Result<()> {
+        Ok(())
+    }
+
+    fn tell_prior(&mut self, _id: TrialId, _params: Params, _values: Values) -> Result<()> {
+        // The sequence doesn't depend on observations, so priors are just accepted.
+        Ok(())
+    }
+}
+
+// Maps `u` in `[0, 1)` onto the range of `var`.
+fn to_param(var: &Variable, u: f64) -> f64 {
+    let bucket = |n: usize| ((u * n as f64) as usize).min(n - 1);
+    match var.range() {
+        Range::Continuous { low, high, .. } => {
+            let v = match var.distribution() {
+                Distribution::Uniform => low + (high - low) * u,
+                Distribution::LogUniform => (low.log2() + (high.log2() - low.log2()) * u).exp2(),
+            };
+            var.range().quantize(v.max(*low).min(high.next_down()))
+        }
+        Range::Discrete { low, high } => {
+            let (low, high) = (*low as f64, *high as f64);
+            let v = match var.distribution() {
+                Distribution::Uniform => low + (high - low) * u,
+                Distribution::LogUniform => (low.log2() + (high.log2() - low.log2()) * u).exp2(),
+            };
+            v.floor().max(low).min(high - 1.0)
+        }
+        Range::IntChoices { values } => values[bucket(values.len())] as f64,
+        Range::Ordinal { choices } => bucket(choices.len()) as f64,
+        Range::Categorical { choices } => {
+            if let Some(weights) = var.weights() {
+                let mut r = u * weights.iter().sum::<f64>();
+                for (i, &w) in weights.iter().enumerate() {
+                    if r < w {
+                        return i as f64;
+                    }
+                    r -= w;
+                }
+                // Only reachable due to rounding errors.
+                weights.iter().rposition(|&w| w > 0.0).unwrap_or(0) as f64
+            } else {
+                bucket(choices.len()) as f64
+            }
+        }
+    }
+}
+
+const BITS: usize = 32;
+
+// `(s, a, m_i)` of the dimensions `2, 3, ...` taken from `new-joe-kuo-6.21201`.
+//
+// `s` is the degree of the primitive polynomial, `a` encodes its coefficients, and
+// `m_i` are the initial direction numbers.
+const DIRECTION_NUMBERS: &[(u32, u32, &[u32])] = &[
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+    (7, 7, &[1, 1, 3, 13, 7, 35, 63]),
+    (7, 8, &[1, 3, 5, 9, 1, 25, 53]),
+    (7, 14, &[1, 3, 1, 13, 9, 35, 107]),
+    (7, 19, &[1, 3, 1, 5, 27, 61, 31]),
+    (7, 21, &[1, 1, 5, 11, 19, 41, 61]),
+    (7, 28, &[1, 3, 5, 3, 3, 13, 69]),
+    (7, 31, &[1, 1, 7, 13, 1, 19, 1]),
+    (7, 32, &[1, 3, 7, 5, 13, 19, 59]),
+    (7, 37, &[1, 1, 3, 9, 25, 29, 41]),
+    (7, 41, &[1, 3, 5, 13, 23, 1, 55]),
+    (7, 42, &[1, 3, 7, 3, 13, 59, 17]),
+    (7, 50, &[1, 3, 1, 3, 5, 53, 69]),
+    (7, 55, &[1, 1, 5, 5, 23, 33, 13]),
+    (7, 56, &[1, 1, 7, 7, 1, 61, 123]),
+    (7, 59, &[1, 1, 7, 9, 13, 61, 49]),
+    (7, 62, &[1, 3, 3, 5, 3, 55, 33]),
+    (8, 14, &[1, 3, 1, 15, 31, 13, 49, 245]),
+    (8, 21, &[1, 3, 5, 15, 31, 59, 63, 97]),
+    (8, 22, &[1, 3, 1, 11, 11, 11, 77, 249]),
+];
+
+// Sobol sequence generated in the Gray code order.
+#[derive(Debug)]
+struct SobolSequence {
+    directions: Vec<[u32; BITS]>,
+    shifts: Vec<u32>,
+    index: u32,
+    point: Vec<u32>,
+}
+impl SobolSequence {
+    fn new(dim: usize) -> Result<Self> {
+        track_assert!(
+            dim <= DIRECTION_NUMBERS.len() + 1,
+            ErrorKind::InvalidInput,
+            "Too many dimensions for the Sobol sequence: {} (max={})",
+            dim,
+            DIRECTION_NUMBERS.len() + 1
+        );
+
+        let mut directions = Vec::with_capacity(dim);
+        if dim > 0 {
+            let mut v = [0; BITS];
+            for (k, v) in v.iter_mut().enumerate() {
+                *v = 1 << (BITS - 1 - k);
+            }
+            directions.push(v);
+        }
+        for &(s, a, m) in DIRECTION_NUMBERS.iter().take(dim.saturating_sub(1)) {
+            let s = s as usize;
+            let mut v = [0; BITS];
+            for k in 0..BITS {
+                v[k] = if k < s {
+                    m[k] << (BITS - 1 - k)
+                } else {
+                    let mut x = v[k - s] ^ (v[k - s] >> s);
+                    for i in 1..s {
+                        if (a >> (s - 1 - i)) & 1 == 1 {
+                            x ^= v[k - i];
+                        }
+                    }
+                    x
+                };
+            }
+            directions.push(v);
+        }
+
+        Ok(Self {
+            directions,
+            shifts: vec![0; dim],
+            index: 0,
+            point: vec![0; dim],
+        })
+    }
+
+    fn scramble(&mut self, rng: &mut ArcRng) {
+        for shift in &mut self.shifts {
+            *shift = rng.gen();
+        }
+    }
+
+    fn next_point(&mut self) -> Vec<f64> {
+        let point = self
+            .point
+            .iter()
+            .zip(self.shifts.iter())
+            .map(|(&x, &shift)| f64::from(x ^ shift) / (1u64 << BITS) as f64)
+            .collect();
+
+        let c = self.index.trailing_ones() as usize;
+        for (x, v) in self.point.iter_mut().zip(self.directions.iter()) {
+            *x ^= v[c % BITS];
+        }
+        self.index = self.index.wrapping_add(1);
+
+        point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn sobol_sequence_works() -> TopLevelResult {
+        // The first points of the (unscrambled) Sobol sequence in three dimensions.
+        let expected = [
+            [0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.5],
+            [0.75, 0.25, 0.25],
+            [0.25, 0.75, 0.75],
+            [0.375, 0.375, 0.625],
+            [0.875, 0.875, 0.125],
+            [0.625, 0.125, 0.875],
+            [0.125, 0.625, 0.375],
+        ];
+
+        let mut sequence = SobolSequence::new(3)?;
+        for x in &expected {
+            assert_eq!(sequence.next_point(), x);
+        }
+
+        // Each one-dimensional projection of the first `2^k` points is stratified.
+        let mut sequence = SobolSequence::new(DIRECTION_NUMBERS.len() + 1)?;
+        let points = (0..64).map(|_| sequence.next_point()).collect::<Vec<_>>();
+        for d in 0..=DIRECTION_NUMBERS.len() {
+            let mut xs = points
+                .iter()
+                .map(|p| (p[d] * 64.0) as usize)
+                .collect::<Vec<_>>();
+            xs.sort_unstable();
+            assert_eq!(xs, (0..64).collect::<Vec<_>>());
+        }
+
+        assert!(SobolSequence::new(DIRECTION_NUMBERS.len() + 2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sobol_solver_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("sobol")
+            .param(domain::var("x").continuous(-1.0, 1.0))
+            .param(domain::var("y").discrete(0, 4))
+            .param(domain::var("z").continuous(1.0, 100.0).log_uniform())
+            .param(domain::var("c").categorical(["a", "b"]))
+            .value(domain::var("v"))
+            .finish()?;
+
+        let factory = SobolSolverFactory {
+            scramble: false,
+            scramble_seed: None,
+        };
+        let mut solver = factory.create_solver(ArcRng::new(0), &problem)?;
+
+        let mut idg = IdGen::new();
+        let params = solver.ask(&mut idg)?.params.into_vec();
+        assert_eq!(params, [-1.0, 0.0, 1.0, 0.0]);
+        let params = solver.ask(&mut idg)?.params.into_vec();
+        assert_eq!(params[..2], [0.0, 2.0]);
+        assert!((params[2] - 10.0).abs() < 1e-8);
+        assert_eq!(params[3], 1.0);
+        for _ in 0..100 {
+            let trial = solver.ask(&mut idg)?;
+            assert!(problem.params_domain.contains(trial.params.get()));
+        }
+
+        let factory = SobolSolverFactory {
+            scramble: true,
+            scramble_seed: Some(10),
+        };
+        let mut solver = factory.create_solver(ArcRng::new(0), &problem)?;
+        let params = solver.ask(&mut idg)?.params.into_vec();
+        assert_ne!(params, [-1.0, 0.0, 1.0, 0.0]);
+        assert!(problem.params_domain.contains(&params));
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! A solver combinator that warm-starts the base solver with prior observations.
+//!
+//! The prior observations are read from a file that contains JSON objects
+//! such as `{"params": [0.1, 2.0], "values": [0.5]}`.
+//! They are told to the base solver via `Solver::tell_prior` before the first trial is asked.
+//!
+//! The `restart` solver uses the best prior as its anchor, `optuna` and external program solvers
+//! receive them as `TELL_PRIOR_CALL` messages, and the solvers that don't depend on observations
+//! (i.e., `random`, `grid` and `sobol`) just accept them.
+//! If the base solver doesn't support prior observations (e.g., `cmaes`),
+//! the first `ask` fails with an `ErrorKind::InvalidInput` error.
+use kurobako_core::json::{self, JsonRecipe};
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::solver::{
+    BoxSolver, BoxSolverFactory, Solver, SolverFactory, SolverRecipe, SolverSpec, SolverSpecBuilder,
+};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId, Values};
+use kurobako_core::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Recipe of `WarmStartSolver`.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct WarmStartSolverRecipe {
+    /// Path of the file that contains the prior observations.
+    #[structopt(long)]
+    pub prior_trials: PathBuf,
+
+    /// Recipe of the base solver.
+    pub base_solver: JsonRecipe,
+}
+impl SolverRecipe for WarmStartSolverRecipe {
+    type Factory = WarmStartSolverFactory;
+
+    fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let file = track!(File::open(&self.prior_trials).map_err(Error::from); self.prior_trials)?;
+        let priors = track!(json::load(BufReader::new(file)); self.prior_trials)?;
+        let base = track!(registry.create_solver_factory_from_json(&self.base_solver))?;
+        Ok(WarmStartSolverFactory { priors, base })
+    }
+}
+
+/// Observation of a trial evaluated before the study begins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorTrial {
+    /// Evaluated parameters.
+    pub params: Params,
+
+    /// Objective values of the parameters.
+    pub values: Values,
+}
+
+/// Factory of `WarmStartSolver`.
+#[derive(Debug)]
+pub struct WarmStartSolverFactory {
+    priors: Vec<PriorTrial>,
+    base: BoxSolverFactory,
+}
+impl SolverFactory for WarmStartSolverFactory {
+    type Solver = WarmStartSolver<BoxSolver>;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        let base = track!(self.base.specification())?;
+        let spec = SolverSpecBuilder::new(&format!("WarmStart with {}", base.name))
+            .attr(
+                "version",
+                &format!("kurobako_solvers={}", env!("CARGO_PKG_VERSION")),
+            )
+            .attr("prior_trials", &self.priors.len().to_string())
+            .capabilities(base.capabilities);
+        Ok(spec.finish())
+    }
+
+    fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        let base = track!(self.base.create_solver(rng, problem))?;
+        track!(WarmStartSolver::new(base, problem, self.priors.clone()))
+    }
+}
+
+/// A solver combinator that warm-starts the base solver with prior observations.
+#[derive(Debug)]
+pub struct WarmStartSolver<T> {
+    base: T,
+
+    // Prior observations that haven't been told to the base solver yet.
+    priors: Vec<PriorTrial>,
+}
+impl<T: Solver> WarmStartSolver<T> {
+    /// Makes a new `WarmStartSolver` instance.
+    ///
+    /// The parameters and values of `priors` must belong to the domains of `problem`.
+    pub fn new(base: T, problem: &ProblemSpec, priors: Vec<PriorTrial>) -> Result<Self> {
+        let values_len = problem.values_domain.variables().len();
+        for (i, prior) in priors.iter().enumerate() {
+            track!(problem.params_domain.validate_params(prior.params.get()); i)?;
+            track_assert_eq!(
+                prior.values.len(),
+                values_len,
+                ErrorKind::InvalidInput,
+                "Wrong number of values in the prior trial #{}",
+                i
+            );
+        }
+        Ok(Self { base, priors })
+    }
+}
+impl<T: Solver> Solver for WarmStartSolver<T> {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        for prior in std::mem::take(&mut self.priors) {
+            track!(self
+                .base
+                .tell_prior(idg.generate(), prior.params, prior.values))?;
+        }
+        track!(self.base.ask(idg))
+    }
+
+    fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
+        track!(self.base.tell(trial))
+    }
+
+    fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+        track!(self.base.tell_prior(id, params, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmaes::CmaesSolverRecipe;
+    use crate::random::RandomSolverRecipe;
+    use crate::restart::RestartSolverRecipe;
+    use kurobako_core::domain;
+    use kurobako_core::epi::problem::ExternalProgramProblemRecipe;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use std::io::Write as _;
+    use trackable::result::TopLevelResult;
+
+    #[derive(Debug, Default)]
+    struct RecordingSolver {
+        priors: Vec<(TrialId, Params, Values)>,
+    }
+    impl Solver for RecordingSolver {
+        fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+            Ok(NextTrial {
+                id: idg.generate(),
+                params: Params::new(vec![0.0]),
+                next_step: Some(1),
+            })
+        }
+
+        fn tell(&mut self, _trial: EvaluatedTrial) -> Result<()> {
+            Ok(())
+        }
+
+        fn tell_prior(&mut self, id: TrialId, params: Params, values: Values) -> Result<()> {
+            self.priors.push((id, params, values));
+            Ok(())
+        }
+    }
+
+    fn prior(x: f64, v: f64) -> PriorTrial {
+        PriorTrial {
+            params: Params::new(vec![x]),
+            values: Values::new(vec![v]),
+        }
+    }
+
+    #[test]
+    fn warm_start_solver_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("foo")
+            .param(domain::var("x").continuous(-5.0, 5.0))
+            .value(domain::var("v"))
+            .finish()?;
+        let priors = vec![prior(1.0, 1.0), prior(-2.0, 4.0)];
+        let mut solver = WarmStartSolver::new(RecordingSolver::default(), &problem, priors)?;
+
+        let mut idg = IdGen::new();
+        let trial = solver.ask(&mut idg)?;
+        assert_eq!(trial.id, TrialId::new(2));
+        solver.tell(trial.evaluated(Values::new(vec![0.0]), 1))?;
+        solver.ask(&mut idg)?;
+
+        let priors = &solver.base.priors;
+        assert_eq!(priors.len(), 2);
+        assert_eq!(priors[0].0, TrialId::new(0));
+        assert_eq!(priors[0].1.get(), [1.0]);
+        assert_eq!(priors[1].0, TrialId::new(1));
+        assert_eq!(priors[1].2, Values::new(vec![4.0]));
+
+        // Out of the domain.
+        let priors = vec![prior(10.0, 1.0)];
+        assert!(WarmStartSolver::new(RecordingSolver::default(), &problem, priors).is_err());
+        Ok(())
+    }
+
+    fn create_solver<R>(prior_trials: &str) -> Result<WarmStartSolver<BoxSolver>>
+    where
+        R: SolverRecipe + serde::de::DeserializeOwned + 'static,
+    {
+        let problem = track!(ProblemSpecBuilder::new("foo")
+            .param(domain::var("x0").continuous(-5.0, 5.0))
+            .param(domain::var("x1").continuous(-5.0, 5.0))
+            .param(domain::var("x2").continuous(-5.0, 5.0))
+            .param(domain::var("x3").continuous(-5.0, 5.0))
+            .value(domain::var("v"))
+            .finish())?;
+
+        let mut file = track!(tempfile::NamedTempFile::new().map_err(Error::from))?;
+        track!(file.write_all(prior_trials.as_bytes()).map_err(Error::from))?;
+        let json = format!(
+            r#"{{"prior_trials": {:?}, "base_solver": {{}}}}"#,
+            file.path()
+        );
+        let recipe: WarmStartSolverRecipe =
+            track!(serde_json::from_str(&json).map_err(Error::from))?;
+
+        let registry = FactoryRegistry::new::<ExternalProgramProblemRecipe, R>();
+        let factory = track!(recipe.create_factory(&registry))?;
+        track!(factory.create_solver(ArcRng::new(0), &problem))
+    }
+
+    #[test]
+    fn prior_trials_file_works() -> TopLevelResult {
+        let priors = r#"{"params": [1.0, 1.0, 1.0, 1.0], "values": [1.0]}
+                        {"params": [2.0, 2.0, 2.0, 2.0], "values": [4.0]}"#;
+
+        // The restart solver uses the best prior as its anchor.
+        let mut solver = create_solver::<RestartSolverRecipe>(priors)?;
+        let trial = solver.ask(&mut IdGen::new())?;
+        assert_eq!(trial.id, TrialId::new(2));
+        assert!(trial.params.contains(&1.0), "{:?}", trial.params);
+
+        // The random solver accepts the priors without using them.
+        let mut solver = create_solver::<RandomSolverRecipe>(priors)?;
+        let trial = solver.ask(&mut IdGen::new())?;
+        assert_eq!(trial.id, TrialId::new(2));
+
+        // The CMA-ES solver doesn't support prior observations.
+        let mut solver = create_solver::<CmaesSolverRecipe>(priors)?;
+        let e = solver.ask(&mut IdGen::new()).expect_err("should fail");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+}
@@ -33,8 +33,8 @@ impl yamakan::Domain for KurobakoDomain {
 impl rand::distributions::Distribution<f64> for KurobakoDomain {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
         match &self.range {
-            Range::Continuous { low, high } => match self.distribution {
-                Distribution::Uniform => rng.gen_range(*low..*high),
+            Range::Continuous { low, high, .. } => match self.distribution {
+                Distribution::Uniform => self.range.quantize(rng.gen_range(*low..*high)),
                 Distribution::LogUniform => rng.gen_range(low.log2()..high.log2()).exp2(),
             },
             Range::Discrete { low, high } => match self.distribution {
@@ -44,7 +44,10 @@ impl rand::distributions::Distribution<f64> for KurobakoDomain {
                     .exp2()
                     .floor(),
             },
-            Range::Categorical { choices } => rng.gen_range(0..choices.len()) as f64,
+            Range::IntChoices { values } => values[rng.gen_range(0..values.len())] as f64,
+            Range::Categorical { choices } | Range::Ordinal { choices } => {
+                rng.gen_range(0..choices.len()) as f64
+            }
         }
     }
 }
@@ -1,21 +1,37 @@
-//! `kurobako run` command.
+//! `kurobako batch-evaluate` command.
 use crate::problem::KurobakoProblemRecipe;
 use crate::solver::KurobakoSolverRecipe;
 use kurobako_core::json;
 use kurobako_core::problem::ProblemRecipe as _;
-use kurobako_core::problem::{Evaluator as _, Problem as _, ProblemFactory as _};
+use kurobako_core::problem::{Evaluator as _, Problem, ProblemFactory as _, ProblemSpec};
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::trial::{Params, Values};
-use kurobako_core::{ErrorKind, Result};
+use kurobako_core::{Error, ErrorKind, Result};
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::Error;
+use std::collections::BTreeMap;
 use std::io;
-use std::io::Write;
+use std::io::{BufRead, Write};
+use std::num::NonZeroUsize;
+use std::sync::{mpsc, Mutex};
+use std::thread;
 use structopt::StructOpt;
 
 /// Options of the `kurobako batch-evaluate` command.
+///
+/// This command reads evaluation requests from the standard input line by line
+/// and writes a reply line to the standard output for each request.
+///
+/// Each request is a JSON object such as `{"params": [0.1, 2.0], "step": 12}`.
+/// `step` is optional and defaults to the last step of the problem.
+///
+/// Each reply is a JSON object such as `{"step": 12, "values": [0.5]}`,
+/// where `step` is the step at which the evaluator actually stopped
+/// (it may differ from the requested one depending on the problem).
+/// If the parameters are unevaluable, `values` is empty and `step` is the requested step.
+///
+/// The replies are written in the order of the requests even if `--workers` is greater than one.
 #[derive(Debug, Clone, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct BatchEvaluateOpt {
@@ -26,6 +42,16 @@ pub struct BatchEvaluateOpt {
     /// Random seed.
     #[structopt(long)]
     pub seed: Option<u64>,
+
+    /// Number of worker threads that evaluate parameters in parallel.
+    ///
+    /// Each worker owns a problem instance created from the recipe,
+    /// and the instance of the `i`-th worker uses the `i`-th random number substream of `--seed`
+    /// (so the results of stochastic problems depend on the assignment of the requests to the workers).
+    /// If the problem can't be evaluated concurrently (see `ProblemSpec::is_concurrent`),
+    /// the parameters are evaluated serially regardless of this option.
+    #[structopt(long, default_value = "1")]
+    pub workers: NonZeroUsize,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -36,55 +62,210 @@ struct EvalCall {
 
 #[derive(Debug, Clone, Serialize)]
 struct EvalReply {
+    step: u64,
     values: Values,
 }
 
 impl BatchEvaluateOpt {
     /// Evaluates the given parameters.
     pub fn run(&self) -> Result<()> {
+        let stdin = io::stdin();
+        track!(self.run_with(stdin.lock(), io::stdout()))
+    }
+
+    fn run_with<R, W>(&self, reader: R, mut writer: W) -> Result<()>
+    where
+        R: BufRead,
+        W: Write + Send,
+    {
         let random_seed = self.seed.unwrap_or_else(rand::random);
-        let rng = ArcRng::new(random_seed);
         let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
         let problem_factory = track!(self.problem.create_factory(&registry))?;
         let problem_spec = track!(problem_factory.specification())?;
 
-        let problem = track!(problem_factory.create_problem(rng))?;
-        let mut writer = io::stdout();
-        loop {
-            let mut line = String::new();
-            let n = io::stdin().read_line(&mut line)?;
-            if n == 0 {
-                break;
+        let rng = ArcRng::new(random_seed);
+        if self.workers.get() == 1 || !problem_spec.is_concurrent() {
+            let problem = track!(problem_factory.create_problem(rng))?;
+            for line in reader.lines() {
+                let line = track!(line.map_err(Error::from))?;
+                let reply = track!(evaluate(&problem, &problem_spec, &line))?;
+                track!(write_reply(&mut writer, &reply))?;
             }
-            let EvalCall { params, step } = serde_json::from_str(&line).map_err(Error::from)?;
+            return Ok(());
+        }
 
-            track_assert_eq!(
-                params.len(),
-                problem_spec.params_domain.variables().len(),
-                ErrorKind::InvalidInput
-            );
+        // The factories are kept until the end of the evaluations
+        // because some of them (e.g., external programs) own resources shared with their problems.
+        let mut factories = vec![problem_factory];
+        for _ in 1..self.workers.get() {
+            factories.push(track!(self.problem.create_factory(&registry))?);
+        }
+        let problems = factories
+            .iter()
+            .enumerate()
+            .map(|(i, f)| track!(f.create_problem(rng.substream(i as u64))))
+            .collect::<Result<Vec<_>>>()?;
 
-            let evaluator_or_error = track!(problem.create_evaluator(params.clone()));
+        let (tx, rx) = mpsc::sync_channel::<(usize, String)>(self.workers.get());
+        let rx = Mutex::new(rx);
+        let output = Mutex::new(OrderedWriter::new(writer));
+        let result: Result<()> = thread::scope(|scope| {
+            for problem in problems {
+                let (rx, output, problem_spec) = (&rx, &output, &problem_spec);
+                scope.spawn(move || loop {
+                    let job = rx.lock().unwrap_or_else(|e| panic!("{}", e)).recv();
+                    let (i, line) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let reply = track!(evaluate(&problem, problem_spec, &line));
+                    output
+                        .lock()
+                        .unwrap_or_else(|e| panic!("{}", e))
+                        .push(i, reply);
+                });
+            }
 
-            let values = match evaluator_or_error {
-                Ok(mut evaluator) => {
-                    let s = step.unwrap_or_else(|| problem_spec.steps.last());
-                    let (_, values) = track!(evaluator.evaluate(s))?;
-                    values
+            for (i, line) in reader.lines().enumerate() {
+                if output
+                    .lock()
+                    .unwrap_or_else(|e| panic!("{}", e))
+                    .error
+                    .is_some()
+                {
+                    break;
                 }
-                Err(e) => {
-                    if *e.kind() != ErrorKind::UnevaluableParams {
-                        return Err(e);
-                    } else {
-                        Values::new(vec![])
-                    }
+                let line = track!(line.map_err(Error::from))?;
+                if tx.send((i, line)).is_err() {
+                    break;
                 }
-            };
+            }
+            drop(tx);
+            Ok(())
+        });
+        track!(result)?;
+
+        let output = output.into_inner().unwrap_or_else(|e| panic!("{}", e));
+        if let Some(e) = output.error {
+            return Err(track!(e));
+        }
+        Ok(())
+    }
+}
+
+fn evaluate<P: Problem>(problem: &P, problem_spec: &ProblemSpec, line: &str) -> Result<EvalReply> {
+    let EvalCall { params, step } = track!(serde_json::from_str(line).map_err(Error::from))?;
+
+    track_assert_eq!(
+        params.len(),
+        problem_spec.params_domain.variables().len(),
+        ErrorKind::InvalidInput
+    );
+
+    let step = step.unwrap_or_else(|| problem_spec.steps.last());
+    let (step, values) = match track!(problem.create_evaluator(params)) {
+        Ok(mut evaluator) => track!(evaluator.evaluate(step))?,
+        Err(e) => {
+            if *e.kind() != ErrorKind::UnevaluableParams {
+                return Err(e);
+            } else {
+                (step, Values::new(vec![]))
+            }
+        }
+    };
+    Ok(EvalReply { step, values })
+}
+
+fn write_reply<W: Write>(mut writer: W, reply: &EvalReply) -> Result<()> {
+    track!(serde_json::to_writer(&mut writer, reply).map_err(Error::from))?;
+    writer.write_all("\n".as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Writes the replies in the order of the requests.
+#[derive(Debug)]
+struct OrderedWriter<W> {
+    writer: W,
+    next: usize,
+    pending: BTreeMap<usize, Result<EvalReply>>,
+    error: Option<Error>,
+}
+impl<W: Write> OrderedWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            next: 0,
+            pending: BTreeMap::new(),
+            error: None,
+        }
+    }
 
-            serde_json::to_writer(&mut writer, &EvalReply { values }).map_err(Error::from)?;
-            writer.write_all("\n".as_bytes())?;
-            writer.flush()?;
+    fn push(&mut self, i: usize, reply: Result<EvalReply>) {
+        self.pending.insert(i, reply);
+        while self.error.is_none() {
+            let reply = match self.pending.remove(&self.next) {
+                Some(reply) => reply,
+                None => break,
+            };
+            self.next += 1;
+            if let Err(e) = reply.and_then(|reply| track!(write_reply(&mut self.writer, &reply))) {
+                self.error = Some(e);
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    fn batch_evaluate(workers: &str, input: &str) -> Result<String> {
+        track!(batch_evaluate_problem(
+            r#"{"delay":{"problem":{"sigopt":{"name":"SPHERE","dim":2}},"seconds_per_step":0.01}}"#,
+            workers,
+            input
+        ))
+    }
+
+    fn batch_evaluate_problem(problem: &str, workers: &str, input: &str) -> Result<String> {
+        let opt = BatchEvaluateOpt::from_iter(&[
+            "batch-evaluate",
+            "--problem",
+            problem,
+            "--seed",
+            "0",
+            "--workers",
+            workers,
+        ]);
+        let mut output = Vec::new();
+        track!(opt.run_with(input.as_bytes(), &mut output))?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn parallel_batch_evaluate_works() -> TopLevelResult {
+        let input = (0..20)
+            .map(|i| format!("{{\"params\":[{}, {}]}}\n", i as f64 / 10.0, -i as f64))
+            .collect::<String>();
+        let serial = batch_evaluate("1", &input)?;
+        let parallel = batch_evaluate("4", &input)?;
+        assert_eq!(serial.lines().count(), 20);
+        assert_eq!(serial, parallel);
+        Ok(())
+    }
+
+    #[test]
+    fn workers_have_distinct_random_streams() -> TopLevelResult {
+        let problem = r#"{"delay":{"problem":{"sigopt":{"name":"SPHERE","dim":2,"noise_stddev":1.0}},
+                          "seconds_per_step":0.01}}"#;
+        let input = "{\"params\":[0.0, 0.0]}\n".repeat(20);
+        let output = batch_evaluate_problem(problem, "4", &input)?;
+        let mut values = output.lines().collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values.len(), 20, "{}", output);
         Ok(())
     }
 }
@@ -0,0 +1,351 @@
+//! `kurobako bench` command.
+//!
+//! This command runs a whole benchmark (i.e., `kurobako studies | kurobako run`
+//! followed by `kurobako report` and `kurobako plot`) described by a profile file.
+//!
+//! A profile is a TOML (if the file has `.toml` extension) or JSON file like the following:
+//!
+//! ```toml
+//! run = ["--parallelism", "4"]
+//! results = "results.json"
+//! plots = [["curve", "-o", "images/curve/"]]
+//!
+//! [studies]
+//! solvers = [{random = {}}]
+//! problems = [{sigopt = {name = "ACKLEY", dim = 2}}]
+//! repeats = 10
+//! budget = 20
+//! concurrency = 1
+//! scheduling = "RANDOM"
+//!
+//! [report]
+//! output = "report.md"
+//! args = ["--stability"]
+//! ```
+//!
+//! ```json
+//! {
+//!   "studies": {
+//!     "solvers": [{"random": {}}],
+//!     "problems": [{"sigopt": {"name": "ACKLEY", "dim": 2}}],
+//!     "repeats": 10,
+//!     "budget": 20,
+//!     "concurrency": 1,
+//!     "scheduling": "RANDOM"
+//!   },
+//!   "run": ["--parallelism", "4"],
+//!   "results": "results.json",
+//!   "report": {"output": "report.md", "args": ["--stability"]},
+//!   "plots": [["curve", "-o", "images/curve/"]]
+//! }
+//! ```
+//!
+//! `studies` has the same format as the output of `kurobako studies`,
+//! and `run`, `report.args` and each element of `plots` are the command line arguments of
+//! `kurobako run`, `kurobako report` and `kurobako plot` respectively.
+//! All fields except `studies` are optional.
+//!
+//! Note that TOML profiles are read by a built-in reader that supports the subset of TOML
+//! needed to write profiles (e.g., multi-line strings aren't supported),
+//! and YAML profiles (files that have `.yaml` or `.yml` extension) are rejected.
+use crate::plot::PlotOpt;
+use crate::report::{ReportOpt, Reporter};
+use crate::runner::{Runner, RunnerOpt};
+use crate::study::StudiesRecipe;
+use kurobako_core::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use trackable::error::ErrorKindExt;
+
+mod toml;
+
+/// Options of the `kurobako bench` command.
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct BenchOpt {
+    /// Benchmark profile file (TOML or JSON).
+    #[structopt(long)]
+    pub config: PathBuf,
+}
+impl BenchOpt {
+    /// Runs the benchmark described by the profile.
+    pub fn run(&self) -> Result<()> {
+        let profile = track!(BenchProfile::load(&self.config))?;
+        track!(profile.run())
+    }
+}
+
+/// Benchmark profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchProfile {
+    /// Studies to be run.
+    pub studies: StudiesRecipe,
+
+    /// Command line arguments of `kurobako run`.
+    #[serde(default)]
+    pub run: Vec<String>,
+
+    /// Output file of the benchmark results (JSONs).
+    ///
+    /// If omitted, the results are written to the standard output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<PathBuf>,
+
+    /// Report settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<BenchReport>,
+
+    /// Command line arguments of `kurobako plot` (one element per plot).
+    #[serde(default)]
+    pub plots: Vec<Vec<String>>,
+}
+impl BenchProfile {
+    /// Loads a profile from the given file.
+    ///
+    /// The file is parsed as TOML if it has `.toml` extension, otherwise as JSON.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let extension = path.as_ref().extension().and_then(|e| e.to_str());
+        track_assert!(
+            !matches!(extension, Some("yaml" | "yml")),
+            ErrorKind::InvalidInput,
+            "YAML profiles aren't supported: {:?}",
+            path.as_ref()
+        );
+
+        if extension == Some("toml") {
+            let text = track!(fs::read_to_string(&path).map_err(Error::from); path.as_ref())?;
+            let value = track!(toml::parse(&text); path.as_ref())?;
+            let profile =
+                track!(serde_json::from_value(value).map_err(Error::from); path.as_ref())?;
+            return Ok(profile);
+        }
+
+        let file = track!(File::open(&path).map_err(Error::from); path.as_ref())?;
+        let profile = track!(serde_json::from_reader(BufReader::new(file)).map_err(Error::from); path.as_ref())?;
+        Ok(profile)
+    }
+
+    /// Runs the benchmark described by this profile.
+    pub fn run(&self) -> Result<()> {
+        // Parses all the options before running the studies so that typos are reported immediately.
+        let runner_opt = track!(parse_args::<RunnerOpt>("run", &self.run))?;
+        let report_opt = if let Some(report) = &self.report {
+            Some(track!(parse_args::<ReportOpt>("report", &report.args))?)
+        } else {
+            None
+        };
+        let plot_opts = self
+            .plots
+            .iter()
+            .map(|args| track!(parse_args::<PlotOpt>("plot", args)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let recipes = self.studies.studies().collect();
+        let records = track!(Runner::new(runner_opt).run_studies(recipes))?;
+
+        if let Some(path) = &self.results {
+            let file = track!(File::create(path).map_err(Error::from); path)?;
+            let mut writer = BufWriter::new(file);
+            for record in &records {
+                track!(serde_json::to_writer(&mut writer, record).map_err(Error::from))?;
+                track!(writeln!(writer).map_err(Error::from))?;
+            }
+        } else {
+            let stdout = std::io::stdout();
+            let mut stdout = stdout.lock();
+            for record in &records {
+                track!(serde_json::to_writer(&mut stdout, record).map_err(Error::from))?;
+                track!(writeln!(stdout).map_err(Error::from))?;
+            }
+        }
+
+        for opt in &plot_opts {
+            track!(opt.plot(&records))?;
+        }
+
+        if let (Some(report), Some(opt)) = (&self.report, report_opt) {
+            let file = track!(File::create(&report.output).map_err(Error::from); report.output)?;
+            let reporter = Reporter::new(records, opt);
+            track!(reporter.report_all(BufWriter::new(file)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Report settings of a benchmark profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Output file of the report (markdown).
+    pub output: PathBuf,
+
+    /// Command line arguments of `kurobako report`.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+fn parse_args<T: StructOpt>(command: &str, args: &[String]) -> Result<T> {
+    let args = std::iter::once(command).chain(args.iter().map(|a| a.as_str()));
+    let opt =
+        track!(T::from_iter_safe(args).map_err(|e| ErrorKind::InvalidInput.cause(e.message)))?;
+    Ok(opt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::StudyRecord;
+    use kurobako_core::json;
+    use trackable::result::TopLevelResult;
+
+    fn write_profile(path: &Path, profile: &serde_json::Value) -> Result<()> {
+        let file = track!(File::create(path).map_err(Error::from))?;
+        track!(serde_json::to_writer(file, profile).map_err(Error::from))
+    }
+
+    #[test]
+    fn bench_works() -> TopLevelResult {
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let results = dir.path().join("results.json");
+        let report = dir.path().join("report.md");
+        let profile = serde_json::json!({
+            "studies": {
+                "solvers": [{"random": {}}],
+                "problems": [{"sigopt": {"name": "ACKLEY", "dim": 2}}],
+                "repeats": 2,
+                "budget": 3,
+                "concurrency": 1,
+                "scheduling": "RANDOM"
+            },
+            "run": ["--quiet"],
+            "results": results,
+            "report": {"output": report, "args": ["--stability"]}
+        });
+        let path = dir.path().join("profile.json");
+        write_profile(&path, &profile)?;
+
+        BenchOpt { config: path }.run()?;
+
+        let file = File::open(&results).map_err(Error::from)?;
+        let records: Vec<StudyRecord> = json::load(BufReader::new(file))?;
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.budget == 3));
+        assert!(std::fs::read_to_string(&report)
+            .map_err(Error::from)?
+            .contains("Stability"));
+        Ok(())
+    }
+
+    #[test]
+    fn toml_profile_works() -> TopLevelResult {
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let results = dir.path().join("results.json");
+        let profile = format!(
+            r#"# A benchmark profile.
+run = ["--quiet"]
+results = {:?}
+
+[studies]
+solvers = [{{random = {{}}}}, {{ restart = {{ restart_interval = 2 }} }}]
+problems = [
+    {{sigopt = {{name = "ACKLEY", dim = 2}}}},  # comment
+]
+repeats = 1
+budget = 3
+concurrency = 1
+scheduling = 'RANDOM'
+"#,
+            results
+        );
+        let path = dir.path().join("profile.toml");
+        std::fs::write(&path, profile).map_err(Error::from)?;
+
+        BenchOpt { config: path }.run()?;
+
+        let file = File::open(&results).map_err(Error::from)?;
+        let records: Vec<StudyRecord> = json::load(BufReader::new(file))?;
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.budget == 3));
+        Ok(())
+    }
+
+    #[test]
+    fn toml_works() -> TopLevelResult {
+        let value = toml::parse(
+            r#"
+a = 1
+b.c = "x\ty"
+"d" = [1.5, -2, true, 'z', []]
+
+[e.f]
+g = {h = 1_000, i = {}}
+
+[[j]]
+k = 1
+
+[[j]]
+k = 2
+"#,
+        )?;
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "a": 1,
+                "b": {"c": "x\ty"},
+                "d": [1.5, -2, true, "z", []],
+                "e": {"f": {"g": {"h": 1000, "i": {}}}},
+                "j": [{"k": 1}, {"k": 2}]
+            })
+        );
+
+        for text in &[
+            "a = 1\na = 2",
+            "a = \"\"\"x\"\"\"",
+            "a = 1 b = 2",
+            "a = 1979-05-27",
+        ] {
+            let e = toml::parse(text).expect_err("should be rejected");
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_profiles_are_rejected() -> TopLevelResult {
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let results = dir.path().join("results.json");
+        let profile = serde_json::json!({
+            "studies": {
+                "solvers": [{"random": {}}],
+                "problems": [{"sigopt": {"name": "ACKLEY", "dim": 2}}],
+                "repeats": 1,
+                "budget": 3,
+                "concurrency": 1,
+                "scheduling": "RANDOM"
+            },
+            "run": ["--no-such-option"],
+            "results": results
+        });
+        let path = dir.path().join("profile.json");
+        write_profile(&path, &profile)?;
+
+        // The arguments are validated before running the studies.
+        assert!(BenchProfile::load(&path).is_ok());
+        let e = BenchOpt { config: path }
+            .run()
+            .expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        assert!(!results.exists());
+
+        for name in &["profile.yaml", "profile.yml"] {
+            let path = dir.path().join(name);
+            write_profile(&path, &profile)?;
+            let e = BenchProfile::load(&path).expect_err("should be rejected");
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! A reader of the subset of [TOML](https://toml.io/) used by benchmark profiles.
+//!
+//! The supported syntax is:
+//!
+//! - comments, `key = value` pairs (keys can be bare, quoted or dotted),
+//! - `[table]` and `[[array.of.tables]]` headers,
+//! - basic (`"..."`) and literal (`'...'`) strings in a line,
+//! - integers, floats, booleans, arrays and inline tables.
+//!
+//! Multi-line strings and date-times aren't supported.
+use kurobako_core::{ErrorKind, Result};
+use serde_json::{Map, Number, Value};
+
+/// Parses a TOML document and converts it to a JSON object.
+pub fn parse(text: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    track!(parser.parse_document())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+impl Parser {
+    fn parse_document(&mut self) -> Result<Value> {
+        let mut root = Value::Object(Map::new());
+        let mut table = Vec::new();
+        loop {
+            self.skip_whitespaces_and_newlines();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.pos += 1;
+                    let is_array = self.eat('[');
+                    self.skip_whitespaces();
+                    table = track!(self.parse_key())?;
+                    track!(self.expect(']'))?;
+                    if is_array {
+                        track!(self.expect(']'))?;
+                        let line = self.line;
+                        let (parent, last) = table.split_at(table.len() - 1);
+                        let parent = track!(navigate(&mut root, parent, line))?;
+                        let tables = parent
+                            .entry(last[0].clone())
+                            .or_insert_with(|| Value::Array(Vec::new()));
+                        let tables = track_assert_some!(
+                            tables.as_array_mut(),
+                            ErrorKind::InvalidInput,
+                            "Not an array of tables: {:?} (line {})",
+                            table,
+                            line
+                        );
+                        tables.push(Value::Object(Map::new()));
+                    } else {
+                        track!(navigate(&mut root, &table, self.line))?;
+                    }
+                }
+                Some(_) => {
+                    let mut key = table.clone();
+                    key.extend(track!(self.parse_key())?);
+                    track!(self.expect('='))?;
+                    self.skip_whitespaces();
+                    let value = track!(self.parse_value())?;
+                    track!(insert(&mut root, &key, value, self.line))?;
+                }
+            }
+            self.skip_whitespaces();
+            track!(self.expect_end_of_line())?;
+        }
+        Ok(root)
+    }
+
+    fn parse_key(&mut self) -> Result<Vec<String>> {
+        let mut key = Vec::new();
+        loop {
+            let part = match self.peek() {
+                Some('"') => track!(self.parse_basic_string())?,
+                Some('\'') => track!(self.parse_literal_string())?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    track_assert_ne!(
+                        start,
+                        self.pos,
+                        ErrorKind::InvalidInput,
+                        "Expected a key (line {})",
+                        self.line
+                    );
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            key.push(part);
+            self.skip_whitespaces();
+            if !self.eat('.') {
+                return Ok(key);
+            }
+            self.skip_whitespaces();
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(track!(self.parse_basic_string())?)),
+            Some('\'') => Ok(Value::String(track!(self.parse_literal_string())?)),
+            Some('[') => track!(self.parse_array()),
+            Some('{') => track!(self.parse_inline_table()),
+            _ => track!(self.parse_scalar()),
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String> {
+        track!(self.expect('"'))?;
+        if self.eat('"') {
+            track_assert_ne!(
+                self.peek(),
+                Some('"'),
+                ErrorKind::InvalidInput,
+                "Multi-line strings aren't supported (line {})",
+                self.line
+            );
+            return Ok(String::new());
+        }
+
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => {
+                    track_panic!(
+                        ErrorKind::InvalidInput,
+                        "Unterminated string (line {})",
+                        self.line
+                    );
+                }
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(track!(self.parse_escape())?),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char> {
+        let c = match self.next() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(u @ ('u' | 'U')) => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let end = (self.pos + len).min(self.chars.len());
+                let hex = self.chars[self.pos..end].iter().collect::<String>();
+                self.pos = end;
+                let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                track_assert_some!(
+                    c,
+                    ErrorKind::InvalidInput,
+                    "Invalid unicode escape: {:?} (line {})",
+                    hex,
+                    self.line
+                )
+            }
+            c => track_panic!(
+                ErrorKind::InvalidInput,
+                "Invalid escape sequence: {:?} (line {})",
+                c,
+                self.line
+            ),
+        };
+        Ok(c)
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String> {
+        track!(self.expect('\''))?;
+        if self.eat('\'') {
+            track_assert_ne!(
+                self.peek(),
+                Some('\''),
+                ErrorKind::InvalidInput,
+                "Multi-line strings aren't supported (line {})",
+                self.line
+            );
+            return Ok(String::new());
+        }
+
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => {
+                    track_panic!(
+                        ErrorKind::InvalidInput,
+                        "Unterminated string (line {})",
+                        self.line
+                    );
+                }
+                Some('\'') => return Ok(s),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value> {
+        track!(self.expect('['))?;
+        let mut array = Vec::new();
+        loop {
+            self.skip_whitespaces_and_newlines();
+            if self.eat(']') {
+                return Ok(Value::Array(array));
+            }
+            array.push(track!(self.parse_value())?);
+            self.skip_whitespaces_and_newlines();
+            if !self.eat(',') {
+                self.skip_whitespaces_and_newlines();
+                track!(self.expect(']'))?;
+                return Ok(Value::Array(array));
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Value> {
+        track!(self.expect('{'))?;
+        let mut table = Value::Object(Map::new());
+        self.skip_whitespaces();
+        if self.eat('}') {
+            return Ok(table);
+        }
+        loop {
+            self.skip_whitespaces();
+            let key = track!(self.parse_key())?;
+            track!(self.expect('='))?;
+            self.skip_whitespaces();
+            let value = track!(self.parse_value())?;
+            track!(insert(&mut table, &key, value, self.line))?;
+            self.skip_whitespaces();
+            if !self.eat(',') {
+                track!(self.expect('}'))?;
+                return Ok(table);
+            }
+        }
+    }
+
+    fn parse_scalar(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "+-._".contains(c))
+        {
+            self.pos += 1;
+        }
+        let token = self.chars[start..self.pos].iter().collect::<String>();
+        let value = match token.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => {
+                let digits = token.replace('_', "");
+                let number = if let Ok(n) = digits.parse::<u64>() {
+                    Some(Number::from(n))
+                } else if let Ok(n) = digits.parse::<i64>() {
+                    Some(Number::from(n))
+                } else if digits.chars().any(|c| c.is_ascii_digit()) {
+                    digits.parse::<f64>().ok().and_then(Number::from_f64)
+                } else {
+                    None
+                };
+                let number = track_assert_some!(
+                    number,
+                    ErrorKind::InvalidInput,
+                    "Unsupported value: {:?} (line {})",
+                    token,
+                    self.line
+                );
+                Value::Number(number)
+            }
+        };
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        track_assert!(
+            self.eat(c),
+            ErrorKind::InvalidInput,
+            "Expected {:?}, but got {:?} (line {})",
+            c,
+            self.peek(),
+            self.line
+        );
+        Ok(())
+    }
+
+    fn expect_end_of_line(&mut self) -> Result<()> {
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => Ok(()),
+            c => track_panic!(
+                ErrorKind::InvalidInput,
+                "Expected a newline, but got {:?} (line {})",
+                c,
+                self.line
+            ),
+        }
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_whitespaces_and_newlines(&mut self) {
+        loop {
+            self.skip_whitespaces();
+            if !(self.eat('\n') || self.eat('\r')) {
+                break;
+            }
+        }
+    }
+}
+
+// Returns the table at the given path (creating missing tables).
+//
+// If an array of tables is on the path, its last element is used.
+fn navigate<'a>(
+    mut value: &'a mut Value,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Map<String, Value>> {
+    for key in path {
+        let table = track_assert_some!(
+            value.as_object_mut(),
+            ErrorKind::InvalidInput,
+            "Not a table: {:?} (line {})",
+            key,
+            line
+        );
+        value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Array(tables) = value {
+            value = track_assert_some!(
+                tables.last_mut(),
+                ErrorKind::InvalidInput,
+                "Not a table: {:?} (line {})",
+                key,
+                line
+            );
+        }
+    }
+    let table = track_assert_some!(
+        value.as_object_mut(),
+        ErrorKind::InvalidInput,
+        "Not a table: {:?} (line {})",
+        path,
+        line
+    );
+    Ok(table)
+}
+
+fn insert(root: &mut Value, key: &[String], value: Value, line: usize) -> Result<()> {
+    let (parent, last) = key.split_at(key.len() - 1);
+    let table = track!(navigate(root, parent, line))?;
+    track_assert!(
+        table.insert(last[0].clone(), value).is_none(),
+        ErrorKind::InvalidInput,
+        "Duplicate key: {:?} (line {})",
+        key,
+        line
+    );
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! `kurobako csv` command.
+use crate::record::StudyRecord;
+use kurobako_core::{Error, ErrorKind, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Options of the `kurobako csv` command.
+///
+/// This command reads benchmark results (JSONs) from the standard input and
+/// writes one CSV row per (study, step) to the standard output.
+/// Each series is a step function, so a column has the latest value at or before the step.
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CsvOpt {
+    /// Series to be dumped.
+    ///
+    /// This option can be specified multiple times.
+    /// If omitted, all the series are dumped.
+    #[structopt(
+        long = "metric",
+        possible_values = Metric::POSSIBLE_VALUES,
+        number_of_values = 1
+    )]
+    pub metrics: Vec<Metric>,
+}
+impl CsvOpt {
+    fn metrics(&self) -> Vec<Metric> {
+        if self.metrics.is_empty() {
+            vec![Metric::BestValue, Metric::Auc, Metric::ElapsedTime]
+        } else {
+            self.metrics.clone()
+        }
+    }
+
+    /// Writes the given studies as CSV.
+    pub fn write_csv<W: Write>(&self, studies: &[StudyRecord], mut writer: W) -> Result<()> {
+        let metrics = self.metrics();
+
+        track_write!(writer, "study_id,solver,problem,step")?;
+        for metric in &metrics {
+            track_write!(writer, ",{}", metric.column())?;
+        }
+        track_writeln!(writer)?;
+
+        for study in studies {
+            let id = track!(study.id())?;
+            let series = metrics.iter().map(|m| m.values(study)).collect::<Vec<_>>();
+            let steps = series
+                .iter()
+                .flat_map(|s| s.keys().copied())
+                .collect::<BTreeSet<_>>();
+            for step in steps {
+                track_write!(
+                    writer,
+                    "{},{},{},{}",
+                    id,
+                    escape(&study.solver.spec.name),
+                    escape(&study.problem.spec.name),
+                    step
+                )?;
+                for values in &series {
+                    // The series are step functions, so the latest value at `step` is used.
+                    match values.range(..=step).next_back() {
+                        Some((_, v)) => track_write!(writer, ",{}", v)?,
+                        None => track_write!(writer, ",")?,
+                    }
+                }
+                track_writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Series of a study.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Metric {
+    BestValue,
+    Auc,
+    ElapsedTime,
+}
+impl Metric {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["best-value", "auc", "elapsed-time"];
+
+    fn column(self) -> &'static str {
+        match self {
+            Metric::BestValue => "best_value",
+            Metric::Auc => "auc",
+            Metric::ElapsedTime => "elapsed_seconds",
+        }
+    }
+
+    fn values(self, study: &StudyRecord) -> BTreeMap<u64, f64> {
+        match self {
+            Metric::BestValue => study.best_values(),
+            Metric::Auc => auc_so_far(study),
+            Metric::ElapsedTime => study.elapsed_times(true),
+        }
+    }
+}
+impl FromStr for Metric {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "best-value" => Ok(Metric::BestValue),
+            "auc" => Ok(Metric::Auc),
+            "elapsed-time" => Ok(Metric::ElapsedTime),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown metric name: {:?}", s),
+        }
+    }
+}
+
+// `StudyRecord::aucs` has a value for every step,
+// so only the steps at which the other series change (and the last step) are kept.
+fn auc_so_far(study: &StudyRecord) -> BTreeMap<u64, f64> {
+    // The same start step as `kurobako report` (i.e., after the first complete trial).
+    let start_step = study
+        .first_complete_trial()
+        .and_then(|t| t.start_step())
+        .map_or(study.problem.spec.steps.last(), |step| {
+            step.max(study.problem.spec.steps.last())
+        });
+    let aucs = study.aucs(start_step);
+
+    let mut steps = study
+        .elapsed_times(false)
+        .into_keys()
+        .collect::<BTreeSet<_>>();
+    steps.extend(study.best_values().into_keys());
+    steps.extend(aucs.keys().next().copied());
+    steps.extend(aucs.keys().next_back().copied());
+    aucs.into_iter()
+        .filter(|(step, _)| steps.contains(step))
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::record;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn auc_series_matches_study_record() -> TopLevelResult {
+        let study = record("test", "test", 4, &[3.0, 1.0, 2.0, 0.5])?;
+        let aucs = auc_so_far(&study);
+        let expected = study.aucs(1);
+        assert!(!aucs.is_empty());
+        for (step, auc) in &aucs {
+            assert_eq!(Some(auc), expected.get(step));
+        }
+        assert_eq!(aucs.values().last().copied(), study.auc(1));
+
+        let mut csv = Vec::new();
+        let opt = CsvOpt {
+            metrics: vec![Metric::BestValue, Metric::Auc],
+        };
+        opt.write_csv(&[study], &mut csv)?;
+        let csv = String::from_utf8(csv).expect("not UTF-8");
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("study_id,solver,problem,step,best_value,auc")
+        );
+        let last = lines.next_back().expect("no rows");
+        assert!(last.ends_with(",4,0.5,5"), "{}", last);
+        Ok(())
+    }
+}
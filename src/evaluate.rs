@@ -1,6 +1,8 @@
 //! `kurobako evaluate` command.
 use crate::problem::KurobakoProblemRecipe;
+use crate::record::StudyRecord;
 use crate::solver::KurobakoSolverRecipe;
+use kurobako_core::domain::{Domain, Range};
 use kurobako_core::json;
 use kurobako_core::problem::{
     Evaluator as _, Problem as _, ProblemFactory as _, ProblemRecipe as _,
@@ -8,8 +10,13 @@ use kurobako_core::problem::{
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::trial::{Params, Values};
-use kurobako_core::{ErrorKind, Result};
+use kurobako_core::{Error, ErrorKind, Result};
+use rustats::fundamental::{average, stddev};
 use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// Options of the `kurobako evaluate` command.
@@ -17,48 +24,227 @@ use structopt::StructOpt;
 #[structopt(rename_all = "kebab-case")]
 pub struct EvaluateOpt {
     /// Evaluation target problem.
-    #[structopt(long, parse(try_from_str = json::parse_json))]
-    pub problem: KurobakoProblemRecipe,
+    ///
+    /// If omitted, the problem recorded in the `--from-study` file is used.
+    #[structopt(long, parse(try_from_str = json::parse_json), required_unless = "from-study")]
+    pub problem: Option<KurobakoProblemRecipe>,
 
     /// Parameters to be evaluated.
-    #[structopt(long, parse(try_from_str = json::parse_json))]
-    pub params: Params,
+    #[structopt(
+        long,
+        parse(try_from_str = json::parse_json),
+        required_unless_one = &["from-study", "params-json"],
+        conflicts_with_all = &["from-study", "params-json"]
+    )]
+    pub params: Option<Params>,
 
-    /// Evaluation step. If omitted, the maximum step of the problem is used.
+    /// Parameters to be evaluated as a JSON object that maps variable names to values
+    /// (e.g., `{"x": 1.2, "optimizer": "adam"}`).
+    ///
+    /// The choices of categorical (and ordinal) variables can be specified by their names or indices.
+    /// Conditional variables can be omitted (or be `null`) to make them inactive,
+    /// but the other variables are required and unknown names are rejected.
+    #[structopt(
+        long,
+        parse(try_from_str = json::parse_json),
+        conflicts_with = "from-study"
+    )]
+    pub params_json: Option<serde_json::Map<String, serde_json::Value>>,
+
+    /// Study record file (JSON) from which the parameters of a trial are taken.
+    ///
+    /// The file needs to contain exactly one study record.
+    /// This is useful to check whether the recorded values are reproduced by the (possibly updated) problem.
+    #[structopt(long, requires = "trial")]
+    pub from_study: Option<PathBuf>,
+
+    /// Index of the trial in the `trials` array of the `--from-study` record.
+    #[structopt(long, requires = "from-study")]
+    pub trial: Option<usize>,
+
+    /// Evaluation step.
+    ///
+    /// If omitted, the steps recorded for the `--from-study` trial or the maximum step of the problem is used.
     #[structopt(long)]
     pub step: Option<u64>,
 
     /// Random seed.
     #[structopt(long)]
     pub seed: Option<u64>,
+
+    /// Number of times to evaluate the parameters.
+    ///
+    /// If this is specified, the same parameters are evaluated by fresh evaluators
+    /// and the mean and standard deviation of each objective value are reported.
+    /// This is useful to characterize the noise of a problem.
+    #[structopt(long)]
+    pub repeat: Option<NonZeroUsize>,
 }
 
 impl EvaluateOpt {
     /// Evaluates the given parameters.
     pub fn evaluate(&self) -> Result<Evaluated> {
+        let recorded = track!(self.load_recorded_trial())?;
+        let (problem_recipe, params) = match (&recorded, &self.problem, &self.params) {
+            (_, Some(problem), Some(params)) => (problem.clone(), Some(params.clone())),
+            (None, Some(problem), None) if self.params_json.is_some() => (problem.clone(), None),
+            (Some(r), Some(problem), None) => (problem.clone(), Some(r.params.clone())),
+            (Some(r), None, None) => (r.problem.clone(), Some(r.params.clone())),
+            _ => track_panic!(
+                ErrorKind::InvalidInput,
+                "Both of a problem and parameters are required"
+            ),
+        };
+
+        // The chosen seed is reported in `Evaluated`, so the evaluation can be replayed by it.
         let random_seed = self.seed.unwrap_or_else(rand::random);
         let rng = ArcRng::new(random_seed);
         let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
-        let problem_factory = track!(self.problem.create_factory(&registry))?;
+        let problem_factory = track!(problem_recipe.create_factory(&registry))?;
         let problem_spec = track!(problem_factory.specification())?;
+        let params = match (params, &self.params_json) {
+            (Some(params), _) => params,
+            (None, Some(named)) => track!(named_params(&problem_spec.params_domain, named))?,
+            (None, None) => track_panic!(ErrorKind::Bug, "No parameters are given"),
+        };
         track_assert_eq!(
-            self.params.len(),
+            params.len(),
             problem_spec.params_domain.variables().len(),
             ErrorKind::InvalidInput
         );
 
-        let problem = track!(problem_factory.create_problem(rng))?;
+        let problem = track!(problem_factory.create_problem(rng.clone()))?;
 
-        let mut evaluator = track!(problem.create_evaluator(self.params.clone()))?;
-        let step = self.step.unwrap_or_else(|| problem_spec.steps.last());
-        let (current_step, values) = track!(evaluator.evaluate(step))?;
+        let step = self
+            .step
+            .or_else(|| recorded.as_ref().map(|r| r.step))
+            .unwrap_or_else(|| problem_spec.steps.last());
+        let repeat = self.repeat.map_or(1, |n| n.get());
+        let mut samples = Vec::with_capacity(repeat);
+        let mut current_step = step;
+        for _ in 0..repeat {
+            let mut evaluator = track!(problem.create_evaluator(params.clone()))?;
+            let (step, values) = track!(evaluator.evaluate(step))?;
+            current_step = step;
+            samples.push(values);
+        }
 
+        let stats = self.repeat.map(|_| RepeatedStats::new(&samples));
         Ok(Evaluated {
-            values,
+            values: samples.swap_remove(0),
             step: current_step,
             seed: random_seed,
+            unseeded_rng_used: rng.unseeded_rng_used(),
+            stats,
+            recorded_values: recorded.map(|r| r.values),
         })
     }
+
+    fn load_recorded_trial(&self) -> Result<Option<RecordedTrial>> {
+        let (path, index) = match (&self.from_study, self.trial) {
+            (Some(path), Some(index)) => (path, index),
+            _ => return Ok(None),
+        };
+
+        let file = track!(File::open(path).map_err(Error::from); path)?;
+        let mut studies: Vec<serde_json::Value> = track!(json::load(BufReader::new(file)); path)?;
+        track_assert_eq!(studies.len(), 1, ErrorKind::InvalidInput; path);
+        let study = track!(StudyRecord::migrate(studies.swap_remove(0)); path)?;
+
+        let trial = track_assert_some!(
+            study.trials.get(index),
+            ErrorKind::InvalidInput,
+            "No such trial: index={}, trials={}",
+            index,
+            study.trials.len()
+        );
+        let values = trial
+            .evaluations
+            .last()
+            .map(|e| e.values.clone())
+            .unwrap_or_else(|| Values::new(Vec::new()));
+        Ok(Some(RecordedTrial {
+            problem: study.problem.recipe.clone(),
+            params: trial.params.clone(),
+            step: trial.steps(),
+            values,
+        }))
+    }
+}
+
+// Converts the given named parameters into the parameters ordered by the variables of the domain.
+fn named_params(
+    domain: &Domain,
+    named: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Params> {
+    let vars = domain.variables();
+    for name in named.keys() {
+        track_assert!(
+            vars.iter().any(|v| v.name() == name),
+            ErrorKind::InvalidInput,
+            "Unknown variable: {:?}",
+            name
+        );
+    }
+
+    let mut params = Vec::with_capacity(vars.len());
+    for var in vars {
+        let value = match named.get(var.name()) {
+            None | Some(serde_json::Value::Null) => {
+                track_assert!(
+                    var.constraint().is_some(),
+                    ErrorKind::InvalidInput,
+                    "Missing parameter: {:?}",
+                    var.name()
+                );
+                params.push(f64::NAN);
+                continue;
+            }
+            Some(serde_json::Value::String(s)) => match var.range() {
+                Range::Categorical { choices } | Range::Ordinal { choices } => {
+                    let i = track_assert_some!(
+                        choices.iter().position(|c| c == s),
+                        ErrorKind::InvalidInput,
+                        "Unknown choice of {:?}: {:?}",
+                        var.name(),
+                        s
+                    );
+                    i as f64
+                }
+                _ => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Non-categorical variable {:?} has a string value: {:?}",
+                    var.name(),
+                    s
+                ),
+            },
+            Some(value) => track_assert_some!(
+                value.as_f64(),
+                ErrorKind::InvalidInput,
+                "Parameter {:?} isn't a number: {}",
+                var.name(),
+                value
+            ),
+        };
+        track_assert!(
+            var.range().contains(value),
+            ErrorKind::InvalidInput,
+            "Parameter {:?} is out of the range: value={}, range={:?}",
+            var.name(),
+            value,
+            var.range()
+        );
+        params.push(value);
+    }
+    Ok(Params::new(params))
+}
+
+#[derive(Debug)]
+struct RecordedTrial {
+    problem: KurobakoProblemRecipe,
+    params: Params,
+    step: u64,
+    values: Values,
 }
 
 /// Evaluated result.
@@ -72,4 +258,119 @@ pub struct Evaluated {
 
     /// Random seed.
     pub seed: u64,
+
+    /// If `true`, some random numbers that cannot be reproduced by `seed` were used in the evaluation.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unseeded_rng_used: bool,
+
+    /// Statistics of the values obtained by repeated evaluations (`--repeat` option).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<RepeatedStats>,
+
+    /// Values recorded in the `--from-study` file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recorded_values: Option<Values>,
+}
+
+/// Statistics of repeatedly evaluated values.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepeatedStats {
+    /// Number of evaluations.
+    pub repeat: usize,
+
+    /// Mean of each objective value.
+    pub mean: Vec<f64>,
+
+    /// Standard deviation of each objective value.
+    pub sd: Vec<f64>,
+}
+impl RepeatedStats {
+    fn new(samples: &[Values]) -> Self {
+        let objectives = samples[0].len();
+        let (mean, sd) = (0..objectives)
+            .map(|i| {
+                let xs = samples.iter().map(|v| v[i]);
+                (average(xs.clone()), stddev(xs))
+            })
+            .unzip();
+        Self {
+            repeat: samples.len(),
+            mean,
+            sd,
+        }
+    }
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{testing, SCHEMA_VERSION};
+    use trackable::result::TopLevelResult;
+
+    fn opt(params_json: &str) -> Result<EvaluateOpt> {
+        Ok(EvaluateOpt {
+            problem: Some(track!(json::parse_json(
+                r#"{"sigopt":{"name":"SPHERE","dim":2}}"#
+            ))?),
+            params: None,
+            params_json: Some(track!(json::parse_json(params_json))?),
+            from_study: None,
+            trial: None,
+            step: None,
+            seed: Some(0),
+            repeat: None,
+        })
+    }
+
+    #[test]
+    fn evaluate_named_params_works() -> TopLevelResult {
+        let evaluated = opt(r#"{"p1": 2.0, "p0": 1.0}"#)?.evaluate()?;
+        assert_eq!(evaluated.values.into_vec(), [5.0]);
+
+        assert!(opt(r#"{"p0": 1.0}"#)?.evaluate().is_err());
+        assert!(opt(r#"{"p0": 1.0, "p1": 2.0, "p2": 3.0}"#)?
+            .evaluate()
+            .is_err());
+        assert!(opt(r#"{"p0": 1.0, "p1": 100.0}"#)?.evaluate().is_err());
+        assert!(opt(r#"{"p0": 1.0, "p1": "foo"}"#)?.evaluate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn recorded_trials_are_migrated() -> TopLevelResult {
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let path = dir.path().join("study.json");
+        let write = |record: &serde_json::Value| -> Result<()> {
+            let json = track!(serde_json::to_string(record).map_err(Error::from))?;
+            track!(std::fs::write(&path, json).map_err(Error::from))
+        };
+        let opt = EvaluateOpt {
+            problem: None,
+            params_json: None,
+            from_study: Some(path.clone()),
+            trial: Some(1),
+            ..opt("{}")?
+        };
+
+        // A record of the schema version `0` (i.e., without `schema_version`).
+        let record = testing::record("foo", "bar", 2, &[1.0, 2.0])?;
+        let mut record = serde_json::to_value(record).map_err(Error::from)?;
+        if let Some(record) = record.as_object_mut() {
+            record.remove("schema_version");
+        }
+        write(&record)?;
+        let recorded = track_assert_some!(opt.load_recorded_trial()?, ErrorKind::Bug);
+        assert_eq!(recorded.values.into_vec(), [2.0]);
+
+        // Records of unknown (newer) versions are rejected.
+        record["schema_version"] = serde_json::json!(SCHEMA_VERSION + 1);
+        write(&record)?;
+        let e = opt.load_recorded_trial().expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
 }
@@ -0,0 +1,113 @@
+//! Filter of study records.
+use crate::record::StudyRecord;
+use regex::Regex;
+use std::borrow::Cow;
+use structopt::StructOpt;
+
+/// Options to select studies by the names of their problems.
+#[derive(Debug, Clone, Default, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ProblemFilterOpt {
+    /// Only uses the studies whose problem names match this regular expression.
+    #[structopt(long)]
+    pub include: Option<Regex>,
+
+    /// Excludes the studies whose problem names match this regular expression.
+    ///
+    /// This takes precedence over `--include`.
+    #[structopt(long)]
+    pub exclude: Option<Regex>,
+}
+impl ProblemFilterOpt {
+    /// Returns `true` if the problem that has the given name is selected by this filter.
+    pub fn is_match(&self, problem_name: &str) -> bool {
+        if self
+            .exclude
+            .as_ref()
+            .is_some_and(|r| r.is_match(problem_name))
+        {
+            return false;
+        }
+        self.include
+            .as_ref()
+            .is_none_or(|r| r.is_match(problem_name))
+    }
+
+    pub(crate) fn filter<'a>(&self, studies: &'a [StudyRecord]) -> Cow<'a, [StudyRecord]> {
+        if self.include.is_none() && self.exclude.is_none() {
+            return Cow::Borrowed(studies);
+        }
+        Cow::Owned(
+            studies
+                .iter()
+                .filter(|s| self.is_match(&s.problem.spec.name))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::KurobakoProblemRecipe;
+    use crate::record::StudyRecordBuilder;
+    use crate::solver::KurobakoSolverRecipe;
+    use crate::study::StudyRecipe;
+    use kurobako_core::json;
+    use kurobako_core::problem::{ProblemFactory as _, ProblemRecipe as _};
+    use kurobako_core::registry::FactoryRegistry;
+    use kurobako_core::solver::{SolverFactory as _, SolverRecipe as _};
+    use trackable::result::TopLevelResult;
+
+    fn study(problem: &str) -> kurobako_core::Result<StudyRecord> {
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let study: StudyRecipe = track!(json::parse_json(&format!(
+            r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"{}","dim":2}}}},
+                "budget":1,"concurrency":1,"scheduling":"RANDOM","seed":0}}"#,
+            problem
+        )))?;
+        let problem = track!(track!(study.problem.create_factory(&registry))?.specification())?;
+        let solver = track!(track!(study.solver.create_factory(&registry))?.specification())?;
+        Ok(StudyRecordBuilder::new(study, solver, problem).finish())
+    }
+
+    #[test]
+    fn problem_filter_works() {
+        let filter = ProblemFilterOpt::from_iter(&["filter", "--include", "^(foo|bar)"]);
+        assert!(filter.is_match("foo"));
+        assert!(filter.is_match("bar(dim=2)"));
+        assert!(!filter.is_match("baz"));
+
+        let filter = ProblemFilterOpt::from_iter(&["filter", "--include", "o", "--exclude", "f"]);
+        assert!(!filter.is_match("foo"));
+        assert!(filter.is_match("boo"));
+
+        assert!(ProblemFilterOpt::default().is_match("baz"));
+    }
+
+    #[test]
+    fn filter_studies_works() -> TopLevelResult {
+        let studies = vec![study("SPHERE")?, study("GRIEWANK")?, study("ACKLEY")?];
+        let names = studies
+            .iter()
+            .map(|s| s.problem.spec.name.clone())
+            .collect::<Vec<_>>();
+
+        let filter = ProblemFilterOpt::from_iter(&["filter", "--include", "(?i)/griewank\\("]);
+        assert_eq!(filter.filter(&studies).len(), 1);
+        assert_eq!(filter.filter(&studies)[0].problem.spec.name, names[1]);
+
+        let filter = ProblemFilterOpt::from_iter(&[
+            "filter",
+            "--include",
+            "(?i)/(sphere|ackley)\\(",
+            "--exclude",
+            "(?i)ackley",
+        ]);
+        let filtered = filter.filter(&studies);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].problem.spec.name, names[0]);
+        Ok(())
+    }
+}
@@ -20,8 +20,12 @@ macro_rules! track_writeln {
 }
 
 pub mod batch_eval;
+pub mod bench;
+pub mod csv;
 pub mod dataset;
 pub mod evaluate;
+pub mod filter;
+pub mod migrate;
 pub mod plot;
 pub mod problem;
 pub mod problem_suites;
@@ -31,6 +35,7 @@ pub mod solver;
 pub mod spec;
 pub mod study;
 pub mod time;
+pub mod validate;
 pub mod variable;
 
 mod markdown;
@@ -2,16 +2,20 @@
 extern crate trackable;
 
 use kurobako::batch_eval::BatchEvaluateOpt;
+use kurobako::bench::BenchOpt;
+use kurobako::csv::CsvOpt;
 use kurobako::dataset::DatasetOpt;
 use kurobako::evaluate::EvaluateOpt;
+use kurobako::migrate::MigrateOpt;
 use kurobako::plot::PlotOpt;
 use kurobako::problem::KurobakoProblemRecipe;
 use kurobako::problem_suites::ProblemSuite;
-use kurobako::report::{ReportOpt, Reporter};
+use kurobako::report::{ReportFormat, ReportOpt, Reporter};
 use kurobako::runner::{Runner, RunnerOpt};
 use kurobako::solver::KurobakoSolverRecipe;
 use kurobako::spec::SpecOpt;
 use kurobako::study::StudiesRecipe;
+use kurobako::validate::ValidateOpt;
 use kurobako::variable::Var;
 use kurobako_core::json;
 use kurobako_core::Error;
@@ -57,6 +61,9 @@ enum Opt {
     /// Generates visualization images from benchmark results (JSONs).
     Plot(PlotOpt),
 
+    /// Converts benchmark results (JSONs) to per-step series in CSV.
+    Csv(CsvOpt),
+
     /// Dataset management.
     Dataset(DatasetOpt),
 
@@ -68,6 +75,17 @@ enum Opt {
 
     /// Show problem or solver specification.
     Spec(SpecOpt),
+
+    /// Runs a whole benchmark described by a profile file (JSON).
+    Bench(BenchOpt),
+
+    /// Upgrades benchmark results (JSONs) recorded by older versions to the current schema.
+    Migrate(MigrateOpt),
+
+    /// Takes study recipes (JSONs), then checks that the solvers have the capabilities required by the problems.
+    ///
+    /// The incompatible pairs are printed, and the command fails if there are any.
+    Validate(ValidateOpt),
 }
 
 fn main() -> trackable::result::TopLevelResult {
@@ -98,15 +116,25 @@ fn main() -> trackable::result::TopLevelResult {
         }
         Opt::Report(opt) => {
             let studies = track!(json::load(io::stdin().lock()))?;
+            let format = opt.format;
             let reporter = Reporter::new(studies, opt);
             let stdout = io::stdout();
             let stdout = stdout.lock();
-            track!(reporter.report_all(stdout))?;
+            match format {
+                ReportFormat::Markdown => track!(reporter.report_all(stdout))?,
+                ReportFormat::Json => track!(reporter.report_json(stdout))?,
+            }
         }
         Opt::Plot(opt) => {
             let studies = track!(json::load(io::stdin().lock()))?;
             track!(opt.plot(&studies))?;
         }
+        Opt::Csv(opt) => {
+            let studies: Vec<_> = track!(json::load(io::stdin().lock()))?;
+            let stdout = io::stdout();
+            let stdout = stdout.lock();
+            track!(opt.write_csv(&studies, stdout))?;
+        }
         Opt::Dataset(opt) => {
             track!(opt.run())?;
         }
@@ -114,6 +142,16 @@ fn main() -> trackable::result::TopLevelResult {
             let evaluated = track!(opt.evaluate())?;
             print_json!(evaluated);
         }
+        Opt::Spec(opt @ (SpecOpt::Diff { .. } | SpecOpt::DiffRecipes { .. })) => {
+            let differences = track!(opt.get_diff())?;
+            for difference in &differences {
+                print!("{}", difference);
+            }
+            if !differences.is_empty() {
+                // Like `diff(1)`, exits with a non-zero code if the specifications differ.
+                std::process::exit(1);
+            }
+        }
         Opt::Spec(opt) => {
             let spec = track!(opt.get_spec())?;
             print_json!(spec);
@@ -121,6 +159,21 @@ fn main() -> trackable::result::TopLevelResult {
         Opt::BatchEvaluate(opt) => {
             track!(opt.run())?;
         }
+        Opt::Bench(opt) => {
+            track!(opt.run())?;
+        }
+        Opt::Migrate(opt) => {
+            track!(opt.run())?;
+        }
+        Opt::Validate(opt) => {
+            let incompatibilities = track!(opt.run())?;
+            for x in &incompatibilities {
+                println!("{}", x);
+            }
+            if !incompatibilities.is_empty() {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
@@ -16,7 +16,7 @@ impl<'a, W: Write> MarkdownWriter<'a, W> {
         Self { writer, level }
     }
 
-    pub fn heading(&mut self, s: &str) -> Result<MarkdownWriter<W>> {
+    pub fn heading(&mut self, s: &str) -> Result<MarkdownWriter<'_, W>> {
         for _ in 0..=self.level {
             track_write!(self.writer, "#")?
         }
@@ -0,0 +1,72 @@
+//! `kurobako migrate` command.
+use crate::record::{StudyRecord, SCHEMA_VERSION};
+use kurobako_core::json;
+use kurobako_core::{Error, Result};
+use std::io::{self, Read, Write};
+use structopt::StructOpt;
+
+/// Options of the `kurobako migrate` command.
+///
+/// This command reads benchmark results (JSONs) from the standard input,
+/// upgrades them to the current schema version, and writes them to the standard output.
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct MigrateOpt {}
+
+impl MigrateOpt {
+    /// Migrates the benchmark results.
+    pub fn run(&self) -> Result<()> {
+        let stdout = io::stdout();
+        let migrated = track!(self.run_with(io::stdin().lock(), stdout.lock()))?;
+        eprintln!(
+            "Migrated {} records to the schema version {}",
+            migrated, SCHEMA_VERSION
+        );
+        Ok(())
+    }
+
+    fn run_with<R: Read, W: Write>(&self, reader: R, mut writer: W) -> Result<usize> {
+        let records: Vec<serde_json::Value> = track!(json::load(reader))?;
+
+        let mut migrated = 0;
+        for record in records {
+            let record = track!(StudyRecord::migrate(record))?;
+            track!(serde_json::to_writer(&mut writer, &record).map_err(Error::from))?;
+            track!(writeln!(writer).map_err(Error::from))?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::record;
+    use kurobako_core::ErrorKind;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn migrate_works() -> TopLevelResult {
+        let mut old =
+            serde_json::to_value(record("test", "test", 2, &[1.0, 0.5])?).map_err(Error::from)?;
+        old.as_object_mut()
+            .expect("not an object")
+            .remove("schema_version");
+        let input = format!("{}\n{}\n", old, old);
+
+        let mut output = Vec::new();
+        assert_eq!(MigrateOpt {}.run_with(input.as_bytes(), &mut output)?, 2);
+        let migrated: Vec<StudyRecord> = json::load(&output[..])?;
+        assert_eq!(migrated.len(), 2);
+        assert!(migrated.iter().all(|r| r.schema_version == SCHEMA_VERSION));
+        assert_eq!(migrated[0].trials.len(), 2);
+
+        old["schema_version"] = serde_json::json!(SCHEMA_VERSION + 1);
+        let e = MigrateOpt {}
+            .run_with(old.to_string().as_bytes(), Vec::new())
+            .expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+}
@@ -1,11 +1,23 @@
 //! `kurobako plot` command.
 use crate::record::StudyRecord;
 use kurobako_core::{Error, ErrorKind, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use structopt::StructOpt;
+use tempfile::{NamedTempFile, TempPath};
 
+pub mod backend;
+pub mod boxplot;
 pub mod curve;
+pub mod palette;
+pub mod pareto;
 pub mod pareto_front;
+pub mod performance_profile;
+pub mod rank;
 pub mod slice;
 
 /// Options of the `kurobako plot` command.
@@ -20,6 +32,18 @@ pub enum PlotOpt {
 
     /// Generates 2D pareto front plots.
     ParetoFront(self::pareto_front::PlotParetoFrontOpt),
+
+    /// Generates 2D scatter plots of the final pareto frontiers of solvers.
+    Pareto(self::pareto::PlotParetoOpt),
+
+    /// Generates a performance profile plot that summarizes solvers over problems.
+    PerformanceProfile(self::performance_profile::PlotPerformanceProfileOpt),
+
+    /// Generates box plots of the final best values of solvers.
+    BoxPlot(self::boxplot::PlotBoxplotOpt),
+
+    /// Generates a plot of the average ranks of solvers over problems versus the consumed budget.
+    Rank(self::rank::PlotRankOpt),
 }
 impl PlotOpt {
     /// Plots a graph.
@@ -28,6 +52,149 @@ impl PlotOpt {
             Self::Curve(opt) => track!(opt.plot(studies)),
             Self::Slice(opt) => track!(opt.plot(studies)),
             Self::ParetoFront(opt) => track!(opt.plot(studies)),
+            Self::Pareto(opt) => track!(opt.plot(studies)),
+            Self::PerformanceProfile(opt) => track!(opt.plot(studies)),
+            Self::BoxPlot(opt) => track!(opt.plot(studies)),
+            Self::Rank(opt) => track!(opt.plot(studies)),
+        }
+    }
+}
+
+/// Format of output images.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Svg,
+    Pdf,
+}
+impl ImageFormat {
+    /// Possible values.
+    pub const POSSIBLE_VALUES: &'static [&'static str] = &["png", "svg", "pdf"];
+
+    /// Returns the file extension of this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+            Self::Pdf => "pdf",
+        }
+    }
+
+    // Returns the gnuplot command that sets the terminal of this format.
+    //
+    // The size of a PDF is specified in inches, so it is converted by assuming 72 pixels per inch.
+    fn gnuplot_terminal(self, width: usize, height: usize) -> String {
+        match self {
+            Self::Png => format!(
+                "set terminal pngcairo size {},{} noenhanced;",
+                width, height
+            ),
+            Self::Svg => format!("set terminal svg size {},{} noenhanced;", width, height),
+            Self::Pdf => format!(
+                "set terminal pdfcairo size {}in,{}in noenhanced;",
+                width as f64 / 72.0,
+                height as f64 / 72.0
+            ),
+        }
+    }
+}
+impl FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "png" => Ok(Self::Png),
+            "svg" => Ok(Self::Svg),
+            "pdf" => Ok(Self::Pdf),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown image format: {:?}", s),
+        }
+    }
+}
+
+/// Options to keep the files given to gnuplot.
+#[derive(Debug, Clone, Default, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct GnuplotDumpOpt {
+    /// Directory where the data and script files given to gnuplot are written (for debugging).
+    ///
+    /// The files are named after the output images (e.g., `foo.dat` and `foo.gp` for `foo.png`)
+    /// and aren't deleted after plotting.
+    /// If this is omitted, temporary files are used instead.
+    #[structopt(long)]
+    pub dump_dir: Option<PathBuf>,
+}
+impl GnuplotDumpOpt {
+    // Creates the data file used to plot the image `output`.
+    fn data_file(&self, output: &Path) -> Result<DataFile> {
+        if let Some(path) = track!(self.dump_path(output, "dat"))? {
+            let file = track!(File::create(&path).map_err(Error::from); path)?;
+            Ok(DataFile::Dump(file, path))
+        } else {
+            let file = track!(NamedTempFile::new().map_err(Error::from))?;
+            Ok(DataFile::Temp(file))
+        }
+    }
+
+    // Executes the script that plots the image `output`.
+    fn execute_gnuplot(&self, output: &Path, script: &str) -> Result<()> {
+        if let Some(path) = track!(self.dump_path(output, "gp"))? {
+            track!(fs::write(&path, format!("{}\n", script)).map_err(Error::from); path)?;
+        }
+        track!(execute_gnuplot(script))
+    }
+
+    fn dump_path(&self, output: &Path, extension: &str) -> Result<Option<PathBuf>> {
+        let Some(dir) = &self.dump_dir else {
+            return Ok(None);
+        };
+        track!(fs::create_dir_all(dir).map_err(Error::from); dir)?;
+        let stem = output.file_stem().unwrap_or_default();
+        Ok(Some(dir.join(stem).with_extension(extension)))
+    }
+}
+
+// Data file given to gnuplot.
+#[derive(Debug)]
+enum DataFile {
+    Temp(NamedTempFile),
+    Dump(File, PathBuf),
+}
+impl DataFile {
+    fn into_path(self) -> DataPath {
+        match self {
+            Self::Temp(f) => DataPath::Temp(f.into_temp_path()),
+            Self::Dump(_, path) => DataPath::Dump(path),
+        }
+    }
+}
+impl Write for DataFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Temp(f) => f.write(buf),
+            Self::Dump(f, _) => f.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Temp(f) => f.flush(),
+            Self::Dump(f, _) => f.flush(),
+        }
+    }
+}
+
+// Path of a data file (temporary files are deleted when this is dropped).
+enum DataPath {
+    Temp(TempPath),
+    Dump(PathBuf),
+}
+impl fmt::Debug for DataPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Temp(path) => fmt::Debug::fmt(&**path, f),
+            Self::Dump(path) => fmt::Debug::fmt(path, f),
         }
     }
 }
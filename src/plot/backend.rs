@@ -0,0 +1,272 @@
+//! Backends that render charts to images.
+#![allow(clippy::format_push_string)]
+use super::{GnuplotDumpOpt, ImageFormat};
+use kurobako_core::Result;
+use std::io::Write as _;
+use std::path::Path;
+
+/// Chart to be rendered by a `PlotBackend`.
+#[derive(Debug, Clone, Default)]
+pub struct Chart {
+    /// Title of the chart.
+    pub title: String,
+
+    /// Label of X axis.
+    pub xlabel: String,
+
+    /// Label of Y axis.
+    pub ylabel: String,
+
+    /// Label of the secondary Y axis (only used if some series are drawn on the axis).
+    pub y2label: Option<String>,
+
+    /// Range of X axis (`None` means that the bound is determined from the data).
+    pub xrange: (Option<f64>, Option<f64>),
+
+    /// Range of Y axis (`None` means that the bound is determined from the data).
+    pub yrange: (Option<f64>, Option<f64>),
+
+    /// Makes Y axis log scale.
+    pub ylogscale: bool,
+
+    /// Image width in pixels.
+    pub width: usize,
+
+    /// Image height in pixels.
+    pub height: usize,
+
+    /// Format of the image.
+    pub format: ImageFormat,
+
+    /// Series to be drawn.
+    pub series: Vec<Series>,
+}
+
+/// Series of a chart.
+#[derive(Debug, Clone)]
+pub struct Series {
+    /// Title shown in the legend (`None` means that the series isn't shown in the legend).
+    pub title: Option<String>,
+
+    /// Points of the series (`NaN` values are regarded as missing).
+    pub points: Vec<(f64, f64)>,
+
+    /// If this is `Some`, a band of `y +- error` is drawn around the points.
+    pub errors: Option<Vec<f64>>,
+
+    /// How the points are drawn.
+    pub style: SeriesStyle,
+
+    /// Color of the series.
+    pub color: Color,
+
+    /// If `true`, the series is drawn on the secondary Y axis.
+    pub y2: bool,
+}
+
+/// Drawing style of a series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum SeriesStyle {
+    Line,
+    DashedLine,
+    DottedLine,
+    Points,
+}
+
+/// Color of a series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// The `n`-th color (starting from `1`) of the default palette.
+    Palette(usize),
+
+    /// Black.
+    Black,
+
+    /// Explicit RGB color.
+    Rgb([u8; 3]),
+}
+impl Color {
+    // Returns the gnuplot option that sets this color.
+    pub(crate) fn gnuplot(self) -> String {
+        match self {
+            Self::Palette(n) => format!(" lc {}", n),
+            Self::Black => " lc rgb \"black\"".to_owned(),
+            Self::Rgb([r, g, b]) => format!(" lc rgb \"#{:02x}{:02x}{:02x}\"", r, g, b),
+        }
+    }
+}
+
+/// Backend that renders charts.
+pub trait PlotBackend {
+    /// Renders the given chart to the image file at `output`.
+    fn render(&self, chart: &Chart, output: &Path) -> Result<()>;
+}
+
+/// Backend that renders charts by executing `gnuplot`.
+#[derive(Debug, Clone, Default)]
+pub struct GnuplotBackend {
+    /// Options to dump the files given to gnuplot.
+    pub dump: GnuplotDumpOpt,
+}
+impl PlotBackend for GnuplotBackend {
+    fn render(&self, chart: &Chart, output: &Path) -> Result<()> {
+        let mut temp_file = track!(self.dump.data_file(output))?;
+        for series in &chart.series {
+            for (i, &(x, y)) in series.points.iter().enumerate() {
+                track_write!(temp_file, "{} {}", x, y)?;
+                if let Some(e) = series.errors.as_ref().and_then(|e| e.get(i)) {
+                    track_write!(temp_file, " {} {}", y - e, y + e)?;
+                }
+                track_writeln!(temp_file)?;
+            }
+            track_writeln!(temp_file)?;
+            track_writeln!(temp_file)?;
+        }
+        let data_path = temp_file.into_path();
+
+        let mut s = format!(
+            "set title {:?}; set ylabel {:?}; set xlabel {:?}; set grid;",
+            chart.title, chart.ylabel, chart.xlabel
+        );
+        s += "set datafile missing \"NaN\";";
+        if chart.series.iter().any(|s| s.y2) {
+            s += &format!(
+                "set y2label {:?}; set ytics nomirror; set y2tics;",
+                chart.y2label.as_deref().unwrap_or_default()
+            );
+        }
+        if chart.ylogscale {
+            s += "set logscale y;"
+        }
+        s += &chart.format.gnuplot_terminal(chart.width, chart.height);
+        s += &format!("set output {:?};", output);
+        if chart.series.iter().any(|s| s.errors.is_some()) {
+            s += "set style fill transparent solid 0.2;";
+            s += "set style fill noborder;";
+        }
+
+        let bound = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        s += &format!(
+            "plot [{}:{}] [{}:{}]",
+            bound(chart.xrange.0),
+            bound(chart.xrange.1),
+            bound(chart.yrange.0),
+            bound(chart.yrange.1)
+        );
+        for (i, series) in chart.series.iter().enumerate() {
+            if i == 0 {
+                s += &format!(" {:?}", data_path);
+            } else {
+                s += ", \"\"";
+            }
+            s += &format!(" index {} u 1:2", i);
+            if series.y2 {
+                s += " axes x1y2";
+            }
+            s += match series.style {
+                SeriesStyle::Line => " w l",
+                SeriesStyle::DashedLine => " w l dt 2",
+                SeriesStyle::DottedLine => " w l dt 3",
+                SeriesStyle::Points => " pt 7",
+            };
+            if let Some(title) = &series.title {
+                s += &format!(" t {:?}", title);
+            } else {
+                s += " notitle";
+            }
+            s += &series.color.gnuplot();
+
+            if series.errors.is_some() {
+                s += &format!(
+                    ", \"\" index {} u 1:3:4 with filledcurves notitle{}",
+                    i,
+                    series.color.gnuplot()
+                );
+            }
+        }
+
+        track!(self.dump.execute_gnuplot(output, &s))?;
+        std::mem::drop(data_path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::Error;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn gnuplot_backend_dumps_files() -> TopLevelResult {
+        let chart = Chart {
+            title: "foo".to_owned(),
+            width: 320,
+            height: 240,
+            series: vec![Series {
+                title: Some("bar".to_owned()),
+                points: vec![(0.0, 1.0), (1.0, 0.5)],
+                errors: Some(vec![0.1, 0.2]),
+                style: SeriesStyle::Line,
+                color: Color::Palette(1),
+                y2: false,
+            }],
+            ..Chart::default()
+        };
+
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let dump = GnuplotDumpOpt {
+            dump_dir: Some(dir.path().join("dump")),
+        };
+
+        // The files are written before invoking gnuplot (which may not be installed).
+        let _ = GnuplotBackend { dump }.render(&chart, &dir.path().join("foo.png"));
+
+        let data_path = dir.path().join("dump/foo.dat");
+        let data = std::fs::read_to_string(&data_path).map_err(Error::from)?;
+        assert_eq!(data, "0 1 0.9 1.1\n1 0.5 0.3 0.7\n\n\n");
+
+        let script =
+            std::fs::read_to_string(dir.path().join("dump/foo.gp")).map_err(Error::from)?;
+        assert!(script.contains(&format!("{:?}", data_path)));
+        assert!(script.contains("with filledcurves"));
+        Ok(())
+    }
+
+    #[test]
+    fn gnuplot_svg_script_works() -> TopLevelResult {
+        let series = |y2| Series {
+            title: None,
+            points: vec![(0.0, 1.0), (1.0, 0.5)],
+            errors: None,
+            style: SeriesStyle::DashedLine,
+            color: Color::Black,
+            y2,
+        };
+        let chart = Chart {
+            title: "foo".to_owned(),
+            y2label: Some("baz".to_owned()),
+            width: 320,
+            height: 240,
+            format: ImageFormat::Svg,
+            series: vec![series(false), series(true)],
+            ..Chart::default()
+        };
+
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let dump = GnuplotDumpOpt {
+            dump_dir: Some(dir.path().join("dump")),
+        };
+        let output = dir.path().join(format!("foo.{}", chart.format.extension()));
+        let _ = GnuplotBackend { dump }.render(&chart, &output);
+
+        let script =
+            std::fs::read_to_string(dir.path().join("dump/foo.gp")).map_err(Error::from)?;
+        assert!(script.contains("set terminal svg size 320,240 noenhanced;"));
+        assert!(script.contains(&format!("set output {:?};", output)));
+        assert!(script.contains("set y2label \"baz\";"));
+        assert!(script.contains(" index 1 u 1:2 axes x1y2 w l dt 2 notitle"));
+        Ok(())
+    }
+}
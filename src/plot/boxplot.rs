@@ -0,0 +1,216 @@
+//! `kurobako plot box-plot` command.
+//!
+//! For each single-objective problem, this draws the distributions of the final best values
+//! of the solvers across repeats as box-and-whisker charts (one box per solver).
+//! Multi-objective problems are skipped.
+#![allow(clippy::format_push_string)]
+use super::palette::{Palette, PaletteOpt};
+use super::{normalize_filename, DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
+use crate::record::{ProblemRecord, StudyRecord};
+use indicatif::{ProgressBar, ProgressStyle};
+use kurobako_core::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Options of the `kurobako plot box-plot` command.
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PlotBoxplotOpt {
+    /// Output directory where generated images are stored.
+    #[structopt(long, short = "o", default_value = "images/boxplot/")]
+    pub output_dir: PathBuf,
+
+    /// Image width in pixels.
+    #[structopt(long, default_value = "800")]
+    pub width: usize,
+
+    /// Image height in pixels.
+    #[structopt(long, default_value = "600")]
+    pub height: usize,
+
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
+    /// Minimum value of Y axis.
+    #[structopt(long)]
+    pub ymin: Option<f64>,
+
+    /// Maximum value of Y axis.
+    #[structopt(long)]
+    pub ymax: Option<f64>,
+
+    /// Makes Y axis log scale.
+    #[structopt(long)]
+    pub ylogscale: bool,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Colors of solvers.
+    #[structopt(flatten)]
+    pub palette: PaletteOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
+}
+impl PlotBoxplotOpt {
+    pub(crate) fn plot(&self, studies: &[StudyRecord]) -> Result<()> {
+        let studies = self.filter.filter(studies);
+        let studies = &*studies;
+        let mut problems = BTreeMap::<_, Vec<_>>::new();
+        for study in studies {
+            if study.problem.spec.values_domain.variables().len() != 1 {
+                continue;
+            }
+            problems
+                .entry(track!(study.problem.id())?)
+                .or_default()
+                .push(study);
+        }
+
+        let pb = ProgressBar::new(problems.len() as u64);
+        let template =
+            "(PLOT) [{elapsed_precise}] [{pos}/{len} {percent:>3}%] [ETA {eta:>3}] {msg}";
+        pb.set_style(ProgressStyle::default_bar().template(template));
+
+        track!(fs::create_dir_all(&self.output_dir).map_err(Error::from); self.output_dir)?;
+
+        let palette = track!(self.palette.load())?;
+        for (problem_id, studies) in problems {
+            let problem = track!(Problem::new(problem_id, studies))?;
+            track!(problem.plot(self, &palette))?;
+            pb.inc(1);
+        }
+        pb.finish_with_message(&format!("done (dir={:?})", self.output_dir));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Problem<'a> {
+    problem_id: String,
+    problem: &'a ProblemRecord,
+
+    // (Solver name, study ID) => final best values of the studies.
+    solvers: BTreeMap<(&'a str, String), Vec<f64>>,
+}
+impl<'a> Problem<'a> {
+    fn new(problem_id: String, studies: Vec<&'a StudyRecord>) -> Result<Self> {
+        let problem = &studies[0].problem;
+        let mut solvers = BTreeMap::<_, Vec<_>>::new();
+        for study in studies {
+            let study_id = track!(study.id())?;
+            let values = solvers
+                .entry((study.solver.spec.name.as_str(), study_id))
+                .or_default();
+            values.extend(study.best_value());
+        }
+
+        // Solvers that have no complete trials aren't drawn.
+        solvers.retain(|_, values| !values.is_empty());
+        Ok(Self {
+            problem_id,
+            problem,
+            solvers,
+        })
+    }
+
+    fn plot(&self, opt: &PlotBoxplotOpt, palette: &Palette) -> Result<()> {
+        if self.solvers.is_empty() {
+            return Ok(());
+        }
+
+        let output = opt.output_dir.join(format!(
+            "{}-{}.{}",
+            normalize_filename(&self.problem.spec.name),
+            self.problem_id,
+            opt.format.extension(),
+        ));
+        let data_path = track!(self.generate_data(track!(opt.dump.data_file(&output))?))?;
+        let script = self.make_gnuplot_script(&data_path, &output, opt, palette);
+        track!(opt.dump.execute_gnuplot(&output, &script))?;
+        std::mem::drop(data_path);
+
+        Ok(())
+    }
+
+    fn make_gnuplot_script(
+        &self,
+        data_path: &DataPath,
+        output: &Path,
+        opt: &PlotBoxplotOpt,
+        palette: &Palette,
+    ) -> String {
+        let palette = palette.resolve(self.solvers.keys().map(|(name, _)| name));
+        let xtics = self
+            .solvers
+            .keys()
+            .enumerate()
+            .map(|(i, (name, _))| format!("{:?} {}", name, i + 1))
+            .collect::<Vec<_>>();
+        let mut s = format!(
+            "set title {:?}; \
+             set ylabel {:?}; \
+             set grid ytics; \
+             unset key; \
+             set style data boxplot; \
+             set style fill solid 0.25 border -1; \
+             set style boxplot outliers pointtype 7; \
+             set xtics ({}) rotate by -30;",
+            format!("Final Best Values: {}", self.problem.spec.name),
+            self.problem.spec.values_domain.variables()[0].name(),
+            xtics.join(", ")
+        );
+        if opt.ylogscale {
+            s += "set logscale y;";
+        }
+
+        s += &opt.format.gnuplot_terminal(opt.width, opt.height);
+        s += &format!("set output {:?};", output);
+
+        s += &format!(
+            "plot [0:{}] [{}:{}]",
+            self.solvers.len() + 1,
+            opt.ymin.map(|v| v.to_string()).unwrap_or_default(),
+            opt.ymax.map(|v| v.to_string()).unwrap_or_default(),
+        );
+        for (i, (name, _)) in self.solvers.keys().enumerate() {
+            if i == 0 {
+                s += &format!(" {:?}", data_path);
+            } else {
+                s += ", \"\"";
+            }
+            s += &format!(
+                " index {} u ({}):1{}",
+                i,
+                i + 1,
+                palette.color(name).gnuplot()
+            );
+        }
+        s
+    }
+
+    fn generate_data(&self, mut temp_file: DataFile) -> Result<DataPath> {
+        for values in self.solvers.values() {
+            for v in values {
+                track_writeln!(temp_file, "{}", v)?;
+            }
+            track_writeln!(temp_file)?;
+            track_writeln!(temp_file)?;
+        }
+
+        Ok(temp_file.into_path())
+    }
+}
@@ -1,33 +1,71 @@
 //! `kurobako plot curve` command.
-#![allow(clippy::format_push_string)]
-use super::{execute_gnuplot, normalize_filename};
+use super::backend::{Chart, Color, GnuplotBackend, PlotBackend as _, Series, SeriesStyle};
+use super::palette::{Palette, PaletteOpt};
+use super::{normalize_filename, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
 use crate::record::{ProblemRecord, StudyRecord};
 use indicatif::{ProgressBar, ProgressStyle};
-use kurobako_core::num::OrderedFloat;
+use kurobako_core::domain::Distribution;
+use kurobako_core::num::{OrderedFloat, SummaryStats};
 use kurobako_core::{Error, ErrorKind, Result};
-use rustats::fundamental::{average, stddev};
 use std::collections::BTreeMap;
 use std::fs;
-use std::io::Write as _;
 use std::path::PathBuf;
 use std::str::FromStr;
 use structopt::StructOpt;
-use tempfile::{NamedTempFile, TempPath};
 
 /// Metric of the Y-axis.
-#[derive(Debug, StructOpt, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, StructOpt, PartialEq, Eq)]
 #[structopt(rename_all = "kebab-case")]
 #[allow(missing_docs)]
 pub enum Metric {
     BestValue,
     Hypervolume,
+    Auc,
     ElapsedTime,
     SolverElapsedTime,
 }
 impl Metric {
+    fn label(self, problem: &ProblemRecord) -> &str {
+        match self {
+            Metric::BestValue => problem.spec.values_domain.variables()[0].name(),
+            Metric::Hypervolume => "Hypervolume",
+            Metric::Auc => "AUC of Best Values",
+            Metric::ElapsedTime => "Cumulative Elapsed Seconds (Ask + Evaluate + Tell)",
+            Metric::SolverElapsedTime => "Cumulative Elapsed Seconds (Ask + Tell)",
+        }
+    }
+
+    fn is_applicable(self, problem: &ProblemRecord) -> bool {
+        let objectives = problem.spec.values_domain.variables().len();
+        match self {
+            // The best value (and its AUC) isn't defined for multi-objective problems.
+            Metric::BestValue | Metric::Auc => objectives == 1,
+            // The hypervolume of a single-objective problem is just a shifted best value.
+            Metric::Hypervolume => objectives > 1,
+            Metric::ElapsedTime | Metric::SolverElapsedTime => true,
+        }
+    }
+
+    fn values(
+        self,
+        study: &StudyRecord,
+        reference_point: Option<&[f64]>,
+        auc_start_step: u64,
+    ) -> Result<BTreeMap<u64, f64>> {
+        match self {
+            Metric::BestValue => Ok(study.best_values()),
+            Metric::Hypervolume => track!(study.hypervolumes(reference_point)),
+            Metric::Auc => Ok(study.aucs(auc_start_step)),
+            Metric::ElapsedTime => Ok(study.elapsed_times(true)),
+            Metric::SolverElapsedTime => Ok(study.elapsed_times(false)),
+        }
+    }
+
     const POSSIBLE_VALUES: &'static [&'static str] = &[
         "best-value",
         "hypervolume",
+        "auc",
         "elapsed-time",
         "solver-elapsed-time",
     ];
@@ -39,6 +77,7 @@ impl FromStr for Metric {
         match s {
             "best-value" => Ok(Metric::BestValue),
             "hypervolume" => Ok(Metric::Hypervolume),
+            "auc" => Ok(Metric::Auc),
             "elapsed-time" => Ok(Metric::ElapsedTime),
             "solver-elapsed-time" => Ok(Metric::SolverElapsedTime),
             _ => track_panic!(ErrorKind::InvalidInput, "Unknown metric name: {:?}", s),
@@ -46,6 +85,98 @@ impl FromStr for Metric {
     }
 }
 
+/// Order of the solvers in the legend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum LegendOrder {
+    Name,
+    Rank,
+}
+impl LegendOrder {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["name", "rank"];
+}
+impl FromStr for LegendOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "name" => Ok(LegendOrder::Name),
+            "rank" => Ok(LegendOrder::Rank),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown legend order: {:?}", s),
+        }
+    }
+}
+
+/// Unit of the X axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum XAxis {
+    Steps,
+    Trials,
+}
+impl XAxis {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["steps", "trials"];
+}
+impl FromStr for XAxis {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "steps" => Ok(XAxis::Steps),
+            "trials" => Ok(XAxis::Trials),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown X axis: {:?}", s),
+        }
+    }
+}
+
+/// Theoretical curve drawn as a reference line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Theoretical {
+    Random,
+}
+impl Theoretical {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["random"];
+}
+impl FromStr for Theoretical {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "random" => Ok(Theoretical::Random),
+            _ => track_panic!(
+                ErrorKind::InvalidInput,
+                "Unknown theoretical curve: {:?}",
+                s
+            ),
+        }
+    }
+}
+
+/// Band drawn around an optimization curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Band {
+    Sd,
+    Sem,
+    None,
+}
+impl Band {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["sd", "sem", "none"];
+}
+impl FromStr for Band {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sd" => Ok(Band::Sd),
+            "sem" => Ok(Band::Sem),
+            "none" => Ok(Band::None),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown band: {:?}", s),
+        }
+    }
+}
+
 /// Options of `kurobako plot curve` command.
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -62,6 +193,14 @@ pub struct PlotCurveOpt {
     #[structopt(long, default_value = "600")]
     pub height: usize,
 
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
     /// Minimum value of Y axis.
     #[structopt(long)]
     pub ymin: Option<f64>,
@@ -82,20 +221,104 @@ pub struct PlotCurveOpt {
     #[structopt(long)]
     pub ylogscale: bool,
 
-    /// Displays errorbar showing standard deviation of optimization curve.
-    #[structopt(long)]
+    /// Band drawn around each optimization curve.
+    ///
+    /// `sd` is the standard deviation of the studies and `sem` is the standard error of the mean
+    /// (i.e., `sd / sqrt(n)`), which shrinks as the number of the studies `n` increases.
+    #[structopt(long, possible_values = Band::POSSIBLE_VALUES)]
+    pub band: Option<Band>,
+
+    /// Deprecated alias of `--band sd`.
+    #[structopt(long, conflicts_with = "band")]
     pub errorbar: bool,
 
-    /// Metric of X axis.
+    /// Metric of Y axis.
+    ///
+    /// Problems for which the metric isn't defined are skipped
+    /// (i.e., `best-value` and `auc` are only for single-objective problems and `hypervolume` is only for multi-objective ones).
+    ///
+    /// `auc` is the running area under the best-value curve (see `kurobako report` for the details).
     #[structopt(
         long,
         default_value = "best-value",
         possible_values = Metric::POSSIBLE_VALUES
     )]
     pub metric: Metric,
+
+    /// Reference point used to compute hypervolumes (e.g., `--reference-point 1.1,1.1`).
+    ///
+    /// This overrides the reference point defined by each problem (and the default one).
+    /// Its arity must match the number of the objectives of every plotted problem.
+    #[structopt(long, use_delimiter = true, allow_hyphen_values = true)]
+    pub reference_point: Option<Vec<f64>>,
+
+    /// Metric of the secondary (right) Y axis.
+    ///
+    /// For example, `--y2-metric elapsed-time` shows the quality/cost tradeoff of solvers
+    /// by drawing the cumulative elapsed time as points in addition to the primary metric.
+    #[structopt(
+        long,
+        possible_values = Metric::POSSIBLE_VALUES
+    )]
+    pub y2_metric: Option<Metric>,
+
+    /// Order of the solvers in the legend (and the assignment of line colors).
+    ///
+    /// If `rank` is specified, the solvers are sorted by the final values of the metric
+    /// (the best one comes first), so the best solver always gets the first color.
+    #[structopt(
+        long,
+        default_value = "name",
+        possible_values = LegendOrder::POSSIBLE_VALUES
+    )]
+    pub legend_order: LegendOrder,
+
+    /// Unit of X axis.
+    ///
+    /// If `steps` is specified, the budget (i.e., the consumed steps divided by the maximum step of the problem) is used.
+    /// If `trials` is specified, the number of finished trials is used.
+    /// The latter is more intuitive when comparing solvers that consume different steps per trial.
+    #[structopt(
+        long,
+        default_value = "steps",
+        possible_values = XAxis::POSSIBLE_VALUES
+    )]
+    pub x_axis: XAxis,
+
+    /// Draws a theoretical curve as a dotted reference line.
+    ///
+    /// If `random` is specified, the expected best value of `n` trials of uniform random sampling
+    /// (i.e., `low + (high - low) / (n + 1)`) is drawn, assuming that the objective values are
+    /// uniformly distributed within the range of the values domain of the problem.
+    /// This is only applicable to the `best-value` metric and
+    /// the problems whose objective has a finite range.
+    #[structopt(long, possible_values = Theoretical::POSSIBLE_VALUES)]
+    pub theoretical: Option<Theoretical>,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Colors and dash styles of solvers.
+    #[structopt(flatten)]
+    pub palette: PaletteOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
 }
 impl PlotCurveOpt {
+    fn band(&self) -> Band {
+        match self.band {
+            Some(band) => band,
+            None if self.errorbar => Band::Sd,
+            None => Band::None,
+        }
+    }
+
     pub(crate) fn plot(&self, studies: &[StudyRecord]) -> Result<()> {
+        let studies = self.filter.filter(studies);
+        let studies = &*studies;
         let mut problems = BTreeMap::<_, Vec<_>>::new();
         for study in studies {
             problems
@@ -111,8 +334,9 @@ impl PlotCurveOpt {
 
         track!(fs::create_dir_all(&self.output_dir).map_err(Error::from); self.output_dir)?;
 
+        let palette = track!(self.palette.load())?;
         for (problem_id, studies) in problems {
-            let problem = track!(Problem::new(problem_id, studies, self))?;
+            let problem = track!(Problem::new(problem_id, studies, self, &palette))?;
             track!(problem.plot())?;
             pb.inc(1);
         }
@@ -126,16 +350,25 @@ impl PlotCurveOpt {
 struct Problem<'a> {
     problem_id: String,
     problem: &'a ProblemRecord,
-    solvers: BTreeMap<(&'a str, String), Solver>,
+    solvers: Vec<((&'a str, String), Solver)>,
     opt: &'a PlotCurveOpt,
+    palette: Palette,
 }
 impl<'a> Problem<'a> {
     fn new(
         problem_id: String,
         studies: Vec<&'a StudyRecord>,
         opt: &'a PlotCurveOpt,
+        palette: &'a Palette,
     ) -> Result<Self> {
         let problem = &studies[0].problem;
+
+        // The same start step as the AUCs in `kurobako report` (i.e., after the first complete trial).
+        let auc_start_step = studies
+            .iter()
+            .filter_map(|s| s.first_complete_trial()?.start_step())
+            .fold(problem.spec.steps.last(), u64::max);
+
         let mut solvers = BTreeMap::<_, Vec<_>>::new();
         for study in studies {
             let study_id = track!(study.id())?;
@@ -144,217 +377,452 @@ impl<'a> Problem<'a> {
                 .or_default()
                 .push(study);
         }
+        let mut solvers = solvers
+            .into_iter()
+            .map(|(k, v)| track!(Solver::new(v, opt, auc_start_step)).map(|s| (k, s)))
+            .collect::<Result<Vec<_>>>()?;
+        if opt.legend_order == LegendOrder::Rank {
+            let maximize = opt.metric == Metric::Hypervolume;
+            // Solvers that have no values come last.
+            solvers.sort_by_key(|(_, s)| match s.final_y() {
+                Some(v) if maximize => (0, OrderedFloat(-v.avg)),
+                Some(v) => (0, OrderedFloat(v.avg)),
+                None => (1, OrderedFloat(0.0)),
+            });
+        }
+        let palette = palette.resolve(solvers.iter().map(|((name, _), _)| *name));
         Ok(Self {
             problem_id,
             problem,
-            solvers: solvers
-                .into_iter()
-                .map(|(k, v)| (k, Solver::new(v, opt)))
-                .collect(),
+            solvers,
             opt,
+            palette,
         })
     }
 
+    fn solver_values(&self) -> impl '_ + Iterator<Item = &Solver> {
+        self.solvers.iter().map(|(_, s)| s)
+    }
+
     fn plot(&self) -> Result<bool> {
-        if self.opt.metric == Metric::BestValue
-            && self.problem.spec.values_domain.variables().len() != 1
-        {
-            // This plot doesn't support multi-objective problems.
+        let mut metrics = std::iter::once(self.opt.metric).chain(self.opt.y2_metric);
+        if !metrics.all(|m| m.is_applicable(self.problem)) {
             return Ok(false);
         }
 
-        let data_path = track!(self.generate_data())?;
-        let script = self.make_gnuplot_script(&data_path);
-        track!(execute_gnuplot(&script))?;
-        std::mem::drop(data_path);
+        let output = self.opt.output_dir.join(format!(
+            "{}-{}.{}",
+            normalize_filename(&self.problem.spec.name),
+            self.problem_id,
+            self.opt.format.extension(),
+        ));
+        let backend = GnuplotBackend {
+            dump: self.opt.dump.clone(),
+        };
+        track!(backend.render(&self.chart(), &output))?;
 
         Ok(true)
     }
 
-    fn make_gnuplot_script(&self, data_path: &TempPath) -> String {
-        let ylabel = match self.opt.metric {
-            Metric::BestValue => self.problem.spec.values_domain.variables()[0].name(),
-            Metric::Hypervolume => "Hypervolume",
-            Metric::ElapsedTime => "Cumulative Elapsed Seconds (Ask + Evaluate + Tell)",
-            Metric::SolverElapsedTime => "Cumulative Elapsed Seconds (Ask + Tell)",
+    fn chart(&self) -> Chart {
+        let xlabel = match self.opt.x_axis {
+            XAxis::Steps => "Budget",
+            XAxis::Trials => "Trials",
         };
-
-        let mut s = format!(
-            "set title {:?}; set ylabel {:?}; set xlabel \"Budget\"; set grid;",
-            self.problem.spec.name, ylabel
-        );
-        s += "set datafile missing \"NaN\";";
-
-        if self.opt.ylogscale {
-            s += "set logscale y;"
+        let problem_steps = match self.opt.x_axis {
+            XAxis::Steps => self.problem.spec.steps.last(),
+            XAxis::Trials => 1,
+        } as f64;
+        let max_step = self
+            .solver_values()
+            .map(|s| s.ys.len())
+            .max()
+            .unwrap_or_else(|| unreachable!());
+        let x = |step: usize| step as f64 / problem_steps;
+
+        let mut series = Vec::new();
+        for ((name, _), solver) in &self.solvers {
+            let color = self.palette.color(name);
+            let ys = (0..max_step).map(|step| solver.y(step));
+            series.push(Series {
+                title: Some((*name).to_owned()),
+                points: ys
+                    .clone()
+                    .enumerate()
+                    .map(|(step, v)| (x(step), v.map_or(f64::NAN, |v| v.avg)))
+                    .collect(),
+                errors: match self.opt.band() {
+                    Band::None => None,
+                    band => Some(ys.map(|v| v.map_or(f64::NAN, |v| v.width(band))).collect()),
+                },
+                style: self.palette.line_style(name),
+                color,
+                y2: false,
+            });
+            if let Some(y2_metric) = self.opt.y2_metric {
+                series.push(Series {
+                    title: Some(format!("{} ({})", name, y2_metric.label(self.problem))),
+                    points: (0..max_step)
+                        .map(|step| (x(step), solver.y2(step).map_or(f64::NAN, |v| v.avg)))
+                        .collect(),
+                    errors: None,
+                    // Points are used so that the series isn't confused with the dash styles of the palette.
+                    style: SeriesStyle::Points,
+                    color,
+                    y2: true,
+                });
+            }
         }
-
-        let output = self.opt.output_dir.join(format!(
-            "{}-{}.png",
-            normalize_filename(&self.problem.spec.name),
-            self.problem_id
-        ));
-        s += &format!(
-            "set terminal pngcairo size {},{} noenhanced; set output {:?};",
-            self.opt.width, self.opt.height, output
-        );
-
-        if self.opt.errorbar {
-            s += "set style fill transparent solid 0.2;";
-            s += "set style fill noborder;";
+        series.extend(self.theoretical_curve(max_step, x));
+
+        Chart {
+            title: self.problem.spec.name.clone(),
+            xlabel: xlabel.to_owned(),
+            ylabel: self.opt.metric.label(self.problem).to_owned(),
+            y2label: self.opt.y2_metric.map(|m| m.label(self.problem).to_owned()),
+            xrange: (self.opt.xmin, self.opt.xmax),
+            yrange: (self.ymin(), self.ymax()),
+            ylogscale: self.opt.ylogscale,
+            width: self.opt.width,
+            height: self.opt.height,
+            format: self.opt.format,
+            series,
         }
+    }
 
-        s += &format!(
-            "plot [{}:{}] [{}:{}]",
-            self.xmin(),
-            self.xmax(),
-            self.ymin(),
-            self.ymax()
-        );
+    fn theoretical_curve<F>(&self, max_step: usize, x: F) -> Option<Series>
+    where
+        F: Fn(usize) -> f64,
+    {
+        match self.opt.theoretical? {
+            Theoretical::Random => {
+                if self.opt.metric != Metric::BestValue {
+                    return None;
+                }
+                let var = &self.problem.spec.values_domain.variables()[0];
+                let low = var.effective_low().or_else(|| self.smallest_positive_y())?;
+                let high = var.effective_high();
+                if !(low.is_finite() && high.is_finite()) {
+                    return None;
+                }
 
-        let problem_steps = self.problem.spec.steps.last();
-        for i in 0..self.solvers.len() {
-            if i == 0 {
-                s += &format!(" {:?}", data_path);
-            } else {
-                s += ", \"\"";
-            }
-            s += &format!(
-                " u ($0/{}):{} w l t columnhead lc {}",
-                problem_steps,
-                (i * 2) + 1,
-                i + 1
-            );
-            if self.opt.errorbar {
-                s += &format!(
-                    ", \"\" u ($0/{}):(${}-${}):(${}+${}) with filledcurves notitle lc {}",
-                    problem_steps,
-                    (i * 2) + 1,
-                    (i * 2) + 1 + 1,
-                    (i * 2) + 1,
-                    (i * 2) + 1 + 1,
-                    i + 1
-                );
+                // `x` is the budget (or the number of trials), so `floor(x)` trials have been finished.
+                let points = (0..max_step)
+                    .map(x)
+                    .map(|x| (x, low + (high - low) / (x.floor() + 1.0)))
+                    .collect();
+                Some(Series {
+                    title: Some("Random Search (Expected)".to_owned()),
+                    points,
+                    errors: None,
+                    style: SeriesStyle::DottedLine,
+                    color: Color::Black,
+                    y2: false,
+                })
             }
         }
-
-        s
     }
 
-    fn ymax(&self) -> String {
+    fn ymax(&self) -> Option<f64> {
         if let Some(y) = self.opt.ymax {
-            y.to_string()
+            Some(y)
         } else if self.opt.metric == Metric::BestValue {
             let max_step = self
-                .solvers
-                .values()
+                .solver_values()
                 .map(|s| s.ys.len())
                 .max()
                 .unwrap_or_else(|| unreachable!());
             let step = max_step / 10;
-            if let Some(y) = self
-                .solvers
-                .values()
+            self.solver_values()
                 .filter_map(|s| s.y(step).map(|v| OrderedFloat(v.avg)))
                 .max()
-            {
-                y.0.to_string()
-            } else {
-                "".to_string()
-            }
+                .map(|y| y.0)
         } else {
-            "".to_string()
+            None
         }
     }
 
-    fn ymin(&self) -> String {
+    fn ymin(&self) -> Option<f64> {
+        let var = &self.problem.spec.values_domain.variables()[0];
         if let Some(y) = self.opt.ymin {
-            y.to_string()
+            Some(y)
+        } else if self.opt.ylogscale
+            && self.opt.metric == Metric::BestValue
+            && var.distribution() == Distribution::LogUniform
+        {
+            // If the variable has no positive lower bound, the smallest positive observed value is used
+            // so that the curves aren't flattened by an extremely small floor.
+            var.effective_low().or_else(|| self.smallest_positive_y())
         } else {
-            "".to_string()
+            None
         }
     }
 
-    fn xmin(&self) -> String {
-        self.opt.xmin.map(|v| v.to_string()).unwrap_or_default()
-    }
-
-    fn xmax(&self) -> String {
-        self.opt.xmax.map(|v| v.to_string()).unwrap_or_default()
-    }
-
-    fn generate_data(&self) -> Result<TempPath> {
-        let mut temp_file = track!(NamedTempFile::new().map_err(Error::from))?;
-
-        for (name, _) in self.solvers.keys() {
-            track_write!(temp_file, "{:?} {:?} ", name, name)?;
-        }
-        track_writeln!(temp_file)?;
-
-        let max_step = self
-            .solvers
-            .values()
-            .map(|s| s.ys.len())
-            .max()
-            .unwrap_or_else(|| unreachable!());
-        for step in 0..max_step {
-            for s in self.solvers.values() {
-                if let Some(v) = s.y(step) {
-                    track_write!(temp_file, "{} {} ", v.avg, v.sd)?;
-                } else {
-                    track_write!(temp_file, "NaN NaN ")?;
-                }
-            }
-            track_writeln!(temp_file)?;
-        }
-
-        Ok(temp_file.into_temp_path())
+    fn smallest_positive_y(&self) -> Option<f64> {
+        self.solver_values()
+            .flat_map(|s| s.ys.iter().flatten())
+            .map(|v| v.avg)
+            .filter(|&y| y > 0.0)
+            .min_by_key(|&y| OrderedFloat(y))
     }
 }
 
 #[derive(Debug)]
 struct Solver {
     ys: Vec<Option<Value>>,
+    y2s: Vec<Option<Value>>,
 }
 impl Solver {
-    fn new(studies: Vec<&StudyRecord>, opt: &PlotCurveOpt) -> Self {
-        let study_metrics = studies
+    fn new(studies: Vec<&StudyRecord>, opt: &PlotCurveOpt, auc_start_step: u64) -> Result<Self> {
+        let ys = track!(Self::metric_values(
+            &studies,
+            opt.metric,
+            opt,
+            auc_start_step
+        ))?;
+        let y2s = if let Some(m) = opt.y2_metric {
+            track!(Self::metric_values(&studies, m, opt, auc_start_step))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { ys, y2s })
+    }
+
+    fn metric_values(
+        studies: &[&StudyRecord],
+        metric: Metric,
+        opt: &PlotCurveOpt,
+        auc_start_step: u64,
+    ) -> Result<Vec<Option<Value>>> {
+        let reference_point = opt.reference_point.as_deref();
+        let mut study_metrics = studies
             .iter()
-            .map(|study| match opt.metric {
-                Metric::BestValue => study.best_values(),
-                Metric::Hypervolume => study.hypervolumes(),
-                Metric::ElapsedTime => study.elapsed_times(true),
-                Metric::SolverElapsedTime => study.elapsed_times(false),
-            })
-            .collect::<Vec<_>>();
+            .map(|study| track!(metric.values(study, reference_point, auc_start_step)))
+            .collect::<Result<Vec<_>>>()?;
+        let xmax = match opt.x_axis {
+            XAxis::Steps => studies[0].study_steps(),
+            XAxis::Trials => {
+                for (study, metrics) in studies.iter().zip(study_metrics.iter_mut()) {
+                    *metrics = Self::reindex_by_trials(study, metrics);
+                }
+                1 + studies
+                    .iter()
+                    .map(|s| s.trials.len() as u64)
+                    .max()
+                    .unwrap_or(0)
+            }
+        };
+
         let mut ys = vec![None];
-        for step in 1..studies[0].study_steps() {
+        for step in 1..xmax {
             let values = study_metrics
                 .iter()
                 .filter_map(|x| x.range(..=step).last().map(|v| *v.1))
                 .collect::<Vec<_>>();
-            if values.is_empty() {
-                ys.push(None);
-            } else {
-                let avg = average(values.iter().copied());
-                let sd = stddev(values.into_iter());
-                ys.push(Some(Value { avg, sd }));
-            }
+            // NaNs (e.g., given by misbehaving problems) are ignored.
+            ys.push(SummaryStats::new(&values).map(|stats| Value {
+                avg: stats.mean,
+                sd: stats.sd,
+                n: stats.count,
+            }));
         }
-        Self { ys }
+        Ok(ys)
+    }
+
+    // Converts the step keys of `metrics` to the numbers of the trials finished until the steps.
+    fn reindex_by_trials(study: &StudyRecord, metrics: &BTreeMap<u64, f64>) -> BTreeMap<u64, f64> {
+        let mut end_steps = study
+            .trials
+            .iter()
+            .filter_map(|t| t.end_step())
+            .collect::<Vec<_>>();
+        end_steps.sort_unstable();
+
+        metrics
+            .iter()
+            .map(|(&step, &v)| {
+                let trials = end_steps.partition_point(|&s| s <= step) as u64;
+                (trials, v)
+            })
+            .collect()
     }
 
     fn y(&self, step: usize) -> Option<&Value> {
         self.ys.get(step).and_then(|v| v.as_ref())
     }
+
+    fn final_y(&self) -> Option<&Value> {
+        self.ys.iter().rev().find_map(|v| v.as_ref())
+    }
+
+    fn y2(&self, step: usize) -> Option<&Value> {
+        self.y2s.get(step).and_then(|v| v.as_ref())
+    }
 }
 
 #[derive(Debug)]
 struct Value {
     avg: f64,
     sd: f64,
+
+    // Number of the studies that have the value.
+    n: usize,
+}
+impl Value {
+    // Returns the half width of the given band.
+    fn width(&self, band: Band) -> f64 {
+        match band {
+            Band::Sd => self.sd,
+            Band::Sem => self.sd / (self.n as f64).sqrt(),
+            Band::None => 0.0,
+        }
+    }
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
 struct BestValues {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing;
+    use kurobako_core::problem::ProblemSpec;
+    use trackable::result::TopLevelResult;
+
+    // Makes a record of a study whose trials have the given values (one trial per step).
+    fn record(values: &[f64]) -> Result<StudyRecord> {
+        testing::record("test", "test", 3, values)
+    }
+
+    #[test]
+    fn band_works() -> TopLevelResult {
+        let studies = [
+            record(&[1.0, 0.5])?,
+            record(&[3.0, 2.0])?,
+            record(&[2.0, 0.0])?,
+        ];
+        let studies = studies.iter().collect::<Vec<_>>();
+        let opt = PlotCurveOpt::from_iter(&["curve", "--band", "sem"]);
+        assert_eq!(opt.band(), Band::Sem);
+
+        let solver = Solver::new(studies, &opt, 1)?;
+        assert_eq!(solver.ys.len(), 3);
+        for step in 1..3 {
+            let v = solver.y(step).expect("never fails");
+            assert_eq!(v.n, 3);
+            assert!(v.width(Band::Sem) > 0.0);
+            assert!(v.width(Band::Sem) < v.width(Band::Sd));
+            assert_eq!(v.width(Band::Sem), v.sd / 3f64.sqrt());
+        }
+
+        assert_eq!(PlotCurveOpt::from_iter(&["curve"]).band(), Band::None);
+        assert_eq!(
+            PlotCurveOpt::from_iter(&["curve", "--errorbar"]).band(),
+            Band::Sd
+        );
+        assert!(PlotCurveOpt::from_iter_safe(&["curve", "--errorbar", "--band", "sem"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn auc_works() -> TopLevelResult {
+        let studies = [record(&[3.0, 1.0, 2.0])?, record(&[2.0, 2.0, 0.5])?];
+        let studies = studies.iter().collect::<Vec<_>>();
+        let opt = PlotCurveOpt::from_iter(&["curve", "--metric", "auc"]);
+        assert!(opt.metric.is_applicable(&studies[0].problem));
+
+        let solver = Solver::new(studies.clone(), &opt, 1)?;
+        let ys = solver
+            .ys
+            .iter()
+            .map(|v| v.as_ref().map(|v| v.avg))
+            .collect::<Vec<_>>();
+        assert_eq!(ys, [None, Some(0.0), Some((3.0 + 2.0) / 2.0)]);
+        assert!(ys.windows(2).all(|w| w[0] <= w[1]));
+
+        let aucs = studies[0].aucs(1);
+        assert!(aucs
+            .values()
+            .zip(aucs.values().skip(1))
+            .all(|(a, b)| a <= b));
+        assert_eq!(aucs.values().last().copied(), studies[0].auc(1));
+        Ok(())
+    }
+
+    #[test]
+    fn log_scale_floor_is_based_on_data() -> TopLevelResult {
+        // A log-uniform objective without a positive lower bound
+        // (`VariableBuilder::finish` rejects it, but deserialized specs aren't validated).
+        let mut spec = serde_json::to_value(&record(&[1.0])?.problem.spec).map_err(Error::from)?;
+        spec["values_domain"][0]["distribution"] = serde_json::json!("LOG_UNIFORM");
+        let spec: ProblemSpec = serde_json::from_value(spec).map_err(Error::from)?;
+        assert_eq!(spec.values_domain.variables()[0].effective_low(), None);
+
+        let values = [vec![4.0], vec![0.25], vec![0.5]];
+        let studies = [testing::record_with_spec(spec, "test", 3, &values)?];
+        let studies = studies.iter().collect::<Vec<_>>();
+        let opt = PlotCurveOpt::from_iter(&["curve", "--ylogscale"]);
+        let palette = Palette::default();
+        let problem = Problem::new("id".to_owned(), studies, &opt, &palette)?;
+        assert_eq!(problem.ymin(), Some(0.25));
+        Ok(())
+    }
+
+    #[test]
+    fn solver_colors_are_stable_across_problems() -> TopLevelResult {
+        let foo = [
+            testing::record("foo", "a", 3, &[1.0, 2.0, 3.0])?,
+            testing::record("foo", "shared", 3, &[1.0, 2.0, 3.0])?,
+        ];
+        let bar = [testing::record("bar", "shared", 3, &[3.0, 2.0, 1.0])?];
+
+        let opt = PlotCurveOpt::from_iter(&["curve"]);
+        let palette = Palette::default();
+        let chart = |studies: &[StudyRecord]| -> Result<Chart> {
+            let studies = studies.iter().collect();
+            let problem = Problem::new("id".to_owned(), studies, &opt, &palette)?;
+            Ok(problem.chart())
+        };
+        let color = |chart: &Chart, solver: &str| {
+            let series = chart.series.iter();
+            series
+                .filter(|s| s.title.as_deref() == Some(solver))
+                .map(|s| s.color)
+                .next()
+        };
+
+        // "shared" is the second solver of "foo" and the first solver of "bar".
+        let (foo, bar) = (chart(&foo)?, chart(&bar)?);
+        assert!(color(&foo, "shared").is_some());
+        assert_eq!(color(&foo, "shared"), color(&bar, "shared"));
+        assert_eq!(color(&foo, "shared"), Some(palette.color("shared")));
+        Ok(())
+    }
+
+    #[test]
+    fn y2_series_are_not_drawn_with_dashes() -> TopLevelResult {
+        let studies = [testing::record("foo", "a", 3, &[1.0, 2.0, 3.0])?];
+        let studies = studies.iter().collect();
+        let opt = PlotCurveOpt::from_iter(&["curve", "--y2-metric", "elapsed-time"]);
+        let palette: Palette =
+            serde_json::from_str(r#"{"a": {"dash": "dashed"}}"#).map_err(Error::from)?;
+        let problem = Problem::new("id".to_owned(), studies, &opt, &palette)?;
+
+        let chart = problem.chart();
+        let styles = chart
+            .series
+            .iter()
+            .map(|s| (s.style, s.y2))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            styles,
+            [
+                (SeriesStyle::DashedLine, false),
+                (SeriesStyle::Points, true)
+            ]
+        );
+        assert_eq!(chart.series[0].color, chart.series[1].color);
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+//! Colors and dash styles of solvers.
+//!
+//! A palette file is a JSON object that maps solver names to their styles, for example:
+//!
+//! ```json
+//! {
+//!   "RandomSearch": {"color": "#000000", "dash": "dotted"},
+//!   "Optuna": {"color": "#0072b2"}
+//! }
+//! ```
+//!
+//! The solvers that aren't in the palette get the colors derived from the hashes of their names,
+//! so a solver is drawn in the same color in every plot unless its color is already taken by
+//! other solvers of the plot (see [`Palette::resolve`]).
+use super::backend::{Color, SeriesStyle};
+use kurobako_core::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use trackable::error::ErrorKindExt;
+
+/// Options to specify the colors and dash styles of solvers.
+#[derive(Debug, Clone, Default, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PaletteOpt {
+    /// JSON file that maps solver names to their colors and dash styles
+    /// (e.g., `{"RandomSearch": {"color": "#000000", "dash": "dotted"}}`).
+    #[structopt(long)]
+    pub palette: Option<PathBuf>,
+}
+impl PaletteOpt {
+    /// Loads the palette (an empty one is returned if `--palette` isn't specified).
+    pub fn load(&self) -> Result<Palette> {
+        let Some(path) = &self.palette else {
+            return Ok(Palette::default());
+        };
+        let file = track!(File::open(path).map_err(Error::from); path)?;
+        let palette: Palette =
+            track!(serde_json::from_reader(BufReader::new(file)).map_err(Error::from); path)?;
+        for (name, style) in &palette.styles {
+            if let Some(color) = &style.color {
+                track!(parse_color(color); name)?;
+            }
+        }
+        Ok(palette)
+    }
+}
+
+/// Mapping from solver names to their styles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Palette {
+    /// Solver name => style.
+    pub styles: BTreeMap<String, SolverStyle>,
+}
+impl Palette {
+    // Colorblind-friendly colors used for the solvers that aren't in the palette.
+    const COLORS: [[u8; 3]; 7] = [
+        [0x00, 0x72, 0xb2],
+        [0xe6, 0x9f, 0x00],
+        [0x00, 0x9e, 0x73],
+        [0xcc, 0x79, 0xa7],
+        [0x56, 0xb4, 0xe9],
+        [0xd5, 0x5e, 0x00],
+        [0x94, 0x00, 0xd3],
+    ];
+
+    /// Returns the palette that has the distinct colors of the solvers drawn in a plot.
+    ///
+    /// Each solver that isn't in this palette gets the color of [`Palette::color`] if it isn't
+    /// taken yet. Otherwise, the next free color of the default colors is used and, if all of them
+    /// are taken, a color whose hue is derived from the hash of the solver name is used.
+    /// The solvers are processed in the order of their names, so the result is deterministic.
+    pub fn resolve<I, S>(&self, solvers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let solvers = solvers
+            .into_iter()
+            .map(|s| s.as_ref().to_owned())
+            .collect::<BTreeSet<_>>();
+        let mut styles = self.styles.clone();
+        let mut used = solvers
+            .iter()
+            .filter_map(|s| self.explicit_color(s))
+            .collect::<BTreeSet<_>>();
+        for solver in solvers {
+            if self.explicit_color(&solver).is_some() {
+                continue;
+            }
+
+            let hash = fnv1a(&solver);
+            let n = Self::COLORS.len();
+            let offset = (hash % n as u64) as usize;
+            let rgb = (0..n)
+                .map(|i| Self::COLORS[(offset + i) % n])
+                .chain((0..).map(|i| hue_color(hash, i)))
+                .find(|rgb| !used.contains(rgb))
+                .unwrap_or_else(|| unreachable!());
+            used.insert(rgb);
+
+            let style = styles.entry(solver).or_default();
+            style.color = Some(format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]));
+        }
+        Self { styles }
+    }
+
+    /// Returns the color of the given solver.
+    ///
+    /// Note that the default colors of different solvers can be the same.
+    /// Use [`Palette::resolve`] to get distinct colors for the solvers in a plot.
+    pub fn color(&self, solver: &str) -> Color {
+        if let Some(rgb) = self.explicit_color(solver) {
+            return Color::Rgb(rgb);
+        }
+        let hash = fnv1a(solver);
+        Color::Rgb(Self::COLORS[(hash % Self::COLORS.len() as u64) as usize])
+    }
+
+    fn explicit_color(&self, solver: &str) -> Option<[u8; 3]> {
+        let color = self.styles.get(solver).and_then(|s| s.color.as_ref());
+        color.and_then(|c| parse_color(c).ok())
+    }
+
+    /// Returns the dash style of the given solver.
+    pub fn dash(&self, solver: &str) -> Dash {
+        self.styles
+            .get(solver)
+            .and_then(|s| s.dash)
+            .unwrap_or(Dash::Solid)
+    }
+
+    /// Returns the line style of the given solver.
+    pub fn line_style(&self, solver: &str) -> SeriesStyle {
+        match self.dash(solver) {
+            Dash::Solid => SeriesStyle::Line,
+            Dash::Dashed => SeriesStyle::DashedLine,
+            Dash::Dotted => SeriesStyle::DottedLine,
+        }
+    }
+
+    // Returns the gnuplot options that set the color and dash style of the given solver.
+    pub(crate) fn gnuplot_style(&self, solver: &str) -> String {
+        let dt = match self.dash(solver) {
+            Dash::Solid => 1,
+            Dash::Dashed => 2,
+            Dash::Dotted => 3,
+        };
+        format!("{} dt {}", self.color(solver).gnuplot(), dt)
+    }
+}
+
+/// Style of a solver.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SolverStyle {
+    /// Color in the `#rrggbb` format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// Dash style.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dash: Option<Dash>,
+}
+
+/// Dash style of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(missing_docs)]
+pub enum Dash {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+// FNV-1a (the hash values of `std::hash` aren't guaranteed to be stable across builds).
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Returns the `i`-th color of the sequence whose hue starts from the one derived from `hash`
+// and then advances by the golden angle (so that the consecutive colors are far from each other).
+fn hue_color(hash: u64, i: u64) -> [u8; 3] {
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    let hue =
+        ((hash >> 11) as f64 / (1u64 << 53) as f64 + i as f64 * GOLDEN_RATIO_CONJUGATE).fract();
+    hsv_to_rgb(hue, 0.8, 0.8)
+}
+
+// Converts an HSV color (each component in `[0, 1]`) to an RGB one.
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> [u8; 3] {
+    let h = h * 6.0;
+    let f = h - h.floor();
+    let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+    let (r, g, b) = match h.floor() as usize % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    let byte = |x: f64| (x * 255.0).round() as u8;
+    [byte(r), byte(g), byte(b)]
+}
+
+fn parse_color(s: &str) -> Result<[u8; 3]> {
+    let hex = s.strip_prefix('#').filter(|h| h.len() == 6);
+    let hex = track_assert_some!(
+        hex,
+        ErrorKind::InvalidInput,
+        "Not a `#rrggbb` color: {:?}",
+        s
+    );
+    let mut rgb = [0; 3];
+    for (i, c) in rgb.iter_mut().enumerate() {
+        let byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16);
+        *c = track!(byte.map_err(|e| ErrorKind::InvalidInput.cause(e)); s)?;
+    }
+    Ok(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn palette_works() -> TopLevelResult {
+        let palette: Palette = serde_json::from_str(
+            r##"{"foo": {"color": "#ff8000", "dash": "dashed"}, "bar": {"dash": "dotted"}}"##,
+        )
+        .map_err(Error::from)?;
+        assert_eq!(palette.color("foo"), Color::Rgb([0xff, 0x80, 0x00]));
+        assert_eq!(palette.line_style("foo"), SeriesStyle::DashedLine);
+        assert_eq!(palette.gnuplot_style("foo"), " lc rgb \"#ff8000\" dt 2");
+
+        // The colors of the solvers that aren't in the palette only depend on their names.
+        assert_eq!(palette.color("bar"), Palette::default().color("bar"));
+        assert_eq!(palette.color("baz"), Palette::default().color("baz"));
+        assert_eq!(palette.dash("baz"), Dash::Solid);
+
+        assert!(parse_color("#12345").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resolved_colors_are_distinct() -> TopLevelResult {
+        let palette: Palette =
+            serde_json::from_str(r##"{"explicit": {"color": "#0072b2", "dash": "dotted"}}"##)
+                .map_err(Error::from)?;
+        let solvers = (0..20)
+            .map(|i| format!("solver{}", i))
+            .chain(std::iter::once("explicit".to_owned()))
+            .collect::<Vec<_>>();
+        assert!(solvers.iter().any(|a| {
+            let a_color = palette.color(a);
+            solvers
+                .iter()
+                .any(|b| a != b && a_color == palette.color(b))
+        }));
+
+        let resolved = palette.resolve(&solvers);
+        for (i, a) in solvers.iter().enumerate() {
+            for b in &solvers[i + 1..] {
+                assert_ne!(resolved.color(a), resolved.color(b), "{} vs {}", a, b);
+            }
+        }
+        assert_eq!(resolved.color("explicit"), palette.color("explicit"));
+        assert_eq!(resolved.dash("explicit"), Dash::Dotted);
+        assert_eq!(resolved.resolve(&solvers).styles.len(), solvers.len());
+
+        // A solver keeps its default color if no other solver takes it.
+        let resolved = palette.resolve(["solver0"]);
+        assert_eq!(resolved.color("solver0"), palette.color("solver0"));
+        assert!(parse_color("#12345g").is_err());
+        Ok(())
+    }
+}
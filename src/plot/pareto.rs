@@ -0,0 +1,251 @@
+//! `kurobako plot pareto` command.
+//!
+//! For each two-objective problem, this draws the final pareto frontiers found by the solvers
+//! in a scatter plot (one color per solver).
+//! If a solver has multiple studies (e.g., with different seeds), their frontiers are drawn together.
+//! Problems that don't have exactly two objectives are skipped.
+#![allow(clippy::format_push_string)]
+use super::palette::{Palette, PaletteOpt};
+use super::{normalize_filename, DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
+use crate::record::{ProblemRecord, StudyRecord};
+use indicatif::{ProgressBar, ProgressStyle};
+use kurobako_core::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Options of the `kurobako plot pareto` command.
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PlotParetoOpt {
+    /// Output directory where generated images are stored.
+    #[structopt(long, short = "o", default_value = "images/pareto/")]
+    pub output_dir: PathBuf,
+
+    /// Image width in pixels.
+    #[structopt(long, default_value = "800")]
+    pub width: usize,
+
+    /// Image height in pixels.
+    #[structopt(long, default_value = "600")]
+    pub height: usize,
+
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
+    /// Minimum value of Y axis.
+    #[structopt(long)]
+    pub ymin: Option<f64>,
+
+    /// Maximum value of Y axis.
+    #[structopt(long)]
+    pub ymax: Option<f64>,
+
+    /// Minimum value of X axis.
+    #[structopt(long)]
+    pub xmin: Option<f64>,
+
+    /// Maximum value of X axis.
+    #[structopt(long)]
+    pub xmax: Option<f64>,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Colors of solvers.
+    #[structopt(flatten)]
+    pub palette: PaletteOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
+}
+impl PlotParetoOpt {
+    pub(crate) fn plot(&self, studies: &[StudyRecord]) -> Result<()> {
+        let studies = self.filter.filter(studies);
+        let studies = &*studies;
+        let mut problems = BTreeMap::<_, Vec<_>>::new();
+        for study in studies {
+            if study.problem.spec.values_domain.variables().len() != 2 {
+                continue;
+            }
+            problems
+                .entry(track!(study.problem.id())?)
+                .or_default()
+                .push(study);
+        }
+
+        let pb = ProgressBar::new(problems.len() as u64);
+        let template =
+            "(PLOT) [{elapsed_precise}] [{pos}/{len} {percent:>3}%] [ETA {eta:>3}] {msg}";
+        pb.set_style(ProgressStyle::default_bar().template(template));
+
+        track!(fs::create_dir_all(&self.output_dir).map_err(Error::from); self.output_dir)?;
+
+        let palette = track!(self.palette.load())?;
+        for (problem_id, studies) in problems {
+            let problem = track!(Problem::new(problem_id, studies))?;
+            track!(problem.plot(self, &palette))?;
+            pb.inc(1);
+        }
+        pb.finish_with_message(&format!("done (dir={:?})", self.output_dir));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Problem<'a> {
+    problem_id: String,
+    problem: &'a ProblemRecord,
+
+    // (Solver name, study ID) => pareto frontiers of the studies.
+    solvers: BTreeMap<(&'a str, String), Vec<&'a [f64]>>,
+}
+impl<'a> Problem<'a> {
+    fn new(problem_id: String, studies: Vec<&'a StudyRecord>) -> Result<Self> {
+        let problem = &studies[0].problem;
+        let mut solvers = BTreeMap::<_, Vec<_>>::new();
+        for study in studies {
+            let study_id = track!(study.id())?;
+            solvers
+                .entry((study.solver.spec.name.as_str(), study_id))
+                .or_default()
+                .extend(study.pareto_frontier());
+        }
+
+        // Solvers that have no complete trials aren't drawn.
+        solvers.retain(|_, frontier| !frontier.is_empty());
+        Ok(Self {
+            problem_id,
+            problem,
+            solvers,
+        })
+    }
+
+    fn plot(&self, opt: &PlotParetoOpt, palette: &Palette) -> Result<()> {
+        if self.solvers.is_empty() {
+            return Ok(());
+        }
+
+        let output = opt.output_dir.join(format!(
+            "{}-{}.{}",
+            normalize_filename(&self.problem.spec.name),
+            self.problem_id,
+            opt.format.extension(),
+        ));
+        let data_path = track!(self.generate_data(track!(opt.dump.data_file(&output))?))?;
+        let script = self.make_gnuplot_script(&data_path, &output, opt, palette);
+        track!(opt.dump.execute_gnuplot(&output, &script))?;
+        std::mem::drop(data_path);
+
+        Ok(())
+    }
+
+    fn make_gnuplot_script(
+        &self,
+        data_path: &DataPath,
+        output: &Path,
+        opt: &PlotParetoOpt,
+        palette: &Palette,
+    ) -> String {
+        let palette = palette.resolve(self.solvers.keys().map(|(name, _)| name));
+        let vars = self.problem.spec.values_domain.variables();
+        let mut s = format!(
+            "set title {:?}; \
+             set ylabel {:?}; \
+             set xlabel {:?}; \
+             set grid; \
+             set key outside;",
+            format!("Pareto Frontier: {}", self.problem.spec.name),
+            vars[1].name(),
+            vars[0].name(),
+        );
+
+        s += &opt.format.gnuplot_terminal(opt.width, opt.height);
+        s += &format!("set output {:?};", output);
+
+        s += &format!(
+            "plot [{}:{}] [{}:{}]",
+            opt.xmin.map(|v| v.to_string()).unwrap_or_default(),
+            opt.xmax.map(|v| v.to_string()).unwrap_or_default(),
+            opt.ymin.map(|v| v.to_string()).unwrap_or_default(),
+            opt.ymax.map(|v| v.to_string()).unwrap_or_default(),
+        );
+        for (i, (name, _)) in self.solvers.keys().enumerate() {
+            if i == 0 {
+                s += &format!(" {:?}", data_path);
+            } else {
+                s += ", \"\"";
+            }
+            s += &format!(
+                " index {} u 1:2 pt 7 t {:?}{}",
+                i,
+                name,
+                palette.color(name).gnuplot()
+            );
+        }
+        s
+    }
+
+    fn generate_data(&self, mut temp_file: DataFile) -> Result<DataPath> {
+        for frontier in self.solvers.values() {
+            for values in frontier {
+                track_writeln!(temp_file, "{} {}", values[0], values[1])?;
+            }
+            track_writeln!(temp_file)?;
+            track_writeln!(temp_file)?;
+        }
+
+        Ok(temp_file.into_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::record_with_spec;
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn frontiers_work() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("test")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish()?;
+        let a = record_with_spec(
+            problem.clone(),
+            "a",
+            3,
+            &[vec![1.0, 2.0], vec![2.0, 1.0], vec![2.5, 2.5]],
+        )?;
+        let b = record_with_spec(problem, "b", 2, &[vec![3.0, 3.0], vec![0.5, 0.5]])?;
+
+        let problem = Problem::new("foo".to_owned(), vec![&a, &b])?;
+        let frontiers = problem
+            .solvers
+            .iter()
+            .map(|((name, _), frontier)| (*name, frontier.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            frontiers,
+            [
+                ("a", vec![&[1.0, 2.0][..], &[2.0, 1.0]]),
+                ("b", vec![&[0.5, 0.5][..]])
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,15 @@
 //! `kurobako plot pareto-front` command.
 #![allow(clippy::format_push_string)]
-use super::{execute_gnuplot, normalize_filename};
+use super::{normalize_filename, DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
 use crate::record::StudyRecord;
 use indicatif::{ProgressBar, ProgressStyle};
 use kurobako_core::{Error, ErrorKind, Result};
 use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
-use tempfile::{NamedTempFile, TempPath};
 
 /// Options of the `kurobako plot pareto-front` command.
 #[derive(Debug, StructOpt)]
@@ -27,6 +27,14 @@ pub struct PlotParetoFrontOpt {
     #[structopt(long, default_value = "600")]
     pub height: usize,
 
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
     /// Minimum value of Y axis.
     #[structopt(long)]
     pub ymin: Option<f64>,
@@ -42,9 +50,19 @@ pub struct PlotParetoFrontOpt {
     /// Maximum value of X axis.
     #[structopt(long)]
     pub xmax: Option<f64>,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
 }
 impl PlotParetoFrontOpt {
     pub(crate) fn plot(&self, study_records: &[StudyRecord]) -> Result<()> {
+        let study_records = self.filter.filter(study_records);
+        let study_records = &*study_records;
         let mut studies = BTreeMap::new();
         for record in study_records {
             track_assert_eq!(
@@ -91,9 +109,19 @@ impl<'a> Study<'a> {
     }
 
     fn plot(&self, opt: &PlotParetoFrontOpt) -> Result<()> {
-        let data_path = track!(self.generate_data())?;
-        let script = track!(self.make_gnuplot_script(&data_path, opt))?;
-        track!(execute_gnuplot(&script))?;
+        let problem = &self.instances[0].problem;
+        let solver = &self.instances[0].solver;
+        let output = opt.output_dir.join(format!(
+            "{}-{}-{}.{}",
+            normalize_filename(&problem.spec.name),
+            normalize_filename(&solver.spec.name),
+            track!(self.instances[0].id())?,
+            opt.format.extension(),
+        ));
+
+        let data_path = track!(self.generate_data(track!(opt.dump.data_file(&output))?))?;
+        let script = self.make_gnuplot_script(&data_path, &output, opt);
+        track!(opt.dump.execute_gnuplot(&output, &script))?;
         std::mem::drop(data_path);
 
         Ok(())
@@ -101,9 +129,10 @@ impl<'a> Study<'a> {
 
     fn make_gnuplot_script(
         &self,
-        data_path: &TempPath,
+        data_path: &DataPath,
+        output: &Path,
         opt: &PlotParetoFrontOpt,
-    ) -> Result<String> {
+    ) -> String {
         let problem = &self.instances[0].problem;
         let solver = &self.instances[0].solver;
         let title = format!(
@@ -120,17 +149,8 @@ impl<'a> Study<'a> {
             problem.spec.values_domain.variables()[0].name(),
         );
 
-        let output = opt.output_dir.join(format!(
-            "{}-{}-{}.png",
-            normalize_filename(&problem.spec.name),
-            normalize_filename(&solver.spec.name),
-            track!(self.instances[0].id())?
-        ));
-
-        s += &format!(
-            "set terminal pngcairo size {},{} noenhanced; set output {:?};",
-            opt.width, opt.height, output
-        );
+        s += &opt.format.gnuplot_terminal(opt.width, opt.height);
+        s += &format!("set output {:?};", output);
         s += "set palette defined (0 'blue', 1 'grey', 2 'red');";
 
         s += &format!(
@@ -142,28 +162,21 @@ impl<'a> Study<'a> {
             data_path
         );
 
-        Ok(s)
+        s
     }
 
-    fn generate_data(&self) -> Result<TempPath> {
-        let mut temp_file = track!(NamedTempFile::new().map_err(Error::from))?;
-
+    fn generate_data(&self, mut temp_file: DataFile) -> Result<DataPath> {
         let problem_steps = self.instances[0].problem.spec.steps.last();
         for study in &self.instances {
-            eprintln!("trials: {:?}", study.trials.len());
-            dbg!(problem_steps);
-            let mut c = 0;
             for trial in &study.trials {
                 if let Some(vs) = trial.values(problem_steps) {
-                    c += 1;
                     let end_step = trial.end_step().unwrap_or_else(|| unreachable!());
                     let budget = end_step as f64 / problem_steps as f64;
                     track_writeln!(temp_file, "{} {} {}", budget, vs[1], vs[0])?;
                 }
             }
-            dbg!(c);
         }
 
-        Ok(temp_file.into_temp_path())
+        Ok(temp_file.into_path())
     }
 }
@@ -0,0 +1,298 @@
+//! `kurobako plot performance-profile` command.
+//!
+//! A performance profile ([Dolan and Moré, 2002][paper]) summarizes the results of
+//! several solvers over several problems in a single chart.
+//!
+//! For each problem `p` and solver `s`, the cost `t_{p,s}` is the average of the best values
+//! found by the studies of the solver.
+//! The performance ratio is defined as `r_{p,s} = t_{p,s} / min_s t_{p,s}`, and the plotted
+//! curve of a solver is the fraction of problems satisfying `r_{p,s} <= τ`.
+//!
+//! Because the ratio requires positive costs, if a problem has a non-positive best value,
+//! all the costs of the problem are shifted so that the minimum one becomes `1.0`
+//! (i.e., `r_{p,s} = t_{p,s} - min_s t_{p,s} + 1`).
+//! The shift keeps the order of the solvers and the ratio `1.0` of the best one,
+//! but the shifted ratios are the absolute gaps from the best cost (plus one) rather than
+//! scale-invariant ratios, so they aren't comparable to the ratios of the problems without the shift.
+//! Solvers that have the same cost on a problem get the same ratio (i.e., ties are not broken).
+//! If a solver has no complete trials on a problem (or has never been run on it),
+//! the ratio is regarded as infinity and the problem is never counted as solved by the solver.
+//!
+//! Note that multi-objective problems are ignored.
+//!
+//! [paper]: https://arxiv.org/abs/cs/0102001
+#![allow(clippy::format_push_string)]
+use super::palette::{Palette, PaletteOpt};
+use super::{DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
+use crate::record::StudyRecord;
+use kurobako_core::num::OrderedFloat;
+use kurobako_core::{Error, ErrorKind, Result};
+use rustats::fundamental::average;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Options of the `kurobako plot performance-profile` command.
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PlotPerformanceProfileOpt {
+    /// Output directory where generated images are stored.
+    #[structopt(long, short = "o", default_value = "images/performance_profile/")]
+    pub output_dir: PathBuf,
+
+    /// Image width in pixels.
+    #[structopt(long, default_value = "800")]
+    pub width: usize,
+
+    /// Image height in pixels.
+    #[structopt(long, default_value = "600")]
+    pub height: usize,
+
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
+    /// Maximum value of τ (X axis).
+    ///
+    /// If omitted, the maximum finite performance ratio is used.
+    #[structopt(long)]
+    pub xmax: Option<f64>,
+
+    /// Makes X axis log scale.
+    #[structopt(long)]
+    pub xlogscale: bool,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Colors and dash styles of solvers.
+    #[structopt(flatten)]
+    pub palette: PaletteOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
+}
+impl PlotPerformanceProfileOpt {
+    pub(crate) fn plot(&self, studies: &[StudyRecord]) -> Result<()> {
+        let studies = self.filter.filter(studies);
+        let studies = &*studies;
+        let profile = track!(PerformanceProfile::new(studies))?;
+        track_assert!(
+            !profile.ratios.is_empty(),
+            ErrorKind::InvalidInput,
+            "No single-objective studies"
+        );
+
+        track!(fs::create_dir_all(&self.output_dir).map_err(Error::from); self.output_dir)?;
+
+        let xmax = self.xmax.unwrap_or_else(|| profile.max_finite_ratio());
+        let output = self.output_path();
+        let data_file = track!(self.dump.data_file(&output))?;
+        let data_path = track!(profile.generate_data(xmax, data_file))?;
+        let palette = track!(self.palette.load())?;
+        let script = self.make_gnuplot_script(&profile, &data_path, xmax, &palette);
+        track!(self.dump.execute_gnuplot(&output, &script))?;
+        std::mem::drop(data_path);
+
+        eprintln!("done (file={:?})", output);
+        Ok(())
+    }
+
+    fn output_path(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("performance-profile.{}", self.format.extension()))
+    }
+
+    fn make_gnuplot_script(
+        &self,
+        profile: &PerformanceProfile,
+        data_path: &DataPath,
+        xmax: f64,
+        palette: &Palette,
+    ) -> String {
+        let palette = palette.resolve(profile.names.values());
+        let mut s = format!(
+            "set title {:?}; set ylabel {:?}; set xlabel \"τ\"; set grid; set key bottom right;",
+            format!("Performance Profile ({} problems)", profile.problems),
+            "Fraction of Problems"
+        );
+        if self.xlogscale {
+            s += "set logscale x;";
+        }
+
+        s += &self.format.gnuplot_terminal(self.width, self.height);
+        s += &format!("set output {:?};", self.output_path());
+
+        s += &format!("plot [1:{}] [0:1.05]", xmax);
+        for (i, name) in profile.names.values().enumerate() {
+            if i == 0 {
+                s += &format!(" {:?}", data_path);
+            } else {
+                s += ", \"\"";
+            }
+            s += &format!(
+                " index {} u 1:2 w steps t {:?}{}",
+                i,
+                name,
+                palette.gnuplot_style(name)
+            );
+        }
+        s
+    }
+}
+
+#[derive(Debug)]
+struct PerformanceProfile {
+    problems: usize,
+
+    // Solver ID => solver name.
+    names: BTreeMap<String, String>,
+
+    // Solver ID => sorted performance ratios (one per problem).
+    ratios: BTreeMap<String, Vec<f64>>,
+}
+impl PerformanceProfile {
+    fn new(studies: &[StudyRecord]) -> Result<Self> {
+        let mut names = BTreeMap::new();
+        let mut problems = BTreeMap::<_, BTreeMap<_, Vec<_>>>::new();
+        for study in studies {
+            if study.problem.spec.values_domain.variables().len() != 1 {
+                continue;
+            }
+
+            let solver_id = track!(study.solver.id())?;
+            names
+                .entry(solver_id.clone())
+                .or_insert_with(|| study.solver.spec.name.clone());
+            problems
+                .entry(track!(study.problem.id())?)
+                .or_default()
+                .entry(solver_id)
+                .or_default()
+                .push(study);
+        }
+
+        let mut ratios = names
+            .keys()
+            .map(|id| (id.clone(), Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+        for solvers in problems.values() {
+            let costs = solvers
+                .iter()
+                .filter_map(|(id, studies)| {
+                    let values = studies
+                        .iter()
+                        .filter_map(|s| s.best_value())
+                        .collect::<Vec<_>>();
+                    if values.is_empty() {
+                        None
+                    } else {
+                        Some((id, average(values.into_iter())))
+                    }
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            let min = costs.values().copied().map(OrderedFloat).min().map(|x| x.0);
+            // See the module documentation for the shift of non-positive costs.
+            let offset = match min {
+                Some(min) if min <= 0.0 => 1.0 - min,
+                _ => 0.0,
+            };
+
+            for (id, solver_ratios) in &mut ratios {
+                let ratio = match (costs.get(id), min) {
+                    (Some(cost), Some(min)) => (cost + offset) / (min + offset),
+                    _ => f64::INFINITY,
+                };
+                solver_ratios.push(ratio);
+            }
+        }
+        for solver_ratios in ratios.values_mut() {
+            solver_ratios.sort_by_key(|&r| OrderedFloat(r));
+        }
+
+        Ok(Self {
+            problems: problems.len(),
+            names,
+            ratios,
+        })
+    }
+
+    fn max_finite_ratio(&self) -> f64 {
+        let max = self
+            .ratios
+            .values()
+            .flat_map(|r| r.iter().copied())
+            .filter(|r| r.is_finite())
+            .map(OrderedFloat)
+            .max()
+            .map_or(1.0, |x| x.0);
+        if max > 1.0 {
+            max
+        } else {
+            2.0
+        }
+    }
+
+    fn generate_data(&self, xmax: f64, mut temp_file: DataFile) -> Result<DataPath> {
+        let n = self.problems as f64;
+        for ratios in self.ratios.values() {
+            let solved_at_one = ratios.iter().take_while(|&&r| r <= 1.0).count();
+            track_writeln!(temp_file, "1 {}", solved_at_one as f64 / n)?;
+            for (i, r) in ratios.iter().enumerate().filter(|(_, r)| r.is_finite()) {
+                track_writeln!(temp_file, "{} {}", r, (i + 1) as f64 / n)?;
+            }
+            let solved = ratios.iter().filter(|r| r.is_finite()).count();
+            track_writeln!(temp_file, "{} {}", xmax, solved as f64 / n)?;
+            track_writeln!(temp_file)?;
+            track_writeln!(temp_file)?;
+        }
+
+        Ok(temp_file.into_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn performance_profile_works() -> TopLevelResult {
+        let studies = vec![
+            testing::record("p1", "a", 1, &[1.0])?,
+            testing::record("p1", "b", 1, &[2.0])?,
+            // The costs are shifted by `1 - (-1)`.
+            testing::record("p2", "a", 1, &[-1.0])?,
+            testing::record("p2", "b", 1, &[1.0])?,
+            // A tie.
+            testing::record("p3", "a", 1, &[3.0])?,
+            testing::record("p3", "b", 1, &[3.0])?,
+            // "b" has no complete trials.
+            testing::record("p4", "a", 1, &[5.0])?,
+            testing::record("p4", "b", 1, &[])?,
+        ];
+        let profile = PerformanceProfile::new(&studies)?;
+        assert_eq!(profile.problems, 4);
+
+        let ratios = profile
+            .ratios
+            .iter()
+            .map(|(id, r)| (profile.names[id].as_str(), r.clone()))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(ratios["a"], [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(ratios["b"], [1.0, 2.0, 3.0, f64::INFINITY]);
+        assert_eq!(profile.max_finite_ratio(), 3.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+//! `kurobako plot rank` command.
+//!
+//! This draws the average ranks (`1` is the best) of solvers over problems
+//! as functions of the consumed budget.
+//!
+//! At each budget fraction, the cost of a solver on a problem is the average of the best values
+//! found by the studies of the solver until the fraction of the budget is consumed,
+//! and the solvers are ranked by the costs on each problem.
+//! Tied solvers get the average of their ranks (e.g., two solvers tied for first get `1.5`).
+//! Solvers that have no complete trials yet (or have never been run on the problem)
+//! are regarded as tied for last.
+//!
+//! Note that multi-objective problems are ignored.
+#![allow(clippy::format_push_string)]
+use super::palette::{Palette, PaletteOpt};
+use super::{DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
+use crate::record::StudyRecord;
+use kurobako_core::num::OrderedFloat;
+use kurobako_core::{Error, ErrorKind, Result};
+use rustats::fundamental::average;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Options of the `kurobako plot rank` command.
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct PlotRankOpt {
+    /// Output directory where generated images are stored.
+    #[structopt(long, short = "o", default_value = "images/rank/")]
+    pub output_dir: PathBuf,
+
+    /// Image width in pixels.
+    #[structopt(long, default_value = "800")]
+    pub width: usize,
+
+    /// Image height in pixels.
+    #[structopt(long, default_value = "600")]
+    pub height: usize,
+
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
+    /// Number of the budget fractions at which the ranks are computed.
+    #[structopt(long, default_value = "100")]
+    pub points: usize,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Colors and dash styles of solvers.
+    #[structopt(flatten)]
+    pub palette: PaletteOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
+}
+impl PlotRankOpt {
+    pub(crate) fn plot(&self, studies: &[StudyRecord]) -> Result<()> {
+        let studies = self.filter.filter(studies);
+        let studies = &*studies;
+        track_assert!(self.points > 0, ErrorKind::InvalidInput);
+        let ranks = track!(Ranks::new(studies, self.points))?;
+        track_assert!(
+            ranks.problems > 0,
+            ErrorKind::InvalidInput,
+            "No single-objective studies"
+        );
+
+        track!(fs::create_dir_all(&self.output_dir).map_err(Error::from); self.output_dir)?;
+
+        let output = self.output_path();
+        let data_path = track!(ranks.generate_data(track!(self.dump.data_file(&output))?))?;
+        let palette = track!(self.palette.load())?;
+        let script = self.make_gnuplot_script(&ranks, &data_path, &palette);
+        track!(self.dump.execute_gnuplot(&output, &script))?;
+        std::mem::drop(data_path);
+
+        eprintln!("done (file={:?})", output);
+        Ok(())
+    }
+
+    fn output_path(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("rank.{}", self.format.extension()))
+    }
+
+    fn make_gnuplot_script(
+        &self,
+        ranks: &Ranks,
+        data_path: &DataPath,
+        palette: &Palette,
+    ) -> String {
+        let palette = palette.resolve(ranks.names.values());
+        let mut s = format!(
+            "set title {:?}; set ylabel {:?}; set xlabel \"Budget\"; set grid;",
+            format!("Average Rank ({} problems)", ranks.problems),
+            "Average Rank"
+        );
+        s += &self.format.gnuplot_terminal(self.width, self.height);
+        s += &format!("set output {:?};", self.output_path());
+
+        // The Y axis is reversed so that the best solver comes to the top.
+        s += &format!("plot [0:1] [{}:0.5]", ranks.names.len() as f64 + 0.5);
+        for (i, name) in ranks.names.values().enumerate() {
+            if i == 0 {
+                s += &format!(" {:?}", data_path);
+            } else {
+                s += ", \"\"";
+            }
+            s += &format!(
+                " index {} u 1:2 w l t {:?}{}",
+                i,
+                name,
+                palette.gnuplot_style(name)
+            );
+        }
+        s
+    }
+}
+
+#[derive(Debug)]
+struct Ranks {
+    problems: usize,
+
+    // Budget fractions.
+    budgets: Vec<f64>,
+
+    // Solver ID => solver name.
+    names: BTreeMap<String, String>,
+
+    // Solver ID => average ranks (one per budget fraction).
+    ranks: BTreeMap<String, Vec<f64>>,
+}
+impl Ranks {
+    fn new(studies: &[StudyRecord], points: usize) -> Result<Self> {
+        let mut names = BTreeMap::new();
+        let mut problems = BTreeMap::<_, BTreeMap<_, Vec<_>>>::new();
+        for study in studies {
+            if study.problem.spec.values_domain.variables().len() != 1 {
+                continue;
+            }
+
+            let solver_id = track!(study.solver.id())?;
+            names
+                .entry(solver_id.clone())
+                .or_insert_with(|| study.solver.spec.name.clone());
+            problems
+                .entry(track!(study.problem.id())?)
+                .or_default()
+                .entry(solver_id)
+                .or_default()
+                .push((study.study_steps(), study.best_values()));
+        }
+
+        let budgets = (1..=points)
+            .map(|i| i as f64 / points as f64)
+            .collect::<Vec<_>>();
+        let mut ranks = names
+            .keys()
+            .map(|id| (id.clone(), vec![0.0; points]))
+            .collect::<BTreeMap<_, _>>();
+        for solvers in problems.values() {
+            for (i, &budget) in budgets.iter().enumerate() {
+                let costs = names
+                    .keys()
+                    .map(|id| {
+                        let values = solvers
+                            .get(id)
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|(study_steps, best_values)| {
+                                let step = (*study_steps as f64 * budget).round() as u64;
+                                best_values.range(..=step).last().map(|(_, &v)| v)
+                            })
+                            .collect::<Vec<_>>();
+                        if values.is_empty() {
+                            None
+                        } else {
+                            Some(average(values.into_iter()))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                for (solver_ranks, rank) in ranks.values_mut().zip(average_ranks(&costs)) {
+                    solver_ranks[i] += rank / problems.len() as f64;
+                }
+            }
+        }
+
+        Ok(Self {
+            problems: problems.len(),
+            budgets,
+            names,
+            ranks,
+        })
+    }
+
+    fn generate_data(&self, mut temp_file: DataFile) -> Result<DataPath> {
+        for ranks in self.ranks.values() {
+            for (budget, rank) in self.budgets.iter().zip(ranks.iter()) {
+                track_writeln!(temp_file, "{} {}", budget, rank)?;
+            }
+            track_writeln!(temp_file)?;
+            track_writeln!(temp_file)?;
+        }
+
+        Ok(temp_file.into_path())
+    }
+}
+
+// Returns the ranks (starting from `1`) of the given costs in ascending order.
+//
+// Tied costs get the average of their ranks and `None` is regarded as the worst cost.
+fn average_ranks(costs: &[Option<f64>]) -> Vec<f64> {
+    let key = |i: usize| costs[i].map_or((1, OrderedFloat(0.0)), |c| (0, OrderedFloat(c)));
+    let mut indices = (0..costs.len()).collect::<Vec<_>>();
+    indices.sort_by_key(|&i| key(i));
+
+    let mut ranks = vec![0.0; costs.len()];
+    let mut start = 0;
+    while start < indices.len() {
+        let end = start
+            + indices[start..]
+                .iter()
+                .take_while(|&&i| key(i) == key(indices[start]))
+                .count();
+        let rank = (start + 1 + end) as f64 / 2.0;
+        for &i in &indices[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::record;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn average_ranks_works() {
+        assert_eq!(
+            average_ranks(&[Some(2.0), Some(1.0), None, Some(2.0)]),
+            [2.5, 1.0, 4.0, 2.5]
+        );
+        assert_eq!(average_ranks(&[None, None]), [1.5, 1.5]);
+    }
+
+    #[test]
+    fn ranks_work() -> TopLevelResult {
+        let studies = vec![
+            record("foo", "a", 4, &[3.0, 1.0, 1.0, 1.0])?,
+            record("foo", "b", 4, &[2.0, 2.0, 2.0, 0.5])?,
+            record("bar", "a", 4, &[1.0, 1.0, 1.0, 1.0])?,
+            record("bar", "b", 4, &[1.0, 1.0, 0.0, 0.0])?,
+        ];
+        let ranks = Ranks::new(&studies, 4)?;
+        assert_eq!(ranks.problems, 2);
+        assert_eq!(ranks.budgets, [0.25, 0.5, 0.75, 1.0]);
+
+        let id = |i: usize| studies[i].solver.id();
+        assert_eq!(ranks.names[&id(0)?], "a");
+        assert_eq!(ranks.ranks[&id(0)?], [1.75, 1.25, 1.5, 2.0]);
+        assert_eq!(ranks.ranks[&id(1)?], [1.25, 1.75, 1.5, 1.0]);
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! `kurobako plot slice` command.
 #![allow(clippy::format_push_string)]
-use super::{execute_gnuplot, normalize_filename};
+use super::{normalize_filename, DataFile, DataPath, GnuplotDumpOpt, ImageFormat};
+use crate::filter::ProblemFilterOpt;
 use crate::record::StudyRecord;
 use indicatif::{ProgressBar, ProgressStyle};
 use kurobako_core::domain::Variable;
@@ -8,9 +9,8 @@ use kurobako_core::{Error, Result};
 use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
-use tempfile::{NamedTempFile, TempPath};
 
 /// Options of the `kurobako plot slice` command.
 #[derive(Debug, StructOpt)]
@@ -28,6 +28,14 @@ pub struct PlotSliceOpt {
     #[structopt(long, default_value = "600")]
     pub height: usize,
 
+    /// Format of the output images.
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = ImageFormat::POSSIBLE_VALUES
+    )]
+    pub format: ImageFormat,
+
     /// Minimum value of Y axis.
     #[structopt(long)]
     pub ymin: Option<f64>,
@@ -43,9 +51,19 @@ pub struct PlotSliceOpt {
     /// Maximum value of X axis.
     #[structopt(long)]
     pub xmax: Option<f64>,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    pub filter: ProblemFilterOpt,
+
+    /// Options to dump the files given to gnuplot.
+    #[structopt(flatten)]
+    pub dump: GnuplotDumpOpt,
 }
 impl PlotSliceOpt {
     pub(crate) fn plot(&self, study_records: &[StudyRecord]) -> Result<()> {
+        let study_records = self.filter.filter(study_records);
+        let study_records = &*study_records;
         let mut studies = BTreeMap::new();
         for record in study_records {
             let id = track!(record.id())?;
@@ -103,9 +121,21 @@ impl<'a> Study<'a> {
             .iter()
             .enumerate()
         {
-            let data_path = track!(self.generate_data(param_index))?;
-            let script = track!(self.make_gnuplot_script(param, &data_path, opt))?;
-            track!(execute_gnuplot(&script))?;
+            let problem = &self.instances[0].problem;
+            let solver = &self.instances[0].solver;
+            let output = opt.output_dir.join(format!(
+                "{}-{}-{}-{}.{}",
+                normalize_filename(&problem.spec.name),
+                normalize_filename(&solver.spec.name),
+                normalize_filename(param.name()),
+                track!(self.instances[0].id())?,
+                opt.format.extension(),
+            ));
+
+            let data_file = track!(opt.dump.data_file(&output))?;
+            let data_path = track!(self.generate_data(param_index, data_file))?;
+            let script = self.make_gnuplot_script(param, &data_path, &output, opt);
+            track!(opt.dump.execute_gnuplot(&output, &script))?;
             std::mem::drop(data_path);
         }
         Ok(())
@@ -114,9 +144,10 @@ impl<'a> Study<'a> {
     fn make_gnuplot_script(
         &self,
         param: &Variable,
-        data_path: &TempPath,
+        data_path: &DataPath,
+        output: &Path,
         opt: &PlotSliceOpt,
-    ) -> Result<String> {
+    ) -> String {
         let problem = &self.instances[0].problem;
         let solver = &self.instances[0].solver;
         let title = format!(
@@ -133,18 +164,8 @@ impl<'a> Study<'a> {
             param.name()
         );
 
-        let output = opt.output_dir.join(format!(
-            "{}-{}-{}-{}.png",
-            normalize_filename(&problem.spec.name),
-            normalize_filename(&solver.spec.name),
-            normalize_filename(param.name()),
-            track!(self.instances[0].id())?
-        ));
-
-        s += &format!(
-            "set terminal pngcairo size {},{} noenhanced; set output {:?};",
-            opt.width, opt.height, output
-        );
+        s += &opt.format.gnuplot_terminal(opt.width, opt.height);
+        s += &format!("set output {:?};", output);
         s += "set palette defined (0 'blue', 1 'grey', 2 'red');";
 
         s += &format!(
@@ -156,12 +177,10 @@ impl<'a> Study<'a> {
             data_path
         );
 
-        Ok(s)
+        s
     }
 
-    fn generate_data(&self, param_index: usize) -> Result<TempPath> {
-        let mut temp_file = track!(NamedTempFile::new().map_err(Error::from))?;
-
+    fn generate_data(&self, param_index: usize, mut temp_file: DataFile) -> Result<DataPath> {
         let problem_steps = self.instances[0].problem.spec.steps.last();
         for study in &self.instances {
             for trial in &study.trials {
@@ -176,6 +195,6 @@ impl<'a> Study<'a> {
             }
         }
 
-        Ok(temp_file.into_temp_path())
+        Ok(temp_file.into_path())
     }
 }
@@ -1,18 +1,24 @@
 //! The problem for `kurobako`.
+use kurobako_core::domain::{Domain, Range, VariableBuilder};
 use kurobako_core::epi::problem::ExternalProgramProblemRecipe;
 use kurobako_core::problem::{
-    BoxProblem, BoxProblemFactory, ProblemFactory, ProblemRecipe, ProblemSpec,
+    BoxEvaluator, BoxProblem, BoxProblemFactory, Evaluator, Problem, ProblemFactory, ProblemRecipe,
+    ProblemSpec,
 };
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
-use kurobako_core::Result;
-use kurobako_problems::{hpobench, nasbench, sigopt, surrogate, warm_starting, zdt};
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{Error, Result};
+use kurobako_problems::{dtlz, hpobench, nasbench, sigopt, surrogate, table, warm_starting, zdt};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 mod average;
+mod delay;
 mod ln;
+mod onehot;
 mod rank;
+mod replay;
 mod study;
 
 /// Problem recipe.
@@ -23,6 +29,13 @@ pub struct KurobakoProblemRecipe {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     name: Option<String>,
 
+    /// If this flag is set, the objective values of the problem are maximized.
+    ///
+    /// The values are negated so that the problem can be handled as a minimization one.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    maximize: bool,
+
     #[structopt(flatten)]
     #[serde(flatten)]
     inner: InnerRecipe,
@@ -34,14 +47,21 @@ impl ProblemRecipe for KurobakoProblemRecipe {
         let inner = track!(self.inner.create_factory(registry))?;
         Ok(KurobakoProblemFactory {
             name: self.name.clone(),
+            maximize: self.maximize,
             inner,
         })
     }
 }
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
 impl From<hpobench::HpobenchProblemRecipe> for KurobakoProblemRecipe {
     fn from(f: hpobench::HpobenchProblemRecipe) -> Self {
         Self {
             name: None,
+            maximize: false,
             inner: InnerRecipe::Hpobench(f),
         }
     }
@@ -50,6 +70,7 @@ impl From<sigopt::SigoptProblemRecipe> for KurobakoProblemRecipe {
     fn from(f: sigopt::SigoptProblemRecipe) -> Self {
         Self {
             name: None,
+            maximize: false,
             inner: InnerRecipe::Sigopt(f),
         }
     }
@@ -58,14 +79,25 @@ impl From<zdt::ZdtProblemRecipe> for KurobakoProblemRecipe {
     fn from(f: zdt::ZdtProblemRecipe) -> Self {
         Self {
             name: None,
+            maximize: false,
             inner: InnerRecipe::Zdt(f),
         }
     }
 }
+impl From<dtlz::DtlzProblemRecipe> for KurobakoProblemRecipe {
+    fn from(f: dtlz::DtlzProblemRecipe) -> Self {
+        Self {
+            name: None,
+            maximize: false,
+            inner: InnerRecipe::Dtlz(f),
+        }
+    }
+}
 impl From<surrogate::SurrogateProblemRecipe> for KurobakoProblemRecipe {
     fn from(f: surrogate::SurrogateProblemRecipe) -> Self {
         Self {
             name: None,
+            maximize: false,
             inner: InnerRecipe::Surrogate(f),
         }
     }
@@ -78,14 +110,21 @@ enum InnerRecipe {
     Command(ExternalProgramProblemRecipe),
     /// Recipe of `SigoptProblem`.
     Sigopt(sigopt::SigoptProblemRecipe),
+    /// Recipe of `ConstrainedSigoptProblem`.
+    ConstrainedSigopt(sigopt::constrained::ConstrainedSigoptProblemRecipe),
     Nasbench(nasbench::NasbenchProblemRecipe),
     Hpobench(hpobench::HpobenchProblemRecipe),
     Zdt(zdt::ZdtProblemRecipe),
+    Dtlz(dtlz::DtlzProblemRecipe),
     Surrogate(surrogate::SurrogateProblemRecipe),
+    Table(table::TableProblemRecipe),
     Study(self::study::StudyProblemRecipe),
     Rank(self::rank::RankProblemRecipe),
     Average(self::average::AverageProblemRecipe),
     Ln(self::ln::LnProblemRecipe),
+    Delay(self::delay::DelayProblemRecipe),
+    Replay(self::replay::ReplayProblemRecipe),
+    CategoricalAsOnehot(self::onehot::OnehotProblemRecipe),
     WarmStarting(warm_starting::WarmStartingProblemRecipe),
 }
 impl ProblemRecipe for InnerRecipe {
@@ -95,14 +134,24 @@ impl ProblemRecipe for InnerRecipe {
         match self {
             Self::Command(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Sigopt(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::ConstrainedSigopt(p) => {
+                track!(p.create_factory(registry).map(BoxProblemFactory::new))
+            }
             Self::Nasbench(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Hpobench(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Zdt(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::Dtlz(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Surrogate(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::Table(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Study(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Rank(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Average(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
             Self::Ln(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::Delay(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::Replay(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
+            Self::CategoricalAsOnehot(p) => {
+                track!(p.create_factory(registry).map(BoxProblemFactory::new))
+            }
             Self::WarmStarting(p) => track!(p.create_factory(registry).map(BoxProblemFactory::new)),
         }
     }
@@ -112,6 +161,7 @@ impl ProblemRecipe for InnerRecipe {
 #[derive(Debug)]
 pub struct KurobakoProblemFactory {
     name: Option<String>,
+    maximize: bool,
     inner: BoxProblemFactory,
 }
 impl ProblemFactory for KurobakoProblemFactory {
@@ -122,10 +172,170 @@ impl ProblemFactory for KurobakoProblemFactory {
         if let Some(name) = &self.name {
             spec.name.clone_from(name)
         }
+        if self.maximize {
+            track!(negate_values(&mut spec))?;
+        }
         Ok(spec)
     }
 
     fn create_problem(&self, rng: ArcRng) -> Result<Self::Problem> {
-        track!(self.inner.create_problem(rng)).map(BoxProblem::new)
+        let inner = track!(self.inner.create_problem(rng))?;
+        if self.maximize {
+            Ok(BoxProblem::new(NegatedProblem(inner)))
+        } else {
+            Ok(inner)
+        }
+    }
+}
+
+// Flips the objective values of the given problem specification.
+//
+// The attributes that describe the original values are flipped too
+// (i.e., `global_optimum` is negated and the negated `ideal_point` and `nadir_point` are swapped).
+fn negate_values(spec: &mut ProblemSpec) -> Result<()> {
+    let values = spec
+        .values_domain
+        .variables()
+        .iter()
+        .map(|var| {
+            let range = match *var.range() {
+                Range::Continuous { low, high, step } => Range::Continuous {
+                    low: -high,
+                    high: -low,
+                    step,
+                },
+                ref range => range.clone(),
+            };
+            VariableBuilder::from(var.clone()).range(range).uniform()
+        })
+        .collect();
+    spec.values_domain = track!(Domain::new(values))?;
+    spec.reference_point = spec
+        .reference_point
+        .take()
+        .map(|p| Params::new(p.iter().map(|v| -v).collect()));
+    if let Some(optimum) = track!(spec.parse_attr::<f64>("global_optimum"))? {
+        spec.attrs
+            .insert("global_optimum".to_owned(), (-optimum).to_string());
+    }
+    let ideal = track!(spec.parse_attr::<Vec<f64>>("ideal_point"))?;
+    let nadir = track!(spec.parse_attr::<Vec<f64>>("nadir_point"))?;
+    for (key, point) in &[("ideal_point", nadir), ("nadir_point", ideal)] {
+        if let Some(point) = point {
+            let point = point.iter().map(|v| -v).collect::<Vec<_>>();
+            let json = track!(serde_json::to_string(&point).map_err(Error::from))?;
+            spec.attrs.insert((*key).to_owned(), json);
+        } else {
+            spec.attrs.remove(*key);
+        }
+    }
+    spec.attrs.insert("maximize".to_owned(), "true".to_owned());
+    Ok(())
+}
+
+#[derive(Debug)]
+struct NegatedProblem(BoxProblem);
+impl Problem for NegatedProblem {
+    type Evaluator = NegatedEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        track!(self.0.create_evaluator(params)).map(NegatedEvaluator)
+    }
+}
+
+#[derive(Debug)]
+struct NegatedEvaluator(BoxEvaluator);
+impl Evaluator for NegatedEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        let (step, values) = track!(self.0.evaluate(next_step))?;
+        let values = values.into_vec().into_iter().map(|v| -v).collect();
+        Ok((step, Values::new(values)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::record_with_spec;
+    use crate::record::StudyRecord;
+    use crate::solver::KurobakoSolverRecipe;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use kurobako_core::{domain, json};
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn maximize_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let evaluate = |recipe: &str| -> Result<(ProblemSpec, Vec<f64>)> {
+            let recipe: KurobakoProblemRecipe = track!(json::parse_json(recipe))?;
+            let factory = track!(recipe.create_factory(&registry))?;
+            let problem = track!(factory.create_problem(ArcRng::new(0)))?;
+            let mut evaluator = track!(problem.create_evaluator(Params::new(vec![1.0, 2.0])))?;
+            let (_, values) = track!(evaluator.evaluate(1))?;
+            Ok((track!(factory.specification())?, values.into_vec()))
+        };
+
+        let (spec, values) = evaluate(r#"{"sigopt": {"name": "SPHERE", "dim": 2}}"#)?;
+        let (negated_spec, negated_values) =
+            evaluate(r#"{"sigopt": {"name": "SPHERE", "dim": 2}, "maximize": true}"#)?;
+        assert_eq!(values, [5.0]);
+        assert_eq!(negated_values, [-5.0]);
+
+        assert_eq!(spec.params_domain, negated_spec.params_domain);
+        let range = spec.values_domain.variables()[0].range();
+        let negated_range = negated_spec.values_domain.variables()[0].range();
+        assert_eq!(negated_range.low(), -range.high());
+        assert_eq!(negated_range.high(), -range.low());
+        assert_eq!(spec.parse_attr::<f64>("global_optimum")?, Some(0.0));
+        assert_eq!(
+            negated_spec.parse_attr::<f64>("global_optimum")?,
+            Some(-0.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn maximized_best_values_are_negated() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let study = |recipe: &str| -> Result<StudyRecord> {
+            let recipe: KurobakoProblemRecipe = track!(json::parse_json(recipe))?;
+            let factory = track!(recipe.create_factory(&registry))?;
+            let problem = track!(factory.create_problem(ArcRng::new(0)))?;
+            let mut values = Vec::new();
+            for xs in &[[1.0, 2.0], [0.5, -1.0], [-3.0, 2.0]] {
+                let mut evaluator = track!(problem.create_evaluator(Params::new(xs.to_vec())))?;
+                values.push(track!(evaluator.evaluate(1))?.1.into_vec());
+            }
+            track!(record_with_spec(
+                track!(factory.specification())?,
+                "test",
+                3,
+                &values
+            ))
+        };
+
+        let record = study(r#"{"sigopt": {"name": "SPHERE", "dim": 2}}"#)?;
+        let negated_record =
+            study(r#"{"sigopt": {"name": "SPHERE", "dim": 2}, "maximize": true}"#)?;
+        assert_eq!(record.best_value(), Some(1.25));
+        assert_eq!(negated_record.best_value(), Some(-13.0));
+        Ok(())
+    }
+
+    #[test]
+    fn normalization_points_are_flipped() -> TopLevelResult {
+        let mut spec = ProblemSpecBuilder::new("test")
+            .attr("ideal_point", "[0.0, 1.0]")
+            .attr("nadir_point", "[2.0, 3.0]")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish()?;
+        negate_values(&mut spec)?;
+        assert_eq!(
+            spec.normalization_points()?,
+            Some((vec![-2.0, -3.0], vec![-0.0, -1.0]))
+        );
+        Ok(())
     }
 }
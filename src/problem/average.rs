@@ -154,9 +154,8 @@ impl Evaluator for StudyEvaluator {
             self.evaluators.sort_by_key(|e| e.current_step);
             let eval = &mut self.evaluators[0];
             let next_step = cmp::max(next_step, eval.current_step + 1);
-            let (current_step, values) = track!(eval
-                .inner
-                .evaluate((next_step + eval.scale - 1) / eval.scale))?;
+            let (current_step, values) =
+                track!(eval.inner.evaluate(next_step.div_ceil(eval.scale)))?;
             eval.last_values = Some(values);
             eval.current_step = current_step * eval.scale;
             let current_step = eval.current_step;
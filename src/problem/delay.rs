@@ -0,0 +1,154 @@
+use kurobako_core::json::JsonRecipe;
+use kurobako_core::problem::{
+    BoxEvaluator, BoxProblem, BoxProblemFactory, Evaluator, Problem, ProblemFactory, ProblemRecipe,
+    ProblemSpec,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Recipe to simulate the evaluation cost of a problem by sleeping during evaluations.
+///
+/// The evaluator of the wrapped problem sleeps for `seconds_per_step` per advanced step.
+/// This is useful to observe the behavior of the schedulers of concurrent studies.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DelayProblemRecipe {
+    /// Problem recipe JSON.
+    pub problem: JsonRecipe,
+
+    /// Wall-clock seconds to sleep per step.
+    #[structopt(long)]
+    pub seconds_per_step: f64,
+}
+
+impl ProblemRecipe for DelayProblemRecipe {
+    type Factory = DelayProblemFactory;
+
+    fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        track_assert!(
+            self.seconds_per_step.is_finite() && self.seconds_per_step >= 0.0,
+            ErrorKind::InvalidInput,
+            "`seconds_per_step` must be a non-negative finite number: {}",
+            self.seconds_per_step
+        );
+
+        let problem = track!(registry.create_problem_factory_from_json(&self.problem))?;
+        Ok(DelayProblemFactory {
+            problem,
+            seconds_per_step: self.seconds_per_step,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DelayProblemFactory {
+    problem: BoxProblemFactory,
+    seconds_per_step: f64,
+}
+
+impl ProblemFactory for DelayProblemFactory {
+    type Problem = DelayProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        track!(self.problem.specification())
+    }
+
+    fn create_problem(&self, rng: ArcRng) -> Result<Self::Problem> {
+        let problem = track!(self.problem.create_problem(rng))?;
+        Ok(DelayProblem {
+            problem,
+            seconds_per_step: self.seconds_per_step,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DelayProblem {
+    problem: BoxProblem,
+    seconds_per_step: f64,
+}
+
+impl Problem for DelayProblem {
+    type Evaluator = DelayEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        let evaluator = track!(self.problem.create_evaluator(params))?;
+        Ok(DelayEvaluator {
+            evaluator,
+            seconds_per_step: self.seconds_per_step,
+            current_step: 0,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DelayEvaluator {
+    evaluator: BoxEvaluator,
+    seconds_per_step: f64,
+    current_step: u64,
+}
+
+impl Evaluator for DelayEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        let (current_step, values) = track!(self.evaluator.evaluate(next_step))?;
+
+        let elapsed_steps = current_step.saturating_sub(self.current_step);
+        thread::sleep(Duration::from_secs_f64(
+            self.seconds_per_step * elapsed_steps as f64,
+        ));
+        self.current_step = current_step;
+
+        Ok((current_step, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use trackable::result::TopLevelResult;
+
+    #[derive(Debug)]
+    struct StepProblem;
+    impl Problem for StepProblem {
+        type Evaluator = StepEvaluator;
+
+        fn create_evaluator(&self, _params: Params) -> Result<Self::Evaluator> {
+            Ok(StepEvaluator)
+        }
+    }
+
+    #[derive(Debug)]
+    struct StepEvaluator;
+    impl Evaluator for StepEvaluator {
+        fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+            Ok((next_step, Values::new(vec![next_step as f64])))
+        }
+    }
+
+    #[test]
+    fn delay_scales_with_steps() -> TopLevelResult {
+        let problem = DelayProblem {
+            problem: BoxProblem::new(StepProblem),
+            seconds_per_step: 0.01,
+        };
+        let mut evaluator = problem.create_evaluator(Params::new(vec![]))?;
+
+        let now = Instant::now();
+        evaluator.evaluate(2)?;
+        assert!(now.elapsed() >= Duration::from_millis(20));
+
+        let now = Instant::now();
+        let (step, values) = evaluator.evaluate(10)?;
+        assert!(now.elapsed() >= Duration::from_millis(80));
+        assert_eq!(step, 10);
+        assert_eq!(values.into_vec(), [10.0]);
+        Ok(())
+    }
+}
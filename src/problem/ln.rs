@@ -41,7 +41,7 @@ impl ProblemFactory for LnProblemFactory {
 
         let mut transformed_vars = Vec::new();
         for var in spec.params_domain.variables() {
-            if let (Range::Continuous { low, high }, Distribution::Uniform) =
+            if let (Range::Continuous { low, high, .. }, Distribution::Uniform) =
                 (var.range(), var.distribution())
             {
                 transformed_vars.push(
@@ -0,0 +1,197 @@
+use kurobako_core::domain::{self, Domain, Range, VariableBuilder};
+use kurobako_core::json::JsonRecipe;
+use kurobako_core::problem::{
+    BoxEvaluator, BoxProblem, BoxProblemFactory, Evaluator, Problem, ProblemFactory, ProblemRecipe,
+    ProblemSpec,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+/// Recipe to present the categorical variables of a problem as one-hot encoded continuous variables.
+///
+/// A categorical variable that has `k` choices is replaced with `k` continuous variables in `[0, 1)`
+/// (named `${VARIABLE_NAME}=${CHOICE}`).
+/// When evaluating, the choice that has the largest value (argmax) is passed to the underlying problem.
+/// Ties are broken by the order of the choices.
+///
+/// Note that problems that have conditional variables aren't supported.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct OnehotProblemRecipe {
+    /// Problem recipe JSON.
+    pub problem: JsonRecipe,
+}
+
+impl ProblemRecipe for OnehotProblemRecipe {
+    type Factory = OnehotProblemFactory;
+
+    fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let problem = track!(registry.create_problem_factory_from_json(&self.problem))?;
+        Ok(OnehotProblemFactory { problem })
+    }
+}
+
+#[derive(Debug)]
+pub struct OnehotProblemFactory {
+    problem: BoxProblemFactory,
+}
+
+impl ProblemFactory for OnehotProblemFactory {
+    type Problem = OnehotProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        let mut spec = track!(self.problem.specification())?;
+
+        let mut transformed_vars = Vec::new();
+        for var in spec.params_domain.variables() {
+            track_assert!(
+                var.constraint().is_none(),
+                ErrorKind::InvalidInput,
+                "Conditional variables aren't supported: {:?}",
+                var.name()
+            );
+
+            if let Range::Categorical { choices } = var.range() {
+                for choice in choices {
+                    transformed_vars.push(
+                        domain::var(&format!("{}={}", var.name(), choice)).continuous(0.0, 1.0),
+                    );
+                }
+            } else {
+                transformed_vars.push(VariableBuilder::from(var.clone()));
+            }
+        }
+        spec.params_domain = track!(Domain::new(transformed_vars))?;
+        spec.attrs
+            .insert("categorical_encoding".to_owned(), "onehot".to_owned());
+
+        Ok(spec)
+    }
+
+    fn create_problem(&self, rng: ArcRng) -> Result<Self::Problem> {
+        let original_spec = track!(self.problem.specification())?;
+        let problem = track!(self.problem.create_problem(rng))?;
+        Ok(OnehotProblem {
+            problem,
+            original_domain: original_spec.params_domain,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct OnehotProblem {
+    problem: BoxProblem,
+    original_domain: Domain,
+}
+
+impl Problem for OnehotProblem {
+    type Evaluator = OnehotEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        let mut params = params.into_vec().into_iter();
+        let mut decoded = Vec::with_capacity(self.original_domain.len());
+        for var in self.original_domain.variables() {
+            if let Range::Categorical { choices } = var.range() {
+                let values = params.by_ref().take(choices.len()).collect::<Vec<_>>();
+                track_assert_eq!(values.len(), choices.len(), ErrorKind::InvalidInput);
+
+                let mut argmax = 0;
+                for (i, &v) in values.iter().enumerate() {
+                    if v > values[argmax] {
+                        argmax = i;
+                    }
+                }
+                decoded.push(argmax as f64);
+            } else {
+                let value = track_assert_some!(params.next(), ErrorKind::InvalidInput);
+                decoded.push(value);
+            }
+        }
+        track_assert!(params.next().is_none(), ErrorKind::InvalidInput);
+
+        let evaluator = track!(self.problem.create_evaluator(Params::new(decoded)))?;
+        Ok(OnehotEvaluator { evaluator })
+    }
+}
+
+#[derive(Debug)]
+pub struct OnehotEvaluator {
+    evaluator: BoxEvaluator,
+}
+
+impl Evaluator for OnehotEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        track!(self.evaluator.evaluate(next_step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::KurobakoProblemRecipe;
+    use crate::solver::KurobakoSolverRecipe;
+    use kurobako_core::Error;
+    use serde_json::json;
+    use std::io::Write as _;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn onehot_encoding_works() -> TopLevelResult {
+        let mut file = tempfile::NamedTempFile::new().map_err(Error::from)?;
+        writeln!(
+            file,
+            "optimizer,lr,loss\nadam,0.1,0.5\nsgd,0.1,0.3\nrmsprop,0.1,0.2"
+        )
+        .map_err(Error::from)?;
+        let recipe = OnehotProblemRecipe {
+            problem: json!({"table": {
+                "path": file.path(),
+                "param_columns": ["optimizer", "lr"],
+                "value_columns": ["loss"]
+            }}),
+        };
+
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let factory = recipe.create_factory(&registry)?;
+        let spec = factory.specification()?;
+        let names = spec
+            .params_domain
+            .variables()
+            .iter()
+            .map(|v| v.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["optimizer=adam", "optimizer=rmsprop", "optimizer=sgd", "lr"]
+        );
+        assert!(spec.params_domain.variables()[..3]
+            .iter()
+            .all(|v| v.range().low() == 0.0 && v.range().high() == 1.0));
+        assert_eq!(
+            spec.attrs.get("categorical_encoding").map(|s| s.as_str()),
+            Some("onehot")
+        );
+
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        let evaluate = |params: Vec<f64>| -> Result<Values> {
+            let mut evaluator = track!(problem.create_evaluator(Params::new(params)))?;
+            track!(evaluator.evaluate(1)).map(|(_, values)| values)
+        };
+        assert_eq!(evaluate(vec![0.1, 0.2, 0.9, 0.1])?.into_vec(), [0.3]);
+        assert_eq!(evaluate(vec![0.1, 0.9, 0.3, 0.1])?.into_vec(), [0.2]);
+
+        // Ties are broken by the order of the choices.
+        assert_eq!(evaluate(vec![0.5, 0.5, 0.5, 0.1])?.into_vec(), [0.5]);
+
+        // The number of the parameters must match the encoded domain.
+        let e = evaluate(vec![0.1, 0.9, 0.1]).expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        let e = evaluate(vec![0.1, 0.9, 0.1, 0.1, 0.1]).expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+}
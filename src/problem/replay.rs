@@ -0,0 +1,292 @@
+use crate::record::StudyRecord;
+use kurobako_core::domain::{Range, Variable};
+use kurobako_core::json;
+use kurobako_core::num::OrderedFloat;
+use kurobako_core::problem::{
+    EvaluableSteps, Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec,
+};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::trial::{Params, Values};
+use kurobako_core::{Error, ErrorKind, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+/// Recipe for a problem that replays the trials recorded in study records.
+///
+/// The final values of the complete trials in the records are returned for the matching parameters.
+/// If there is no matching trial, the values of the nearest recorded trial are returned
+/// (the distance is measured in the parameter space normalized by the range of each variable).
+///
+/// Note that intermediate values aren't replayed,
+/// so the problem can be evaluated only at the last step of the recorded problem.
+#[derive(Debug, Clone, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ReplayProblemRecipe {
+    /// Path of the study records (all of the studies must share the same problem).
+    pub records: PathBuf,
+
+    /// If this flag is set, unrecorded parameters result in `ErrorKind::UnevaluableParams` errors
+    /// instead of falling back to the nearest recorded trial.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exact: bool,
+}
+impl ProblemRecipe for ReplayProblemRecipe {
+    type Factory = ReplayProblemFactory;
+
+    fn create_factory(&self, _registry: &FactoryRegistry) -> Result<Self::Factory> {
+        let file = track!(File::open(&self.records).map_err(Error::from); self.records)?;
+        let records: Vec<serde_json::Value> =
+            track!(json::load(BufReader::new(file)); self.records)?;
+        let studies = records
+            .into_iter()
+            .map(|record| track!(StudyRecord::migrate(record)))
+            .collect::<Result<Vec<_>>>()?;
+        track_assert!(
+            !studies.is_empty(),
+            ErrorKind::InvalidInput,
+            "No study records: {:?}",
+            self.records
+        );
+
+        let problem_id = track!(studies[0].problem.id())?;
+        let mut spec = studies[0].problem.spec.clone();
+        let last_step = spec.steps.last();
+
+        let mut trials = Vec::new();
+        let mut lookup = HashMap::new();
+        for study in &studies {
+            track_assert_eq!(
+                track!(study.problem.id())?,
+                problem_id,
+                ErrorKind::InvalidInput,
+                "The study records contain multiple problems"
+            );
+
+            for trial in &study.trials {
+                if trial.end_step() != Some(last_step) {
+                    continue;
+                }
+                let values = match trial.values(last_step) {
+                    Some(values) if !values.is_empty() => Values::new(values.to_vec()),
+                    _ => continue,
+                };
+                let key = trial.params.iter().copied().map(OrderedFloat).collect();
+                lookup.entry(key).or_insert(trials.len());
+                trials.push((trial.params.clone(), values));
+            }
+        }
+        track_assert!(
+            !trials.is_empty(),
+            ErrorKind::InvalidInput,
+            "No complete trials: {:?}",
+            self.records
+        );
+
+        spec.name = format!("Replay: {}", spec.name);
+        spec.steps = track!(EvaluableSteps::new(vec![last_step]))?;
+        Ok(ReplayProblemFactory {
+            spec,
+            trials: Arc::new(ReplayTrials { trials, lookup }),
+            exact: self.exact,
+        })
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !(*b)
+}
+
+#[derive(Debug)]
+pub struct ReplayProblemFactory {
+    spec: ProblemSpec,
+    trials: Arc<ReplayTrials>,
+    exact: bool,
+}
+impl ProblemFactory for ReplayProblemFactory {
+    type Problem = ReplayProblem;
+
+    fn specification(&self) -> Result<ProblemSpec> {
+        Ok(self.spec.clone())
+    }
+
+    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+        Ok(ReplayProblem {
+            vars: self.spec.params_domain.variables().to_vec(),
+            trials: Arc::clone(&self.trials),
+            exact: self.exact,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ReplayTrials {
+    trials: Vec<(Params, Values)>,
+
+    // Params => the index of the first trial that has the parameters.
+    lookup: HashMap<Vec<OrderedFloat<f64>>, usize>,
+}
+impl ReplayTrials {
+    fn get(&self, params: &[f64]) -> Option<&Values> {
+        let key = params.iter().copied().map(OrderedFloat).collect::<Vec<_>>();
+        self.lookup.get(&key).map(|&i| &self.trials[i].1)
+    }
+
+    fn nearest(&self, vars: &[Variable], params: &[f64]) -> &Values {
+        let distance = |other: &[f64]| {
+            vars.iter()
+                .zip(params.iter().zip(other.iter()))
+                .map(|(var, (&a, &b))| match var.range() {
+                    Range::Categorical { .. } | Range::Ordinal { .. } if a != b => 1.0,
+                    Range::Categorical { .. } | Range::Ordinal { .. } => 0.0,
+                    range => {
+                        let width = range.high() - range.low();
+                        let d = if width.is_finite() && width > 0.0 {
+                            (a - b) / width
+                        } else {
+                            a - b
+                        };
+                        d * d
+                    }
+                })
+                .sum::<f64>()
+        };
+        self.trials
+            .iter()
+            .map(|(p, v)| (OrderedFloat(distance(p)), v))
+            .min_by_key(|(d, _)| *d)
+            .map(|(_, v)| v)
+            .unwrap_or_else(|| unreachable!())
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayProblem {
+    vars: Vec<Variable>,
+    trials: Arc<ReplayTrials>,
+    exact: bool,
+}
+impl Problem for ReplayProblem {
+    type Evaluator = ReplayEvaluator;
+
+    fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
+        let values = if let Some(values) = self.trials.get(params.get()) {
+            values.clone()
+        } else {
+            track_assert!(
+                !self.exact,
+                ErrorKind::UnevaluableParams,
+                "Unrecorded parameters: {:?}",
+                params
+            );
+            self.trials.nearest(&self.vars, params.get()).clone()
+        };
+        Ok(ReplayEvaluator { values })
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayEvaluator {
+    values: Values,
+}
+impl Evaluator for ReplayEvaluator {
+    fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
+        Ok((next_step, self.values.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::KurobakoProblemRecipe;
+    use crate::record::{StudyRecordBuilder, TrialRecordBuilder};
+    use crate::solver::KurobakoSolverRecipe;
+    use crate::study::StudyRecipe;
+    use crate::time::ElapsedSeconds;
+    use kurobako_core::solver::{SolverFactory as _, SolverRecipe as _};
+    use kurobako_core::trial::TrialId;
+    use trackable::result::TopLevelResult;
+
+    // Writes a study record that contains two trials of the two-dimensional sphere function.
+    fn write_record(path: &std::path::Path, registry: &FactoryRegistry) -> Result<ProblemSpec> {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{
+                "solver": {"random": {}},
+                "problem": {"sigopt": {"name": "SPHERE", "dim": 2}},
+                "budget": 2,
+                "concurrency": 1,
+                "scheduling": "RANDOM",
+                "seed": 0
+            }"#
+        ))?;
+        let problem = track!(track!(study.problem.create_factory(registry))?.specification())?;
+        let solver = track!(track!(study.solver.create_factory(registry))?.specification())?;
+
+        let mut record = StudyRecordBuilder::new(study, solver, problem.clone());
+        for (i, (params, value)) in [([0.0, 0.0], 0.0), ([1.0, 2.0], 5.0)].iter().enumerate() {
+            record.add_trial(TrialRecordBuilder {
+                id: TrialId::new(i as u64),
+                thread_id: 0,
+                params: Params::new(params.to_vec()),
+                values: Values::new(vec![*value]),
+                start_step: 0,
+                end_step: 1,
+                ask_elapsed: ElapsedSeconds::zero(),
+                tell_elapsed: ElapsedSeconds::zero(),
+                evaluate_elapsed: ElapsedSeconds::zero(),
+            });
+        }
+
+        let file = track!(File::create(path).map_err(Error::from))?;
+        track!(serde_json::to_writer(file, &record.finish()).map_err(Error::from))?;
+        Ok(problem)
+    }
+
+    #[test]
+    fn replay_problem_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let path =
+            std::env::temp_dir().join(format!("kurobako-replay-{}.json", std::process::id()));
+        let recorded = write_record(&path, &registry)?;
+        let recipe = ReplayProblemRecipe {
+            records: path.clone(),
+            exact: true,
+        };
+        let factory = recipe.create_factory(&registry)?;
+        std::fs::remove_file(&path).map_err(Error::from)?;
+
+        let spec = factory.specification()?;
+        assert_eq!(spec.name, format!("Replay: {}", recorded.name));
+        assert_eq!(spec.params_domain, recorded.params_domain);
+        assert_eq!(spec.values_domain, recorded.values_domain);
+
+        let problem = factory.create_problem(ArcRng::new(0))?;
+        let mut evaluator = problem.create_evaluator(Params::new(vec![1.0, 2.0]))?;
+        assert_eq!(evaluator.evaluate(1)?, (1, Values::new(vec![5.0])));
+
+        // Unrecorded parameters.
+        let params = Params::new(vec![0.9, 2.5]);
+        assert_eq!(
+            problem
+                .create_evaluator(params.clone())
+                .err()
+                .map(|e| *e.kind()),
+            Some(ErrorKind::UnevaluableParams)
+        );
+
+        let problem = ReplayProblem {
+            exact: false,
+            ..problem
+        };
+        let mut evaluator = problem.create_evaluator(params)?;
+        assert_eq!(evaluator.evaluate(1)?, (1, Values::new(vec![5.0])));
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use kurobako_core::problem::{
     Evaluator, Problem, ProblemFactory, ProblemRecipe, ProblemSpec, ProblemSpecBuilder,
 };
 use kurobako_core::registry::FactoryRegistry;
-use kurobako_core::rng::ArcRng;
+use kurobako_core::rng::{ArcRng, Rng as _};
 use kurobako_core::solver::{SolverFactory as _, SolverRecipe as _, SolverSpec};
 use kurobako_core::trial::{Params, Values};
 use kurobako_core::{Error, ErrorKind, Result};
@@ -80,10 +80,11 @@ impl ProblemFactory for StudyProblemFactory {
         track!(spec.finish())
     }
 
-    fn create_problem(&self, _rng: ArcRng) -> Result<Self::Problem> {
+    fn create_problem(&self, rng: ArcRng) -> Result<Self::Problem> {
         Ok(StudyProblem {
             study: self.study.clone(),
             vars: self.vars.clone(),
+            rng,
         })
     }
 }
@@ -92,6 +93,7 @@ impl ProblemFactory for StudyProblemFactory {
 pub struct StudyProblem {
     study: JsonRecipe,
     vars: Vec<Var>,
+    rng: ArcRng,
 }
 impl StudyProblem {
     fn bind(&self, vals: &[f64]) -> Result<JsonRecipe> {
@@ -123,11 +125,11 @@ impl StudyProblem {
                 let n = track_assert_some!(serde_json::Number::from_f64(val), ErrorKind::InvalidInput; val);
                 *json = serde_json::Value::Number(n);
             }
-            Range::Discrete { .. } => {
+            Range::Discrete { .. } | Range::IntChoices { .. } => {
                 let n = serde_json::Number::from(val as i64);
                 *json = serde_json::Value::Number(n);
             }
-            Range::Categorical { choices } => {
+            Range::Categorical { choices } | Range::Ordinal { choices } => {
                 *json = serde_json::Value::String(choices[val as usize].clone());
             }
         }
@@ -139,22 +141,33 @@ impl Problem for StudyProblem {
 
     fn create_evaluator(&self, params: Params) -> Result<Self::Evaluator> {
         let study = track!(self.bind(params.get()))?;
-        let study: StudyRecipe =
+        let mut study: StudyRecipe =
             track!(serde_json::from_value(study.clone()).map_err(Error::from); study)?;
+        if study.seed.is_none() {
+            study.seed = Some(track!(self.rng.with_lock(|rng| rng.gen()))?);
+        }
         let mut runner = track!(StudyRunner::new(&study))?;
         track!(runner.run_init())?;
-        Ok(StudyEvaluator { runner })
+        Ok(StudyEvaluator {
+            runner,
+            rng: self.rng.clone(),
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct StudyEvaluator {
     runner: StudyRunner,
+    rng: ArcRng,
 }
 impl Evaluator for StudyEvaluator {
     fn evaluate(&mut self, next_step: u64) -> Result<(u64, Values)> {
         loop {
             track!(self.runner.run_once())?;
+            if self.runner.unseeded_rng_used() {
+                // The inner study cannot be replayed by its seed, and so can't the outer one.
+                self.rng.mark_unseeded_rng_used();
+            }
             if self.runner.current_step() < next_step {
                 continue;
             }
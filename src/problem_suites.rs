@@ -1,6 +1,6 @@
 //! Built-in problem suites.
 use crate::problem::KurobakoProblemRecipe;
-use kurobako_problems::{hpobench, sigopt, surrogate, zdt};
+use kurobako_problems::{dtlz, hpobench, sigopt, surrogate, zdt};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -12,6 +12,7 @@ pub enum ProblemSuite {
     Sigopt(SigoptProblemSuite),
     Hpobench(HpobenchProblemSuite),
     Zdt(ZdtProblemSuite),
+    Dtlz(DtlzProblemSuite),
     Surrogate(SurrogateProblemSuite),
 }
 impl ProblemSuite {
@@ -21,6 +22,7 @@ impl ProblemSuite {
             Self::Sigopt(s) => s.recipes(),
             Self::Hpobench(s) => s.recipes(),
             Self::Zdt(s) => s.recipes(),
+            Self::Dtlz(s) => s.recipes(),
             Self::Surrogate(s) => s.recipes(),
         }
     }
@@ -69,11 +71,42 @@ impl ZdtProblemSuite {
                 zdt::Zdt::Function6,
             ]
             .into_iter()
-            .map(|zdt| KurobakoProblemRecipe::from(zdt::ZdtProblemRecipe { zdt })),
+            .map(|zdt| {
+                KurobakoProblemRecipe::from(zdt::ZdtProblemRecipe {
+                    zdt,
+                    constrained: false,
+                })
+            }),
         )
     }
 }
 
+/// Problem suite containing problems for the DTLZ1-4 functions.
+///
+/// The number of dimensions of each problem is `objectives + k - 1`
+/// where `k` is `5` for DTLZ1 and `10` for the others as recommended in the paper.
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub struct DtlzProblemSuite {
+    /// Number of objectives.
+    #[structopt(long, default_value = "3")]
+    pub objectives: usize,
+}
+impl DtlzProblemSuite {
+    fn recipes(&self) -> Box<dyn Iterator<Item = KurobakoProblemRecipe>> {
+        let objectives = self.objectives;
+        Box::new((1..=4).map(move |problem| {
+            let k = if problem == 1 { 5 } else { 10 };
+            KurobakoProblemRecipe::from(dtlz::DtlzProblemRecipe {
+                problem,
+                objectives,
+                dimensions: objectives + k - 1,
+            })
+        }))
+    }
+}
+
 /// Problem suite defined in `https://github.com/sigopt/evalset`.
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -97,6 +130,8 @@ impl SigoptProblemSuite {
                 dim: Some(dim),
                 res,
                 int: int.unwrap_or_default(),
+                noise_stddev: None,
+                transform: Default::default(),
             }
         }
 
@@ -111,12 +146,13 @@ impl SigoptProblemSuite {
                 recipe(DeflectedCorrugatedSpring, 4, None, None),
                 recipe(DeflectedCorrugatedSpring, 7, None, None),
                 recipe(Easom, 2, None, None),
-                recipe(Easom, 4, None, None),
-                recipe(Easom, 5, None, None),
+                recipe(Griewank, 4, None, None),
                 recipe(Hartmann3, 3, Some(vec![0]), None),
                 recipe(Hartmann6, 6, None, Some(10.0)),
                 recipe(HelicalValley, 3, None, None),
                 recipe(LennardJones6, 6, None, None),
+                recipe(Levy, 5, None, None),
+                recipe(Levy13, 2, None, None),
                 recipe(McCourt01, 7, None, Some(10.0)),
                 recipe(McCourt03, 9, None, None),
                 recipe(McCourt06, 5, None, None),
@@ -152,6 +188,7 @@ impl SigoptProblemSuite {
                 recipe(Sargan, 2, Some(vec![0]), None),
                 recipe(Schwefel20, 2, None, None),
                 recipe(Schwefel20, 2, Some(vec![0]), None),
+                recipe(Schwefel26, 4, None, None),
                 recipe(Schwefel36, 2, None, None),
                 recipe(Shekel05, 4, None, None),
                 recipe(Sphere, 7, Some(vec![0, 1, 2, 3, 4]), None),
@@ -171,8 +208,7 @@ impl SigoptProblemSuite {
                 recipe(CarromTable, 2, Some(vec![0]), None),
                 recipe(Deb02, 6, None, None),
                 recipe(DeflectedCorrugatedSpring, 4, None, None),
-                recipe(Easom, 4, None, None),
-                recipe(Easom, 5, None, None),
+                recipe(Easom, 2, None, None),
                 recipe(Exponential, 6, None, None),
                 recipe(Hartmann3, 3, None, None),
                 recipe(LennardJones6, 6, None, None),
@@ -1,9 +1,12 @@
 pub use self::problem::ProblemRecord;
 pub use self::solver::SolverRecord;
-pub use self::study::{StudyRecord, StudyRecordBuilder};
+pub use self::study::{StudyRecord, StudyRecordBuilder, SCHEMA_VERSION};
 pub use self::trial::{EvaluationRecord, TrialRecord, TrialRecordBuilder};
 
 mod problem;
 mod solver;
 mod study;
 mod trial;
+
+#[cfg(test)]
+pub(crate) mod testing;
@@ -9,7 +9,7 @@ use kurobako_core::num::OrderedFloat;
 use kurobako_core::problem::ProblemSpec;
 use kurobako_core::solver::SolverSpec;
 use kurobako_core::trial::{Params, TrialId, Values};
-use kurobako_core::{Error, Result};
+use kurobako_core::{Error, ErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
@@ -26,6 +26,11 @@ pub struct StudyRecordBuilder {
     start_time: DateTime,
     trials: BTreeMap<TrialId, TrialRecord>,
     pareto_frontier: BTreeMap<TrialId, (Params, Values)>,
+    unseeded_rng_used: bool,
+    memoized_evaluations: u64,
+    target_reached_step: Option<u64>,
+    interrupted: bool,
+    timed_out: bool,
 }
 impl StudyRecordBuilder {
     pub fn new(recipe: StudyRecipe, solver: SolverSpec, problem: ProblemSpec) -> Self {
@@ -36,6 +41,11 @@ impl StudyRecordBuilder {
             start_time: Local::now(),
             trials: BTreeMap::new(),
             pareto_frontier: BTreeMap::new(),
+            unseeded_rng_used: false,
+            memoized_evaluations: 0,
+            target_reached_step: None,
+            interrupted: false,
+            timed_out: false,
         }
     }
 
@@ -56,27 +66,48 @@ impl StudyRecordBuilder {
         });
 
         if t.steps() == self.problem.steps.last() {
-            let is_dominated = self
-                .pareto_frontier
-                .values()
-                .any(|(_, vs)| vs.partial_cmp(&trial.values) == Some(Ordering::Less));
-            if !is_dominated {
-                let dominated = self
-                    .pareto_frontier
-                    .iter()
-                    .filter(|(_, (_, vs))| trial.values.partial_cmp(vs) == Some(Ordering::Less))
-                    .map(|(&id, _)| id)
-                    .collect::<Vec<_>>();
-
-                self.pareto_frontier
-                    .insert(trial.id, (trial.params, trial.values));
-                for id in dominated {
-                    self.pareto_frontier.remove(&id);
-                }
-            }
+            update_pareto_frontier(
+                &mut self.pareto_frontier,
+                trial.id,
+                trial.params,
+                trial.values,
+            );
         }
     }
 
+    /// Returns the trial ID namespace of this study.
+    ///
+    /// This is a prefix of the hash of the study recipe (including the random seed),
+    /// so parallel studies are very likely to have distinct namespaces.
+    pub fn trial_id_namespace(&self) -> Result<u32> {
+        let mut hasher = Sha256::new();
+        hasher.update(&track!(
+            serde_json::to_vec(&self.recipe).map_err(Error::from)
+        )?);
+        let hash = hasher.finalize();
+        Ok(u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]))
+    }
+
+    pub fn unseeded_rng_used(&mut self, used: bool) {
+        self.unseeded_rng_used |= used;
+    }
+
+    pub fn memoized_evaluations(&mut self, count: u64) {
+        self.memoized_evaluations = count;
+    }
+
+    pub fn target_reached(&mut self, step: u64) {
+        self.target_reached_step = Some(step);
+    }
+
+    pub fn interrupted(&mut self) {
+        self.interrupted = true;
+    }
+
+    pub fn timed_out(&mut self) {
+        self.timed_out = true;
+    }
+
     pub fn pareto_frontier(&self) -> impl '_ + Iterator<Item = (TrialId, &Params, &Values)> {
         self.pareto_frontier
             .iter()
@@ -85,10 +116,17 @@ impl StudyRecordBuilder {
 
     pub fn finish(self) -> StudyRecord {
         StudyRecord {
+            schema_version: SCHEMA_VERSION,
             start_time: self.start_time,
             end_time: Local::now(),
             budget: self.recipe.budget,
             seed: self.recipe.seed.unwrap_or_else(|| unreachable!()),
+            unseeded_rng_used: self.unseeded_rng_used,
+            memoized_evaluations: self.memoized_evaluations,
+            stop_on_target: self.recipe.stop_on_target,
+            target_reached_step: self.target_reached_step,
+            interrupted: self.interrupted,
+            timed_out: self.timed_out,
             concurrency: self.recipe.concurrency,
             scheduling: self.recipe.scheduling,
             solver: SolverRecord {
@@ -104,19 +142,121 @@ impl StudyRecordBuilder {
     }
 }
 
+/// Direction of the optimization of an objective.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Smaller values are better.
+    #[default]
+    Minimize,
+
+    /// Larger values are better.
+    Maximize,
+}
+
+/// Configuration of `StudyRecord::auc_with_config`.
+#[derive(Debug, Clone, Default)]
+pub struct AucConfig {
+    /// Steps before this are excluded from the area.
+    pub start_step: u64,
+
+    /// Direction of the objective.
+    pub direction: Direction,
+
+    /// Optimal value of the objective that is used as the baseline of the area.
+    ///
+    /// If `None`, the lower (or upper if maximized) bound of the objective range is used.
+    /// If the bound isn't finite, `0.0` is used instead.
+    pub optimum: Option<f64>,
+
+    /// Index of the objective.
+    ///
+    /// If `None`, the problem must be single-objective.
+    pub objective: Option<usize>,
+}
+
+/// The current schema version of `StudyRecord`.
+///
+/// # Version history
+///
+/// - `0`: Records that don't have the `schema_version` field.
+/// - `1`: Adds the `schema_version` field.
+///   The optional fields added before this version are filled with the following defaults
+///   when reading older records:
+///   `unseeded_rng_used` (`false`), `memoized_evaluations` (`0`),
+///   `stop_on_target` and `target_reached_step` (absent).
+/// - `2`: Adds the `interrupted` field (`false` when reading older records,
+///   because the studies of the older versions weren't marked even if they were interrupted).
+/// - `3`: Adds the `timed_out` field (`false` when reading older records,
+///   so the studies stopped by `--study-timeout` of the older versions aren't regarded as timed out).
+pub const SCHEMA_VERSION: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StudyRecord {
+    // See `SCHEMA_VERSION`.
+    #[serde(default)]
+    pub schema_version: u32,
+
     pub start_time: DateTime,
     pub end_time: DateTime,
     pub seed: u64,
+
+    // If `true`, some random numbers that cannot be reproduced by `seed` were used in the study
+    // (i.e., an exact replay of the study isn't guaranteed).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unseeded_rng_used: bool,
+
+    // Number of evaluations whose values were taken from the cache instead of invoking the evaluator
+    // (see `RunnerOpt::memoize_evaluations`).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub memoized_evaluations: u64,
+
     pub budget: u64,
     pub concurrency: NonZeroUsize,
     pub scheduling: Scheduling,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_target: Option<f64>,
+
+    // The step at which the best value reached `stop_on_target`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_reached_step: Option<u64>,
+
+    // If `true`, the study was stopped by `SIGINT` before consuming the budget
+    // (such a study is executed again by `kurobako run --resume`).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub interrupted: bool,
+
+    // If `true`, the study was stopped by `RunnerOpt::study_timeout` before consuming the budget.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub timed_out: bool,
+
     pub solver: SolverRecord,
     pub problem: ProblemRecord,
     pub trials: Vec<TrialRecord>,
 }
 impl StudyRecord {
+    /// Reads a record of the given (possibly older) schema version and upgrades it to the current one.
+    pub fn migrate(record: serde_json::Value) -> Result<Self> {
+        let version = record
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        track_assert!(
+            version <= u64::from(SCHEMA_VERSION),
+            ErrorKind::InvalidInput,
+            "Unsupported schema version: {} (the latest version is {})",
+            version,
+            SCHEMA_VERSION
+        );
+
+        // The fields added so far have default values,
+        // so the records of the older versions can be deserialized as is
+        // (see `SCHEMA_VERSION` for the defaults).
+        let mut record: Self = track!(serde_json::from_value(record).map_err(Error::from))?;
+        record.schema_version = SCHEMA_VERSION;
+        Ok(record)
+    }
+
     pub fn id(&self) -> Result<String> {
         let mut hasher = Sha256::new();
         hasher.update(&track!(
@@ -128,6 +268,9 @@ impl StudyRecord {
         hasher.update(&track!(
             serde_json::to_vec(&self.scheduling).map_err(Error::from)
         )?);
+        if let Some(target) = self.stop_on_target {
+            hasher.update(&track!(serde_json::to_vec(&target).map_err(Error::from))?);
+        }
         hasher.update(&track!(
             serde_json::to_vec(&self.solver).map_err(Error::from)
         )?);
@@ -142,10 +285,35 @@ impl StudyRecord {
         Ok(id)
     }
 
+    /// Returns the recipe of the study.
+    pub fn recipe(&self) -> StudyRecipe {
+        StudyRecipe {
+            solver: self.solver.recipe.clone(),
+            problem: self.problem.recipe.clone(),
+            budget: self.budget,
+            concurrency: self.concurrency,
+            scheduling: self.scheduling.clone(),
+            seed: Some(self.seed),
+            stop_on_target: self.stop_on_target,
+        }
+    }
+
     pub fn study_steps(&self) -> u64 {
         self.problem.spec.steps.last() * self.budget
     }
 
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    pub fn steps_to_target(&self) -> Option<u64> {
+        self.stop_on_target?;
+        Some(
+            self.target_reached_step
+                .unwrap_or_else(|| self.study_steps() + 1),
+        )
+    }
+
     pub fn best_values(&self) -> BTreeMap<u64, f64> {
         let mut best_values = BTreeMap::new();
 
@@ -174,7 +342,21 @@ impl StudyRecord {
         best_values
     }
 
-    pub fn hypervolumes(&self) -> BTreeMap<u64, f64> {
+    /// Returns the hypervolumes of the pareto frontiers at the steps where the frontier changes.
+    ///
+    /// If `reference_point` is `Some(_)`, it's used instead of the reference point of the problem (or the default one).
+    pub fn hypervolumes(&self, reference_point: Option<&[f64]>) -> Result<BTreeMap<u64, f64>> {
+        let objectives = self.problem.spec.values_domain.len();
+        if let Some(reference_point) = reference_point {
+            track_assert_eq!(
+                reference_point.len(),
+                objectives,
+                ErrorKind::InvalidInput,
+                "The arity of the reference point doesn't match the number of objectives: problem={:?}",
+                self.problem.spec.name
+            );
+        }
+
         let mut hypervolumes = BTreeMap::new();
 
         let problem_steps = self.problem.spec.steps.last();
@@ -192,19 +374,35 @@ impl StudyRecord {
 
         trials.sort_by_key(|t| t.0);
 
-        let ref_pt = match &self.problem.spec.reference_point {
-            Some(reference_point) => reference_point.to_vec(),
-            None => vec![100.0; self.problem.spec.values_domain.len()],
+        // If the problem defines the ideal and nadir points, the objective values are normalized
+        // so that the ideal point becomes `0.0` and the nadir point becomes `1.0` in each objective.
+        let normalization = track!(self.problem.spec.normalization_points())?;
+        let normalize = |values: &[f64]| -> Vec<f64> {
+            if let Some((ideal, nadir)) = &normalization {
+                values
+                    .iter()
+                    .zip(ideal.iter().zip(nadir.iter()))
+                    .map(|(v, (i, n))| (v - i) / (n - i))
+                    .collect()
+            } else {
+                values.to_vec()
+            }
+        };
+
+        let ref_pt = match reference_point.or(self.problem.spec.reference_point.as_deref()) {
+            Some(reference_point) => normalize(reference_point),
+            None if normalization.is_some() => vec![1.1; objectives],
+            None => vec![100.0; objectives],
         };
 
         let mut pts = Vec::new();
         for (step, values) in trials {
-            pts.push(values.to_vec());
+            pts.push(normalize(values));
             let hv = hypervolume::compute(&pts, &ref_pt);
             hypervolumes.insert(step, hv);
         }
 
-        hypervolumes
+        Ok(hypervolumes)
     }
 
     pub fn elapsed_times(&self, include_evaluate_time: bool) -> BTreeMap<u64, f64> {
@@ -277,6 +475,31 @@ impl StudyRecord {
         Iter { queue }
     }
 
+    /// Returns the objective values of the non-dominated complete trials.
+    pub fn pareto_frontier(&self) -> Vec<&[f64]> {
+        let problem_steps = self.problem.spec.steps.last();
+        let mut frontier = BTreeMap::new();
+        for (i, vs) in self
+            .trials
+            .iter()
+            .filter_map(|t| t.values(problem_steps))
+            .enumerate()
+        {
+            update_pareto_frontier(&mut frontier, i, vs, Values::new(vs.to_vec()));
+        }
+        frontier.into_values().map(|(vs, _)| vs).collect()
+    }
+
+    /// Returns the objective value of the last complete trial (i.e., the one that finished at the latest step).
+    pub fn final_value(&self) -> Option<f64> {
+        let problem_steps = self.problem.spec.steps.last();
+        self.trials
+            .iter()
+            .filter_map(|t| Some((t.end_step()?, t.value(problem_steps)?)))
+            .max_by_key(|(step, _)| *step)
+            .map(|(_, value)| value)
+    }
+
     pub fn best_value(&self) -> Option<f64> {
         let problem_steps = self.problem.spec.steps.last();
         self.trials
@@ -287,31 +510,24 @@ impl StudyRecord {
             .map(|x| x.0)
     }
 
+    /// Returns the area under the curve of the best values of the study (see `AucConfig` for the defaults).
     pub fn auc(&self, start_step: u64) -> Option<f64> {
-        let vars = self.problem.spec.values_domain.variables();
-        if vars.len() != 1 {
-            return None;
-        }
-
-        let mut global_min = vars[0].range().low();
-        if !global_min.is_finite() {
-            global_min = 0.0;
-        }
+        self.auc_with_config(&AucConfig {
+            start_step,
+            ..AucConfig::default()
+        })
+    }
 
+    /// Returns the area under the curve of the gap between the best values and the optimum.
+    ///
+    /// The area is normalized by the number of steps of the problem,
+    /// and `None` is returned if the target objective isn't available
+    /// or no trial finished by `config.start_step`.
+    pub fn auc_with_config(&self, config: &AucConfig) -> Option<f64> {
+        let trials = self.gaps(config)?;
         let problem_steps = self.problem.spec.steps.last();
-        let mut trials = self
-            .trials
-            .iter()
-            .filter_map(|t| {
-                if let (Some(step), Some(value)) = (t.end_step(), t.value(problem_steps)) {
-                    Some((step, value))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-        trials.sort_by_key(|t| t.0);
 
+        let start_step = config.start_step;
         let mut prev_step = 0;
         let mut current_min = f64::INFINITY;
         let mut auc = 0.0;
@@ -322,7 +538,7 @@ impl StudyRecord {
                 if prev_step == 0 {
                     return None;
                 }
-                auc += (current_min - global_min) * (step - prev_step) as f64;
+                auc += current_min * (step - prev_step) as f64;
             }
 
             if value < current_min {
@@ -332,11 +548,97 @@ impl StudyRecord {
         }
 
         let study_steps = self.budget * problem_steps;
-        auc += (current_min - global_min) * (study_steps - prev_step) as f64;
+        auc += current_min * (study_steps - prev_step) as f64;
 
         Some(auc / problem_steps as f64)
     }
 
+    /// Returns the running areas under the curve of the best values of the study
+    /// (i.e., the areas from `start_step` to each step).
+    ///
+    /// The value at the last step is equal to `self.auc(start_step)`.
+    /// If no trial finished by `start_step`, an empty map is returned.
+    pub fn aucs(&self, start_step: u64) -> BTreeMap<u64, f64> {
+        let config = AucConfig {
+            start_step,
+            ..AucConfig::default()
+        };
+        let mut aucs = BTreeMap::new();
+        let Some(trials) = self.gaps(&config) else {
+            return aucs;
+        };
+        let mut trials = trials.into_iter().peekable();
+
+        let mut current_min = f64::INFINITY;
+        while let Some((_, value)) = trials.next_if(|t| t.0 <= start_step) {
+            current_min = current_min.min(value);
+        }
+        if current_min == f64::INFINITY {
+            return aucs;
+        }
+
+        let problem_steps = self.problem.spec.steps.last();
+        let study_steps = self.budget * problem_steps;
+        let mut auc = 0.0;
+        aucs.insert(start_step, auc);
+        for step in start_step + 1..=study_steps {
+            auc += current_min;
+            while let Some((_, value)) = trials.next_if(|t| t.0 <= step) {
+                current_min = current_min.min(value);
+            }
+            aucs.insert(step, auc / problem_steps as f64);
+        }
+        aucs
+    }
+
+    // Returns the `(end step, gap from the optimum)` pairs of the complete trials sorted by the steps.
+    fn gaps(&self, config: &AucConfig) -> Option<Vec<(u64, f64)>> {
+        let vars = self.problem.spec.values_domain.variables();
+        let objective = match config.objective {
+            Some(i) if i < vars.len() => i,
+            None if vars.len() == 1 => 0,
+            _ => return None,
+        };
+
+        let optimum = config.optimum.unwrap_or_else(|| {
+            let range = vars[objective].range();
+            let bound = match config.direction {
+                Direction::Minimize => range.low(),
+                Direction::Maximize => range.high(),
+            };
+            if bound.is_finite() {
+                bound
+            } else {
+                0.0
+            }
+        });
+        let gap = |value: f64| match config.direction {
+            Direction::Minimize => value - optimum,
+            Direction::Maximize => optimum - value,
+        };
+
+        let problem_steps = self.problem.spec.steps.last();
+        let mut trials = self
+            .trials
+            .iter()
+            .filter_map(|t| {
+                let value = t.values(problem_steps)?.get(objective)?;
+                Some((t.end_step()?, gap(*value)))
+            })
+            .collect::<Vec<_>>();
+        trials.sort_by_key(|t| t.0);
+        Some(trials)
+    }
+
+    /// Returns the number of the trials that have been evaluated until the last step of the problem.
+    pub fn complete_trials(&self) -> usize {
+        let problem_steps = self.problem.spec.steps.last();
+        self.trials
+            .iter()
+            .filter(|t| t.values(problem_steps).is_some())
+            .count()
+    }
+
     pub fn solver_elapsed(&self) -> Duration {
         self.trials.iter().map(|t| t.solver_elapsed()).sum()
     }
@@ -349,3 +651,175 @@ impl StudyRecord {
             .min_by_key(|t| t.start_step())
     }
 }
+
+// Adds an entry to `frontier` unless it's dominated, and removes the entries dominated by it.
+fn update_pareto_frontier<K: Ord + Copy, T>(
+    frontier: &mut BTreeMap<K, (T, Values)>,
+    key: K,
+    item: T,
+    values: Values,
+) {
+    let is_dominated = frontier
+        .values()
+        .any(|(_, vs)| vs.partial_cmp(&values) == Some(Ordering::Less));
+    if is_dominated {
+        return;
+    }
+
+    frontier.retain(|_, (_, vs)| values.partial_cmp(vs) != Some(Ordering::Less));
+    frontier.insert(key, (item, values));
+}
+
+fn is_false(&b: &bool) -> bool {
+    !b
+}
+
+fn is_zero(&n: &u64) -> bool {
+    n == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing::{record, record_with_spec};
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn migrate_works() -> TopLevelResult {
+        let record = record("test", "test", 2, &[1.0, 0.5])?;
+        let mut old = serde_json::to_value(&record).map_err(Error::from)?;
+        let fields = old.as_object_mut().expect("not an object");
+
+        // The fields that aren't in the records of the version 0.
+        for key in &[
+            "schema_version",
+            "unseeded_rng_used",
+            "memoized_evaluations",
+            "stop_on_target",
+            "target_reached_step",
+            "interrupted",
+            "timed_out",
+        ] {
+            fields.remove(*key);
+        }
+
+        let migrated = StudyRecord::migrate(old.clone())?;
+        assert_eq!(migrated.schema_version, SCHEMA_VERSION);
+        assert!(!migrated.unseeded_rng_used);
+        assert_eq!(migrated.memoized_evaluations, 0);
+        assert_eq!(migrated.stop_on_target, None);
+        assert_eq!(migrated.target_reached_step, None);
+        assert_eq!(migrated.id()?, record.id()?);
+        assert_eq!(migrated.trials.len(), record.trials.len());
+
+        old["schema_version"] = serde_json::json!(0);
+        assert_eq!(
+            StudyRecord::migrate(old.clone())?.schema_version,
+            SCHEMA_VERSION
+        );
+
+        // The version 1 doesn't have `interrupted` and the version 2 doesn't have `timed_out`.
+        for version in 1..SCHEMA_VERSION {
+            old["schema_version"] = serde_json::json!(version);
+            let migrated = StudyRecord::migrate(old.clone())?;
+            assert_eq!(migrated.schema_version, SCHEMA_VERSION);
+            assert!(!migrated.interrupted);
+            assert!(!migrated.timed_out);
+        }
+
+        old["schema_version"] = serde_json::json!(SCHEMA_VERSION + 1);
+        let e = StudyRecord::migrate(old).expect_err("should be rejected");
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn pareto_frontier_works() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("test")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish()?;
+        let values = [
+            vec![1.0, 3.0],
+            vec![2.0, 2.0],
+            vec![2.0, 4.0],
+            vec![3.0, 1.0],
+            vec![0.5, 3.5],
+            vec![2.0, 2.0],
+        ];
+        let record = record_with_spec(problem, "test", 6, &values)?;
+        assert_eq!(
+            record.pareto_frontier(),
+            [
+                &[1.0, 3.0][..],
+                &[2.0, 2.0],
+                &[3.0, 1.0],
+                &[0.5, 3.5],
+                &[2.0, 2.0]
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn normalized_hypervolumes_work() -> TopLevelResult {
+        let problem = ProblemSpecBuilder::new("test")
+            .attr("ideal_point", "[0.0, 0.0]")
+            .attr("nadir_point", "[1.0, 100.0]")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y0"))
+            .value(domain::var("y1"))
+            .finish()?;
+        let values = [vec![0.5, 50.0], vec![0.25, 75.0], vec![2.0, 200.0]];
+        let record = record_with_spec(problem, "test", 3, &values)?;
+
+        // The normalized points are `[0.5, 0.5]` and `[0.25, 0.75]` (the last one is out of the reference point),
+        // and the default reference point is `[1.1, 1.1]`.
+        let hypervolumes = record.hypervolumes(None)?;
+        let expected = [(1, 0.36), (2, 0.36 + 0.85 * 0.35 - 0.6 * 0.35), (3, 0.4475)];
+        assert_eq!(hypervolumes.len(), expected.len());
+        for (step, hv) in &expected {
+            assert!((hypervolumes[step] - hv).abs() < 1e-9, "{:?}", hypervolumes);
+        }
+
+        // Explicit reference points are normalized too.
+        let hypervolumes = record.hypervolumes(Some(&[1.0, 100.0]))?;
+        assert!((hypervolumes[&1] - 0.25).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn auc_works() -> TopLevelResult {
+        let record = track!(record("test", "test", 4, &[3.0, 1.0, 2.0, 0.5]))?;
+
+        // The objective range is unbounded, so `0.0` is used as the optimum by default.
+        assert_eq!(record.auc(1), Some(3.0 + 1.0 + 1.0));
+        assert_eq!(record.auc(2), Some(1.0 + 1.0));
+
+        let config = AucConfig {
+            start_step: 1,
+            optimum: Some(0.5),
+            ..AucConfig::default()
+        };
+        assert_eq!(record.auc_with_config(&config), Some(2.5 + 0.5 + 0.5));
+
+        let config = AucConfig {
+            start_step: 1,
+            direction: Direction::Maximize,
+            optimum: Some(4.0),
+            ..AucConfig::default()
+        };
+        assert_eq!(record.auc_with_config(&config), Some(1.0 + 1.0 + 1.0));
+
+        let config = AucConfig {
+            start_step: 1,
+            objective: Some(1),
+            ..AucConfig::default()
+        };
+        assert_eq!(record.auc_with_config(&config), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! Helpers to make study records in tests.
+use super::{StudyRecord, StudyRecordBuilder, TrialRecordBuilder};
+use crate::study::StudyRecipe;
+use crate::time::ElapsedSeconds;
+use kurobako_core::domain;
+use kurobako_core::json;
+use kurobako_core::problem::{ProblemSpec, ProblemSpecBuilder};
+use kurobako_core::solver::SolverSpecBuilder;
+use kurobako_core::trial::{Params, TrialId, Values};
+use kurobako_core::Result;
+
+/// Makes a record of a study whose trials have the given values (one trial per step).
+///
+/// The problem has a parameter and an objective whose range is unbounded.
+pub fn record(problem: &str, solver: &str, budget: u64, values: &[f64]) -> Result<StudyRecord> {
+    let problem = track!(ProblemSpecBuilder::new(problem)
+        .param(domain::var("x").continuous(0.0, 1.0))
+        .value(domain::var("y"))
+        .finish())?;
+    let values = values.iter().map(|&v| vec![v]).collect::<Vec<_>>();
+    track!(record_with_spec(problem, solver, budget, &values))
+}
+
+/// Makes a record of a study of the given problem whose trials have the given values (one trial per step).
+pub fn record_with_spec(
+    problem: ProblemSpec,
+    solver: &str,
+    budget: u64,
+    values: &[Vec<f64>],
+) -> Result<StudyRecord> {
+    let recipe: StudyRecipe = track!(json::parse_json(&format!(
+        r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"SPHERE"}}}},
+            "budget":{},"concurrency":1,"scheduling":"RANDOM","seed":0}}"#,
+        budget
+    )))?;
+    let solver = SolverSpecBuilder::new(solver).finish();
+    let params = vec![0.5; problem.params_domain.len()];
+
+    let mut builder = StudyRecordBuilder::new(recipe, solver, problem);
+    for (i, values) in values.iter().enumerate() {
+        builder.add_trial(TrialRecordBuilder {
+            id: TrialId::new(i as u64),
+            thread_id: 0,
+            params: Params::new(params.clone()),
+            values: Values::new(values.clone()),
+            start_step: i as u64,
+            end_step: i as u64 + 1,
+            ask_elapsed: ElapsedSeconds::zero(),
+            tell_elapsed: ElapsedSeconds::zero(),
+            evaluate_elapsed: ElapsedSeconds::zero(),
+        });
+    }
+    Ok(builder.finish())
+}
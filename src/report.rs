@@ -1,21 +1,31 @@
 //! `kurobako report` command.
-use self::rankings::{Borda, Firsts};
+use self::quantile::{quantile, P2Quantile};
+use self::rankings::{wilcoxon_signed_rank, Borda, Firsts, Score};
+use self::stability::Stability;
+use crate::filter::ProblemFilterOpt;
 use crate::markdown as md;
 use crate::markdown::MarkdownWriter;
 use crate::record::{ProblemRecord, SolverRecord, StudyRecord};
-use kurobako_core::num::OrderedFloat;
+use kurobako_core::num::{self, OrderedFloat, SummaryStats};
 use kurobako_core::{Error, ErrorKind, Result};
-use rustats::fundamental::{average, stddev};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustats::fundamental::average;
 use rustats::hypothesis_testings::MannWhitneyU;
 use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
+use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::time::Duration;
 use structopt::StructOpt;
 
+mod quantile;
 mod rankings;
+mod stability;
 
 /// Options of the `kurobako report` command.
 #[derive(Debug, StructOpt, Serialize)]
@@ -27,6 +37,241 @@ pub struct ReportOpt {
         possible_values = Metric::POSSIBLE_VALUES
     )]
     pub metrics: Vec<Metric>,
+
+    /// Makes timed-out studies contribute to rankings instead of being excluded.
+    ///
+    /// A study is regarded as timed out if it was stopped by `--study-timeout` of `kurobako run`
+    /// (studies stopped by `stop_on_target` or interrupted by `SIGINT` aren't).
+    /// If this flag is set, such a study is treated as having achieved its last-known best value
+    /// (and AUC is computed as if the best value had been kept until the end of the budget).
+    /// If the study has no complete trials, `--worst-value` is used as its best value instead
+    /// and its AUC is regarded as infinity.
+    ///
+    /// If this flag isn't set, a study without complete trials is excluded from the comparisons.
+    #[structopt(long)]
+    pub timeout_as_worst: bool,
+
+    /// Value assigned to timed-out studies that have no complete trials.
+    ///
+    /// This is only used if `--timeout-as-worst` is specified.
+    ///
+    /// If omitted, the upper bound of the objective range of the problem is used
+    /// (or infinity if the range is unbounded).
+    #[structopt(long)]
+    pub worst_value: Option<f64>,
+
+    /// Adds a section that analyzes the stability of the rankings under resampling of seeds.
+    ///
+    /// For each problem, the best values of the studies are left out one seed at a time,
+    /// and solvers are ranked by the means of the remaining best values.
+    /// The stability score of a solver is the fraction of such resamples in which
+    /// the ranking of the solver is unchanged.
+    /// Low scores indicate that the number of repeats is insufficient to trust the ranking.
+    #[structopt(long)]
+    pub stability: bool,
+
+    /// Checks that the studies form a complete factorial design before reporting.
+    ///
+    /// If some `(solver, problem, seed)` combinations are missing, they are printed to the standard error
+    /// and the command fails without generating a report.
+    /// If no seed is shared among studies (i.e., the studies weren't generated with `kurobako studies --seed`),
+    /// it's checked that every `(solver, problem)` pair has the same number of studies instead.
+    #[structopt(long)]
+    pub check_complete: bool,
+
+    /// Method used to compare two solvers on a problem.
+    ///
+    /// - `mann-whitney-u`: Mann-Whitney U test. Solvers whose results aren't significantly different are tied.
+    /// - `wilcoxon`: Wilcoxon signed-rank test on the results paired by seeds.
+    ///   This is more powerful than `mann-whitney-u` if the solvers are run with the same seeds.
+    ///   If the seeds of two solvers don't align, the Mann-Whitney U test is used instead.
+    /// - `mean`: Compares the means of the results without any significance test.
+    ///   This is useful when the number of repeats is too small for the test to detect differences.
+    #[structopt(
+        long,
+        default_value = "mann-whitney-u",
+        possible_values = RankingTest::POSSIBLE_VALUES
+    )]
+    pub ranking_test: RankingTest,
+
+    /// Family-wise significance level of the Mann-Whitney U (or Wilcoxon signed-rank) tests.
+    ///
+    /// The significance level of each test is adjusted according to the number of solvers.
+    #[structopt(
+        long,
+        default_value = "0.05",
+        possible_values = Alpha::POSSIBLE_VALUES
+    )]
+    pub alpha: Alpha,
+
+    /// Output format of the report.
+    ///
+    /// - `markdown`: Human-readable report.
+    /// - `json`: Machine-readable summary of the rankings (the same numbers as the markdown report).
+    #[structopt(
+        long,
+        default_value = "markdown",
+        possible_values = ReportFormat::POSSIBLE_VALUES
+    )]
+    pub format: ReportFormat,
+
+    /// Aggregation of the results of the studies shown in the individual results.
+    ///
+    /// - `mean`: `average +- standard deviation`.
+    /// - `median`: `median (25th percentile, 75th percentile)`.
+    ///   This is more robust than `mean` if the distributions of the results are skewed (e.g., by outliers).
+    ///
+    /// Note that this doesn't affect the rankings.
+    #[structopt(
+        long,
+        default_value = "mean",
+        possible_values = Aggregation::POSSIBLE_VALUES
+    )]
+    pub aggregation: Aggregation,
+
+    /// Adds a "Best 95% CI" column to the individual results.
+    ///
+    /// The column shows the 95% bootstrap confidence interval of the mean best value of each solver,
+    /// which is computed by resampling the studies with replacement the given number of times.
+    /// The resampling is seeded by the report ID, so the intervals are reproducible.
+    #[structopt(long)]
+    pub bootstrap: Option<NonZeroUsize>,
+
+    /// Problem filter.
+    #[structopt(flatten)]
+    #[serde(skip)]
+    pub filter: ProblemFilterOpt,
+}
+
+/// Output format of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[allow(missing_docs)]
+pub enum ReportFormat {
+    Markdown,
+    Json,
+}
+impl ReportFormat {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["markdown", "json"];
+}
+impl FromStr for ReportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "markdown" => Ok(ReportFormat::Markdown),
+            "json" => Ok(ReportFormat::Json),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown report format: {:?}", s),
+        }
+    }
+}
+
+/// Aggregation of the results of studies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[allow(missing_docs)]
+pub enum Aggregation {
+    Mean,
+    Median,
+}
+impl Aggregation {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["mean", "median"];
+
+    fn label(self) -> &'static str {
+        match self {
+            Aggregation::Mean => "avg +- sd",
+            Aggregation::Median => "median (p25, p75)",
+        }
+    }
+
+    // NaNs are excluded from the summary, and their number is appended if any.
+    fn summarize(self, xs: &[f64], precision: usize) -> String {
+        let xs_without_nan = num::sanitize(xs);
+        let mut summary = match (self, SummaryStats::new(&xs_without_nan)) {
+            (_, None) => "-".to_owned(),
+            (Aggregation::Mean, Some(stats)) => {
+                format!("{:.*} +- {:.*}", precision, stats.mean, precision, stats.sd)
+            }
+            (Aggregation::Median, Some(_)) => format!(
+                "{:.*} ({:.*}, {:.*})",
+                precision,
+                quantile(&xs_without_nan, 0.5),
+                precision,
+                quantile(&xs_without_nan, 0.25),
+                precision,
+                quantile(&xs_without_nan, 0.75)
+            ),
+        };
+        if xs_without_nan.len() < xs.len() {
+            summary += &format!(" ({} NaN)", xs.len() - xs_without_nan.len());
+        }
+        summary
+    }
+}
+impl FromStr for Aggregation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mean" => Ok(Aggregation::Mean),
+            "median" => Ok(Aggregation::Median),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown aggregation: {:?}", s),
+        }
+    }
+}
+
+/// Method used to compare two solvers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[allow(missing_docs)]
+pub enum RankingTest {
+    MannWhitneyU,
+    Wilcoxon,
+    Mean,
+}
+impl RankingTest {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["mann-whitney-u", "wilcoxon", "mean"];
+}
+impl FromStr for RankingTest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mann-whitney-u" => Ok(RankingTest::MannWhitneyU),
+            "wilcoxon" => Ok(RankingTest::Wilcoxon),
+            "mean" => Ok(RankingTest::Mean),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown ranking test: {:?}", s),
+        }
+    }
+}
+
+/// Significance level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[allow(missing_docs)]
+pub enum Alpha {
+    P01,
+    P05,
+    P10,
+}
+impl Alpha {
+    const POSSIBLE_VALUES: &'static [&'static str] = &["0.01", "0.05", "0.1"];
+
+    fn value(self) -> f64 {
+        match self {
+            Alpha::P01 => 0.01,
+            Alpha::P05 => 0.05,
+            Alpha::P10 => 0.1,
+        }
+    }
+}
+impl FromStr for Alpha {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "0.01" => Ok(Alpha::P01),
+            "0.05" => Ok(Alpha::P05),
+            "0.1" => Ok(Alpha::P10),
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown alpha: {:?}", s),
+        }
+    }
 }
 
 /// Evaluation metric.
@@ -35,14 +280,38 @@ pub enum Metric {
     /// Best value.
     BestValue,
 
+    /// Objective value of the last complete trial.
+    ///
+    /// Unlike `BestValue`, this isn't a running minimum,
+    /// so it's suitable for comparing the final states of solvers on noisy problems.
+    FinalValue,
+
     /// Auc of the best value.
     Auc,
 
     /// Wall-clock time consumed by solver.
     ElapsedTime,
+
+    /// Number of steps taken until the best value reached the target (`--stop-on-target`).
+    ///
+    /// Studies that didn't reach the target are regarded as the worst.
+    StepsToTarget,
+
+    /// Simple regret (i.e., the difference between the best value and the global optimum).
+    ///
+    /// The global optimum is taken from the `global_optimum` attribute of a problem.
+    /// Problems that don't have the attribute aren't considered in the overall results.
+    Regret,
 }
 impl Metric {
-    const POSSIBLE_VALUES: &'static [&'static str] = &["best-value", "auc", "elapsed-time"];
+    const POSSIBLE_VALUES: &'static [&'static str] = &[
+        "best-value",
+        "final-value",
+        "auc",
+        "elapsed-time",
+        "steps-to-target",
+        "regret",
+    ];
 }
 impl FromStr for Metric {
     type Err = Error;
@@ -50,8 +319,11 @@ impl FromStr for Metric {
     fn from_str(s: &str) -> Result<Self> {
         match s {
             "best-value" => Ok(Metric::BestValue),
+            "final-value" => Ok(Metric::FinalValue),
             "auc" => Ok(Metric::Auc),
             "elapsed-time" => Ok(Metric::ElapsedTime),
+            "steps-to-target" => Ok(Metric::StepsToTarget),
+            "regret" => Ok(Metric::Regret),
             _ => track_panic!(ErrorKind::InvalidInput, "Unknown metric name: {:?}", s),
         }
     }
@@ -65,7 +337,8 @@ pub struct Reporter {
 }
 impl Reporter {
     /// Makes a `Reporter` instance.
-    pub fn new(studies: Vec<StudyRecord>, mut opt: ReportOpt) -> Self {
+    pub fn new(mut studies: Vec<StudyRecord>, mut opt: ReportOpt) -> Self {
+        studies.retain(|s| opt.filter.is_match(&s.problem.spec.name));
         if opt.metrics.is_empty() {
             opt.metrics = vec![Metric::BestValue, Metric::Auc];
         }
@@ -74,6 +347,10 @@ impl Reporter {
 
     /// Prints a full report.
     pub fn report_all(&self, mut writer: impl Write) -> Result<()> {
+        if self.opt.check_complete {
+            track!(self.check_complete())?;
+        }
+
         let mut writer = MarkdownWriter::new(&mut writer);
         let mut writer = track!(writer.heading("Benchmark Result Report"))?;
 
@@ -97,11 +374,42 @@ impl Reporter {
             .iter()
             .map(|m| match m {
                 Metric::BestValue => "best value",
+                Metric::FinalValue => "final value",
                 Metric::Auc => "AUC",
                 Metric::ElapsedTime => "elapsed time",
+                Metric::StepsToTarget => "steps to target",
+                Metric::Regret => "simple regret",
             })
             .collect::<Vec<_>>();
         track!(list.item(&format!("Metrics Precedence: `{}`", metrics.join(" -> "))))?;
+        match self.opt.ranking_test {
+            RankingTest::MannWhitneyU => track!(list.item(&format!(
+                "Ranking Test: Mann-Whitney U test (family-wise alpha={})",
+                self.opt.alpha.value()
+            )))?,
+            RankingTest::Wilcoxon => track!(list.item(&format!(
+                "Ranking Test: Wilcoxon signed-rank test (family-wise alpha={}), \
+                 paired by seeds",
+                self.opt.alpha.value()
+            )))?,
+            RankingTest::Mean => track!(list.item("Ranking Test: mean (no significance test)"))?,
+        }
+        if self.opt.timeout_as_worst {
+            track!(
+                list.item("Timed-out Studies: treated as achieving their last-known best values")
+            )?;
+        }
+        let memoized = self
+            .studies
+            .iter()
+            .map(|s| s.memoized_evaluations)
+            .sum::<u64>();
+        if memoized > 0 {
+            track!(list.item(&format!(
+                "Memoized Evaluations: {} (the evaluator wasn't invoked for repeated parameters)",
+                memoized
+            )))?;
+        }
         track_writeln!(writer.inner_mut())?;
 
         track_writeln!(
@@ -118,6 +426,9 @@ impl Reporter {
             let mut list = writer.list().numbered();
             track!(list.item("[Overall Results](#overall-results)"))?;
             track!(list.item("[Individual Results](#individual-results)"))?;
+            if self.opt.stability {
+                track!(list.item("[Stability Analysis](#stability-analysis)"))?;
+            }
             track!(list.item("[Solvers](#solvers)"))?;
             track!(list.item("[Problems](#problems)"))?;
             track!(list.item("[Studies](#studies)"))?;
@@ -126,6 +437,9 @@ impl Reporter {
 
         track!(self.report_overall_results(&mut writer))?;
         track!(self.report_individual_results(&mut writer))?;
+        if self.opt.stability {
+            track!(self.report_stability(&mut writer))?;
+        }
         track!(self.report_solvers(&mut writer))?;
         track!(self.report_problems(&mut writer))?;
         track!(self.report_studies(&mut writer))?;
@@ -133,36 +447,119 @@ impl Reporter {
         Ok(())
     }
 
+    /// Prints a JSON summary of the rankings.
+    ///
+    /// The rankings are the same as the ones in the report printed by `report_all`.
+    pub fn report_json<W: Write>(&self, mut writer: W) -> Result<()> {
+        if self.opt.check_complete {
+            track!(self.check_complete())?;
+        }
+
+        let contests = track!(self.contests())?;
+        let overall = track!(self.overall_results(&contests))?;
+
+        let solvers = overall
+            .solver_ids
+            .iter()
+            .zip(overall.solvers.iter())
+            .map(|(id, solver)| json!({"id": id, "name": solver.spec.name}))
+            .collect::<Vec<_>>();
+        let problems = track!(self.problems())?
+            .map(|(id, problem)| json!({"id": id, "name": problem.spec.name}))
+            .collect::<Vec<_>>();
+        let overall_results = overall
+            .solver_ids
+            .iter()
+            .zip(overall.borda_scores.iter())
+            .zip(overall.firsts_scores.iter())
+            .map(|((id, borda), firsts)| json!({"solver": id, "borda": borda, "firsts": firsts}))
+            .collect::<Vec<_>>();
+        let excluded_problems = overall
+            .excluded_problems
+            .iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        let problems_without_optimum = overall
+            .problems_without_optimum
+            .iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        let contests = contests
+            .iter()
+            .map(|(problem_id, contest)| {
+                let rankings = self
+                    .contest_rankings(contest)
+                    .into_iter()
+                    .map(|(ranking, solver_id)| json!({"solver": solver_id, "ranking": ranking}))
+                    .collect::<Vec<_>>();
+                json!({"problem": problem_id, "rankings": rankings})
+            })
+            .collect::<Vec<_>>();
+
+        let summary = json!({
+            "id": track!(self.id())?,
+            "kurobako_version": env!("CARGO_PKG_VERSION"),
+            "solvers": solvers,
+            "problems": problems,
+            "overall": {
+                "results": overall_results,
+                "excluded_problems": excluded_problems,
+                "problems_without_global_optimum": problems_without_optimum,
+            },
+            "contests": contests,
+        });
+        track!(serde_json::to_writer_pretty(&mut writer, &summary).map_err(Error::from))?;
+        track_writeln!(writer)?;
+        Ok(())
+    }
+
+    // Report ID is derived from the studies and the options that affect the rankings.
+    fn id(&self) -> Result<String> {
+        use std::fmt::Write as _;
+
+        let mut study_ids = self
+            .studies
+            .iter()
+            .map(|s| Ok((track!(s.id())?, s.seed)))
+            .collect::<Result<Vec<_>>>()?;
+        study_ids.sort();
+
+        let mut hasher = Sha256::new();
+        hasher.update(&track!(serde_json::to_vec(&study_ids).map_err(Error::from))?);
+        hasher.update(&track!(
+            serde_json::to_vec(&self.opt.metrics).map_err(Error::from)
+        )?);
+        hasher.update(&track!(
+            serde_json::to_vec(&self.opt.ranking_test).map_err(Error::from)
+        )?);
+        hasher.update(&track!(
+            serde_json::to_vec(&self.opt.alpha).map_err(Error::from)
+        )?);
+        hasher.update([u8::from(self.opt.timeout_as_worst)]);
+        if let Some(v) = self.opt.worst_value {
+            hasher.update(v.to_le_bytes());
+        }
+
+        let mut id = String::with_capacity(64);
+        for b in hasher.finalize().as_slice() {
+            track_write!(&mut id, "{:02x}", b)?;
+        }
+        Ok(id)
+    }
+
     fn report_overall_results<W: Write>(&self, writer: &mut MarkdownWriter<W>) -> Result<()> {
         let mut writer = track!(writer.heading("Overall Results"))?;
         track_writeln!(writer.inner_mut())?;
 
         let contests = track!(self.contests())?;
-        let (solver_ids, solvers): (Vec<_>, Vec<_>) = track!(self.solvers())?.unzip();
-        let mut borda_ranking = Borda::new(solver_ids.iter());
-        let mut firsts_ranking = Firsts::new(solver_ids.iter());
-        let mut excluded_problems = Vec::new();
-        let alpha = self.alpha(solvers.len());
-        for (problem_id, contest) in contests {
-            if !solver_ids
-                .iter()
-                .all(|s| contest.competitors.contains_key(s))
-            {
-                excluded_problems.push((problem_id, contest.problem));
-                continue;
-            }
-
-            borda_ranking.compete(|&a, &b| {
-                let a = &contest.competitors[a];
-                let b = &contest.competitors[b];
-                self.compete(a, b, contest.auc_start_step, alpha)
-            });
-            firsts_ranking.compete(|&a, &b| {
-                let a = &contest.competitors[a];
-                let b = &contest.competitors[b];
-                self.compete(a, b, contest.auc_start_step, alpha)
-            });
-        }
+        let OverallResults {
+            solver_ids,
+            solvers,
+            borda_scores,
+            firsts_scores,
+            excluded_problems,
+            problems_without_optimum,
+        } = track!(self.overall_results(&contests))?;
 
         let mut table = md::Table::new(
             vec![
@@ -176,8 +573,8 @@ impl Reporter {
         for (((solver_id, solver), borda), firsts) in solver_ids
             .iter()
             .zip(solvers.iter())
-            .zip(borda_ranking.scores())
-            .zip(firsts_ranking.scores())
+            .zip(borda_scores)
+            .zip(firsts_scores)
         {
             table
                 .row()
@@ -204,6 +601,23 @@ impl Reporter {
             track!(writer.newline())?;
         }
 
+        if !problems_without_optimum.is_empty() {
+            let mut writer = track!(writer.heading("Note"))?;
+            track!(writer.newline())?;
+            track_writeln!(
+                writer.inner_mut(),
+                "The following problems aren't considered in the above \
+                 result because they don't have the `global_optimum` attribute \
+                 required by the simple regret metric:"
+            )?;
+
+            let mut list = writer.list();
+            for (problem_id, problem) in problems_without_optimum {
+                track!(list.item(&format!("[{}](#id-{})", problem.spec.name, problem_id)))?;
+            }
+            track!(writer.newline())?;
+        }
+
         Ok(())
     }
 
@@ -211,6 +625,15 @@ impl Reporter {
         let mut writer = track!(writer.heading("Individual Results"))?;
         track_writeln!(writer.inner_mut())?;
 
+        // Seeds the bootstrap resampling by the report ID, so that the intervals are reproducible.
+        let mut rng = if self.opt.bootstrap.is_some() {
+            let id = track!(self.id())?;
+            let seed = track!(u64::from_str_radix(&id[..16], 16).map_err(Error::from))?;
+            StdRng::seed_from_u64(seed)
+        } else {
+            StdRng::seed_from_u64(0)
+        };
+
         let contests = track!(self.contests())?;
         for (problem_no, (problem_id, contest)) in contests.into_iter().enumerate() {
             let mut writer = track!(writer.heading(&format!(
@@ -220,38 +643,40 @@ impl Reporter {
                 problem_id
             )))?;
 
-            // FIXME: Reduce redundant calculation.
+            if self.opt.ranking_test == RankingTest::Wilcoxon && !contest.is_paired() {
+                track_writeln!(
+                    writer.inner_mut(),
+                    "Note: the seeds of the studies don't align across the solvers, \
+                     so the Mann-Whitney U test is used instead of the Wilcoxon signed-rank test \
+                     for the unaligned pairs.\n"
+                )?;
+            }
+
             let auc_start_step = contest.auc_start_step;
-            let mut rankings = BTreeMap::new();
-            let alpha = self.alpha(contest.competitors.len());
-            for (solver_id0, competitor0) in &contest.competitors {
-                let mut ranking = 1;
-                for (solver_id1, competitor1) in &contest.competitors {
-                    if solver_id0 == solver_id1 {
-                        continue;
-                    }
+            let rankings = self.contest_rankings(&contest);
 
-                    if self.compete(competitor0, competitor1, auc_start_step, alpha)
-                        == Ordering::Greater
-                    {
-                        ranking += 1;
-                    }
-                }
-                rankings.insert(solver_id0, ranking);
+            let aggregation = self.opt.aggregation;
+            let header = |name: &str| {
+                md::ColumnHeader::new(
+                    &format!("{} ({})", name, aggregation.label()),
+                    md::Align::Right,
+                )
+            };
+            let mut headers = vec![
+                md::ColumnHeader::new("Ranking", md::Align::Right),
+                md::ColumnHeader::new("Solver", md::Align::Left),
+                header("Best"),
+            ];
+            if self.opt.bootstrap.is_some() {
+                headers.push(md::ColumnHeader::new("Best 95% CI", md::Align::Right));
             }
-            let mut rankings = rankings.into_iter().map(|x| (x.1, x.0)).collect::<Vec<_>>();
-            rankings.sort();
-
-            let mut table = md::Table::new(
-                vec![
-                    md::ColumnHeader::new("Ranking", md::Align::Right),
-                    md::ColumnHeader::new("Solver", md::Align::Left),
-                    md::ColumnHeader::new("Best (avg +- sd)", md::Align::Right),
-                    md::ColumnHeader::new("AUC (avg +- sd)", md::Align::Right),
-                    md::ColumnHeader::new("Elapsed (avg +- sd)", md::Align::Right),
-                ]
-                .into_iter(),
-            );
+            headers.extend(vec![
+                header("Final"),
+                header("AUC"),
+                header("Regret"),
+                header("Elapsed"),
+            ]);
+            let mut table = md::Table::new(headers.into_iter());
             for (ranking, solver_id) in rankings {
                 let c = &contest.competitors[solver_id];
 
@@ -263,34 +688,37 @@ impl Reporter {
                 );
 
                 let best_values = c.best_values().map(|x| x.0).collect::<Vec<_>>();
-                let best_value = format!(
-                    "{:.06} +- {:.06}",
-                    average(best_values.iter().copied()),
-                    stddev(best_values.iter().copied())
-                );
+                let best_value = aggregation.summarize(&best_values, 6);
+                let best_value_ci = self.opt.bootstrap.map(|n| {
+                    let (lower, upper) = bootstrap_mean_ci(&best_values, n.get(), &mut rng);
+                    format!("[{:.06}, {:.06}]", lower, upper)
+                });
+
+                let final_values = c.final_values().map(|x| x.0).collect::<Vec<_>>();
+                let final_value = aggregation.summarize(&final_values, 6);
 
                 let aucs = c.aucs(auc_start_step).map(|x| x.0).collect::<Vec<_>>();
-                let auc = format!(
-                    "{:.03} +- {:.03}",
-                    average(aucs.iter().copied()),
-                    stddev(aucs.iter().copied())
-                );
+                let auc = aggregation.summarize(&aucs, 3);
+
+                let regret = if let Some(optimum) = contest.global_optimum {
+                    let regrets = c.regrets(optimum).map(|x| x.0).collect::<Vec<_>>();
+                    aggregation.summarize(&regrets, 6)
+                } else {
+                    "-".to_owned()
+                };
 
                 let elapsed_times = c
                     .elapsed_times()
                     .map(|x| x.as_secs_f64())
                     .collect::<Vec<_>>();
-                let elapsed_time = format!(
-                    "{:.03} +- {:.03}",
-                    average(elapsed_times.iter().copied()),
-                    stddev(elapsed_times.iter().copied())
-                );
-                table
-                    .row()
-                    .item(ranking)
-                    .item(solver)
-                    .item(best_value)
+                let elapsed_time = aggregation.summarize(&elapsed_times, 3);
+                let row = table.row().item(ranking).item(solver).item(best_value);
+                if let Some(ci) = best_value_ci {
+                    row.item(ci);
+                }
+                row.item(final_value)
                     .item(auc)
+                    .item(regret)
                     .item(elapsed_time);
             }
 
@@ -301,11 +729,134 @@ impl Reporter {
         Ok(())
     }
 
+    fn report_stability<W: Write>(&self, writer: &mut MarkdownWriter<W>) -> Result<()> {
+        let mut writer = track!(writer.heading("Stability Analysis"))?;
+        track_writeln!(
+            writer.inner_mut(),
+            "Solvers are ranked by their mean best values while leaving out one seed at a time. \
+             \"Stability\" is the fraction of such resamples in which the ranking of a solver \
+             doesn't change (`1.0` means the ranking is stable).\n"
+        )?;
+
+        let contests = track!(self.contests())?;
+        let mut per_problem = Vec::new();
+        for (problem_id, contest) in &contests {
+            let samples = contest
+                .competitors
+                .iter()
+                .map(|(solver_id, c)| {
+                    let xs = c
+                        .studies
+                        .iter()
+                        .filter_map(|s| c.best_value(s).map(|x| (s.seed, x.0)))
+                        .filter(|(_, x)| !x.is_nan())
+                        .collect();
+                    (solver_id.as_str(), xs)
+                })
+                .filter(|(_, xs): &(_, Vec<_>)| !xs.is_empty())
+                .collect::<BTreeMap<_, _>>();
+            per_problem.push((problem_id, contest, stability::leave_one_out(&samples)));
+        }
+
+        let mut table = md::Table::new(
+            vec![
+                md::ColumnHeader::new("Solver", md::Align::Left),
+                md::ColumnHeader::new("Stability (avg)", md::Align::Right),
+                md::ColumnHeader::new("Unstable Problems", md::Align::Right),
+            ]
+            .into_iter(),
+        );
+        for (solver_id, solver) in track!(self.solvers())? {
+            let stabilities = per_problem
+                .iter()
+                .filter_map(|(_, _, s)| s.get(solver_id.as_str()))
+                .collect::<Vec<_>>();
+            let unstables = stabilities.iter().filter(|s| !s.is_stable()).count();
+            table
+                .row()
+                .item(format!("[{}](#id-{})", solver.spec.name, solver_id))
+                .item(format!(
+                    "{:.03}",
+                    average(stabilities.iter().map(|s| s.score))
+                ))
+                .item(format!("{}/{}", unstables, stabilities.len()));
+        }
+        track!(writer.write_table(&table))?;
+        track!(writer.newline())?;
+
+        for (problem_no, (problem_id, contest, stabilities)) in per_problem.into_iter().enumerate()
+        {
+            let mut writer = track!(writer.heading(&format!(
+                "({}) Problem: [{}](#id-{})",
+                problem_no + 1,
+                contest.problem.spec.name,
+                problem_id
+            )))?;
+
+            let mut table = md::Table::new(
+                vec![
+                    md::ColumnHeader::new("Ranking", md::Align::Right),
+                    md::ColumnHeader::new("Solver", md::Align::Left),
+                    md::ColumnHeader::new("Stability", md::Align::Right),
+                    md::ColumnHeader::new("SD of Resampled Mean Best", md::Align::Right),
+                ]
+                .into_iter(),
+            );
+            let mut stabilities = stabilities.into_iter().collect::<Vec<(_, Stability)>>();
+            stabilities.sort_by_key(|(id, s)| (s.ranking, *id));
+            for (solver_id, s) in stabilities {
+                let c = &contest.competitors[solver_id];
+                table
+                    .row()
+                    .item(s.ranking)
+                    .item(format!("[{}](#id-{})", c.solver.spec.name, solver_id))
+                    .item(format!("{:.03}", s.score))
+                    .item(format!("{:.06}", s.sd));
+            }
+            track!(writer.write_table(&table))?;
+            track_writeln!(writer.inner_mut())?;
+        }
+
+        Ok(())
+    }
+
     fn report_solvers<W: Write>(&self, writer: &mut MarkdownWriter<W>) -> Result<()> {
         let mut writer = track!(writer.heading("Solvers"))?;
         for (id, solver) in track!(self.solvers())? {
             let mut writer = track!(writer.heading(&format!("ID: {}", id)))?;
 
+            let mut quantiles = [
+                P2Quantile::new(0.5),
+                P2Quantile::new(0.9),
+                P2Quantile::new(0.99),
+            ];
+            for study in &self.studies {
+                if track!(study.solver.id())? != id {
+                    continue;
+                }
+                for e in study.evaluations() {
+                    let elapsed =
+                        e.ask_elapsed.get() + e.tell_elapsed.get() + e.evaluate_elapsed.get();
+                    for q in &mut quantiles {
+                        q.add(elapsed);
+                    }
+                }
+            }
+            if let [Some(p50), Some(p90), Some(p99)] = [
+                quantiles[0].estimate(),
+                quantiles[1].estimate(),
+                quantiles[2].estimate(),
+            ] {
+                track_writeln!(
+                    writer.inner_mut(),
+                    "elapsed seconds per evaluation (ask + evaluate + tell): \
+                     p50={:.06}, p90={:.06}, p99={:.06}\n",
+                    p50,
+                    p90,
+                    p99
+                )?;
+            }
+
             track_writeln!(writer.inner_mut(), "recipe:")?;
             let json = track!(serde_json::to_string_pretty(&solver.recipe).map_err(Error::from))?;
             track!(writer.code_block("json", &json))?;
@@ -366,11 +917,77 @@ impl Reporter {
             if studies[0].concurrency.get() > 1 {
                 track!(list.item(&format!("scheduling: {}", studies[0].scheduling)))?;
             }
+            let throughputs = studies
+                .iter()
+                .filter_map(|s| {
+                    let elapsed = s.solver_elapsed().as_secs_f64();
+                    (elapsed > 0.0).then(|| s.complete_trials() as f64 / elapsed)
+                })
+                .collect::<Vec<_>>();
+            if !throughputs.is_empty() {
+                track!(list.item(&format!(
+                    "throughput (trials/s): {:.03}",
+                    average(throughputs.into_iter())
+                )))?;
+            }
             track_writeln!(writer.inner_mut())?;
         }
         Ok(())
     }
 
+    /// Checks that every solver has been run on every problem for every seed.
+    pub fn check_complete(&self) -> Result<()> {
+        let solvers = track!(self.solvers())?.collect::<Vec<_>>();
+        let problems = track!(self.problems())?.collect::<Vec<_>>();
+
+        let mut seed_counts = BTreeMap::<_, usize>::new();
+        let mut cells = BTreeMap::<_, BTreeSet<_>>::new();
+        for study in &self.studies {
+            *seed_counts.entry(study.seed).or_default() += 1;
+            cells
+                .entry((track!(study.solver.id())?, track!(study.problem.id())?))
+                .or_default()
+                .insert(study.seed);
+        }
+        let seeded = seed_counts.values().any(|&n| n > 1);
+        let max_studies = cells.values().map(|seeds| seeds.len()).max().unwrap_or(0);
+
+        let mut missing = 0;
+        for (solver_id, solver) in &solvers {
+            for (problem_id, problem) in &problems {
+                let seeds = cells
+                    .get(&(solver_id.clone(), problem_id.clone()))
+                    .cloned()
+                    .unwrap_or_default();
+                if seeded {
+                    for seed in seed_counts.keys().filter(|s| !seeds.contains(s)) {
+                        eprintln!(
+                            "Missing study: solver={:?}, problem={:?}, seed={}",
+                            solver.spec.name, problem.spec.name, seed
+                        );
+                        missing += 1;
+                    }
+                } else if seeds.len() < max_studies {
+                    eprintln!(
+                        "Missing studies: solver={:?}, problem={:?}, studies={}/{}",
+                        solver.spec.name,
+                        problem.spec.name,
+                        seeds.len(),
+                        max_studies
+                    );
+                    missing += max_studies - seeds.len();
+                }
+            }
+        }
+        track_assert_eq!(
+            missing,
+            0,
+            ErrorKind::InvalidInput,
+            "The studies don't form a complete factorial design"
+        );
+        Ok(())
+    }
+
     fn solvers(&self) -> Result<impl '_ + Iterator<Item = (String, &SolverRecord)>> {
         let mut map = BTreeMap::new();
         for study in &self.studies {
@@ -389,17 +1006,90 @@ impl Reporter {
         Ok(map.into_iter().map(|(k, v)| (k.1, v)))
     }
 
-    fn compete(&self, a: &Competitor, b: &Competitor, auc_start_step: u64, alpha: f64) -> Ordering {
-        for metric in &self.opt.metrics {
-            let order = match metric {
-                Metric::BestValue => {
-                    MannWhitneyU::new(a.best_values(), b.best_values()).order(alpha)
+    fn overall_results<'a>(
+        &'a self,
+        contests: &BTreeMap<String, Contest<'a>>,
+    ) -> Result<OverallResults<'a>> {
+        let (solver_ids, solvers): (Vec<_>, Vec<_>) = track!(self.solvers())?.unzip();
+        let mut borda_ranking = Borda::new(solver_ids.iter());
+        let mut firsts_ranking = Firsts::new(solver_ids.iter());
+        let mut excluded_problems = Vec::new();
+        let mut problems_without_optimum = Vec::new();
+        let alpha = self.alpha(solvers.len());
+        for (problem_id, contest) in contests {
+            if !solver_ids
+                .iter()
+                .all(|s| contest.competitors.contains_key(s))
+            {
+                excluded_problems.push((problem_id.clone(), contest.problem));
+                continue;
+            }
+            if self.opt.metrics.contains(&Metric::Regret) && contest.global_optimum.is_none() {
+                problems_without_optimum.push((problem_id.clone(), contest.problem));
+                continue;
+            }
+
+            borda_ranking.compete(|&a, &b| {
+                let a = &contest.competitors[a];
+                let b = &contest.competitors[b];
+                self.compete(a, b, contest, alpha)
+            });
+            firsts_ranking.compete(|&a, &b| {
+                let a = &contest.competitors[a];
+                let b = &contest.competitors[b];
+                self.compete(a, b, contest, alpha)
+            });
+        }
+
+        let borda_scores = borda_ranking.scores().collect();
+        let firsts_scores = firsts_ranking.scores().collect();
+        Ok(OverallResults {
+            solver_ids,
+            solvers,
+            borda_scores,
+            firsts_scores,
+            excluded_problems,
+            problems_without_optimum,
+        })
+    }
+
+    // Returns the pairs of the ranking and the ID of each solver, ordered by the rankings.
+    fn contest_rankings<'c>(&self, contest: &'c Contest) -> Vec<(usize, &'c String)> {
+        let mut rankings = BTreeMap::new();
+        let alpha = self.alpha(contest.competitors.len());
+        for (solver_id0, competitor0) in &contest.competitors {
+            let mut ranking = 1;
+            for (solver_id1, competitor1) in &contest.competitors {
+                if solver_id0 == solver_id1 {
+                    continue;
                 }
-                Metric::Auc => {
-                    MannWhitneyU::new(a.aucs(auc_start_step), b.aucs(auc_start_step)).order(alpha)
+
+                if self.compete(competitor0, competitor1, contest, alpha) == Ordering::Greater {
+                    ranking += 1;
                 }
-                Metric::ElapsedTime => {
-                    MannWhitneyU::new(a.elapsed_times(), b.elapsed_times()).order(alpha)
+            }
+            rankings.insert(solver_id0, ranking);
+        }
+        let mut rankings = rankings.into_iter().map(|x| (x.1, x.0)).collect::<Vec<_>>();
+        rankings.sort();
+        rankings
+    }
+
+    fn compete(&self, a: &Competitor, b: &Competitor, contest: &Contest, alpha: f64) -> Ordering {
+        let auc_start_step = contest.auc_start_step;
+        for metric in &self.opt.metrics {
+            let order = match metric {
+                Metric::BestValue => self.test(a, b, |c, s| c.best_value(s), alpha),
+                Metric::FinalValue => self.test(a, b, |c, s| c.final_value(s), alpha),
+                Metric::Auc => self.test(a, b, |c, s| c.auc(s, auc_start_step), alpha),
+                Metric::ElapsedTime => self.test(a, b, |_, s| Some(s.solver_elapsed()), alpha),
+                Metric::StepsToTarget => self.test(a, b, |_, s| s.steps_to_target(), alpha),
+                Metric::Regret => {
+                    if let Some(optimum) = contest.global_optimum {
+                        self.test(a, b, |c, s| c.regret(s, optimum), alpha)
+                    } else {
+                        Ordering::Equal
+                    }
                 }
             };
             if order != Ordering::Equal {
@@ -409,21 +1099,53 @@ impl Reporter {
         Ordering::Equal
     }
 
+    // `f` extracts the metric value of a study of a competitor.
+    fn test<T, F>(&self, a: &Competitor, b: &Competitor, f: F, alpha: f64) -> Ordering
+    where
+        T: Ord + Sample,
+        F: Fn(&Competitor, &StudyRecord) -> Option<T>,
+    {
+        // NaNs (e.g., given by misbehaving problems) are excluded in the same way as `num::sanitize`.
+        let f = |c: &Competitor, s: &StudyRecord| f(c, s).filter(|x| !x.to_f64().is_nan());
+        if self.opt.ranking_test == RankingTest::Wilcoxon && a.is_paired_with(b) {
+            let pairs = a
+                .studies
+                .iter()
+                .zip(b.studies.iter())
+                .filter_map(|(x, y)| Some((f(a, x)?.to_f64(), f(b, y)?.to_f64())));
+            return wilcoxon_signed_rank(pairs, alpha);
+        }
+
+        let xs = a.studies.iter().filter_map(|s| f(a, s));
+        let ys = b.studies.iter().filter_map(|s| f(b, s));
+        match self.opt.ranking_test {
+            RankingTest::MannWhitneyU | RankingTest::Wilcoxon => {
+                MannWhitneyU::new(xs, ys).order(alpha)
+            }
+            RankingTest::Mean => {
+                let a = average(xs.map(|x| x.to_f64()));
+                let b = average(ys.map(|x| x.to_f64()));
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            }
+        }
+    }
+
     fn alpha(&self, solvers: usize) -> f64 {
         let n = num_integer::binomial(solvers, 2);
+        let target = self.opt.alpha.value();
         let candidates = [
-            0.05f64, 0.025, 0.01, 0.007_5, 0.005, 0.002_5, 0.001, 0.000_75, 0.000_5, 0.000_25,
-            0.000_1, 0.000_075, 0.000_05, 0.000_025, 0.000_01,
+            0.1f64, 0.075, 0.05, 0.025, 0.01, 0.007_5, 0.005, 0.002_5, 0.001, 0.000_75, 0.000_5,
+            0.000_25, 0.000_1, 0.000_075, 0.000_05, 0.000_025, 0.000_01,
         ];
-        for &a in &candidates {
-            if 1.0 - (1.0 - a).powi(n as i32) < 0.05 {
+        for &a in candidates.iter().filter(|&&a| a <= target) {
+            if 1.0 - (1.0 - a).powi(n as i32) < target {
                 return a;
             }
         }
         0.000_01
     }
 
-    fn contests(&self) -> Result<BTreeMap<String, Contest>> {
+    fn contests(&self) -> Result<BTreeMap<String, Contest<'_>>> {
         let mut contests = BTreeMap::new();
         for study in &self.studies {
             let problem_id = track!(study.problem.id())?;
@@ -431,7 +1153,12 @@ impl Reporter {
                 problem: &study.problem,
                 competitors: BTreeMap::new(),
                 auc_start_step: study.problem.spec.steps.last(),
+                global_optimum: None,
             });
+            if contest.global_optimum.is_none() {
+                contest.global_optimum =
+                    track!(study.problem.spec.parse_attr::<f64>("global_optimum"))?;
+            }
             if let Some(trial) = study.first_complete_trial() {
                 if let Some(step) = trial.start_step() {
                     if contest.auc_start_step < step {
@@ -447,40 +1174,250 @@ impl Reporter {
                 .or_insert_with(|| Competitor {
                     solver: &study.solver,
                     studies: Vec::new(),
+                    opt: &self.opt,
                 })
                 .studies
                 .push(study)
         }
+        for contest in contests.values_mut() {
+            for competitor in contest.competitors.values_mut() {
+                competitor.studies.sort_by_key(|s| s.seed);
+            }
+        }
         Ok(contests)
     }
 }
 
+trait Sample {
+    fn to_f64(&self) -> f64;
+}
+impl Sample for OrderedFloat<f64> {
+    fn to_f64(&self) -> f64 {
+        self.0
+    }
+}
+impl Sample for Duration {
+    fn to_f64(&self) -> f64 {
+        self.as_secs_f64()
+    }
+}
+impl Sample for u64 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+}
+
+struct OverallResults<'a> {
+    solver_ids: Vec<String>,
+    solvers: Vec<&'a SolverRecord>,
+    borda_scores: Vec<Score>,
+    firsts_scores: Vec<Score>,
+    excluded_problems: Vec<(String, &'a ProblemRecord)>,
+    problems_without_optimum: Vec<(String, &'a ProblemRecord)>,
+}
+
 struct Contest<'a> {
     problem: &'a ProblemRecord,
     competitors: BTreeMap<String, Competitor<'a>>,
     auc_start_step: u64,
+
+    // Taken from the `global_optimum` attribute of the problem.
+    global_optimum: Option<f64>,
+}
+impl<'a> Contest<'a> {
+    fn is_paired(&self) -> bool {
+        let mut competitors = self.competitors.values();
+        competitors
+            .next()
+            .is_none_or(|first| competitors.all(|c| first.is_paired_with(c)))
+    }
 }
 
 struct Competitor<'a> {
     solver: &'a SolverRecord,
     studies: Vec<&'a StudyRecord>,
+    opt: &'a ReportOpt,
 }
 impl<'a> Competitor<'a> {
+    fn best_value(&self, study: &StudyRecord) -> Option<OrderedFloat<f64>> {
+        study
+            .best_value()
+            .or_else(|| self.worst_value(study))
+            .map(OrderedFloat)
+    }
+
     fn best_values(&self) -> impl '_ + Iterator<Item = OrderedFloat<f64>> {
-        self.studies
-            .iter()
-            .filter_map(|s| s.best_value())
+        self.studies.iter().filter_map(move |s| self.best_value(s))
+    }
+
+    fn final_value(&self, study: &StudyRecord) -> Option<OrderedFloat<f64>> {
+        study
+            .final_value()
+            .or_else(|| self.worst_value(study))
+            .map(OrderedFloat)
+    }
+
+    fn final_values(&self) -> impl '_ + Iterator<Item = OrderedFloat<f64>> {
+        self.studies.iter().filter_map(move |s| self.final_value(s))
+    }
+
+    fn auc(&self, study: &StudyRecord, start_step: u64) -> Option<OrderedFloat<f64>> {
+        study
+            .auc(start_step)
+            .or_else(|| self.worst_value(study).map(|_| f64::INFINITY))
             .map(OrderedFloat)
     }
 
     fn aucs(&self, start_step: u64) -> impl '_ + Iterator<Item = OrderedFloat<f64>> {
         self.studies
             .iter()
-            .filter_map(move |s| s.auc(start_step))
-            .map(OrderedFloat)
+            .filter_map(move |s| self.auc(s, start_step))
+    }
+
+    fn regret(&self, study: &StudyRecord, global_optimum: f64) -> Option<OrderedFloat<f64>> {
+        self.best_value(study)
+            .map(|v| OrderedFloat(v.0 - global_optimum))
+    }
+
+    fn regrets(&self, global_optimum: f64) -> impl '_ + Iterator<Item = OrderedFloat<f64>> {
+        self.studies
+            .iter()
+            .filter_map(move |s| self.regret(s, global_optimum))
+    }
+
+    // Returns `true` if the studies of `self` and `other` can be paired by their seeds.
+    //
+    // Note that the studies are sorted by their seeds.
+    fn is_paired_with(&self, other: &Competitor) -> bool {
+        self.studies.len() == other.studies.len()
+            && self.studies.windows(2).all(|w| w[0].seed != w[1].seed)
+            && self
+                .studies
+                .iter()
+                .zip(other.studies.iter())
+                .all(|(a, b)| a.seed == b.seed)
+    }
+
+    fn worst_value(&self, study: &StudyRecord) -> Option<f64> {
+        let vars = study.problem.spec.values_domain.variables();
+        if !(self.opt.timeout_as_worst && study.is_timed_out()) || vars.len() != 1 {
+            return None;
+        }
+        Some(
+            self.opt
+                .worst_value
+                .unwrap_or_else(|| vars[0].range().high()),
+        )
     }
 
     fn elapsed_times(&self) -> impl '_ + Iterator<Item = Duration> {
         self.studies.iter().map(|s| s.solver_elapsed())
     }
 }
+
+// Returns the 95% bootstrap confidence interval of the mean of the given values.
+fn bootstrap_mean_ci<R: Rng>(xs: &[f64], resamples: usize, rng: &mut R) -> (f64, f64) {
+    if xs.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let means = (0..resamples)
+        .map(|_| {
+            let sum = (0..xs.len())
+                .map(|_| xs[rng.gen_range(0..xs.len())])
+                .sum::<f64>();
+            sum / xs.len() as f64
+        })
+        .collect::<Vec<_>>();
+    (quantile(&means, 0.025), quantile(&means, 0.975))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::testing;
+    use trackable::result::TopLevelResult;
+
+    // Returns the pairs of the ranking and the name of each solver on the (only) problem.
+    fn rankings(studies: Vec<StudyRecord>, args: &[&str]) -> Result<Vec<(usize, String)>> {
+        let reporter = Reporter::new(studies, ReportOpt::from_iter(args));
+        let contests = track!(reporter.contests())?;
+        let contest = track_assert_some!(contests.values().next(), ErrorKind::Bug);
+        Ok(reporter
+            .contest_rankings(contest)
+            .into_iter()
+            .map(|(ranking, id)| (ranking, contest.competitors[id].solver.spec.name.clone()))
+            .collect())
+    }
+
+    #[test]
+    fn nans_are_excluded_from_rankings() -> TopLevelResult {
+        let mut studies = Vec::new();
+        for &(solver, value) in &[("a", 1.0), ("a", f64::NAN), ("b", 2.0), ("b", 2.0)] {
+            studies.push(testing::record("foo", solver, 1, &[value])?);
+        }
+
+        // If the NaN were included, the mean of "a" would be NaN and the solvers would be tied.
+        let args = [
+            "report",
+            "--metrics",
+            "final-value",
+            "--ranking-test",
+            "mean",
+        ];
+        assert_eq!(
+            rankings(studies, &args)?,
+            [(1, "a".to_owned()), (2, "b".to_owned())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn median_aggregation_is_robust_to_outliers() {
+        let best_values = [1.0, 2.0, 2.0, 3.0, 100.0];
+        assert_eq!(
+            Aggregation::Mean.summarize(&best_values, 3),
+            "21.600 +- 39.205"
+        );
+        assert_eq!(
+            Aggregation::Median.summarize(&best_values, 3),
+            "2.000 (2.000, 3.000)"
+        );
+    }
+
+    #[test]
+    fn nans_are_excluded_from_summaries() {
+        let best_values = [1.0, f64::NAN, 3.0];
+        assert_eq!(
+            Aggregation::Mean.summarize(&best_values, 3),
+            "2.000 +- 1.000 (1 NaN)"
+        );
+        assert_eq!(
+            Aggregation::Median.summarize(&best_values, 3),
+            "2.000 (1.500, 2.500) (1 NaN)"
+        );
+        assert_eq!(Aggregation::Mean.summarize(&[f64::NAN], 3), "- (1 NaN)");
+
+        // Infinities (e.g., the AUCs of timed-out studies) are kept.
+        let aucs = [1.0, 2.0, f64::INFINITY];
+        assert_eq!(Aggregation::Mean.summarize(&aucs, 3), "inf +- inf");
+        assert_eq!(
+            Aggregation::Median.summarize(&aucs, 3),
+            "2.000 (1.500, inf)"
+        );
+    }
+
+    #[test]
+    fn bootstrap_ci_brackets_mean() {
+        let xs = [1.0, 2.0, 2.5, 3.0, 4.0, 10.0];
+        let mean = average(xs.iter().copied());
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let (lower, upper) = bootstrap_mean_ci(&xs, 1000, &mut rng);
+        assert!(lower < mean && mean < upper, "{} {} {}", lower, mean, upper);
+        assert!(xs[0] <= lower && upper <= xs[5]);
+
+        let (lower, upper) = bootstrap_mean_ci(&[3.0], 100, &mut rng);
+        assert_eq!((lower, upper), (3.0, 3.0));
+    }
+}
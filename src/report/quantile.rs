@@ -0,0 +1,198 @@
+/// Streaming quantile estimator based on the P² algorithm.
+///
+/// This estimates a quantile in constant memory without storing observations.
+/// Non-finite observations are ignored.
+///
+/// # References
+///
+/// - [The P² algorithm for dynamic calculation of quantiles and histograms without storing observations][paper]
+///
+/// [paper]: https://www.cse.wustl.edu/~jain/papers/ftp/psqr.pdf
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired_positions: [f64; 5],
+    increments: [f64; 5],
+}
+impl P2Quantile {
+    pub fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired_positions: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        if !x.is_finite() {
+            return;
+        }
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights
+                    .sort_by(|a, b| a.partial_cmp(b).unwrap_or_else(|| unreachable!()));
+            }
+            return;
+        }
+        self.count += 1;
+
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (1..5)
+                .find(|&i| x < self.heights[i])
+                .unwrap_or_else(|| unreachable!())
+                - 1
+        };
+        for i in k + 1..5 {
+            self.positions[i] += 1.0;
+        }
+        for i in 0..5 {
+            self.desired_positions[i] += self.increments[i];
+        }
+
+        for i in 1..4 {
+            let d = self.desired_positions[i] - self.positions[i];
+            if (d >= 1.0 && self.positions[i + 1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i - 1] - self.positions[i] < -1.0)
+            {
+                let d = d.signum();
+                let h = self.parabolic(i, d);
+                self.heights[i] = if self.heights[i - 1] < h && h < self.heights[i + 1] {
+                    h
+                } else {
+                    self.linear(i, d)
+                };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    pub fn estimate(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else if self.count < 5 {
+            let mut xs = self.heights[..self.count].to_vec();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or_else(|| unreachable!()));
+            let i = ((xs.len() - 1) as f64 * self.p).round() as usize;
+            Some(xs[i])
+        } else {
+            Some(self.heights[2])
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        let j = if d < 0.0 { i - 1 } else { i + 1 };
+        q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+    }
+}
+
+/// Returns the `p`-quantile of the given values (linearly interpolated between the closest ranks).
+///
+/// `NaN` is returned if `xs` is empty.
+pub fn quantile(xs: &[f64], p: f64) -> f64 {
+    if xs.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut xs = xs.to_vec();
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let position = (xs.len() - 1) as f64 * p;
+    let (i, frac) = (position.floor() as usize, position.fract());
+    // `frac == 0.0` is checked to avoid `0 * inf` (i.e., NaN).
+    if i + 1 < xs.len() && frac > 0.0 {
+        xs[i] + (xs[i + 1] - xs[i]) * frac
+    } else {
+        xs[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng as _, SeedableRng as _};
+
+    fn assert_estimates_close(xs: &[f64], tolerance: f64) {
+        for &p in &[0.1, 0.5, 0.9, 0.99] {
+            let mut estimator = P2Quantile::new(p);
+            for &x in xs {
+                estimator.add(x);
+            }
+            let estimate = estimator.estimate().expect("no estimate");
+            let exact = quantile(xs, p);
+            assert!(
+                (estimate - exact).abs() <= tolerance,
+                "p={}, estimate={}, exact={}",
+                p,
+                estimate,
+                exact
+            );
+        }
+    }
+
+    #[test]
+    fn p2_quantile_approximates_exact_quantiles() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let uniform = (0..10_000).map(|_| rng.gen::<f64>()).collect::<Vec<_>>();
+        assert_estimates_close(&uniform, 0.01);
+
+        let exponential = (0..10_000)
+            .map(|_| -(1.0 - rng.gen::<f64>()).ln())
+            .collect::<Vec<_>>();
+        assert_estimates_close(&exponential, 0.2);
+
+        let ascending = (0..1000).map(f64::from).collect::<Vec<_>>();
+        assert_estimates_close(&ascending, 10.0);
+    }
+
+    #[test]
+    fn p2_quantile_ignores_non_finite_values() {
+        let mut estimator = P2Quantile::new(0.5);
+        assert_eq!(estimator.estimate(), None);
+
+        for &x in &[3.0, f64::NAN, 1.0, f64::INFINITY, 2.0] {
+            estimator.add(x);
+        }
+        assert_eq!(estimator.estimate(), Some(2.0));
+
+        for i in 0..100 {
+            estimator.add(f64::from(i % 5));
+            estimator.add(f64::NAN);
+            estimator.add(f64::NEG_INFINITY);
+        }
+        let estimate = estimator.estimate().expect("no estimate");
+        assert!(estimate.is_finite() && (estimate - 2.0).abs() <= 1.0);
+    }
+
+    #[test]
+    fn quantile_works() {
+        let xs = [5.0, 1.0, 4.0, 2.0, 3.0];
+        assert_eq!(quantile(&xs, 0.0), 1.0);
+        assert_eq!(quantile(&xs, 0.25), 2.0);
+        assert_eq!(quantile(&xs, 0.5), 3.0);
+        assert_eq!(quantile(&xs, 1.0), 5.0);
+        assert_eq!(quantile(&[1.0, 2.0], 0.5), 1.5);
+        assert!(quantile(&[], 0.5).is_nan());
+    }
+}
@@ -1,3 +1,5 @@
+use kurobako_core::num::OrderedFloat;
+use rustats::distributions::{Cdf as _, StandardNormal};
 use std::cmp::Ordering;
 use std::iter;
 
@@ -71,3 +73,81 @@ impl<T: Ord> Firsts<T> {
         self.items.iter().map(|t| t.1)
     }
 }
+
+/// Wilcoxon signed-rank test.
+///
+/// Returns `Ordering::Less` if the first elements of `pairs` are statistically less than the second ones,
+/// `Ordering::Greater` if they are statistically greater, otherwise `Ordering::Equal`.
+///
+/// Pairs that have no difference are discarded, and the p-value is computed by
+/// the normal approximation (with the tie correction).
+pub fn wilcoxon_signed_rank<I>(pairs: I, alpha: f64) -> Ordering
+where
+    I: Iterator<Item = (f64, f64)>,
+{
+    let mut diffs = pairs
+        .map(|(a, b)| a - b)
+        .filter(|d| *d != 0.0 && !d.is_nan())
+        .collect::<Vec<_>>();
+    diffs.sort_by_key(|d| OrderedFloat(d.abs()));
+
+    let mut w_plus = 0.0;
+    let mut w_minus = 0.0;
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < diffs.len() {
+        let j = (i..diffs.len())
+            .find(|&j| diffs[j].abs() != diffs[i].abs())
+            .unwrap_or(diffs.len());
+        let rank = (i + 1 + j) as f64 / 2.0;
+        for d in &diffs[i..j] {
+            if *d > 0.0 {
+                w_plus += rank;
+            } else {
+                w_minus += rank;
+            }
+        }
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+
+    let n = diffs.len() as f64;
+    let mean = n * (n + 1.0) / 4.0;
+    let var = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - ties / 48.0;
+    if var <= 0.0 {
+        return Ordering::Equal;
+    }
+
+    let z = (w_plus - mean) / var.sqrt();
+    let p = (1.0 - StandardNormal.cdf(&z.abs())) * 2.0;
+    if p >= alpha {
+        Ordering::Equal
+    } else if w_plus < w_minus {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wilcoxon_signed_rank_works() {
+        let xs = [1.83, 0.50, 1.62, 2.48, 1.68, 1.88, 1.55, 3.06, 1.30];
+        let ys = [0.878, 0.647, 0.598, 2.05, 1.06, 1.29, 1.06, 3.15, 1.29];
+        let pairs = || xs.iter().copied().zip(ys.iter().copied());
+        assert_eq!(wilcoxon_signed_rank(pairs(), 0.05), Ordering::Greater);
+        assert_eq!(
+            wilcoxon_signed_rank(pairs().map(|(x, y)| (y, x)), 0.05),
+            Ordering::Less
+        );
+        assert_eq!(wilcoxon_signed_rank(pairs(), 0.01), Ordering::Equal);
+        assert_eq!(
+            wilcoxon_signed_rank(xs.iter().map(|&x| (x, x)), 0.05),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use kurobako_core::num::OrderedFloat;
+use rustats::fundamental::{average, stddev};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Stability of a solver's ranking on a problem under leave-one-out resampling of seeds.
+#[derive(Debug, Clone)]
+pub struct Stability {
+    /// Ranking computed from all the seeds.
+    pub ranking: usize,
+
+    /// Fraction of the resamples in which the ranking is the same as `ranking`.
+    pub score: f64,
+
+    /// Standard deviation of the mean best values over the resamples.
+    pub sd: f64,
+}
+impl Stability {
+    pub fn is_stable(&self) -> bool {
+        (self.score - 1.0).abs() < f64::EPSILON
+    }
+}
+
+/// Computes the stability of each solver.
+///
+/// `samples` is a map from solver IDs to pairs of seeds and the best values of the studies run with them.
+/// In each resample, the samples of a seed are left out from every solver
+/// (the samples of a solver are kept if all of them have the seed).
+pub fn leave_one_out<K: Clone + Ord>(
+    samples: &BTreeMap<K, Vec<(u64, f64)>>,
+) -> BTreeMap<K, Stability> {
+    let full = samples
+        .iter()
+        .map(|(k, xs)| (k.clone(), average(xs.iter().map(|&(_, x)| x))))
+        .collect::<BTreeMap<_, _>>();
+    let full_rankings = rankings(&full);
+
+    let seeds = samples
+        .values()
+        .flat_map(|xs| xs.iter().map(|&(seed, _)| seed))
+        .collect::<BTreeSet<_>>();
+    let mut matches = BTreeMap::<_, usize>::new();
+    let mut means = BTreeMap::<_, Vec<f64>>::new();
+    for &seed in &seeds {
+        let resampled = samples
+            .iter()
+            .map(|(k, xs)| {
+                let keep_all = xs.iter().all(|&(s, _)| s == seed);
+                let ys = xs
+                    .iter()
+                    .filter(|&&(s, _)| s != seed || keep_all)
+                    .map(|&(_, x)| x);
+                (k.clone(), average(ys))
+            })
+            .collect::<BTreeMap<_, _>>();
+        for (k, ranking) in rankings(&resampled) {
+            if full_rankings[&k] == ranking {
+                *matches.entry(k.clone()).or_default() += 1;
+            }
+            means.entry(k.clone()).or_default().push(resampled[&k]);
+        }
+    }
+
+    full_rankings
+        .into_iter()
+        .map(|(k, ranking)| {
+            let score = matches.get(&k).copied().unwrap_or(0) as f64 / seeds.len().max(1) as f64;
+            let sd = means.get(&k).map_or(0.0, |xs| stddev(xs.iter().copied()));
+            (k, Stability { ranking, score, sd })
+        })
+        .collect()
+}
+
+fn rankings<K: Clone + Ord>(means: &BTreeMap<K, f64>) -> BTreeMap<K, usize> {
+    means
+        .iter()
+        .map(|(k, &m)| {
+            let ranking = 1 + means
+                .values()
+                .filter(|&&n| OrderedFloat(n) < OrderedFloat(m))
+                .count();
+            (k.clone(), ranking)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leave_one_out_matches_samples_by_seed() {
+        // The seed `2` is the only one in which "b" beats "a",
+        // and the samples of "b" are given in a different order from "a".
+        let mut samples = BTreeMap::new();
+        samples.insert("a", vec![(0, 1.0), (1, 1.0), (2, 5.0)]);
+        samples.insert("b", vec![(2, 0.0), (0, 2.0), (1, 2.0)]);
+
+        let stabilities = leave_one_out(&samples);
+        assert_eq!(stabilities["a"].ranking, 2);
+        assert_eq!(stabilities["b"].ranking, 1);
+
+        // Leaving out the seed `2` flips the rankings.
+        assert!((stabilities["a"].score - 2.0 / 3.0).abs() < 1e-9);
+        assert!((stabilities["b"].score - 2.0 / 3.0).abs() < 1e-9);
+        assert!(!stabilities["a"].is_stable());
+    }
+
+    #[test]
+    fn leave_one_out_handles_missing_seeds() {
+        let mut samples = BTreeMap::new();
+        samples.insert("a", vec![(0, 1.0), (1, 1.0)]);
+        samples.insert("b", vec![(3, 2.0)]);
+
+        let stabilities = leave_one_out(&samples);
+        assert!(stabilities["a"].is_stable());
+        assert!(stabilities["b"].is_stable());
+        assert_eq!(stabilities["b"].ranking, 2);
+        assert_eq!(stabilities["b"].sd, 0.0);
+    }
+}
@@ -5,6 +5,7 @@ use crate::solver::KurobakoSolverRecipe;
 use crate::study::{Scheduling, StudyRecipe};
 use crate::time::ElapsedSeconds;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use kurobako_core::json;
 use kurobako_core::problem::ProblemRecipe as _;
 use kurobako_core::problem::{
     BoxEvaluator, BoxProblem, Evaluator as _, Problem as _, ProblemFactory as _, ProblemSpec,
@@ -13,16 +14,22 @@ use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::solver::{BoxSolver, Solver as _, SolverFactory as _, SolverRecipe as _};
 use kurobako_core::trial::Values;
-use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, TrialId};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId};
 use kurobako_core::{Error, ErrorKind, Result};
+use rand::distributions::{Distribution as _, WeightedIndex};
 use rand::seq::SliceRandom;
+use sha2::{Digest as _, Sha256};
 use std::collections::{HashMap, VecDeque};
-use std::io::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Write as _};
 use std::num::NonZeroUsize;
-use std::sync::atomic::{self, AtomicUsize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 use structopt::StructOpt;
+use tempfile::NamedTempFile;
 use trackable::error::ErrorKindExt;
 
 /// Options of the `kurobako run` command.
@@ -30,12 +37,184 @@ use trackable::error::ErrorKindExt;
 #[structopt(rename_all = "kebab-case")]
 pub struct RunnerOpt {
     /// Number of worker threads used to execute the benchmark.
+    ///
+    /// Each worker thread executes one study at a time,
+    /// so this also bounds the number of studies executed at the same time
+    /// unless `--max-concurrent-studies` is specified.
     #[structopt(long, short = "p", default_value = "1")]
     pub parallelism: NonZeroUsize,
 
+    /// Maximum number of studies that are executed (i.e., whose problems and solvers are alive) at the same time.
+    ///
+    /// This bounds the total resources (e.g., memory used by external processes and datasets)
+    /// separately from `--parallelism`.
+    /// If omitted, it's the same as `--parallelism`.
+    #[structopt(long)]
+    pub max_concurrent_studies: Option<NonZeroUsize>,
+
     /// Disables progress bar.
     #[structopt(long, short = "q")]
     pub quiet: bool,
+
+    /// Makes trial IDs globally unique across studies.
+    ///
+    /// If this is specified, the upper 32 bits of each trial ID are a prefix of the hash of the study recipe
+    /// (see `IdGen::with_namespace`), so the trials of parallel studies can be merged without collisions.
+    /// Otherwise, the trial IDs of each study start from `0`.
+    #[structopt(long)]
+    pub global_trial_ids: bool,
+
+    /// Memoizes the evaluation results within each study.
+    ///
+    /// If this is specified, when a solver asks the same parameters (and steps) as an earlier trial,
+    /// the cached values are returned without invoking the problem evaluator.
+    /// Such trials are still recorded and consume the budget (steps) as usual,
+    /// but their evaluation elapsed time becomes almost zero and the noise of stochastic problems isn't resampled.
+    ///
+    /// Note that this changes the semantics of the time-related metrics,
+    /// so the number of memoized evaluations is recorded in each study record and shown in reports.
+    #[structopt(long)]
+    pub memoize_evaluations: bool,
+
+    /// Fails studies if solvers ask parameters that don't belong to the problem domains.
+    ///
+    /// This is useful to detect buggy (external) solvers immediately.
+    #[structopt(long)]
+    pub strict_domain: bool,
+
+    /// Flushes the standard output after writing each study record.
+    ///
+    /// This is useful when the results are consumed on the fly (e.g., by a live dashboard).
+    #[structopt(long)]
+    pub flush: bool,
+
+    /// Number of study records that are buffered before being written to the standard output.
+    ///
+    /// Buffering reduces the number of write system calls when many short studies are executed.
+    /// The remaining records are written when the benchmark finishes (even if it's canceled).
+    #[structopt(long, conflicts_with = "flush")]
+    pub buffer_records: Option<NonZeroUsize>,
+
+    /// Wall-clock time limit of each study in seconds.
+    ///
+    /// If a study exceeds this limit, it's stopped before asking the next trial and
+    /// the partial record is written as usual.
+    /// Such a record is marked as timed out (see `--timeout-as-worst` of `kurobako report`).
+    /// Other studies aren't affected.
+    #[structopt(long)]
+    pub study_timeout: Option<f64>,
+
+    /// Sorts the studies by their problem recipes before executing them.
+    ///
+    /// Each worker thread caches the most recently used external program problem,
+    /// so running the studies that share the same problem back-to-back avoids relaunching the program.
+    /// The sort is stable (i.e., the relative order of the studies of each problem is preserved).
+    ///
+    /// Note that the studies are dispatched to the idle worker threads in the sorted order,
+    /// so if `--parallelism` is greater than one, the studies of a problem are spread over the workers
+    /// (and each worker launches the program at most once per problem as long as the studies of other problems
+    /// don't interleave on that worker).
+    #[structopt(long)]
+    pub sort_studies: bool,
+
+    /// Resumes an interrupted benchmark by using the given file as a checkpoint.
+    ///
+    /// The studies whose records are already in the file are skipped
+    /// (the records are matched by the study recipes including the random seeds if they are specified),
+    /// and the records of the executed studies are appended to the file instead of being written to the standard output.
+    /// If the file doesn't exist, it's created.
+    ///
+    /// The partial records of the studies interrupted by `SIGINT` are removed from the file
+    /// and the studies are executed again.
+    /// Note that the studies stopped by `--study-timeout` are regarded as completed.
+    #[structopt(long)]
+    pub resume: Option<PathBuf>,
+
+    /// Pins each worker thread to a distinct CPU core (round-robin over the cores available to the process).
+    ///
+    /// This reduces the cross-socket memory traffic on NUMA machines, and mainly helps when
+    /// `--parallelism` approaches the number of cores.
+    /// Note that external programs spawned by a worker inherit the affinity of the worker.
+    /// This is only supported on Linux (it's ignored with a warning on other platforms).
+    #[structopt(long)]
+    pub pin_threads: bool,
+}
+
+impl RunnerOpt {
+    // Each worker thread executes one study at a time,
+    // so the number of workers is also the maximum number of studies executed at the same time.
+    fn workers(&self) -> NonZeroUsize {
+        self.max_concurrent_studies
+            .map_or(self.parallelism, |n| n.min(self.parallelism))
+    }
+}
+
+#[derive(Debug)]
+struct RecordWriter {
+    file: Option<File>,
+    flush: bool,
+    buffer_records: usize,
+    buffer: Mutex<(Vec<u8>, usize)>,
+}
+impl RecordWriter {
+    fn new(opt: &RunnerOpt) -> Result<Self> {
+        let file = if let Some(path) = &opt.resume {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            Some(track!(file.map_err(Error::from); path)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            file,
+            flush: opt.flush,
+            buffer_records: opt.buffer_records.map_or(1, |n| n.get()),
+            buffer: Mutex::new((Vec::new(), 0)),
+        })
+    }
+
+    fn write(&self, record: &StudyRecord) -> Result<()> {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| panic!("{}", e));
+        let (bytes, count) = &mut *buffer;
+        track!(serde_json::to_writer(&mut *bytes, record).map_err(Error::from))?;
+        bytes.push(b'\n');
+        *count += 1;
+        if *count >= self.buffer_records {
+            track!(self.write_buffer(bytes))?;
+            *count = 0;
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| panic!("{}", e));
+        let (bytes, count) = &mut *buffer;
+        if *count > 0 {
+            track!(self.write_buffer(bytes))?;
+            *count = 0;
+        }
+        Ok(())
+    }
+
+    fn write_buffer(&self, bytes: &mut Vec<u8>) -> Result<()> {
+        // Note that the buffer is locked by the caller, so the records are never interleaved.
+        if let Some(mut file) = self.file.as_ref() {
+            track!(file.write_all(bytes).map_err(Error::from))?;
+            if self.flush {
+                track!(file.flush().map_err(Error::from))?;
+            }
+            bytes.clear();
+            return Ok(());
+        }
+
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        track!(stdout.write_all(bytes).map_err(Error::from))?;
+        if self.flush {
+            track!(stdout.flush().map_err(Error::from))?;
+        }
+        bytes.clear();
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -70,10 +249,19 @@ pub struct Runner {
     mpb: Arc<MultiProgress>,
     opt: RunnerOpt,
     cancel: Cancel,
+    create_registry: fn() -> FactoryRegistry,
 }
 impl Runner {
     /// Makes a `Runner` instance.
     pub fn new(opt: RunnerOpt) -> Self {
+        Self::with_registry(opt, default_registry)
+    }
+
+    /// Makes a `Runner` instance that uses the registries created by the given function.
+    ///
+    /// This is useful to run studies using user-defined recipes
+    /// (see `FactoryRegistry::register_problem_recipe` and `FactoryRegistry::register_solver_recipe`).
+    pub fn with_registry(opt: RunnerOpt, create_registry: fn() -> FactoryRegistry) -> Self {
         let target = if opt.quiet {
             ProgressDrawTarget::hidden()
         } else {
@@ -84,26 +272,82 @@ impl Runner {
             mpb: Arc::new(mpb),
             opt,
             cancel: Cancel::new(),
+            create_registry,
         }
     }
 
     /// Runs the benchmark.
+    ///
+    /// The study recipes are read from the standard input and the results are written to the standard output.
+    ///
+    /// If `SIGINT` is received (e.g., Ctrl-C is pressed), the running studies are stopped before asking
+    /// the next trials and the records produced so far (including the partial records of the stopped studies)
+    /// are written before returning an error.
+    /// The second `SIGINT` terminates the process immediately.
     pub fn run(mut self) -> Result<()> {
         let recipes = track!(self.read_study_recipes())?;
+        install_sigint_handler();
+        track!(self.run_recipes(recipes, None))
+    }
+
+    /// Runs the given studies and returns the results.
+    ///
+    /// Note that the order of the results may differ from the order of the recipes.
+    pub fn run_studies(self, recipes: Vec<StudyRecipe>) -> Result<Vec<StudyRecord>> {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        track!(self.run_recipes(recipes, Some(Arc::clone(&records))))?;
+        let records = records.lock().unwrap_or_else(|e| panic!("{}", e));
+        Ok(records.clone())
+    }
+
+    fn run_recipes(
+        self,
+        mut recipes: Vec<StudyRecipe>,
+        records: Option<Arc<Mutex<Vec<StudyRecord>>>>,
+    ) -> Result<()> {
+        if self.opt.sort_studies {
+            track!(sort_by_problem(&mut recipes))?;
+        }
+        if records.is_none() {
+            if let Some(path) = &self.opt.resume {
+                let total = recipes.len();
+                let interrupted = track!(remove_interrupted_studies(path))?;
+                recipes = track!(skip_completed_studies(recipes, path))?;
+                if !self.opt.quiet {
+                    eprintln!(
+                        "Skipped {} completed studies and removed {} interrupted ones (checkpoint: {:?})",
+                        total - recipes.len(),
+                        interrupted,
+                        path
+                    );
+                }
+            }
+        }
+
         let pb = self.create_pb(&recipes);
 
-        self.spawn_runners(recipes, pb);
+        let writer = Arc::new(track!(RecordWriter::new(&self.opt))?);
+        self.spawn_runners(recipes, pb, records, Arc::clone(&writer));
         track!(self.mpb.join().map_err(|e| ErrorKind::Other.cause(e)))?;
         eprintln!();
+        track!(writer.finish())?;
 
         if let Some(e) = self.cancel.take() {
             Err(e)
+        } else if is_interrupted() {
+            track_panic!(ErrorKind::Other, "Interrupted by SIGINT");
         } else {
             Ok(())
         }
     }
 
-    fn spawn_runners(&self, recipes: Vec<StudyRecipe>, pb: ProgressBar) {
+    fn spawn_runners(
+        &self,
+        recipes: Vec<StudyRecipe>,
+        pb: ProgressBar,
+        records: Option<Arc<Mutex<Vec<StudyRecord>>>>,
+        writer: Arc<RecordWriter>,
+    ) {
         pb.tick();
 
         let pb_len = recipes.len() as u64;
@@ -111,16 +355,36 @@ impl Runner {
             recipes.into_iter().map(Some).collect::<Vec<_>>(),
         ));
 
+        let workers = self.opt.workers();
+
+        let cores = if self.opt.pin_threads {
+            available_cores()
+        } else {
+            Vec::new()
+        };
+
         let next_index = Arc::new(AtomicUsize::new(0));
-        for _ in 0..self.opt.parallelism.get() {
+        for worker in 0..workers.get() {
+            let core = if cores.is_empty() {
+                None
+            } else {
+                Some(cores[worker % cores.len()])
+            };
             let pb = pb.clone();
             let recipes = Arc::clone(&recipes);
             let next_index = Arc::clone(&next_index);
             let cancel = self.cancel.clone();
             let opt = self.opt.clone();
             let mpb = Arc::clone(&self.mpb);
+            let create_registry = self.create_registry;
+            let records = records.clone();
+            let writer = Arc::clone(&writer);
             thread::spawn(move || {
-                while !cancel.is_canceled() {
+                if let Some(core) = core {
+                    pin_current_thread(core);
+                }
+                let registry = create_registry();
+                while !cancel.is_canceled() && !is_interrupted() {
                     let i = next_index.fetch_add(1, atomic::Ordering::SeqCst);
                     let recipe = {
                         let mut recipes = recipes.lock().unwrap_or_else(|e| panic!("{}", e));
@@ -130,16 +394,25 @@ impl Runner {
                         recipes[i].take().unwrap_or_else(|| unreachable!())
                     };
 
-                    let result = track!(StudyRunner::with_mpb(&recipe, &opt, &mpb))
+                    // The seed of a top-level study is recorded in `StudyRecord`,
+                    // so it's OK to choose it randomly.
+                    let mut recipe = recipe;
+                    recipe.seed.get_or_insert_with(rand::random);
+
+                    let result = track!(StudyRunner::with_mpb(&recipe, &opt, &mpb, &registry))
                         .and_then(|runner| track!(runner.run()));
 
-                    fn output(record: StudyRecord) -> Result<()> {
-                        let stdout = std::io::stdout();
-                        let mut stdout = stdout.lock();
-                        track!(serde_json::to_writer(&mut stdout, &record).map_err(Error::from))?;
-                        track!(writeln!(stdout).map_err(Error::from))?;
-                        Ok(())
-                    }
+                    let output = |record: StudyRecord| -> Result<()> {
+                        if let Some(records) = &records {
+                            records
+                                .lock()
+                                .unwrap_or_else(|e| panic!("{}", e))
+                                .push(record);
+                            return Ok(());
+                        }
+
+                        track!(writer.write(&record))
+                    };
                     let result = track!(result.and_then(output));
                     pb.inc(1);
 
@@ -173,43 +446,258 @@ impl Runner {
     }
 }
 
+// Removes the recipes whose complete records are found in the given file.
+// Removes the records of the interrupted studies from the checkpoint file and returns the number of them.
+//
+// The file is replaced with a rewritten one, so the remaining records are kept even if this fails halfway.
+fn remove_interrupted_studies(path: &Path) -> Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let file = track!(File::open(path).map_err(Error::from); path)?;
+    let records: Vec<serde_json::Value> = track!(json::load(BufReader::new(file)); path)?;
+    let total = records.len();
+    let mut remaining = Vec::with_capacity(total);
+    for record in records {
+        if !track!(StudyRecord::migrate(record.clone()); path)?.interrupted {
+            remaining.push(record);
+        }
+    }
+    if remaining.len() == total {
+        return Ok(0);
+    }
+
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let mut temp =
+        track!(NamedTempFile::new_in(dir.unwrap_or_else(|| Path::new("."))).map_err(Error::from))?;
+    for record in &remaining {
+        track!(serde_json::to_writer(&mut temp, record).map_err(Error::from))?;
+        track!(writeln!(temp).map_err(Error::from))?;
+    }
+    track!(temp.persist(path).map_err(|e| Error::from(e.error)); path)?;
+    Ok(total - remaining.len())
+}
+
+fn skip_completed_studies(recipes: Vec<StudyRecipe>, path: &Path) -> Result<Vec<StudyRecipe>> {
+    if !path.exists() {
+        return Ok(recipes);
+    }
+
+    let file = track!(File::open(path).map_err(Error::from); path)?;
+    let records: Vec<serde_json::Value> = track!(json::load(BufReader::new(file)); path)?;
+
+    // Recipe (without seed) => seeds of the completed studies.
+    let mut completed = HashMap::<_, Vec<u64>>::new();
+    for record in records {
+        let record = track!(StudyRecord::migrate(record); path)?;
+        let recipe = record.recipe();
+        let key = track!(recipe_key(&recipe))?;
+        completed.entry(key).or_default().push(record.seed);
+    }
+
+    let keys = recipes
+        .iter()
+        .map(|recipe| track!(recipe_key(recipe)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut skip = vec![false; recipes.len()];
+
+    // The recipes that have seeds are matched first, so that they aren't preempted by
+    // the recipes whose seeds are chosen at random.
+    for (i, recipe) in recipes.iter().enumerate() {
+        if let (Some(seed), Some(seeds)) = (recipe.seed, completed.get_mut(&keys[i])) {
+            if let Some(j) = seeds.iter().position(|&s| s == seed) {
+                seeds.swap_remove(j);
+                skip[i] = true;
+            }
+        }
+    }
+    for (i, recipe) in recipes.iter().enumerate() {
+        if let (None, Some(seeds)) = (recipe.seed, completed.get_mut(&keys[i])) {
+            skip[i] = seeds.pop().is_some();
+        }
+    }
+
+    Ok(recipes
+        .into_iter()
+        .zip(skip)
+        .filter(|(_, skip)| !skip)
+        .map(|(recipe, _)| recipe)
+        .collect())
+}
+
+fn recipe_key(recipe: &StudyRecipe) -> Result<Vec<u8>> {
+    let mut recipe = recipe.clone();
+    recipe.seed = None;
+    track!(serde_json::to_vec(&recipe).map_err(Error::from))
+}
+
+// Returns the CPU cores on which the current process is allowed to run.
+#[cfg(target_os = "linux")]
+fn available_cores() -> Vec<usize> {
+    unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            eprintln!(
+                "WARNING: Failed to get the CPU affinity (threads aren't pinned): {}",
+                std::io::Error::last_os_error()
+            );
+            return Vec::new();
+        }
+        (0..libc::CPU_SETSIZE as usize)
+            .filter(|&core| libc::CPU_ISSET(core, &set))
+            .collect()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_cores() -> Vec<usize> {
+    eprintln!("WARNING: `--pin-threads` is only supported on Linux (threads aren't pinned)");
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(core: usize) {
+    unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            eprintln!(
+                "WARNING: Failed to pin a worker thread to the core {}: {}",
+                core,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_core: usize) {}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+fn is_interrupted() -> bool {
+    INTERRUPTED.load(atomic::Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+fn install_sigint_handler() {
+    extern "C" fn handle(_signum: libc::c_int) {
+        INTERRUPTED.store(true, atomic::Ordering::SeqCst);
+
+        // Restores the default behavior, so that the second SIGINT terminates the process.
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handle as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn install_sigint_handler() {}
+
+// Stable-sorts the recipes by the hash of their problem recipes.
+fn sort_by_problem(recipes: &mut Vec<StudyRecipe>) -> Result<()> {
+    let mut keyed = recipes
+        .drain(..)
+        .map(|recipe| {
+            let json = track!(serde_json::to_vec(&recipe.problem).map_err(Error::from))?;
+            Ok((Sha256::digest(&json), recipe))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    keyed.sort_by_key(|(hash, _)| *hash);
+    recipes.extend(keyed.into_iter().map(|(_, recipe)| recipe));
+    Ok(())
+}
+
+fn default_registry() -> FactoryRegistry {
+    FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>()
+}
+
+// Indices of the random number streams of a study (see `ArcRng::substream`).
+//
+// The problem instance of the `i`-th evaluation thread uses the stream `EVALUATION_SUBSTREAM + i`.
+const SCHEDULING_SUBSTREAM: u64 = 0;
+const SOLVER_SUBSTREAM: u64 = 1;
+const EVALUATION_SUBSTREAM: u64 = 2;
+
 #[derive(Debug)]
 pub(crate) struct StudyRunner {
+    rng: ArcRng,
     solver: BoxSolver,
-    problem: BoxProblem,
+    problems: Vec<BoxProblem>,
     problem_spec: ProblemSpec,
     study_record: StudyRecordBuilder,
     pb: ProgressBar,
     idg: IdGen,
     threads: EvaluationThreads,
     evaluators: HashMap<TrialId, EvaluatorState>,
+    cache: Option<EvaluationCache>,
     study_steps: u64,
+    stop_on_target: Option<f64>,
     opt: RunnerOpt,
+    start_time: Instant,
     _mpb: Option<MultiProgress>,
 }
 impl StudyRunner {
     pub fn new(study: &StudyRecipe) -> Result<Self> {
         let opt = RunnerOpt {
             parallelism: unsafe { NonZeroUsize::new_unchecked(1) },
+            max_concurrent_studies: None,
             quiet: true,
+            global_trial_ids: false,
+            memoize_evaluations: false,
+            strict_domain: false,
+            flush: false,
+            buffer_records: None,
+            study_timeout: None,
+            sort_studies: false,
+            resume: None,
+            pin_threads: false,
         };
         let mpb = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
-        let mut this = track!(Self::with_mpb(study, &opt, &mpb))?;
+        let registry = default_registry();
+        let mut this = track!(Self::with_mpb(study, &opt, &mpb, &registry))?;
         this._mpb = Some(mpb);
         Ok(this)
     }
 
-    fn with_mpb(study: &StudyRecipe, opt: &RunnerOpt, mpb: &MultiProgress) -> Result<Self> {
-        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+    fn with_mpb(
+        study: &StudyRecipe,
+        opt: &RunnerOpt,
+        mpb: &MultiProgress,
+        registry: &FactoryRegistry,
+    ) -> Result<Self> {
+        if let Some(timeout) = opt.study_timeout {
+            track_assert!(
+                timeout.is_finite() && timeout >= 0.0,
+                ErrorKind::InvalidInput,
+                "`--study-timeout` must be a non-negative finite number: {}",
+                timeout
+            );
+        }
 
+        // The chosen seed is recorded in `StudyRecord`, so the study can be replayed by it.
         let random_seed = study.seed.unwrap_or_else(rand::random);
         let rng = ArcRng::new(random_seed);
 
-        let problem_factory = track!(study.problem.create_factory(&registry))?;
+        // Each evaluation thread has its own problem instance (and random number stream),
+        // so the evaluations of a thread don't depend on the draws of the solver and the other threads.
+        let problem_factory = track!(study.problem.create_factory(registry))?;
         let problem_spec = track!(problem_factory.specification())?;
-        let problem = track!(problem_factory.create_problem(rng.clone()))?;
+        let problems = (0..study.concurrency.get() as u64)
+            .map(
+                |i| track!(problem_factory.create_problem(rng.substream(EVALUATION_SUBSTREAM + i))),
+            )
+            .collect::<Result<Vec<_>>>()?;
 
-        let solver_factory = track!(study.solver.create_factory(&registry))?;
+        let solver_factory = track!(study.solver.create_factory(registry))?;
         let solver_spec = track!(solver_factory.specification())?;
 
         let incapables = solver_spec
@@ -218,7 +706,17 @@ impl StudyRunner {
             .collect::<Vec<_>>();
         track_assert!(incapables.is_empty(), ErrorKind::Incapable; incapables);
 
-        let solver = track!(solver_factory.create_solver(rng.clone(), &problem_spec))?;
+        if study.stop_on_target.is_some() {
+            track_assert_eq!(
+                problem_spec.values_domain.variables().len(),
+                1,
+                ErrorKind::InvalidInput;
+                problem_spec.name
+            );
+        }
+
+        let solver =
+            track!(solver_factory.create_solver(rng.substream(SOLVER_SUBSTREAM), &problem_spec))?;
 
         let study_steps = problem_spec.steps.last() * study.budget;
         let pb = mpb.add(ProgressBar::new(study_steps));
@@ -232,24 +730,41 @@ impl StudyRunner {
         let mut recipe = study.clone();
         recipe.seed = Some(random_seed);
         let study_record = StudyRecordBuilder::new(recipe, solver_spec, problem_spec.clone());
-        let threads = EvaluationThreads::new(study, rng);
+        let idg = if opt.global_trial_ids {
+            IdGen::with_namespace(track!(study_record.trial_id_namespace())?)
+        } else {
+            IdGen::new()
+        };
+        let threads = track!(EvaluationThreads::new(
+            study,
+            rng.substream(SCHEDULING_SUBSTREAM)
+        ))?;
         Ok(Self {
+            rng,
             solver,
-            problem,
+            problems,
             problem_spec,
             study_record,
             pb,
-            idg: IdGen::new(),
+            idg,
             threads,
             evaluators: HashMap::new(),
+            cache: if opt.memoize_evaluations {
+                Some(EvaluationCache::default())
+            } else {
+                None
+            },
             study_steps,
+            stop_on_target: study.stop_on_target,
             opt: opt.clone(),
+            start_time: Instant::now(),
             _mpb: None,
         })
     }
 
     pub fn run_init(&mut self) -> Result<()> {
         self.pb.reset_elapsed();
+        self.start_time = Instant::now();
         Ok(())
     }
 
@@ -267,9 +782,10 @@ impl StudyRunner {
 
         let problem_spec = &self.problem_spec;
         let evaluators = &mut self.evaluators;
+        let cache = self.cache.as_mut();
         let ((elapsed_steps, evaluated_trial), evaluate_elapsed) =
             ElapsedSeconds::try_time(|| {
-                track!(thread.evaluate(asked_trial.id, next_step, problem_spec, evaluators))
+                track!(thread.evaluate(&asked_trial, next_step, problem_spec, evaluators, cache))
             })?;
         self.pb.inc(elapsed_steps);
         let end_step = self.pb.position();
@@ -295,11 +811,17 @@ impl StudyRunner {
     }
 
     fn fill_waiting_queue(&mut self) -> Result<()> {
-        while self.threads.has_idle_thread() {
+        while let Some(thread_id) = self.threads.idle_thread_id() {
             let (asked_trial, ask_elapsed) =
                 ElapsedSeconds::try_time(|| track!(self.solver.ask(&mut self.idg)))?;
+            if self.opt.strict_domain {
+                track!(self
+                    .problem_spec
+                    .params_domain
+                    .validate_params(asked_trial.params.get()); asked_trial.id)?;
+            }
 
-            if let Err(e) = track!(self.init_evaluator(&asked_trial)) {
+            if let Err(e) = track!(self.init_evaluator(&asked_trial, thread_id)) {
                 if *e.kind() != ErrorKind::UnevaluableParams {
                     return Err(e);
                 } else {
@@ -327,6 +849,11 @@ impl StudyRunner {
         self.study_steps
     }
 
+    /// Returns `true` if random numbers that cannot be reproduced by the seed have been consumed in this study.
+    pub fn unseeded_rng_used(&self) -> bool {
+        self.rng.unseeded_rng_used()
+    }
+
     pub fn best_values(&self) -> Option<&Values> {
         // Note that even if there are more than one trials on the pareto front,
         // the only last one will be returned.
@@ -340,17 +867,59 @@ impl StudyRunner {
             if self.pb.is_hidden() && !self.opt.quiet {
                 eprintln!("DONE: {}/{}", self.pb.position(), self.study_steps);
             }
+            if is_interrupted() {
+                self.study_record.interrupted();
+                break;
+            }
+            if self.is_study_timed_out() {
+                if !self.opt.quiet {
+                    eprintln!(
+                        "TIMEOUT: {:?} ({}/{} steps)",
+                        self.problem_spec.name,
+                        self.pb.position(),
+                        self.study_steps
+                    );
+                }
+                self.study_record.timed_out();
+                break;
+            }
             track!(self.run_once())?;
+
+            if self.is_target_reached() {
+                let step = self.pb.position();
+                self.study_record.target_reached(step);
+                break;
+            }
         }
 
         self.pb.finish_and_clear();
+        self.study_record
+            .unseeded_rng_used(self.rng.unseeded_rng_used());
+        if let Some(cache) = &self.cache {
+            self.study_record.memoized_evaluations(cache.hits);
+        }
         Ok(self.study_record.finish())
     }
 
+    fn is_study_timed_out(&self) -> bool {
+        self.opt
+            .study_timeout
+            .is_some_and(|timeout| self.start_time.elapsed().as_secs_f64() >= timeout)
+    }
+
+    fn is_target_reached(&self) -> bool {
+        if let Some(target) = self.stop_on_target {
+            if let Some(values) = self.best_values() {
+                return values.len() == 1 && values[0] <= target;
+            }
+        }
+        false
+    }
+
     #[allow(clippy::map_entry)]
-    fn init_evaluator(&mut self, trial: &NextTrial) -> Result<()> {
+    fn init_evaluator(&mut self, trial: &NextTrial, thread_id: usize) -> Result<()> {
         if !self.evaluators.contains_key(&trial.id) {
-            let evaluator = track!(EvaluatorState::new(&self.problem, trial))?;
+            let evaluator = track!(EvaluatorState::new(&self.problems[thread_id], trial))?;
             self.evaluators.insert(trial.id, evaluator);
         }
         Ok(())
@@ -366,21 +935,45 @@ impl StudyRunner {
 struct EvaluationThreads {
     threads: Vec<EvaluationThread>,
     scheduling: Scheduling,
+    weighted_index: Option<WeightedIndex<f64>>,
     rng: ArcRng,
 }
 impl EvaluationThreads {
-    fn new(recipe: &StudyRecipe, rng: ArcRng) -> Self {
-        Self {
+    fn new(recipe: &StudyRecipe, rng: ArcRng) -> Result<Self> {
+        let weighted_index = if let Scheduling::Weighted { weights } = &recipe.scheduling {
+            track_assert_eq!(
+                weights.len(),
+                recipe.concurrency.get(),
+                ErrorKind::InvalidInput,
+                "The number of weights must be equal to the concurrency"
+            );
+            track_assert!(
+                weights.iter().all(|w| w.is_finite() && *w > 0.0),
+                ErrorKind::InvalidInput,
+                "Weights must be positive finite numbers: {:?}",
+                weights
+            );
+            let index = WeightedIndex::new(weights.iter().map(|w| 1.0 / w));
+            Some(track!(index.map_err(|e| ErrorKind::InvalidInput.cause(e)))?)
+        } else {
+            None
+        };
+        Ok(Self {
             threads: (0..recipe.concurrency.get())
                 .map(EvaluationThread::new)
                 .collect(),
-            scheduling: recipe.scheduling,
+            scheduling: recipe.scheduling.clone(),
+            weighted_index,
             rng,
-        }
+        })
     }
 
-    fn has_idle_thread(&self) -> bool {
-        self.threads.iter().any(|t| t.is_idle())
+    // Returns the ID of the thread to which the next trial will be assigned (if any).
+    fn idle_thread_id(&self) -> Option<usize> {
+        self.threads
+            .iter()
+            .find(|t| t.is_idle())
+            .map(|t| t.thread_id)
     }
 
     fn next(&mut self) -> Result<&mut EvaluationThread> {
@@ -397,6 +990,11 @@ impl EvaluationThreads {
                     track_assert_some!(self.threads.choose_mut(&mut self.rng), ErrorKind::Bug);
                 Ok(thread)
             }
+            Scheduling::Weighted { .. } => {
+                let index = track_assert_some!(self.weighted_index.as_ref(), ErrorKind::Bug);
+                let i = index.sample(&mut self.rng);
+                Ok(&mut self.threads[i])
+            }
         }
     }
 
@@ -439,18 +1037,32 @@ impl EvaluationThread {
 
     fn evaluate(
         &mut self,
-        trial_id: TrialId,
+        trial: &NextTrial,
         next_step: u64,
         problem_spec: &ProblemSpec,
         evaluators: &mut HashMap<TrialId, EvaluatorState>,
+        cache: Option<&mut EvaluationCache>,
     ) -> Result<(u64, EvaluatedTrial)> {
+        let trial_id = trial.id;
         let mut state = track_assert_some!(evaluators.remove(&trial_id), ErrorKind::Bug);
 
         let next_step = track_assert_some!(
             problem_spec.steps.iter().find(|&s| s >= next_step),
             ErrorKind::Bug
         );
-        let (current_step, values) = track!(state.evaluator.evaluate(next_step))?;
+        let (current_step, values) = if let Some(cache) = cache {
+            let key = (trial.params.clone(), state.current_step, next_step);
+            if let Some(result) = cache.entries.get(&key) {
+                cache.hits += 1;
+                result.clone()
+            } else {
+                let result = track!(state.evaluator.evaluate(next_step))?;
+                cache.entries.insert(key, result.clone());
+                result
+            }
+        } else {
+            track!(state.evaluator.evaluate(next_step))?
+        };
         track_assert!(state.current_step <= current_step, ErrorKind::Bug);
         let elapsed_steps = current_step - state.current_step;
         self.elapsed_steps += elapsed_steps;
@@ -469,6 +1081,13 @@ impl EvaluationThread {
     }
 }
 
+/// Cache of evaluation results keyed by `(params, start_step, next_step)`.
+#[derive(Debug, Default)]
+struct EvaluationCache {
+    entries: HashMap<(Params, u64, u64), (u64, Values)>,
+    hits: u64,
+}
+
 #[derive(Debug)]
 struct WaitingTrial {
     asked_trial: NextTrial,
@@ -489,3 +1108,370 @@ impl EvaluatorState {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_objective_study_works() -> trackable::result::TopLevelResult {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"nsga2":{"population":10,"tournament":2,"crossover":0.5,"mutation":0.3}},
+                "problem":{"zdt":{"zdt":"1"}},"budget":50,"concurrency":1,"scheduling":"RANDOM","seed":0}"#
+        ))?;
+        let mut runner = track!(StudyRunner::new(&study))?;
+        track!(runner.run_init())?;
+        while runner.current_step() < runner.max_step() {
+            track!(runner.run_once())?;
+        }
+
+        // If the objective values were collapsed into a scalar, the frontier would have only one point.
+        let frontier = runner.study_record.pareto_frontier().collect::<Vec<_>>();
+        assert!(frontier.len() > 1);
+        assert!(frontier.iter().all(|(_, _, values)| values.len() == 2));
+
+        // The reference point given at report time overrides the default one.
+        let record = runner.study_record.finish();
+        let default = track!(record.hypervolumes(None))?;
+        let near = track!(record.hypervolumes(Some(&[10.0, 10.0])))?;
+        let far = track!(record.hypervolumes(Some(&[20.0, 20.0])))?;
+        assert_eq!(
+            default.keys().collect::<Vec<_>>(),
+            far.keys().collect::<Vec<_>>()
+        );
+        let (near, far) = (near.values().last(), far.values().last());
+        assert!(near < far, "near={:?}, far={:?}", near, far);
+        assert!(record.hypervolumes(Some(&[10.0])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn nsga2_variation_operators_work() -> trackable::result::TopLevelResult {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"nsga2":{"population":10,"tournament":2,"crossover":0.9,"mutation":0.1,
+                                   "crossover_type":"sbx","mutation_type":"polynomial"}},
+                "problem":{"zdt":{"zdt":"1"}},"budget":100,"concurrency":1,"scheduling":"RANDOM","seed":0}"#
+        ))?;
+        let mut runner = track!(StudyRunner::new(&study))?;
+        track!(runner.run_init())?;
+        while runner.current_step() < runner.max_step() {
+            track!(runner.run_once())?;
+        }
+
+        let frontier = runner.study_record.pareto_frontier().collect::<Vec<_>>();
+        assert!(frontier.len() > 1);
+
+        let f1 = frontier.iter().map(|(_, _, values)| values[0]);
+        let (min, max) = f1.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+        assert!(max - min > 0.1, "min={}, max={}", min, max);
+        Ok(())
+    }
+
+    #[test]
+    fn cmaes_converges_on_sphere() -> trackable::result::TopLevelResult {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"cmaes":{}},"problem":{"sigopt":{"name":"SPHERE","dim":2}},
+                "budget":300,"concurrency":1,"scheduling":"RANDOM","seed":0}"#
+        ))?;
+        let mut runner = track!(StudyRunner::new(&study))?;
+        track!(runner.run_init())?;
+        while runner.current_step() < runner.max_step() {
+            track!(runner.run_once())?;
+        }
+
+        let best = track_assert_some!(runner.best_values(), ErrorKind::Bug)[0];
+        assert!(best < 1e-3, "best={}", best);
+        Ok(())
+    }
+
+    #[test]
+    fn study_timeout_works() -> trackable::result::TopLevelResult {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"random":{}},
+                "problem":{"delay":{"problem":{"sigopt":{"name":"SPHERE","dim":2}},"seconds_per_step":0.05}},
+                "budget":100,"concurrency":1,"scheduling":"RANDOM","seed":0}"#
+        ))?;
+        let mut runner = track!(StudyRunner::new(&study))?;
+        runner.opt.study_timeout = Some(0.2);
+
+        let record = track!(runner.run())?;
+        assert!(!record.trials.is_empty());
+        assert!(record.trials.len() < 100);
+        assert!(record.is_timed_out());
+
+        // Studies stopped by reaching their targets aren't regarded as timed out.
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"random":{}},"problem":{"sigopt":{"name":"SPHERE","dim":2}},
+                "budget":100,"concurrency":1,"scheduling":"RANDOM","seed":0,"stop_on_target":1e9}"#
+        ))?;
+        let record = track!(StudyRunner::new(&study)?.run())?;
+        assert_eq!(record.target_reached_step, Some(1));
+        assert!(!record.is_timed_out());
+        Ok(())
+    }
+
+    #[test]
+    fn sort_by_problem_works() -> trackable::result::TopLevelResult {
+        let recipe = |problem: &str, seed: u64| -> Result<StudyRecipe> {
+            track!(json::parse_json(&format!(
+                r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"{}"}}}},
+                    "budget":10,"concurrency":1,"scheduling":"RANDOM","seed":{}}}"#,
+                problem, seed
+            )))
+        };
+        let mut recipes = vec![
+            recipe("SPHERE", 0)?,
+            recipe("ACKLEY", 1)?,
+            recipe("SPHERE", 2)?,
+            recipe("ACKLEY", 3)?,
+            recipe("SPHERE", 4)?,
+        ];
+        track!(sort_by_problem(&mut recipes))?;
+
+        let seeds = recipes.iter().map(|r| r.seed.unwrap()).collect::<Vec<_>>();
+        assert!(
+            seeds == [0, 2, 4, 1, 3] || seeds == [1, 3, 0, 2, 4],
+            "{:?}",
+            seeds
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resume_works() -> trackable::result::TopLevelResult {
+        let recipe = |seed: u64| -> Result<StudyRecipe> {
+            track!(json::parse_json(&format!(
+                r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"SPHERE"}}}},
+                    "budget":5,"concurrency":1,"scheduling":"RANDOM","seed":{}}}"#,
+                seed
+            )))
+        };
+        let dir = track!(tempfile::tempdir().map_err(Error::from))?;
+        let path = dir.path().join("checkpoint.json");
+        let opt = RunnerOpt::from_iter(&["run", "--quiet", "--resume", path.to_str().unwrap()]);
+
+        let load_seeds = || -> Result<Vec<u64>> {
+            let file = track!(File::open(&path).map_err(Error::from))?;
+            let records: Vec<StudyRecord> = track!(json::load(BufReader::new(file)))?;
+            Ok(records.into_iter().map(|r| r.seed).collect())
+        };
+
+        track!(Runner::new(opt.clone()).run_recipes(vec![recipe(0)?], None))?;
+        assert_eq!(load_seeds()?, [0]);
+
+        // Only the missing studies are executed, and their records are appended.
+        let recipes = vec![recipe(0)?, recipe(1)?, recipe(2)?];
+        track!(Runner::new(opt.clone()).run_recipes(recipes.clone(), None))?;
+        let mut seeds = load_seeds()?;
+        assert_eq!(seeds[0], 0);
+        seeds.sort_unstable();
+        assert_eq!(seeds, [0, 1, 2]);
+
+        track!(Runner::new(opt).run_recipes(recipes, None))?;
+        assert_eq!(load_seeds()?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_studies_are_resumed() -> trackable::result::TopLevelResult {
+        let recipe = |seed: u64| -> Result<StudyRecipe> {
+            track!(json::parse_json(&format!(
+                r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"SPHERE"}}}},
+                    "budget":5,"concurrency":1,"scheduling":"RANDOM","seed":{}}}"#,
+                seed
+            )))
+        };
+        let dir = track!(tempfile::tempdir().map_err(Error::from))?;
+        let path = dir.path().join("checkpoint.json");
+        let opt = RunnerOpt::from_iter(&["run", "--quiet", "--resume", path.to_str().unwrap()]);
+        let load = || -> Result<Vec<StudyRecord>> {
+            let file = track!(File::open(&path).map_err(Error::from))?;
+            track!(json::load(BufReader::new(file)))
+        };
+
+        // Makes a checkpoint that has a complete record and an interrupted one.
+        track!(Runner::new(opt.clone()).run_recipes(vec![recipe(0)?, recipe(1)?], None))?;
+        let mut records = load()?;
+        records.sort_by_key(|r| r.seed);
+        records[1].interrupted = true;
+        records[1].trials.truncate(2);
+        let mut file = track!(File::create(&path).map_err(Error::from))?;
+        for record in &records {
+            track!(serde_json::to_writer(&mut file, record).map_err(Error::from))?;
+            track!(writeln!(file).map_err(Error::from))?;
+        }
+
+        let recipes = vec![recipe(0)?, recipe(1)?, recipe(2)?];
+        track!(Runner::new(opt).run_recipes(recipes, None))?;
+        let records = load()?;
+        let mut seeds = records.iter().map(|r| r.seed).collect::<Vec<_>>();
+        seeds.sort_unstable();
+        assert_eq!(seeds, [0, 1, 2]);
+        assert!(records.iter().all(|r| !r.interrupted && !r.is_timed_out()));
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_runs_are_reproducible() -> trackable::result::TopLevelResult {
+        let recipe = |seed: u64| -> Result<StudyRecipe> {
+            track!(json::parse_json(&format!(
+                r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"SPHERE","dim":2}}}},
+                    "budget":20,"concurrency":3,"scheduling":"RANDOM","seed":{}}}"#,
+                seed
+            )))
+        };
+        let dir = track!(tempfile::tempdir().map_err(Error::from))?;
+        let run = |name: &str| -> Result<Vec<_>> {
+            let path = dir.path().join(name);
+            let opt = RunnerOpt::from_iter(&[
+                "run",
+                "--quiet",
+                "--parallelism",
+                "2",
+                "--resume",
+                path.to_str().unwrap(),
+            ]);
+            let recipes = (0..4).map(recipe).collect::<Result<Vec<_>>>()?;
+            track!(Runner::new(opt).run_recipes(recipes, None))?;
+
+            let file = track!(File::open(&path).map_err(Error::from))?;
+            let records: Vec<StudyRecord> = track!(json::load(BufReader::new(file)))?;
+            let mut trials = records
+                .into_iter()
+                .map(|r| {
+                    let trials = r
+                        .trials
+                        .iter()
+                        .map(|t| {
+                            let evaluations = t
+                                .evaluations
+                                .iter()
+                                .map(|e| (e.values.clone(), e.start_step, e.end_step))
+                                .collect::<Vec<_>>();
+                            (t.thread_id, t.params.clone(), evaluations)
+                        })
+                        .collect::<Vec<_>>();
+                    (r.seed, trials)
+                })
+                .collect::<Vec<_>>();
+
+            // The records are written in the order the studies finish.
+            trials.sort_by_key(|(seed, _)| *seed);
+            Ok(trials)
+        };
+
+        let first = run("first.json")?;
+        assert_eq!(first.len(), 4);
+        assert_eq!(first, run("second.json")?);
+        Ok(())
+    }
+
+    #[test]
+    fn max_concurrent_studies_works() -> trackable::result::TopLevelResult {
+        let opt = |args: &[&str]| RunnerOpt::from_iter([&["run", "--quiet"], args].concat());
+        assert_eq!(opt(&["-p", "4"]).workers().get(), 4);
+        assert_eq!(
+            opt(&["-p", "4", "--max-concurrent-studies", "2"])
+                .workers()
+                .get(),
+            2
+        );
+        assert_eq!(
+            opt(&["-p", "1", "--max-concurrent-studies", "3"])
+                .workers()
+                .get(),
+            1
+        );
+
+        let recipes = (0..3)
+            .map(|seed| {
+                track!(json::parse_json(&format!(
+                    r#"{{"solver":{{"random":{{}}}},"problem":{{"sigopt":{{"name":"SPHERE"}}}},
+                        "budget":5,"concurrency":1,"scheduling":"RANDOM","seed":{}}}"#,
+                    seed
+                )))
+            })
+            .collect::<Result<Vec<StudyRecipe>>>()?;
+        let runner = Runner::new(opt(&["-p", "4", "--max-concurrent-studies", "2"]));
+        let records = track!(runner.run_studies(recipes))?;
+        assert_eq!(records.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn evaluation_threads_have_own_random_streams() -> trackable::result::TopLevelResult {
+        // Returns the noises of the evaluations of each thread.
+        let noises = |solver: &str| -> Result<Vec<Vec<f64>>> {
+            let study: StudyRecipe = track!(json::parse_json(&format!(
+                r#"{{"solver":{},"problem":{{"sigopt":{{"name":"SPHERE","dim":2,"noise_stddev":1.0}}}},
+                    "budget":10,"concurrency":2,"scheduling":"FAIR","seed":0}}"#,
+                solver
+            )))?;
+            let record = track!(StudyRunner::new(&study)?.run())?;
+            let mut noises = vec![Vec::new(); 2];
+            for trial in &record.trials {
+                let value = trial.evaluations[0].values[0];
+                let sphere = trial.params.get().iter().map(|x| x * x).sum::<f64>();
+                noises[trial.thread_id].push(value - sphere);
+            }
+            Ok(noises)
+        };
+
+        // The grid solver draws no random numbers but the random solver does.
+        let grid = noises(r#"{"grid":{"resolution":4}}"#)?;
+        assert_eq!(grid[0].len(), 5);
+        assert_eq!(grid[1].len(), 5);
+        assert_ne!(grid[0], grid[1]);
+        for (a, b) in grid.iter().zip(noises(r#"{"random":{}}"#)?.iter()) {
+            for (a, b) in a.iter().zip(b) {
+                assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn weighted_scheduling_works() -> trackable::result::TopLevelResult {
+        let study: StudyRecipe = track!(json::parse_json(
+            r#"{"solver":{"random":{}},"problem":{"sigopt":{"name":"SPHERE"}},
+                "budget":10,"concurrency":3,"scheduling":{"WEIGHTED":{"weights":[1.0,2.0,4.0]}}}"#
+        ))?;
+        let mut threads = track!(EvaluationThreads::new(&study, ArcRng::new(0)))?;
+
+        let n = 7000;
+        let mut counts = [0; 3];
+        for _ in 0..n {
+            counts[track!(threads.next())?.thread_id] += 1;
+        }
+        for (count, expected) in counts.iter().zip([4.0 / 7.0, 2.0 / 7.0, 1.0 / 7.0]) {
+            let freq = f64::from(*count) / f64::from(n);
+            assert!((freq - expected).abs() < 0.02, "{:?}", counts);
+        }
+
+        let mut study = study;
+        study.scheduling = Scheduling::Weighted {
+            weights: vec![1.0, 2.0],
+        };
+        assert!(EvaluationThreads::new(&study, ArcRng::new(0)).is_err());
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pin_current_thread_works() {
+        let cores = available_cores();
+        assert!(!cores.is_empty());
+
+        let core = *cores.last().unwrap();
+        let pinned = thread::spawn(move || {
+            pin_current_thread(core);
+            available_cores()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(pinned, [core]);
+    }
+}
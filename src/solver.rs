@@ -5,7 +5,9 @@ use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::rng::ArcRng;
 use kurobako_core::solver::{BoxSolver, BoxSolverFactory, SolverFactory, SolverRecipe, SolverSpec};
 use kurobako_core::Result;
-use kurobako_solvers::{asha, nsga2, optuna, random};
+use kurobako_solvers::{
+    asha, cmaes, grid, nsga2, optuna, pruner, random, restart, sobol, warm_start,
+};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
@@ -40,9 +42,15 @@ impl SolverRecipe for KurobakoSolverRecipe {
 enum InnerRecipe {
     Command(epi::solver::ExternalProgramSolverRecipe),
     Random(random::RandomSolverRecipe),
+    Grid(grid::GridSolverRecipe),
+    Restart(restart::RestartSolverRecipe),
+    Sobol(sobol::SobolSolverRecipe),
+    Cmaes(cmaes::CmaesSolverRecipe),
     Asha(asha::AshaSolverRecipe),
+    Pruner(pruner::PrunerSolverRecipe),
     Nsga2(nsga2::Nsga2SolverRecipe),
     Optuna(optuna::OptunaSolverRecipe),
+    WarmStart(warm_start::WarmStartSolverRecipe),
 }
 impl SolverRecipe for InnerRecipe {
     type Factory = BoxSolverFactory;
@@ -50,10 +58,16 @@ impl SolverRecipe for InnerRecipe {
     fn create_factory(&self, registry: &FactoryRegistry) -> Result<Self::Factory> {
         match self {
             Self::Random(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::Grid(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::Restart(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::Sobol(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::Cmaes(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
             Self::Optuna(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
             Self::Asha(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::Pruner(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
             Self::Nsga2(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
             Self::Command(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
+            Self::WarmStart(r) => track!(r.create_factory(registry)).map(BoxSolverFactory::new),
         }
     }
 }
@@ -5,8 +5,13 @@ use kurobako_core::json;
 use kurobako_core::problem::{ProblemFactory as _, ProblemRecipe as _, ProblemSpec};
 use kurobako_core::registry::FactoryRegistry;
 use kurobako_core::solver::{SolverFactory as _, SolverRecipe as _, SolverSpec};
-use kurobako_core::Result;
+use kurobako_core::{Error, ErrorKind, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 /// Options of the `kurobako spec` command.
@@ -26,6 +31,28 @@ pub enum SpecOpt {
         #[structopt(parse(try_from_str = json::parse_json))]
         solver: KurobakoSolverRecipe,
     },
+
+    /// Show the differences between two specifications.
+    ///
+    /// Each file needs to contain a specification (JSON) printed by `kurobako spec problem` or `kurobako spec solver`.
+    Diff {
+        /// Path of the first specification file.
+        a: PathBuf,
+
+        /// Path of the second specification file.
+        b: PathBuf,
+    },
+
+    /// Show the differences between the specifications of two recipes.
+    ///
+    /// Each recipe needs to be a problem recipe (JSON) or a solver recipe (JSON).
+    DiffRecipes {
+        /// First recipe (JSON).
+        a: String,
+
+        /// Second recipe (JSON).
+        b: String,
+    },
 }
 
 impl SpecOpt {
@@ -43,6 +70,42 @@ impl SpecOpt {
                 let solver_spec = track!(solver_factory.specification())?;
                 Ok(Spec::Solver(solver_spec))
             }
+            Self::Diff { .. } | Self::DiffRecipes { .. } => track_panic!(
+                ErrorKind::InvalidInput,
+                "`kurobako spec diff` doesn't produce a specification"
+            ),
+        }
+    }
+
+    /// Returns the differences between the two specifications given to
+    /// `kurobako spec diff` or `kurobako spec diff-recipes`.
+    pub fn get_diff(&self) -> Result<Vec<SpecDifference>> {
+        match self {
+            Self::Diff { a, b } => {
+                let a = track!(Spec::load(a))?;
+                let b = track!(Spec::load(b))?;
+                track!(a.diff(&b))
+            }
+            Self::DiffRecipes { a, b } => {
+                let a = track!(track!(Self::from_recipe(a))?.get_spec())?;
+                let b = track!(track!(Self::from_recipe(b))?.get_spec())?;
+                track!(a.diff(&b))
+            }
+            _ => track_panic!(ErrorKind::InvalidInput, "Not a diff command: {:?}", self),
+        }
+    }
+
+    fn from_recipe(json: &str) -> Result<Self> {
+        if let Ok(problem) = json::parse_json(json) {
+            Ok(Self::Problem { problem })
+        } else if let Ok(solver) = json::parse_json(json) {
+            Ok(Self::Solver { solver })
+        } else {
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "Neither a problem recipe nor a solver recipe: {}",
+                json
+            );
         }
     }
 }
@@ -57,3 +120,171 @@ pub enum Spec {
     /// Solver specification.
     Solver(SolverSpec),
 }
+impl Spec {
+    /// Loads a specification from the given JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = track!(File::open(&path).map_err(Error::from); path.as_ref())?;
+        let spec = track!(serde_json::from_reader(BufReader::new(file)).map_err(Error::from); path.as_ref())?;
+        Ok(spec)
+    }
+
+    /// Returns the differences between this and the given specifications.
+    ///
+    /// The differences are sorted by their paths (e.g., `params_domain.variables[0].range`).
+    pub fn diff(&self, other: &Self) -> Result<Vec<SpecDifference>> {
+        let a = track!(serde_json::to_value(self).map_err(Error::from))?;
+        let b = track!(serde_json::to_value(other).map_err(Error::from))?;
+
+        let mut differences = Vec::new();
+        diff_values(String::new(), Some(&a), Some(&b), &mut differences);
+        Ok(differences)
+    }
+}
+
+/// A difference between two specifications.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpecDifference {
+    /// Path of the differing field.
+    pub path: String,
+
+    /// Value of the field in the first specification (`None` means that the field is absent).
+    pub a: Option<Value>,
+
+    /// Value of the field in the second specification (`None` means that the field is absent).
+    pub b: Option<Value>,
+}
+impl fmt::Display for SpecDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(a) = &self.a {
+            writeln!(f, "- {}: {}", self.path, a)?;
+        }
+        if let Some(b) = &self.b {
+            writeln!(f, "+ {}: {}", self.path, b)?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_values(
+    path: String,
+    a: Option<&Value>,
+    b: Option<&Value>,
+    differences: &mut Vec<SpecDifference>,
+) {
+    match (a, b) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys = a.keys().chain(b.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(child, a.get(key), b.get(key), differences);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_values(format!("{}[{}]", path, i), a.get(i), b.get(i), differences);
+            }
+        }
+        _ if a == b => {}
+        _ => differences.push(SpecDifference {
+            path,
+            a: a.cloned(),
+            b: b.cloned(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn diff_works() -> TopLevelResult {
+        let a = ProblemSpecBuilder::new("foo")
+            .attr("version", "1")
+            .param(domain::var("x").continuous(0.0, 1.0))
+            .value(domain::var("y"))
+            .steps(vec![1, 2])
+            .finish()?;
+        let b = ProblemSpecBuilder::new("foo")
+            .attr("version", "2")
+            .attr("github", "bar")
+            .param(domain::var("x").continuous(0.0, 2.0))
+            .value(domain::var("y"))
+            .steps(vec![1, 2])
+            .finish()?;
+
+        let dir = tempfile::tempdir().map_err(Error::from)?;
+        let (path_a, path_b) = (dir.path().join("a.json"), dir.path().join("b.json"));
+        let write = |path: &Path, spec: ProblemSpec| -> Result<()> {
+            let file = track!(File::create(path).map_err(Error::from))?;
+            track!(serde_json::to_writer(file, &Spec::Problem(spec)).map_err(Error::from))
+        };
+        write(&path_a, a)?;
+        write(&path_b, b)?;
+
+        let opt = SpecOpt::Diff {
+            a: path_a.clone(),
+            b: path_b,
+        };
+        let differences = opt.get_diff()?;
+        let paths = differences
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "problem.attrs.github",
+                "problem.attrs.version",
+                "problem.params_domain[0].range.high"
+            ]
+        );
+        assert_eq!(differences[0].a, None);
+        assert_eq!(differences[0].b, Some(Value::from("bar")));
+        assert_eq!(
+            differences[1].to_string(),
+            "- problem.attrs.version: \"1\"\n+ problem.attrs.version: \"2\"\n"
+        );
+
+        let opt = SpecOpt::Diff {
+            a: path_a.clone(),
+            b: path_a,
+        };
+        assert!(opt.get_diff()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn diff_recipes_works() -> TopLevelResult {
+        let opt = SpecOpt::DiffRecipes {
+            a: r#"{"sigopt":{"name":"SPHERE","dim":2}}"#.to_owned(),
+            b: r#"{"sigopt":{"name":"SPHERE","dim":3}}"#.to_owned(),
+        };
+        let differences = opt.get_diff()?;
+        assert!(differences
+            .iter()
+            .any(|d| d.path == "problem.params_domain[2]" && d.a.is_none() && d.b.is_some()));
+
+        let opt = SpecOpt::DiffRecipes {
+            a: r#"{"sigopt":{"name":"SPHERE"}}"#.to_owned(),
+            b: r#"{"sigopt":{"name":"SPHERE"}}"#.to_owned(),
+        };
+        assert!(opt.get_diff()?.is_empty());
+
+        let opt = SpecOpt::DiffRecipes {
+            a: r#"{"random":{}}"#.to_owned(),
+            b: r#"{"sigopt":{"name":"SPHERE"}}"#.to_owned(),
+        };
+        assert!(!opt.get_diff()?.is_empty());
+        Ok(())
+    }
+}
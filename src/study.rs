@@ -30,23 +30,44 @@ pub struct StudyRecipe {
     pub scheduling: Scheduling,
 
     /// Random seed.
+    ///
+    /// The solver, the scheduler of the evaluation threads and the problem instance of each thread
+    /// use independent random number streams derived from this seed.
+    /// Note that the solver and problem used to share a single stream, so the results of
+    /// the studies recorded by older versions can't be reproduced by the same seed.
     #[structopt(long)]
     pub seed: Option<u64>,
+
+    /// Stops the study as soon as the best value reaches the given target value.
+    ///
+    /// This is only applicable to single-objective problems.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_target: Option<f64>,
 }
 
 /// Logical threads scheduling policy for executing a study.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, StructOpt, Serialize, Deserialize)]
-#[structopt(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-#[allow(missing_docs)]
 pub enum Scheduling {
+    /// Selects the next thread to be evaluated uniformly at random.
+    #[default]
     Random,
+
+    /// Selects the thread that has consumed the fewest steps.
     Fair,
-}
-impl Default for Scheduling {
-    fn default() -> Self {
-        Self::Random
-    }
+
+    /// Selects the next thread with a probability proportional to the inverse of its weight.
+    ///
+    /// This simulates heterogeneous workers: a thread whose weight is twice another's is
+    /// regarded as half as fast, so it evaluates approximately half as many trials.
+    /// The number of weights must be equal to the concurrency of the study.
+    ///
+    /// The command-line representation is `weighted:W0,W1,...` (e.g., `weighted:1,1,2`).
+    Weighted {
+        /// Positive weights of the threads.
+        weights: Vec<f64>,
+    },
 }
 impl FromStr for Scheduling {
     type Err = Error;
@@ -55,6 +76,13 @@ impl FromStr for Scheduling {
         match s {
             "random" => Ok(Self::Random),
             "fair" => Ok(Self::Fair),
+            _ if s.starts_with("weighted:") => {
+                let weights = s["weighted:".len()..]
+                    .split(',')
+                    .map(|w| track!(w.trim().parse::<f64>().map_err(Error::from); w))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Self::Weighted { weights })
+            }
             _ => track_panic!(ErrorKind::InvalidInput, "Unknown scheduling type: {:?}", s),
         }
     }
@@ -64,6 +92,16 @@ impl fmt::Display for Scheduling {
         match self {
             Self::Random => write!(f, "random"),
             Self::Fair => write!(f, "fair"),
+            Self::Weighted { weights } => {
+                write!(f, "weighted:")?;
+                for (i, w) in weights.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", w)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -92,15 +130,27 @@ pub struct StudiesRecipe {
     #[structopt(long, default_value = "1")]
     pub concurrency: NonZeroUsize,
 
-    /// Scheduling policy of logical threads.
+    /// Scheduling policy of logical threads (`random`, `fair` or `weighted:W0,W1,...`).
     ///
     /// This option is ignored when `concurrency` is less then `2`.
     #[structopt(long, default_value = "random")]
     pub scheduling: Scheduling,
 
-    /// Random seed.
-    #[structopt(long)]
+    /// Base random seed of the studies.
+    ///
+    /// If this is specified, the `i`-th repetition of each solver and problem combination gets
+    /// the seed `seed + i`, so the emitted study recipes fully determine the benchmark results
+    /// (note that the studies of different solvers in the same repetition share the same seed).
+    /// Otherwise, the seed of each study is chosen at random when it's executed.
+    #[structopt(long, alias = "seed-base")]
     pub seed: Option<u64>,
+
+    /// Stops each study as soon as the best value reaches the given target value.
+    ///
+    /// This is only applicable to single-objective problems.
+    #[structopt(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_target: Option<f64>,
 }
 impl StudiesRecipe {
     /// Returns a iterator that iterates over the study recipes specified by this recipe.
@@ -115,8 +165,9 @@ impl StudiesRecipe {
                         problem: problem.clone(),
                         budget: self.budget,
                         concurrency: self.concurrency,
-                        scheduling: self.scheduling,
+                        scheduling: self.scheduling.clone(),
                         seed,
+                        stop_on_target: self.stop_on_target,
                     };
                     studies.push(study);
                 }
@@ -125,3 +176,33 @@ impl StudiesRecipe {
         studies.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn studies_have_distinct_seeds() {
+        let recipe = StudiesRecipe::from_iter(&[
+            "studies",
+            "--solvers",
+            r#"{"random":{}}"#,
+            r#"{"random":{"ask_all_steps":true}}"#,
+            "--problems",
+            r#"{"sigopt":{"name":"SPHERE"}}"#,
+            r#"{"sigopt":{"name":"ACKLEY"}}"#,
+            "--repeats",
+            "3",
+            "--seed-base",
+            "100",
+        ]);
+
+        let studies = recipe.studies().collect::<Vec<_>>();
+        assert_eq!(studies.len(), 2 * 2 * 3);
+        for chunk in studies.chunks(2 * 3) {
+            let seeds = chunk.iter().map(|s| s.seed).collect::<Vec<_>>();
+            let expected = [100, 100, 101, 101, 102, 102];
+            assert_eq!(seeds, expected.iter().map(|&s| Some(s)).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! `kurobako validate` command.
+use crate::problem::KurobakoProblemRecipe;
+use crate::solver::KurobakoSolverRecipe;
+use crate::study::StudyRecipe;
+use kurobako_core::problem::{ProblemFactory as _, ProblemRecipe as _, ProblemSpec};
+use kurobako_core::registry::FactoryRegistry;
+use kurobako_core::solver::{Capability, SolverFactory as _, SolverRecipe as _, SolverSpec};
+use kurobako_core::{Error, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use structopt::StructOpt;
+
+/// Options of the `kurobako validate` command.
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ValidateOpt {}
+
+impl ValidateOpt {
+    /// Reads study recipes from the standard input and returns the incompatible (solver, problem) pairs.
+    ///
+    /// Only the factories and the specifications of the solvers and problems are created
+    /// (neither solvers nor problems are instantiated).
+    pub fn run(&self) -> Result<Vec<Incompatibility>> {
+        let stdin = std::io::stdin();
+        let recipes = serde_json::Deserializer::from_reader(stdin.lock())
+            .into_iter()
+            .map(|recipe| track!(recipe.map_err(Error::from)))
+            .collect::<Result<Vec<StudyRecipe>>>()?;
+        track!(validate(&recipes))
+    }
+}
+
+/// An incompatible pair of a solver and a problem.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Incompatibility {
+    /// Name of the solver.
+    pub solver: String,
+
+    /// Name of the problem.
+    pub problem: String,
+
+    /// Capabilities required by the problem but the solver doesn't have.
+    pub missing: Vec<Capability>,
+}
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} cannot solve {:?} (missing capabilities: {:?})",
+            self.solver, self.problem, self.missing
+        )
+    }
+}
+
+/// Checks that the solver of each study has the capabilities required by the problem.
+///
+/// The incompatible pairs are deduplicated and sorted by the solver and problem names.
+pub fn validate(recipes: &[StudyRecipe]) -> Result<Vec<Incompatibility>> {
+    let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+
+    // The specifications are cached because the same recipes usually appear many times (e.g., repeats).
+    let mut problems = HashMap::new();
+    let mut solvers = HashMap::new();
+    let mut incompatibilities = BTreeSet::new();
+    for recipe in recipes {
+        let key = track!(serde_json::to_string(&recipe.problem).map_err(Error::from))?;
+        if !problems.contains_key(&key) {
+            let factory = track!(recipe.problem.create_factory(&registry))?;
+            problems.insert(key.clone(), track!(factory.specification())?);
+        }
+        let problem = &problems[&key];
+
+        let key = track!(serde_json::to_string(&recipe.solver).map_err(Error::from))?;
+        if !solvers.contains_key(&key) {
+            let factory = track!(recipe.solver.create_factory(&registry))?;
+            solvers.insert(key.clone(), track!(factory.specification())?);
+        }
+        let solver = &solvers[&key];
+
+        if let Some(x) = check_capabilities(solver, problem) {
+            incompatibilities.insert(x);
+        }
+    }
+    Ok(incompatibilities.into_iter().collect())
+}
+
+fn check_capabilities(solver: &SolverSpec, problem: &ProblemSpec) -> Option<Incompatibility> {
+    let missing = solver
+        .capabilities
+        .incapables(&problem.requirements())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        None
+    } else {
+        Some(Incompatibility {
+            solver: solver.name.clone(),
+            problem: problem.name.clone(),
+            missing,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurobako_core::domain;
+    use kurobako_core::json;
+    use kurobako_core::problem::ProblemSpecBuilder;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn check_capabilities_works() -> TopLevelResult {
+        let registry = FactoryRegistry::new::<KurobakoProblemRecipe, KurobakoSolverRecipe>();
+        let nsga2: KurobakoSolverRecipe = json::parse_json(
+            r#"{"nsga2":{"population":10,"tournament":2,"crossover":0.5,"mutation":0.3}}"#,
+        )?;
+        let nsga2 = nsga2.create_factory(&registry)?.specification()?;
+        let random: KurobakoSolverRecipe = json::parse_json(r#"{"random":{}}"#)?;
+        let random = random.create_factory(&registry)?.specification()?;
+
+        let conditional = ProblemSpecBuilder::new("conditional")
+            .param(domain::var("a").categorical(["x", "y"]))
+            .param(
+                domain::var("b")
+                    .continuous(0.0, 1.0)
+                    .constraint(domain::Constraint::new("a == 0")),
+            )
+            .value(domain::var("v"))
+            .finish()?;
+
+        let x = check_capabilities(&nsga2, &conditional);
+        assert_eq!(x.map(|x| x.missing), Some(vec![Capability::Conditional]));
+        assert_eq!(check_capabilities(&random, &conditional), None);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_works() -> TopLevelResult {
+        let recipes: Vec<StudyRecipe> = json::load(
+            r#"{"solver":{"random":{}},"problem":{"zdt":{"zdt":"1"}},
+                "budget":10,"concurrency":1,"scheduling":"RANDOM","seed":0}
+               {"solver":{"cmaes":{}},"problem":{"zdt":{"zdt":"1"}},
+                "budget":10,"concurrency":1,"scheduling":"RANDOM","seed":0}
+               {"solver":{"cmaes":{}},"problem":{"zdt":{"zdt":"1"}},
+                "budget":10,"concurrency":1,"scheduling":"RANDOM","seed":1}"#
+                .as_bytes(),
+        )?;
+        let incompatibilities = validate(&recipes)?;
+        assert_eq!(incompatibilities.len(), 1);
+        assert!(incompatibilities[0]
+            .missing
+            .contains(&Capability::MultiObjective));
+        Ok(())
+    }
+}
@@ -18,6 +18,11 @@ pub struct Var {
     #[serde(default)]
     pub log_uniform: bool,
 
+    /// Marks the variable as a fidelity (budget) knob.
+    #[structopt(long)]
+    #[serde(default)]
+    pub fidelity: bool,
+
     #[structopt(flatten)]
     #[allow(missing_docs)]
     pub range: Range,
@@ -25,12 +30,14 @@ pub struct Var {
 impl Var {
     /// Converts to `VariableBuilder`.
     pub fn to_domain_var(&self) -> VariableBuilder {
-        let builder = VariableBuilder::new(&self.path.to_string()).range(self.range.clone());
+        let mut builder = VariableBuilder::new(&self.path.to_string()).range(self.range.clone());
         if self.log_uniform {
-            builder.log_uniform()
-        } else {
-            builder
+            builder = builder.log_uniform();
+        }
+        if self.fidelity {
+            builder = builder.fidelity();
         }
+        builder
     }
 }
 
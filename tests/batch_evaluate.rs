@@ -0,0 +1,90 @@
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+// Writes a NASBench dataset (nasbench-rs binary format) that contains a single model
+// consisting only of the input and output nodes.
+fn write_dataset(path: &std::path::Path) {
+    let mut bytes = b"nasbench-rs".to_vec();
+    bytes.extend_from_slice(&1u32.to_be_bytes()); // Number of models.
+
+    // Model spec: the operations (input, output), the adjacency matrix and the module hash.
+    bytes.extend_from_slice(&[2, 0, 4, 2]);
+    bytes.extend_from_slice(&1u32.to_be_bytes());
+    bytes.extend_from_slice(&0x0437_21b9_c7fe_8c5f_ad81_1d47_d831_32ecu128.to_be_bytes());
+
+    // Model stats: the trainable parameters and the validation accuracy of each epoch.
+    bytes.extend_from_slice(&100u32.to_be_bytes());
+    let epochs = [(4, 0.1), (12, 0.5), (36, 0.8), (108, 0.9)];
+    bytes.push(epochs.len() as u8);
+    for &(epoch, accuracy) in &epochs {
+        bytes.extend_from_slice(&[epoch, 1]);
+        for _ in 0..2 {
+            for &x in &[1.0f64, accuracy, accuracy, accuracy] {
+                bytes.extend_from_slice(&x.to_be_bytes());
+            }
+        }
+    }
+    std::fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn batch_evaluate_nasbench_at_multiple_steps() {
+    let dir = tempfile::tempdir().unwrap();
+    let dataset = dir.path().join("nasbench.bin");
+    write_dataset(&dataset);
+
+    let problem = serde_json::json!({"nasbench": {"dataset": dataset, "encoding": "A"}});
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kurobako"))
+        .args(["batch-evaluate", "--problem", &problem.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn `kurobako batch-evaluate`");
+
+    // Only the edge from the input node to the output node is enabled.
+    let mut params = vec![0.0; 26];
+    params[10] = 1.0;
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        let calls = [
+            serde_json::json!({"params": params, "step": 12}),
+            serde_json::json!({"params": params, "step": 108}),
+            serde_json::json!({"params": params, "step": 20}),
+            serde_json::json!({"params": params}),
+        ];
+        for call in &calls {
+            writeln!(stdin, "{}", call).unwrap();
+        }
+    }
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let replies = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect::<Vec<serde_json::Value>>();
+
+    let replies = replies
+        .iter()
+        .map(|reply| {
+            (
+                reply["step"].as_u64().unwrap(),
+                reply["values"][0].as_f64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(replies.len(), 4);
+    assert_eq!(replies[0].0, 12);
+    assert!((replies[0].1 - 0.5).abs() < 1e-9);
+    assert_eq!(replies[1].0, 108);
+    assert!((replies[1].1 - 0.1).abs() < 1e-9);
+
+    // The evaluation stops at the next recorded epoch.
+    assert_eq!(replies[2].0, 36);
+    assert!((replies[2].1 - 0.2).abs() < 1e-9);
+
+    // The last step is used if `step` is omitted.
+    assert_eq!(replies[3].0, 108);
+    assert!((replies[3].1 - 0.1).abs() < 1e-9);
+}
@@ -0,0 +1,48 @@
+#![cfg(unix)]
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::process::{Command, Stdio};
+
+#[test]
+fn sigint_flushes_completed_studies() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kurobako"))
+        .args(["run", "--quiet", "--flush"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn `kurobako run`");
+
+    // The first study finishes immediately, and the rest take about ten seconds each.
+    let fast = r#"{"solver":{"random":{}},"problem":{"sigopt":{"name":"SPHERE","dim":2}},
+                   "budget":10,"concurrency":1,"scheduling":"RANDOM","seed":0}"#;
+    let slow = r#"{"solver":{"random":{}},
+                   "problem":{"delay":{"problem":{"sigopt":{"name":"SPHERE","dim":2}},"seconds_per_step":0.1}},
+                   "budget":100,"concurrency":1,"scheduling":"RANDOM","seed":0}"#;
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        writeln!(stdin, "{}", fast.replace('\n', " ")).unwrap();
+        for _ in 0..3 {
+            writeln!(stdin, "{}", slow.replace('\n', " ")).unwrap();
+        }
+    }
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let first = lines.next().expect("no study records").unwrap();
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGINT);
+    }
+    let rest = lines.collect::<Result<Vec<_>, _>>().unwrap();
+    let status = child.wait().unwrap();
+    assert!(!status.success());
+
+    let records = std::iter::once(first).chain(rest).collect::<Vec<_>>();
+    assert!(!records.is_empty() && records.len() < 4, "{:?}", records);
+    for (i, record) in records.iter().enumerate() {
+        let record: serde_json::Value = serde_json::from_str(record).unwrap();
+        assert!(record.get("trials").is_some());
+
+        // The partial records of the stopped studies are marked as interrupted.
+        let interrupted = record.get("interrupted").and_then(|v| v.as_bool());
+        assert_eq!(interrupted.unwrap_or(false), i > 0, "{}", record);
+    }
+}